@@ -0,0 +1,235 @@
+// src/core/api/drive_upgrades.rs
+
+use candid::{Encode, Principal};
+use ic_cdk::api::management_canister::main::{
+    clear_chunk_store, install_chunked_code, upload_chunk, CanisterInstallMode, ClearChunkStoreArgument,
+    InstallChunkedCodeArgument, UploadChunkArgument,
+};
+
+use crate::{
+    core::{
+        state::{
+            drive_upgrades::{
+                state::state::{read_drive_wasm_chunks, DRIVE_UPGRADE_ROLLOUTS, DRIVE_WASM_VERSIONS},
+                types::{DriveUpgradeRollout, DriveUpgradeRolloutID, DriveUpgradeRolloutStatus, DriveUpgradeTarget, DriveUpgradeTargetStatus, DriveWasmVersion},
+            },
+            giftcards_spawnorg::{
+                state::state::{DEPLOYMENTS_BY_GIFTCARD_SPAWNORG_ID, DRIVE_TO_GIFTCARD_SPAWNORG_HASHTABLE},
+                types::DriveID,
+            },
+        },
+        types::IDPrefix,
+    },
+    debug_log,
+    rest::drive_upgrades::types::DriveUpgradeArgs,
+};
+
+
+// How long a drive may sit in Upgrading before execute treats its runner as gone.
+// A runner that traps after its await leaves the target Upgrading with nobody to finish it.
+pub const DRIVE_UPGRADE_LEASE_MS: u64 = 15 * 60 * 1000;
+
+pub fn get_drive_upgrade_rollout(rollout_id: &DriveUpgradeRolloutID) -> Option<DriveUpgradeRollout> {
+    DRIVE_UPGRADE_ROLLOUTS.with(|store| store.borrow().get(rollout_id))
+}
+
+pub fn save_drive_upgrade_rollout(rollout: &mut DriveUpgradeRollout) {
+    rollout.updated_at_ms = ic_cdk::api::time() / 1_000_000;
+    DRIVE_UPGRADE_ROLLOUTS.with(|store| {
+        store.borrow_mut().insert(rollout.id.clone(), rollout.clone());
+    });
+}
+
+// Lists spawned drives that are not yet on the version and splits them into stages.
+// Stage 0 is the canary (at least one drive), every later stage holds up to batch_size drives.
+// Returns the targets and the number of stages.
+pub fn plan_drive_upgrade_targets(
+    version: &str,
+    drive_ids: Option<&Vec<DriveID>>,
+    canary_percent: u8,
+    batch_size: u32,
+) -> (Vec<DriveUpgradeTarget>, u32) {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+    let mut targets: Vec<DriveUpgradeTarget> = DEPLOYMENTS_BY_GIFTCARD_SPAWNORG_ID.with(|records| {
+        records
+            .borrow()
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.version != version)
+            .filter(|record| drive_ids.map_or(true, |ids| ids.contains(&record.drive_id)))
+            .map(|record| DriveUpgradeTarget {
+                canister_id: record.drive_id.0.trim_start_matches(IDPrefix::Drive.as_str()).to_string(),
+                drive_id: record.drive_id,
+                from_version: record.version,
+                stage: 0,
+                status: DriveUpgradeTargetStatus::Pending,
+                error: None,
+                updated_at_ms: now_ms,
+            })
+            .collect()
+    });
+
+    if targets.is_empty() {
+        return (targets, 0);
+    }
+
+    let total = targets.len();
+    let canary_count = ((total * canary_percent as usize) + 99) / 100;
+    let canary_count = canary_count.clamp(1, total);
+    let remaining = total - canary_count;
+    let batch_size = batch_size as usize;
+
+    for (index, target) in targets.iter_mut().enumerate() {
+        target.stage = if index < canary_count {
+            0
+        } else {
+            (1 + (index - canary_count) / batch_size) as u32
+        };
+    }
+
+    let total_stages = 1 + ((remaining + batch_size - 1) / batch_size) as u32;
+    (targets, total_stages)
+}
+
+// Upgrades a single drive in place with a registered wasm version.
+// The factory is a controller of every drive it spawned, unless the owner removed it since.
+pub async fn upgrade_drive_canister(canister_id: &str, wasm: &DriveWasmVersion) -> Result<(), String> {
+    let target_canister = Principal::from_text(canister_id)
+        .map_err(|_| format!("Invalid canister id {}", canister_id))?;
+
+    let chunks = read_drive_wasm_chunks(&wasm.version, wasm.total_chunks)
+        .ok_or_else(|| format!("Wasm chunks for version {} are missing", wasm.version))?;
+
+    let wasm_module_hash = hex::decode(&wasm.wasm_sha256)
+        .map_err(|_| "Registered wasm hash is not valid hex".to_string())?;
+
+    let arg = Encode!(&Option::<DriveUpgradeArgs>::Some(DriveUpgradeArgs {
+        version: wasm.version.clone(),
+    }))
+    .map_err(|e| format!("Failed to encode upgrade arguments: {:?}", e))?;
+
+    let mut chunk_hashes = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        match upload_chunk(UploadChunkArgument { canister_id: target_canister, chunk }).await {
+            Ok((chunk_hash,)) => chunk_hashes.push(chunk_hash),
+            Err(e) => return Err(format!("Failed to upload WASM chunk: {:?}", e)),
+        }
+    }
+
+    let result = install_chunked_code(InstallChunkedCodeArgument {
+        mode: CanisterInstallMode::Upgrade(None),
+        target_canister,
+        store_canister: None,
+        chunk_hashes_list: chunk_hashes,
+        wasm_module_hash,
+        arg,
+    })
+    .await
+    .map_err(|e| format!("Failed to upgrade drive: {:?}", e));
+
+    // The chunk store counts against the drive's memory, so empty it whether or not the install worked
+    if let Err(e) = clear_chunk_store(ClearChunkStoreArgument { canister_id: target_canister }).await {
+        debug_log!("Failed to clear chunk store of {}: {:?}", canister_id, e);
+    }
+
+    result
+}
+
+// Keeps the spawn history in step with what each drive is actually running
+fn record_drive_version(drive_id: &DriveID, version: &str) {
+    let giftcard_id = DRIVE_TO_GIFTCARD_SPAWNORG_HASHTABLE.with(|map| map.borrow().get(drive_id));
+    if let Some(giftcard_id) = giftcard_id {
+        DEPLOYMENTS_BY_GIFTCARD_SPAWNORG_ID.with(|records| {
+            let mut records = records.borrow_mut();
+            if let Some(mut record) = records.get(&giftcard_id) {
+                record.version = version.to_string();
+                records.insert(giftcard_id, record);
+            }
+        });
+    }
+}
+
+// Upgrades drives one at a time until the current stage is done (or every stage, when
+// all_stages is set). The rollout is re-read after every await, so a pause or abort that
+// lands while a drive is upgrading takes effect before the next drive is touched, and a
+// runner whose generation was replaced by a later execute stops after its current drive.
+pub async fn run_drive_upgrade_rollout(rollout_id: &DriveUpgradeRolloutID, generation: u64, all_stages: bool) -> Option<DriveUpgradeRollout> {
+    loop {
+        let mut rollout = get_drive_upgrade_rollout(rollout_id)?;
+        if rollout.status != DriveUpgradeRolloutStatus::Running || rollout.run_generation != generation {
+            return Some(rollout);
+        }
+
+        let wasm = match DRIVE_WASM_VERSIONS.with(|store| store.borrow().get(&rollout.version)) {
+            Some(wasm) => wasm,
+            None => {
+                rollout.status = DriveUpgradeRolloutStatus::Halted;
+                save_drive_upgrade_rollout(&mut rollout);
+                return Some(rollout);
+            }
+        };
+
+        let stage = rollout.stages_completed;
+        let next_target = rollout.targets.iter().position(|target| {
+            target.stage == stage
+                && matches!(
+                    target.status,
+                    DriveUpgradeTargetStatus::Pending | DriveUpgradeTargetStatus::Upgrading | DriveUpgradeTargetStatus::Failed
+                )
+        });
+
+        let index = match next_target {
+            Some(index) => index,
+            None => {
+                // Stage finished
+                rollout.stages_completed += 1;
+                if rollout.stages_completed >= rollout.total_stages {
+                    rollout.status = DriveUpgradeRolloutStatus::Completed;
+                } else if !all_stages {
+                    rollout.status = DriveUpgradeRolloutStatus::Paused;
+                }
+                save_drive_upgrade_rollout(&mut rollout);
+                continue;
+            }
+        };
+
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        rollout.targets[index].status = DriveUpgradeTargetStatus::Upgrading;
+        rollout.targets[index].error = None;
+        rollout.targets[index].updated_at_ms = now_ms;
+        save_drive_upgrade_rollout(&mut rollout);
+
+        let target = rollout.targets[index].clone();
+        debug_log!("Upgrading drive {} to {}", target.drive_id, wasm.version);
+        let result = upgrade_drive_canister(&target.canister_id, &wasm).await;
+
+        let mut rollout = get_drive_upgrade_rollout(rollout_id)?;
+        let superseded = rollout.run_generation != generation;
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        match result {
+            Ok(()) => {
+                // The install happened either way, so the drive's version is recorded even when superseded
+                record_drive_version(&target.drive_id, &wasm.version);
+                if rollout.targets[index].status == DriveUpgradeTargetStatus::Upgrading {
+                    rollout.targets[index].status = DriveUpgradeTargetStatus::Upgraded;
+                    rollout.targets[index].updated_at_ms = now_ms;
+                }
+            },
+            Err(e) => {
+                debug_log!("Drive {} failed to upgrade: {}", target.drive_id, e);
+                if !superseded {
+                    rollout.targets[index].status = DriveUpgradeTargetStatus::Failed;
+                    rollout.targets[index].error = Some(e);
+                    rollout.targets[index].updated_at_ms = now_ms;
+                    if !rollout.status.is_terminal() {
+                        rollout.status = DriveUpgradeRolloutStatus::Halted;
+                    }
+                }
+            }
+        }
+        save_drive_upgrade_rollout(&mut rollout);
+        if superseded {
+            return Some(rollout);
+        }
+    }
+}
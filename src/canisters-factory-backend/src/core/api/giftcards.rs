@@ -0,0 +1,24 @@
+// src/core/api/giftcards.rs
+
+use std::time::Duration;
+
+use crate::core::state::{
+    giftcards_refuel::state::state::reconcile_giftcard_refuel_reservations,
+    giftcards_spawnorg::state::state::reconcile_giftcard_spawnorg_reservations,
+};
+
+pub const GIFTCARD_RESERVATION_SWEEP_SECONDS: u64 = 5 * 60;
+
+// Timers do not survive upgrades, so this is started from both init and post_upgrade
+pub fn start_giftcard_reservation_timer() {
+    ic_cdk_timers::set_timer_interval(
+        Duration::from_secs(GIFTCARD_RESERVATION_SWEEP_SECONDS),
+        reconcile_giftcard_reservations,
+    );
+}
+
+pub fn reconcile_giftcard_reservations() {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+    reconcile_giftcard_refuel_reservations(now_ms);
+    reconcile_giftcard_spawnorg_reservations(now_ms);
+}
@@ -1,2 +1,5 @@
 pub mod uuid;
-pub mod helpers;
\ No newline at end of file
+pub mod helpers;
+pub mod drive_upgrades;
+pub mod giftcards;
+pub mod topups;
\ No newline at end of file
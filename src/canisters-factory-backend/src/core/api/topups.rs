@@ -0,0 +1,92 @@
+// src/core/api/topups.rs
+
+use std::{cell::Cell, time::Duration};
+
+use candid::Principal;
+use ic_cdk::api::management_canister::main::{canister_status, deposit_cycles, CanisterIdRecord};
+
+use crate::{
+    core::state::{
+        giftcards_spawnorg::types::DriveID,
+        topups::state::state::{check_drive_topup, finish_drive_topup, record_drive_topup_error, DRIVE_TOPUP_SUBSCRIPTIONS},
+    },
+    debug_log,
+};
+
+pub const DRIVE_TOPUP_SWEEP_SECONDS: u64 = 60 * 60;
+
+thread_local! {
+    // Start of the sweep in flight, 0 when idle. A sweep that trapped mid-way is
+    // treated as finished once a full interval has passed.
+    static DRIVE_TOPUP_SWEEP_STARTED_MS: Cell<u64> = Cell::new(0);
+}
+
+// Timers do not survive upgrades, so this is started from both init and post_upgrade
+pub fn start_drive_topup_timer() {
+    ic_cdk_timers::set_timer_interval(
+        Duration::from_secs(DRIVE_TOPUP_SWEEP_SECONDS),
+        || ic_cdk::spawn(run_drive_topup_sweep()),
+    );
+}
+
+pub async fn run_drive_topup_sweep() {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+    let started_ms = DRIVE_TOPUP_SWEEP_STARTED_MS.with(|started| started.get());
+    if started_ms != 0 && now_ms.saturating_sub(started_ms) < DRIVE_TOPUP_SWEEP_SECONDS * 1000 {
+        debug_log!("Drive top-up sweep already running, skipping");
+        return;
+    }
+    DRIVE_TOPUP_SWEEP_STARTED_MS.with(|started| started.set(now_ms));
+
+    // Drives with nothing prepaid are skipped without calling the management canister
+    let drive_ids: Vec<DriveID> = DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+        store.borrow()
+            .iter()
+            .filter(|(_, subscription)| subscription.active && subscription.prepaid_cycles > 0)
+            .map(|(drive_id, _)| drive_id)
+            .collect()
+    });
+
+    for drive_id in drive_ids {
+        topup_drive_if_low(&drive_id).await;
+    }
+
+    DRIVE_TOPUP_SWEEP_STARTED_MS.with(|started| started.set(0));
+}
+
+async fn topup_drive_if_low(drive_id: &DriveID) {
+    let canister_id = match DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| store.borrow().get(drive_id)) {
+        Some(subscription) => subscription.canister_id,
+        None => return,
+    };
+    let canister_principal = match Principal::from_text(&canister_id) {
+        Ok(principal) => principal,
+        Err(_) => {
+            record_drive_topup_error(drive_id, ic_cdk::api::time() / 1_000_000, format!("Invalid canister id {}", canister_id));
+            return;
+        }
+    };
+
+    // The factory stays a controller of the drives it spawns, so it can read their balance
+    let balance = match canister_status(CanisterIdRecord { canister_id: canister_principal }).await {
+        Ok((status,)) => u64::try_from(status.cycles.0).unwrap_or(u64::MAX),
+        Err(e) => {
+            debug_log!("Failed to read cycles balance of drive {}: {:?}", drive_id, e);
+            record_drive_topup_error(drive_id, ic_cdk::api::time() / 1_000_000, format!("Failed to read canister status: {:?}", e));
+            return;
+        }
+    };
+
+    // Re-read after the await, the subscription may have been changed or drained meanwhile
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+    let amount = match check_drive_topup(drive_id, balance, now_ms) {
+        Some(amount) => amount,
+        None => return,
+    };
+
+    debug_log!("Topping up drive {} with {} cycles, balance was {}", drive_id, amount, balance);
+    let result = deposit_cycles(CanisterIdRecord { canister_id: canister_principal }, amount as u128)
+        .await
+        .map_err(|e| format!("Failed to deposit cycles: {:?}", e));
+    finish_drive_topup(drive_id, amount, ic_cdk::api::time() / 1_000_000, result);
+}
@@ -0,0 +1,88 @@
+// src/core/state/drive_upgrades/state.rs
+
+pub mod state {
+    use std::cell::RefCell;
+    use ic_stable_structures::memory_manager::MemoryId;
+    use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+
+    use crate::core::state::drive_upgrades::types::{
+        DriveUpgradeRollout, DriveUpgradeRolloutID, DriveWasmChunk, DriveWasmChunkKey, DriveWasmVersion,
+    };
+    use crate::MEMORY_MANAGER;
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+
+    // Define memory IDs for each storage
+    pub const DRIVE_WASM_VERSIONS_MEMORY_ID: MemoryId = MemoryId::new(18);
+    pub const DRIVE_WASM_CHUNKS_MEMORY_ID: MemoryId = MemoryId::new(19);
+    pub const DRIVE_UPGRADE_ROLLOUTS_MEMORY_ID: MemoryId = MemoryId::new(20);
+
+    thread_local! {
+        // Registered drive wasm versions, keyed by version label
+        pub(crate) static DRIVE_WASM_VERSIONS: RefCell<StableBTreeMap<String, DriveWasmVersion, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(DRIVE_WASM_VERSIONS_MEMORY_ID))
+            )
+        );
+
+        // Wasm bytes for both registered versions and uploads that are still in progress
+        pub(crate) static DRIVE_WASM_CHUNKS: RefCell<StableBTreeMap<DriveWasmChunkKey, DriveWasmChunk, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(DRIVE_WASM_CHUNKS_MEMORY_ID))
+            )
+        );
+
+        pub(crate) static DRIVE_UPGRADE_ROLLOUTS: RefCell<StableBTreeMap<DriveUpgradeRolloutID, DriveUpgradeRollout, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(DRIVE_UPGRADE_ROLLOUTS_MEMORY_ID))
+            )
+        );
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        DRIVE_WASM_VERSIONS.with(|_| {});
+        DRIVE_WASM_CHUNKS.with(|_| {});
+        DRIVE_UPGRADE_ROLLOUTS.with(|_| {});
+    }
+
+    // Returns the chunks 0..total_chunks of a version, or None if any is missing
+    pub fn read_drive_wasm_chunks(version: &str, total_chunks: u32) -> Option<Vec<Vec<u8>>> {
+        DRIVE_WASM_CHUNKS.with(|store| {
+            let store = store.borrow();
+            (0..total_chunks)
+                .map(|index| {
+                    store
+                        .get(&DriveWasmChunkKey { version: version.to_string(), index })
+                        .map(|chunk| chunk.0)
+                })
+                .collect()
+        })
+    }
+
+    // Removes every stored chunk of a version, including ones beyond total_chunks
+    pub fn delete_drive_wasm_chunks(version: &str) {
+        DRIVE_WASM_CHUNKS.with(|store| {
+            let mut store = store.borrow_mut();
+            let keys: Vec<DriveWasmChunkKey> = store
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| key.version == version)
+                .collect();
+            for key in keys {
+                store.remove(&key);
+            }
+        });
+    }
+
+    // Only one rollout may be in flight at a time so drives are never upgraded by two plans
+    pub fn active_drive_upgrade_rollout() -> Option<DriveUpgradeRollout> {
+        DRIVE_UPGRADE_ROLLOUTS.with(|store| {
+            store
+                .borrow()
+                .iter()
+                .map(|(_, rollout)| rollout)
+                .find(|rollout| !rollout.status.is_terminal())
+        })
+    }
+}
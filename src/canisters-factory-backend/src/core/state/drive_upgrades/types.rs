@@ -0,0 +1,184 @@
+// src/core/state/drive_upgrades/types.rs
+use std::{borrow::Cow, fmt};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Serialize, Deserialize};
+use crate::core::state::giftcards_spawnorg::types::DriveID;
+
+
+// A drive wasm build that has been uploaded to the factory and can be rolled out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveWasmVersion {
+    pub version: String,
+    pub wasm_sha256: String, // hex encoded
+    pub size_bytes: u64,
+    pub total_chunks: u32,
+    pub note: String,
+    pub registered_at_ms: u64,
+}
+
+impl Storable for DriveWasmVersion {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 4096,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Failed to serialize DriveWasmVersion");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(bytes.as_ref())
+            .expect("Failed to deserialize DriveWasmVersion")
+    }
+}
+
+
+// Chunks are keyed by (version, index) so a version can be uploaded across many requests
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
+pub struct DriveWasmChunkKey {
+    pub version: String,
+    pub index: u32,
+}
+
+impl Storable for DriveWasmChunkKey {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 512,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Failed to serialize DriveWasmChunkKey");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(bytes.as_ref())
+            .expect("Failed to deserialize DriveWasmChunkKey")
+    }
+}
+
+
+// Raw wasm bytes, stored as-is (cbor would double the size of a byte array)
+#[derive(Debug, Clone)]
+pub struct DriveWasmChunk(pub Vec<u8>);
+
+impl Storable for DriveWasmChunk {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        DriveWasmChunk(bytes.into_owned())
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
+pub struct DriveUpgradeRolloutID(pub String);
+impl fmt::Display for DriveUpgradeRolloutID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Storable for DriveUpgradeRolloutID {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Failed to serialize DriveUpgradeRolloutID");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(bytes.as_ref())
+            .expect("Failed to deserialize DriveUpgradeRolloutID")
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DriveUpgradeRolloutStatus {
+    Planned,   // created, no stage executed yet
+    Running,   // an execute call is currently upgrading drives
+    Paused,    // between stages, waiting for the next execute call
+    Halted,    // a drive failed to upgrade, execute retries the failed stage
+    Completed,
+    Aborted,
+}
+
+impl DriveUpgradeRolloutStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, DriveUpgradeRolloutStatus::Completed | DriveUpgradeRolloutStatus::Aborted)
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DriveUpgradeTargetStatus {
+    Pending,
+    Upgrading,
+    Upgraded,
+    Failed,
+    Skipped,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveUpgradeTarget {
+    pub drive_id: DriveID,
+    pub canister_id: String,
+    pub from_version: String,
+    pub stage: u32, // 0 is the canary stage
+    pub status: DriveUpgradeTargetStatus,
+    pub error: Option<String>,
+    pub updated_at_ms: u64,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveUpgradeRollout {
+    pub id: DriveUpgradeRolloutID,
+    pub version: String,
+    pub canary_percent: u8,
+    pub batch_size: u32,
+    pub total_stages: u32,
+    pub stages_completed: u32,
+    pub status: DriveUpgradeRolloutStatus,
+    pub targets: Vec<DriveUpgradeTarget>,
+    pub note: String,
+    pub created_at_ms: u64,
+    pub updated_at_ms: u64,
+    // Bumped by every execute call, a runner whose generation is no longer current stops
+    #[serde(default)]
+    pub run_generation: u64,
+}
+
+impl Storable for DriveUpgradeRollout {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Failed to serialize DriveUpgradeRollout");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(bytes.as_ref())
+            .expect("Failed to deserialize DriveUpgradeRollout")
+    }
+}
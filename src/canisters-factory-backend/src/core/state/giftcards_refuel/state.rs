@@ -11,7 +11,8 @@ pub mod state {
     use crate::core::state::giftcards_refuel::types::GiftcardRefuelID;
     use crate::core::state::giftcards_refuel::types::GiftcardRefuel;
 
-    use crate::core::types::{UserID};
+    use crate::core::types::{GiftcardRedemptionStatus, GiftcardReservationError, UserID, GIFTCARD_REDEEM_ERROR_MAX_CHARS, GIFTCARD_RESERVATION_TIMEOUT_MS};
+    use crate::debug_log;
     use crate::MEMORY_MANAGER;
     
 
@@ -67,4 +68,88 @@ pub mod state {
         USER_TO_GIFTCARDS_REFUEL_HASHTABLE.with(|_| {});
         GIFTCARD_REFUEL_BY_ID.with(|_| {});
     }
+
+    // Moves an Available (or Failed) giftcard to Reserved and persists it.
+    // Must run before the first await of a redemption.
+    pub fn reserve_giftcard_refuel(giftcard_id: &GiftcardRefuelID) -> Result<GiftcardRefuel, GiftcardReservationError> {
+        GIFTCARD_REFUEL_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            let mut giftcard = store.get(giftcard_id).ok_or(GiftcardReservationError::NotFound)?;
+            if giftcard.redeemed || giftcard.redemption_status == GiftcardRedemptionStatus::Redeemed {
+                return Err(GiftcardReservationError::AlreadyRedeemed);
+            }
+            if giftcard.redemption_status == GiftcardRedemptionStatus::Reserved {
+                return Err(GiftcardReservationError::InProgress);
+            }
+            if giftcard.redemption_status == GiftcardRedemptionStatus::NeedsReview {
+                return Err(GiftcardReservationError::NeedsReview);
+            }
+            giftcard.redemption_status = GiftcardRedemptionStatus::Reserved;
+            giftcard.reserved_at_ms = ic_cdk::api::time() / 1_000_000;
+            giftcard.last_redeem_error = None;
+            store.insert(giftcard_id.clone(), giftcard.clone());
+            Ok(giftcard)
+        })
+    }
+
+    // Settles a reservation once the deposit returned. An error releases the giftcard.
+    pub fn finish_giftcard_refuel_redemption(giftcard_id: &GiftcardRefuelID, result: Result<(), String>) {
+        GIFTCARD_REFUEL_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut giftcard) = store.get(giftcard_id) {
+                match result {
+                    Ok(()) => {
+                        giftcard.redeemed = true;
+                        giftcard.redemption_status = GiftcardRedemptionStatus::Redeemed;
+                        giftcard.last_redeem_error = None;
+                    },
+                    Err(e) => {
+                        giftcard.redemption_status = GiftcardRedemptionStatus::Failed;
+                        giftcard.last_redeem_error = Some(e.chars().take(GIFTCARD_REDEEM_ERROR_MAX_CHARS).collect());
+                    }
+                }
+                store.insert(giftcard_id.clone(), giftcard);
+            }
+        });
+    }
+
+    // Locks a giftcard until the owner settles it. Used when we cannot tell whether the
+    // cycles went out, so neither releasing nor consuming the card is safe.
+    pub fn mark_giftcard_refuel_needs_review(giftcard_id: &GiftcardRefuelID, reason: &str) {
+        GIFTCARD_REFUEL_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut giftcard) = store.get(giftcard_id) {
+                giftcard.redemption_status = GiftcardRedemptionStatus::NeedsReview;
+                giftcard.last_redeem_error = Some(reason.chars().take(GIFTCARD_REDEEM_ERROR_MAX_CHARS).collect());
+                store.insert(giftcard_id.clone(), giftcard);
+            }
+        });
+    }
+
+    // A reservation only outlives its redemption if the callback trapped after the await.
+    // The history record is written in the same callback as a successful redemption,
+    // so its presence means the cycles went out. Without it the deposit may still have
+    // landed, so the card waits for the owner instead of being released.
+    pub fn reconcile_giftcard_refuel_reservations(now_ms: u64) {
+        let stuck: Vec<GiftcardRefuelID> = GIFTCARD_REFUEL_BY_ID.with(|store| {
+            store.borrow()
+                .iter()
+                .filter(|(_, giftcard)| {
+                    giftcard.redemption_status == GiftcardRedemptionStatus::Reserved
+                        && now_ms.saturating_sub(giftcard.reserved_at_ms) > GIFTCARD_RESERVATION_TIMEOUT_MS
+                })
+                .map(|(id, _)| id)
+                .collect()
+        });
+
+        for giftcard_id in stuck {
+            let deposited = DEPLOYMENTS_BY_GIFTCARD_REFUEL_ID.with(|records| records.borrow().contains_key(&giftcard_id));
+            debug_log!("Reconciling stuck reservation of {}, deposited: {}", giftcard_id, deposited);
+            if deposited {
+                finish_giftcard_refuel_redemption(&giftcard_id, Ok(()));
+            } else {
+                mark_giftcard_refuel_needs_review(&giftcard_id, "Reservation expired before the deposit completed");
+            }
+        }
+    }
 }
\ No newline at end of file
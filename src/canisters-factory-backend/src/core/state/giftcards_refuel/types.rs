@@ -4,7 +4,7 @@ use std::{borrow::Cow, fmt};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Serialize, Deserialize};
 use serde_diff::{SerdeDiff};
-use crate::core::{ state::giftcards_spawnorg::types::{DriveID, DriveRESTUrlEndpoint}, types::{ICPPrincipalString, PublicKeyICP, UserID}};
+use crate::core::{ state::giftcards_spawnorg::types::{DriveID, DriveRESTUrlEndpoint}, types::{GiftcardRedemptionStatus, ICPPrincipalString, PublicKeyICP, UserID}};
 
 
 
@@ -76,6 +76,12 @@ pub struct GiftcardRefuel {
     pub timestamp_ms: u64,
     pub external_id: String, // eg. stripe charge id or evm tx hash
     pub redeemed: bool,
+    #[serde(default)]
+    pub redemption_status: GiftcardRedemptionStatus,
+    #[serde(default)]
+    pub reserved_at_ms: u64,
+    #[serde(default)]
+    pub last_redeem_error: Option<String>,
 }
 
 impl Storable for GiftcardRefuel {
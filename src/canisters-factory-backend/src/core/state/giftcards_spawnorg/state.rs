@@ -17,7 +17,7 @@ pub mod state {
     use crate::core::state::giftcards_spawnorg::types::GiftcardSpawnOrg;
 
     use crate::core::state::giftcards_spawnorg::types::GiftcardSpawnOrgIDVec;
-    use crate::core::types::{UserID,PublicKeyICP};
+    use crate::core::types::{GiftcardRedemptionStatus, GiftcardReservationError, PublicKeyICP, UserID, GIFTCARD_REDEEM_ERROR_MAX_CHARS, GIFTCARD_RESERVATION_TIMEOUT_MS};
     use crate::debug_log;
     use crate::MEMORY_MANAGER;
 
@@ -128,5 +128,105 @@ pub mod state {
             debug_log!("Confirmed URL host set to: {}", url.borrow().get().0);
         });
     }
-    
+
+    // Moves an Available (or Failed) giftcard to Reserved and persists it.
+    // Must run before the first await of a redemption.
+    pub fn reserve_giftcard_spawnorg(giftcard_id: &GiftcardSpawnOrgID) -> Result<GiftcardSpawnOrg, GiftcardReservationError> {
+        GIFTCARD_SPAWNORG_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            let mut giftcard = store.get(giftcard_id).ok_or(GiftcardReservationError::NotFound)?;
+            if giftcard.redeemed || giftcard.redemption_status == GiftcardRedemptionStatus::Redeemed {
+                return Err(GiftcardReservationError::AlreadyRedeemed);
+            }
+            if giftcard.redemption_status == GiftcardRedemptionStatus::Reserved {
+                return Err(GiftcardReservationError::InProgress);
+            }
+            if giftcard.redemption_status == GiftcardRedemptionStatus::NeedsReview {
+                return Err(GiftcardReservationError::NeedsReview);
+            }
+            giftcard.redemption_status = GiftcardRedemptionStatus::Reserved;
+            giftcard.reserved_at_ms = ic_cdk::api::time() / 1_000_000;
+            giftcard.last_redeem_error = None;
+            store.insert(giftcard_id.clone(), giftcard.clone());
+            Ok(giftcard)
+        })
+    }
+
+    // Settles a reservation once the deploy returned. An error releases the giftcard.
+    pub fn finish_giftcard_spawnorg_redemption(giftcard_id: &GiftcardSpawnOrgID, result: Result<(), String>) {
+        GIFTCARD_SPAWNORG_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut giftcard) = store.get(giftcard_id) {
+                match result {
+                    Ok(()) => {
+                        giftcard.redeemed = true;
+                        giftcard.redemption_status = GiftcardRedemptionStatus::Redeemed;
+                        giftcard.last_redeem_error = None;
+                    },
+                    Err(e) => {
+                        giftcard.redemption_status = GiftcardRedemptionStatus::Failed;
+                        giftcard.last_redeem_error = Some(e.chars().take(GIFTCARD_REDEEM_ERROR_MAX_CHARS).collect());
+                    }
+                }
+                store.insert(giftcard_id.clone(), giftcard);
+            }
+        });
+    }
+
+    // Remembers the canister created for a giftcard as soon as create_canister returns,
+    // so a failed install is retried on that canister instead of spending the cycles again.
+    pub fn record_giftcard_spawnorg_canister(giftcard_id: &GiftcardSpawnOrgID, canister_id: &str, owner_icp_principal: &str) {
+        GIFTCARD_SPAWNORG_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut giftcard) = store.get(giftcard_id) {
+                giftcard.spawned_canister_id = Some(canister_id.to_string());
+                giftcard.spawned_owner_icp_principal = Some(owner_icp_principal.to_string());
+                store.insert(giftcard_id.clone(), giftcard);
+            }
+        });
+    }
+
+    // Locks a giftcard until the owner settles it. Used when we cannot tell whether the
+    // drive was spawned, so neither releasing nor consuming the card is safe.
+    pub fn mark_giftcard_spawnorg_needs_review(giftcard_id: &GiftcardSpawnOrgID, reason: &str) {
+        GIFTCARD_SPAWNORG_BY_ID.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut giftcard) = store.get(giftcard_id) {
+                let reason = match &giftcard.spawned_canister_id {
+                    Some(canister_id) => format!("{} (canister {})", reason, canister_id),
+                    None => reason.to_string(),
+                };
+                giftcard.redemption_status = GiftcardRedemptionStatus::NeedsReview;
+                giftcard.last_redeem_error = Some(reason.chars().take(GIFTCARD_REDEEM_ERROR_MAX_CHARS).collect());
+                store.insert(giftcard_id.clone(), giftcard);
+            }
+        });
+    }
+
+    // A reservation only outlives its redemption if a callback trapped after an await.
+    // The deployment record is written in the same callback as a successful redemption,
+    // so its presence means the drive was spawned. Without it a canister may still exist,
+    // so the card waits for the owner instead of being released.
+    pub fn reconcile_giftcard_spawnorg_reservations(now_ms: u64) {
+        let stuck: Vec<GiftcardSpawnOrgID> = GIFTCARD_SPAWNORG_BY_ID.with(|store| {
+            store.borrow()
+                .iter()
+                .filter(|(_, giftcard)| {
+                    giftcard.redemption_status == GiftcardRedemptionStatus::Reserved
+                        && now_ms.saturating_sub(giftcard.reserved_at_ms) > GIFTCARD_RESERVATION_TIMEOUT_MS
+                })
+                .map(|(id, _)| id)
+                .collect()
+        });
+
+        for giftcard_id in stuck {
+            let deployed = DEPLOYMENTS_BY_GIFTCARD_SPAWNORG_ID.with(|records| records.borrow().contains_key(&giftcard_id));
+            debug_log!("Reconciling stuck reservation of {}, deployed: {}", giftcard_id, deployed);
+            if deployed {
+                finish_giftcard_spawnorg_redemption(&giftcard_id, Ok(()));
+            } else {
+                mark_giftcard_spawnorg_needs_review(&giftcard_id, "Reservation expired before the drive was deployed");
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -4,7 +4,7 @@ use std::{borrow::Cow, fmt};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Serialize, Deserialize};
 use serde_diff::{SerdeDiff};
-use crate::core::{ types::{ICPPrincipalString, PublicKeyICP, UserID}};
+use crate::core::{ types::{GiftcardRedemptionStatus, ICPPrincipalString, PublicKeyICP, UserID}};
 
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff, PartialOrd, Ord)]
@@ -106,6 +106,17 @@ pub struct GiftcardSpawnOrg {
     pub timestamp_ms: u64,
     pub external_id: Option<String>, // eg. stripe charge id or evm tx hash
     pub redeemed: bool,
+    #[serde(default)]
+    pub redemption_status: GiftcardRedemptionStatus,
+    #[serde(default)]
+    pub reserved_at_ms: u64,
+    #[serde(default)]
+    pub last_redeem_error: Option<String>,
+    // Set once create_canister succeeded, a later redeem only retries the install
+    #[serde(default)]
+    pub spawned_canister_id: Option<String>,
+    #[serde(default)]
+    pub spawned_owner_icp_principal: Option<String>,
     pub disk_auth_json: Option<String>,
 }
 
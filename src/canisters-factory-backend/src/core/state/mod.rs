@@ -3,4 +3,6 @@
 pub mod giftcards_spawnorg;
 pub mod giftcards_refuel;
 pub mod api_keys; 
-pub mod templates;
\ No newline at end of file
+pub mod templates;
+pub mod drive_upgrades;
+pub mod topups;
\ No newline at end of file
@@ -0,0 +1,95 @@
+// src/core/state/topups/state.rs
+
+pub mod state {
+    use std::cell::RefCell;
+    use ic_stable_structures::memory_manager::MemoryId;
+    use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+
+    use crate::core::state::giftcards_spawnorg::types::DriveID;
+    use crate::core::state::topups::types::{DriveTopupSubscription, DRIVE_TOPUP_ERROR_MAX_CHARS};
+    use crate::MEMORY_MANAGER;
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+
+    // Define memory IDs for each storage
+    pub const DRIVE_TOPUP_SUBSCRIPTIONS_MEMORY_ID: MemoryId = MemoryId::new(21);
+
+    thread_local! {
+        pub(crate) static DRIVE_TOPUP_SUBSCRIPTIONS: RefCell<StableBTreeMap<DriveID, DriveTopupSubscription, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(DRIVE_TOPUP_SUBSCRIPTIONS_MEMORY_ID))
+            )
+        );
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|_| {});
+    }
+
+    pub fn get_drive_topup_subscription(drive_id: &DriveID) -> Option<DriveTopupSubscription> {
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| store.borrow().get(drive_id))
+    }
+
+    // Adds funded cycles to the prepaid balance, None if the drive has no subscription
+    pub fn credit_drive_topup(drive_id: &DriveID, cycles: u64) -> Option<DriveTopupSubscription> {
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            let mut store = store.borrow_mut();
+            let mut subscription = store.get(drive_id)?;
+            subscription.prepaid_cycles = subscription.prepaid_cycles.saturating_add(cycles);
+            store.insert(drive_id.clone(), subscription.clone());
+            Some(subscription)
+        })
+    }
+
+    // Records the balance seen by the sweep. Returns the amount taken out of the prepaid
+    // balance if the drive needs a top-up, which must happen before the deposit is awaited.
+    pub fn check_drive_topup(drive_id: &DriveID, balance: u64, now_ms: u64) -> Option<u64> {
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            let mut store = store.borrow_mut();
+            let mut subscription = store.get(drive_id)?;
+            subscription.last_checked_at_ms = now_ms;
+            subscription.last_balance = Some(balance);
+
+            let amount = subscription.topup_cycles.min(subscription.prepaid_cycles);
+            let needs_topup = subscription.active && balance < subscription.min_cycles && amount > 0;
+            if needs_topup {
+                subscription.prepaid_cycles -= amount;
+            }
+            store.insert(drive_id.clone(), subscription);
+            if needs_topup { Some(amount) } else { None }
+        })
+    }
+
+    // Settles a top-up once the deposit returned. A failed deposit goes back to the prepaid balance.
+    pub fn finish_drive_topup(drive_id: &DriveID, amount: u64, now_ms: u64, result: Result<(), String>) {
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            let mut store = store.borrow_mut();
+            // Deleted while the deposit was in flight, nothing left to refund into
+            let Some(mut subscription) = store.get(drive_id) else { return };
+            match result {
+                Ok(()) => {
+                    subscription.last_topup_at_ms = Some(now_ms);
+                    subscription.total_topped_up_cycles = subscription.total_topped_up_cycles.saturating_add(amount);
+                    subscription.last_error = None;
+                },
+                Err(e) => {
+                    subscription.prepaid_cycles = subscription.prepaid_cycles.saturating_add(amount);
+                    subscription.last_error = Some(e.chars().take(DRIVE_TOPUP_ERROR_MAX_CHARS).collect());
+                }
+            }
+            store.insert(drive_id.clone(), subscription);
+        });
+    }
+
+    pub fn record_drive_topup_error(drive_id: &DriveID, now_ms: u64, error: String) {
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut subscription) = store.get(drive_id) {
+                subscription.last_checked_at_ms = now_ms;
+                subscription.last_error = Some(error.chars().take(DRIVE_TOPUP_ERROR_MAX_CHARS).collect());
+                store.insert(drive_id.clone(), subscription);
+            }
+        });
+    }
+}
@@ -0,0 +1,47 @@
+// src/core/state/topups/types.rs
+use std::borrow::Cow;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Serialize, Deserialize};
+use crate::core::state::giftcards_spawnorg::types::DriveID;
+
+
+// Keeps stored errors well inside the Storable bound
+pub const DRIVE_TOPUP_ERROR_MAX_CHARS: usize = 256;
+
+
+// Auto top-up for a drive spawned by this factory.
+// Cycles come out of prepaid_cycles, which is funded by redeeming refuel giftcards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveTopupSubscription {
+    pub drive_id: DriveID,
+    pub canister_id: String,
+    pub min_cycles: u64,   // top up once the drive balance drops below this
+    pub topup_cycles: u64, // deposited per top-up, capped by what is prepaid
+    pub prepaid_cycles: u64,
+    pub active: bool,
+    pub created_at_ms: u64,
+    pub last_checked_at_ms: u64,
+    pub last_balance: Option<u64>,
+    pub last_topup_at_ms: Option<u64>,
+    pub total_topped_up_cycles: u64,
+    pub last_error: Option<String>,
+}
+
+impl Storable for DriveTopupSubscription {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 4096,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Failed to serialize DriveTopupSubscription");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(bytes.as_ref())
+            .expect("Failed to deserialize DriveTopupSubscription")
+    }
+}
@@ -81,7 +81,8 @@ pub enum IDPrefix {
     Drive,
     User,
     GiftcardSpawnOrg,
-    GiftcardRefuel
+    GiftcardRefuel,
+    DriveUpgradeRollout
 }
 
 impl IDPrefix {
@@ -92,12 +93,56 @@ impl IDPrefix {
             IDPrefix::User => "UserID_",
             IDPrefix::GiftcardSpawnOrg => "GiftcardSpawnOrgID_",
             IDPrefix::GiftcardRefuel => "GiftcardRefuelID_",
+            IDPrefix::DriveUpgradeRollout => "DriveUpgradeRolloutID_",
         }
     }
 }
 
 
 
+// Redemption lifecycle shared by spawnorg and refuel giftcards.
+// A card is Reserved before the first inter-canister await, so a concurrent redeem sees it taken.
+// Failed cards are released: they can be redeemed again.
+// NeedsReview cards stay locked until the owner settles them through the update route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff, PartialOrd, Ord, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GiftcardRedemptionStatus {
+    #[default]
+    Available,
+    Reserved,
+    Redeemed,
+    Failed,
+    NeedsReview,
+}
+
+impl GiftcardRedemptionStatus {
+    // The owner may only settle a NeedsReview card, either as Redeemed (the drive or
+    // deposit went out) or as Available (nothing went out, release it).
+    pub fn resolve_review(self, target: GiftcardRedemptionStatus) -> Result<GiftcardRedemptionStatus, String> {
+        if self != GiftcardRedemptionStatus::NeedsReview {
+            return Err("Only giftcards in NEEDS_REVIEW can have their redemption_status set".to_string());
+        }
+        match target {
+            GiftcardRedemptionStatus::Available | GiftcardRedemptionStatus::Redeemed => Ok(target),
+            _ => Err("redemption_status can only be set to AVAILABLE or REDEEMED".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GiftcardReservationError {
+    NotFound,
+    AlreadyRedeemed,
+    InProgress,
+    NeedsReview,
+}
+
+// Reservations older than this are reconciled by the timer
+pub const GIFTCARD_RESERVATION_TIMEOUT_MS: u64 = 30 * 60 * 1000;
+// Keeps the stored error inside the bounded giftcard storables
+pub const GIFTCARD_REDEEM_ERROR_MAX_CHARS: usize = 256;
+
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff)]
 pub enum AuthPrefixEnum {
     ApiKey,
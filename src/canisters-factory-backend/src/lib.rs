@@ -63,6 +63,8 @@ fn init() {
     let args = ic_cdk::api::call::arg_data::<(Option<InitArgs>,)>(ic_cdk::api::call::ArgDecoderConfig::default()).0;
     debug_log!("INIT FUNCTION - Args extracted, calling initialize_canister...");
     initialize_canister(args);
+    crate::core::api::giftcards::start_giftcard_reservation_timer();
+    crate::core::api::topups::start_drive_topup_timer();
     debug_log!("INIT FUNCTION COMPLETED");
 }
 
@@ -96,6 +98,8 @@ fn initialize_canister(args: Option<InitArgs>) {
                 crate::core::state::api_keys::state::state::initialize();
                 crate::core::state::giftcards_spawnorg::state::state::initialize();
                 crate::core::state::giftcards_refuel::state::state::initialize();
+                crate::core::state::drive_upgrades::state::state::initialize();
+                crate::core::state::topups::state::state::initialize();
                 
                 // Initialize the drive with all parameters
                 init_self_factory(
@@ -134,6 +138,8 @@ fn post_upgrade() {
     debug_log!("Post-upgrade initialization...");
 
     router::init_routes();
+    crate::core::api::giftcards::start_giftcard_reservation_timer();
+    crate::core::api::topups::start_drive_topup_timer();
 
     // Then check if we need to set up state
     let already_initialized = INITIALIZED_FLAG.with(|flag_cell| {
@@ -0,0 +1,659 @@
+// src/rest/drive_upgrades/handler.rs
+
+pub mod drive_upgrades_handlers {
+    use crate::{
+        core::{
+            api::{
+                drive_upgrades::{get_drive_upgrade_rollout, plan_drive_upgrade_targets, run_drive_upgrade_rollout, save_drive_upgrade_rollout, DRIVE_UPGRADE_LEASE_MS},
+                uuid::generate_uuidv4,
+            },
+            state::{
+                drive_upgrades::{
+                    state::state::{active_drive_upgrade_rollout, delete_drive_wasm_chunks, read_drive_wasm_chunks, DRIVE_UPGRADE_ROLLOUTS, DRIVE_WASM_CHUNKS, DRIVE_WASM_VERSIONS},
+                    types::{DriveUpgradeRollout, DriveUpgradeRolloutID, DriveUpgradeRolloutStatus, DriveUpgradeTargetStatus, DriveWasmChunk, DriveWasmChunkKey, DriveWasmVersion},
+                },
+                giftcards_spawnorg::{
+                    state::state::{DRIVE_TO_GIFTCARD_SPAWNORG_HASHTABLE, OWNER_ID},
+                    types::DriveID,
+                },
+            },
+            types::IDPrefix,
+        },
+        debug_log,
+        rest::{
+            auth::{authenticate_request, create_auth_error_response},
+            drive_upgrades::types::{
+                DeleteDriveWasmVersionRequestBody, DeleteDriveWasmVersionResponse, DeletedDriveWasmVersionData, DriveUpgradeRolloutActionRequestBody, DriveUpgradeRolloutResponse, ErrorResponse, ExecuteDriveUpgradeRequestBody, ListDriveUpgradeRolloutsResponse, ListDriveUpgradeRolloutsResponseData, ListDriveWasmVersionsResponse, ListDriveWasmVersionsResponseData, PlanDriveUpgradeRequestBody, RegisterDriveWasmVersionRequestBody, RegisterDriveWasmVersionResponse, UploadDriveWasmChunkRequestBody, UploadDriveWasmChunkResponse, UploadDriveWasmChunkResponseData, MAX_DRIVE_WASM_CHUNK_BYTES
+            },
+        },
+    };
+    use base64::{engine::general_purpose, Engine as _};
+    use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
+    use matchit::Params;
+    use sha2::{Digest, Sha256};
+
+    pub async fn upload_drive_wasm_chunk_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Only owner can upload drive wasm
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let upload_request = match serde_json::from_slice::<UploadDriveWasmChunkRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = upload_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        // Registered versions are immutable, a rollout may be reading them
+        let already_registered = DRIVE_WASM_VERSIONS.with(|store| store.borrow().contains_key(&upload_request.version));
+        if already_registered {
+            return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, format!("Version {} is already registered", upload_request.version)).encode()
+            );
+        }
+
+        let chunk = match general_purpose::STANDARD.decode(&upload_request.chunk_base64) {
+            Ok(bytes) => bytes,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Chunk is not valid base64".to_string()).encode()
+            ),
+        };
+
+        if chunk.len() > MAX_DRIVE_WASM_CHUNK_BYTES {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, format!("Chunk must be at most {} bytes", MAX_DRIVE_WASM_CHUNK_BYTES)).encode()
+            );
+        }
+
+        let size_bytes = chunk.len() as u64;
+        DRIVE_WASM_CHUNKS.with(|store| {
+            store.borrow_mut().insert(
+                DriveWasmChunkKey { version: upload_request.version.clone(), index: upload_request.index },
+                DriveWasmChunk(chunk),
+            );
+        });
+
+        create_response(
+            StatusCode::OK,
+            UploadDriveWasmChunkResponse::ok(&UploadDriveWasmChunkResponseData {
+                version: upload_request.version,
+                index: upload_request.index,
+                size_bytes,
+            }).encode()
+        )
+    }
+
+    pub async fn register_drive_wasm_version_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Only owner can register drive wasm
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let register_request = match serde_json::from_slice::<RegisterDriveWasmVersionRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = register_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let already_registered = DRIVE_WASM_VERSIONS.with(|store| store.borrow().contains_key(&register_request.version));
+        if already_registered {
+            return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, format!("Version {} is already registered", register_request.version)).encode()
+            );
+        }
+
+        let chunks = match read_drive_wasm_chunks(&register_request.version, register_request.total_chunks) {
+            Some(chunks) => chunks,
+            None => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, format!("Not every chunk of version {} has been uploaded", register_request.version)).encode()
+            ),
+        };
+
+        // The hash covers the whole module, so it also catches chunks uploaded out of order
+        let mut hasher = Sha256::new();
+        let mut size_bytes = 0u64;
+        for chunk in &chunks {
+            hasher.update(chunk);
+            size_bytes += chunk.len() as u64;
+        }
+        let wasm_sha256 = hex::encode(hasher.finalize());
+        if wasm_sha256 != register_request.wasm_sha256.to_lowercase() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, format!("Wasm hash mismatch, uploaded chunks hash to {}", wasm_sha256)).encode()
+            );
+        }
+
+        // Drop leftovers from an earlier attempt with more chunks
+        DRIVE_WASM_CHUNKS.with(|store| {
+            let mut store = store.borrow_mut();
+            let extra_keys: Vec<DriveWasmChunkKey> = store
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| key.version == register_request.version && key.index >= register_request.total_chunks)
+                .collect();
+            for key in extra_keys {
+                store.remove(&key);
+            }
+        });
+
+        let wasm_version = DriveWasmVersion {
+            version: register_request.version.clone(),
+            wasm_sha256,
+            size_bytes,
+            total_chunks: register_request.total_chunks,
+            note: register_request.note.unwrap_or_default(),
+            registered_at_ms: ic_cdk::api::time() / 1_000_000,
+        };
+
+        DRIVE_WASM_VERSIONS.with(|store| {
+            store.borrow_mut().insert(wasm_version.version.clone(), wasm_version.clone());
+        });
+
+        debug_log!("Registered drive wasm version {} ({} bytes)", wasm_version.version, wasm_version.size_bytes);
+
+        create_response(
+            StatusCode::OK,
+            RegisterDriveWasmVersionResponse::ok(&wasm_version).encode()
+        )
+    }
+
+    pub async fn list_drive_wasm_versions_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let mut items: Vec<DriveWasmVersion> = DRIVE_WASM_VERSIONS.with(|store| {
+            store.borrow().iter().map(|(_, version)| version).collect()
+        });
+        items.sort_by(|a, b| b.registered_at_ms.cmp(&a.registered_at_ms));
+
+        create_response(
+            StatusCode::OK,
+            ListDriveWasmVersionsResponse::ok(&ListDriveWasmVersionsResponseData {
+                total: items.len(),
+                items,
+            }).encode()
+        )
+    }
+
+    pub async fn delete_drive_wasm_version_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let delete_request = match serde_json::from_slice::<DeleteDriveWasmVersionRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = delete_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        if let Some(rollout) = active_drive_upgrade_rollout() {
+            if rollout.version == delete_request.version {
+                return create_response(
+                    StatusCode::CONFLICT,
+                    ErrorResponse::err(409, format!("Version is used by rollout {}", rollout.id)).encode()
+                );
+            }
+        }
+
+        // Also discards chunks of a version that was never registered
+        let registered = DRIVE_WASM_VERSIONS.with(|store| store.borrow_mut().remove(&delete_request.version).is_some());
+        let has_chunks = DRIVE_WASM_CHUNKS.with(|store| {
+            store.borrow().iter().any(|(key, _)| key.version == delete_request.version)
+        });
+        if !registered && !has_chunks {
+            return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Drive wasm version not found".to_string()).encode()
+            );
+        }
+        delete_drive_wasm_chunks(&delete_request.version);
+
+        create_response(
+            StatusCode::OK,
+            DeleteDriveWasmVersionResponse::ok(&DeletedDriveWasmVersionData {
+                version: delete_request.version,
+                deleted: true,
+            }).encode()
+        )
+    }
+
+    pub async fn plan_drive_upgrade_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Only owner can plan drive upgrades
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let plan_request = match serde_json::from_slice::<PlanDriveUpgradeRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = plan_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let registered = DRIVE_WASM_VERSIONS.with(|store| store.borrow().contains_key(&plan_request.version));
+        if !registered {
+            return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, format!("Drive wasm version {} is not registered", plan_request.version)).encode()
+            );
+        }
+
+        if let Some(rollout) = active_drive_upgrade_rollout() {
+            return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, format!("Rollout {} is still in progress, finish or abort it first", rollout.id)).encode()
+            );
+        }
+
+        let drive_ids: Option<Vec<DriveID>> = plan_request.drive_ids.as_ref().map(|ids| {
+            ids.iter().map(|id| DriveID(id.clone())).collect()
+        });
+        if let Some(drive_ids) = &drive_ids {
+            for drive_id in drive_ids {
+                let spawned = DRIVE_TO_GIFTCARD_SPAWNORG_HASHTABLE.with(|map| map.borrow().contains_key(drive_id));
+                if !spawned {
+                    return create_response(
+                        StatusCode::NOT_FOUND,
+                        ErrorResponse::err(404, format!("Drive {} was not spawned by this factory", drive_id)).encode()
+                    );
+                }
+            }
+        }
+
+        let (targets, total_stages) = plan_drive_upgrade_targets(
+            &plan_request.version,
+            drive_ids.as_ref(),
+            plan_request.canary_percent,
+            plan_request.batch_size,
+        );
+        if targets.is_empty() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, format!("Every drive is already on version {}", plan_request.version)).encode()
+            );
+        }
+
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let mut rollout = DriveUpgradeRollout {
+            id: DriveUpgradeRolloutID(generate_uuidv4(IDPrefix::DriveUpgradeRollout)),
+            version: plan_request.version,
+            canary_percent: plan_request.canary_percent,
+            batch_size: plan_request.batch_size,
+            total_stages,
+            stages_completed: 0,
+            status: DriveUpgradeRolloutStatus::Planned,
+            targets,
+            note: plan_request.note.unwrap_or_default(),
+            created_at_ms: now_ms,
+            updated_at_ms: now_ms,
+            run_generation: 0,
+        };
+        save_drive_upgrade_rollout(&mut rollout);
+
+        create_response(
+            StatusCode::OK,
+            DriveUpgradeRolloutResponse::ok(&rollout).encode()
+        )
+    }
+
+    pub async fn execute_drive_upgrade_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Only owner can execute drive upgrades
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let execute_request = match serde_json::from_slice::<ExecuteDriveUpgradeRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = execute_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let rollout_id = DriveUpgradeRolloutID(execute_request.rollout_id);
+        let mut rollout = match get_drive_upgrade_rollout(&rollout_id) {
+            Some(rollout) => rollout,
+            None => return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Rollout not found".to_string()).encode()
+            ),
+        };
+
+        match rollout.status {
+            DriveUpgradeRolloutStatus::Planned | DriveUpgradeRolloutStatus::Paused | DriveUpgradeRolloutStatus::Halted => {},
+            DriveUpgradeRolloutStatus::Running => return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "Rollout is already running".to_string()).encode()
+            ),
+            DriveUpgradeRolloutStatus::Completed | DriveUpgradeRolloutStatus::Aborted => return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "Rollout has already finished".to_string()).encode()
+            ),
+        }
+
+        // A paused or halted rollout can still have a drive mid-install from the previous execute.
+        // Starting another runner now would upgrade drives twice, so wait for it to land.
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        if rollout.targets.iter().any(|target| {
+            target.status == DriveUpgradeTargetStatus::Upgrading
+                && now_ms < target.updated_at_ms + DRIVE_UPGRADE_LEASE_MS
+        }) {
+            return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "A drive is still upgrading, execute again once it finishes".to_string()).encode()
+            );
+        }
+
+        // Mark as running before the first await so a second execute is turned away
+        rollout.status = DriveUpgradeRolloutStatus::Running;
+        rollout.run_generation += 1;
+        let generation = rollout.run_generation;
+        save_drive_upgrade_rollout(&mut rollout);
+
+        match run_drive_upgrade_rollout(&rollout_id, generation, execute_request.all_stages).await {
+            Some(rollout) => create_response(
+                StatusCode::OK,
+                DriveUpgradeRolloutResponse::ok(&rollout).encode()
+            ),
+            None => create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Rollout not found".to_string()).encode()
+            ),
+        }
+    }
+
+    pub async fn pause_drive_upgrade_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let pause_request = match serde_json::from_slice::<DriveUpgradeRolloutActionRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = pause_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let mut rollout = match get_drive_upgrade_rollout(&DriveUpgradeRolloutID(pause_request.rollout_id)) {
+            Some(rollout) => rollout,
+            None => return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Rollout not found".to_string()).encode()
+            ),
+        };
+
+        if rollout.status.is_terminal() {
+            return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "Rollout has already finished".to_string()).encode()
+            );
+        }
+
+        // A running execute finishes the drive it is on, then stops
+        rollout.status = DriveUpgradeRolloutStatus::Paused;
+        save_drive_upgrade_rollout(&mut rollout);
+
+        create_response(
+            StatusCode::OK,
+            DriveUpgradeRolloutResponse::ok(&rollout).encode()
+        )
+    }
+
+    pub async fn abort_drive_upgrade_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let abort_request = match serde_json::from_slice::<DriveUpgradeRolloutActionRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = abort_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let mut rollout = match get_drive_upgrade_rollout(&DriveUpgradeRolloutID(abort_request.rollout_id)) {
+            Some(rollout) => rollout,
+            None => return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Rollout not found".to_string()).encode()
+            ),
+        };
+
+        if rollout.status.is_terminal() {
+            return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "Rollout has already finished".to_string()).encode()
+            );
+        }
+
+        // Drives already upgraded stay on the new version, the rest are left alone
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        rollout.status = DriveUpgradeRolloutStatus::Aborted;
+        for target in rollout.targets.iter_mut() {
+            if target.status == DriveUpgradeTargetStatus::Pending {
+                target.status = DriveUpgradeTargetStatus::Skipped;
+                target.updated_at_ms = now_ms;
+            }
+        }
+        save_drive_upgrade_rollout(&mut rollout);
+
+        create_response(
+            StatusCode::OK,
+            DriveUpgradeRolloutResponse::ok(&rollout).encode()
+        )
+    }
+
+    pub async fn get_drive_upgrade_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let rollout_id = DriveUpgradeRolloutID(params.get("rollout_id").unwrap().to_string());
+        match get_drive_upgrade_rollout(&rollout_id) {
+            Some(rollout) => create_response(
+                StatusCode::OK,
+                DriveUpgradeRolloutResponse::ok(&rollout).encode()
+            ),
+            None => create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Rollout not found".to_string()).encode()
+            ),
+        }
+    }
+
+    pub async fn list_drive_upgrades_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let mut items: Vec<DriveUpgradeRollout> = DRIVE_UPGRADE_ROLLOUTS.with(|store| {
+            store.borrow().iter().map(|(_, rollout)| rollout).collect()
+        });
+        items.sort_by(|a, b| b.created_at_ms.cmp(&a.created_at_ms));
+
+        create_response(
+            StatusCode::OK,
+            ListDriveUpgradeRolloutsResponse::ok(&ListDriveUpgradeRolloutsResponseData {
+                total: items.len(),
+                items,
+            }).encode()
+        )
+    }
+
+    fn create_response(status_code: StatusCode, body: Vec<u8>) -> HttpResponse<'static> {
+        HttpResponse::builder()
+            .with_status_code(status_code)
+            .with_headers(vec![
+                ("content-type".to_string(), "application/json".to_string()),
+                (
+                    "strict-transport-security".to_string(),
+                    "max-age=31536000; includeSubDomains".to_string(),
+                ),
+                ("x-content-type-options".to_string(), "nosniff".to_string()),
+                ("referrer-policy".to_string(), "no-referrer".to_string()),
+                (
+                    "cache-control".to_string(),
+                    "no-store, max-age=0".to_string(),
+                ),
+                ("pragma".to_string(), "no-cache".to_string()),
+            ])
+            .with_body(body)
+            .build()
+    }
+}
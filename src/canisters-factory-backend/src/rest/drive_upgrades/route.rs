@@ -0,0 +1,80 @@
+// src/rest/drive_upgrades/route.rs
+use crate::debug_log;
+use crate::rest::router::{self, genroute};
+use crate::rest::types::RouteHandler;
+
+
+// ROUTE_PREFIX
+pub const DRIVE_WASM_UPLOAD_CHUNK_PATH: &str =       genroute!("/drive_upgrades/wasm/upload_chunk");
+pub const DRIVE_WASM_REGISTER_PATH: &str =           genroute!("/drive_upgrades/wasm/register");
+pub const DRIVE_WASM_LIST_PATH: &str =               genroute!("/drive_upgrades/wasm/list");
+pub const DRIVE_WASM_DELETE_PATH: &str =             genroute!("/drive_upgrades/wasm/delete");
+pub const DRIVE_UPGRADE_PLAN_PATH: &str =            genroute!("/drive_upgrades/plan");
+pub const DRIVE_UPGRADE_EXECUTE_PATH: &str =         genroute!("/drive_upgrades/execute");
+pub const DRIVE_UPGRADE_PAUSE_PATH: &str =           genroute!("/drive_upgrades/pause");
+pub const DRIVE_UPGRADE_ABORT_PATH: &str =           genroute!("/drive_upgrades/abort");
+pub const DRIVE_UPGRADE_GET_PATH: &str =             genroute!("/drive_upgrades/get/{rollout_id}");
+pub const DRIVE_UPGRADE_LIST_PATH: &str =            genroute!("/drive_upgrades/list");
+
+type HandlerEntry = (&'static str, &'static str, RouteHandler);
+
+pub fn init_routes() {
+    let routes: &[HandlerEntry] = &[
+        (
+            "POST",
+            DRIVE_WASM_UPLOAD_CHUNK_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::upload_drive_wasm_chunk_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_WASM_REGISTER_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::register_drive_wasm_version_handler(req, params)),
+        ),
+        (
+            "GET",
+            DRIVE_WASM_LIST_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::list_drive_wasm_versions_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_WASM_DELETE_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::delete_drive_wasm_version_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_UPGRADE_PLAN_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::plan_drive_upgrade_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_UPGRADE_EXECUTE_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::execute_drive_upgrade_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_UPGRADE_PAUSE_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::pause_drive_upgrade_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_UPGRADE_ABORT_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::abort_drive_upgrade_handler(req, params)),
+        ),
+        (
+            "GET",
+            DRIVE_UPGRADE_GET_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::get_drive_upgrade_handler(req, params)),
+        ),
+        (
+            "GET",
+            DRIVE_UPGRADE_LIST_PATH,
+            |req, params| Box::pin(crate::rest::drive_upgrades::handler::drive_upgrades_handlers::list_drive_upgrades_handler(req, params)),
+        ),
+    ];
+
+    for &(method, path, handler) in routes {
+        debug_log!("Registering {} route: {}", method, path);
+        router::insert_route(method, path, handler);
+    }
+
+}
@@ -0,0 +1,255 @@
+// src/rest/drive_upgrades/types.rs
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use crate::{
+    core::{
+        state::drive_upgrades::types::{DriveUpgradeRollout, DriveWasmVersion},
+        types::IDPrefix,
+    },
+    rest::types::{validate_id_string, validate_short_string, ApiResponse, ValidationError},
+};
+
+
+// Largest decoded chunk accepted, the management canister rejects chunks over 1MiB
+pub const MAX_DRIVE_WASM_CHUNK_BYTES: usize = 1_000_000;
+pub const MAX_DRIVE_WASM_CHUNKS: u32 = 100;
+
+fn validate_version_label(version: &str) -> Result<(), ValidationError> {
+    if version.is_empty() || version.len() > 64 {
+        return Err(ValidationError {
+            field: "version".to_string(),
+            message: "Version must be between 1 and 64 characters".to_string(),
+        });
+    }
+    if !version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_') {
+        return Err(ValidationError {
+            field: "version".to_string(),
+            message: "Version may only contain letters, digits, '.', '-' and '_'".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_rollout_id(rollout_id: &str) -> Result<(), ValidationError> {
+    validate_id_string(rollout_id, "rollout_id")?;
+    let prefix = IDPrefix::DriveUpgradeRollout.as_str();
+    if !rollout_id.starts_with(prefix) {
+        return Err(ValidationError {
+            field: "rollout_id".to_string(),
+            message: format!("Rollout ID must start with '{}'", prefix),
+        });
+    }
+    Ok(())
+}
+
+
+// Sent to the drive's post_upgrade so it can report the version it is running
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct DriveUpgradeArgs {
+    pub version: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UploadDriveWasmChunkRequestBody {
+    pub version: String,
+    pub index: u32,
+    pub chunk_base64: String,
+}
+impl UploadDriveWasmChunkRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_version_label(&self.version)?;
+
+        if self.index >= MAX_DRIVE_WASM_CHUNKS {
+            return Err(ValidationError {
+                field: "index".to_string(),
+                message: format!("Chunk index must be less than {}", MAX_DRIVE_WASM_CHUNKS),
+            });
+        }
+
+        if self.chunk_base64.is_empty() {
+            return Err(ValidationError {
+                field: "chunk_base64".to_string(),
+                message: "Chunk cannot be empty".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadDriveWasmChunkResponseData {
+    pub version: String,
+    pub index: u32,
+    pub size_bytes: u64,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterDriveWasmVersionRequestBody {
+    pub version: String,
+    pub wasm_sha256: String,
+    pub total_chunks: u32,
+    pub note: Option<String>,
+}
+impl RegisterDriveWasmVersionRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_version_label(&self.version)?;
+
+        if self.wasm_sha256.len() != 64 || !self.wasm_sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ValidationError {
+                field: "wasm_sha256".to_string(),
+                message: "Wasm hash must be a 64 character hex encoded sha256".to_string(),
+            });
+        }
+
+        if self.total_chunks == 0 || self.total_chunks > MAX_DRIVE_WASM_CHUNKS {
+            return Err(ValidationError {
+                field: "total_chunks".to_string(),
+                message: format!("Total chunks must be between 1 and {}", MAX_DRIVE_WASM_CHUNKS),
+            });
+        }
+
+        if let Some(note) = &self.note {
+            validate_short_string(note, "note")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeleteDriveWasmVersionRequestBody {
+    pub version: String,
+}
+impl DeleteDriveWasmVersionRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_version_label(&self.version)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletedDriveWasmVersionData {
+    pub version: String,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListDriveWasmVersionsResponseData {
+    pub items: Vec<DriveWasmVersion>,
+    pub total: usize,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlanDriveUpgradeRequestBody {
+    pub version: String,
+    #[serde(default = "default_canary_percent")]
+    pub canary_percent: u8,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u32,
+    // Limit the rollout to these drives, otherwise every spawned drive not already on the version
+    pub drive_ids: Option<Vec<String>>,
+    pub note: Option<String>,
+}
+
+fn default_canary_percent() -> u8 {
+    10
+}
+
+fn default_batch_size() -> u32 {
+    10
+}
+
+impl PlanDriveUpgradeRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_version_label(&self.version)?;
+
+        if self.canary_percent == 0 || self.canary_percent > 100 {
+            return Err(ValidationError {
+                field: "canary_percent".to_string(),
+                message: "Canary percent must be between 1 and 100".to_string(),
+            });
+        }
+
+        if self.batch_size == 0 || self.batch_size > 1000 {
+            return Err(ValidationError {
+                field: "batch_size".to_string(),
+                message: "Batch size must be between 1 and 1000".to_string(),
+            });
+        }
+
+        if let Some(drive_ids) = &self.drive_ids {
+            if drive_ids.is_empty() {
+                return Err(ValidationError {
+                    field: "drive_ids".to_string(),
+                    message: "Drive IDs cannot be an empty list".to_string(),
+                });
+            }
+            for drive_id in drive_ids {
+                validate_id_string(drive_id, "drive_ids")?;
+                if !drive_id.starts_with(IDPrefix::Drive.as_str()) {
+                    return Err(ValidationError {
+                        field: "drive_ids".to_string(),
+                        message: format!("Drive ID must start with '{}'", IDPrefix::Drive.as_str()),
+                    });
+                }
+            }
+        }
+
+        if let Some(note) = &self.note {
+            validate_short_string(note, "note")?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecuteDriveUpgradeRequestBody {
+    pub rollout_id: String,
+    // Keep going through every remaining stage instead of pausing after the next one
+    #[serde(default)]
+    pub all_stages: bool,
+}
+impl ExecuteDriveUpgradeRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_rollout_id(&self.rollout_id)
+    }
+}
+
+
+// Shared by pause and abort
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriveUpgradeRolloutActionRequestBody {
+    pub rollout_id: String,
+}
+impl DriveUpgradeRolloutActionRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_rollout_id(&self.rollout_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListDriveUpgradeRolloutsResponseData {
+    pub items: Vec<DriveUpgradeRollout>,
+    pub total: usize,
+}
+
+
+pub type UploadDriveWasmChunkResponse<'a> = ApiResponse<'a, UploadDriveWasmChunkResponseData>;
+pub type RegisterDriveWasmVersionResponse<'a> = ApiResponse<'a, DriveWasmVersion>;
+pub type DeleteDriveWasmVersionResponse<'a> = ApiResponse<'a, DeletedDriveWasmVersionData>;
+pub type ListDriveWasmVersionsResponse<'a> = ApiResponse<'a, ListDriveWasmVersionsResponseData>;
+pub type DriveUpgradeRolloutResponse<'a> = ApiResponse<'a, DriveUpgradeRollout>;
+pub type ListDriveUpgradeRolloutsResponse<'a> = ApiResponse<'a, ListDriveUpgradeRolloutsResponseData>;
+pub type ErrorResponse<'a> = ApiResponse<'a, ()>;
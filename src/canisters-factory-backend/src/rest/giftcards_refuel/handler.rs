@@ -5,6 +5,7 @@ pub mod giftcards_handlers {
     use crate::core::api::helpers::is_local_environment;
     use crate::core::api::uuid::format_drive_id;
     use crate::core::state::giftcards_refuel::state::state::DEPLOYMENTS_BY_GIFTCARD_REFUEL_ID;
+    use crate::core::state::giftcards_refuel::state::state::{finish_giftcard_refuel_redemption, reserve_giftcard_refuel};
     use crate::core::state::giftcards_refuel::types::FactoryRefuelHistoryRecord;
     use crate::core::state::giftcards_refuel::types::GiftcardRefuelIDVec;
     use crate::core::state::giftcards_spawnorg::state::state::OWNER_ID;
@@ -25,7 +26,7 @@ pub mod giftcards_handlers {
                     state::state::{HISTORICAL_GIFTCARDS_REFUELS, USER_TO_GIFTCARDS_REFUEL_HASHTABLE, GIFTCARD_REFUEL_BY_ID},
                     
                 }, 
-            types::{IDPrefix, UserID}
+            types::{GiftcardRedemptionStatus, GiftcardReservationError, IDPrefix, UserID}
         }, 
         debug_log, 
         rest::{
@@ -281,6 +282,9 @@ pub mod giftcards_handlers {
                         timestamp_ms: current_time,
                         external_id: create_req.external_id,
                         redeemed: false,
+                        redemption_status: GiftcardRedemptionStatus::Available,
+                        reserved_at_ms: 0,
+                        last_redeem_error: None,
                     };
             
                     // Add to GIFTCARD_REFUEL_BY_ID
@@ -334,6 +338,18 @@ pub mod giftcards_handlers {
                     if let Some(external_id) = update_req.external_id {
                         giftcard.external_id = external_id;
                     }
+                    if let Some(target_status) = update_req.redemption_status {
+                        match giftcard.redemption_status.resolve_review(target_status) {
+                            Ok(status) => {
+                                giftcard.redemption_status = status;
+                                giftcard.redeemed = status == GiftcardRedemptionStatus::Redeemed;
+                            },
+                            Err(e) => return create_response(
+                                StatusCode::CONFLICT,
+                                ErrorResponse::err(409, e).encode()
+                            ),
+                        }
+                    }
             
                     // Update the giftcard in GIFTCARD_REFUEL_BY_ID
                     GIFTCARD_REFUEL_BY_ID.with(|store| {
@@ -464,26 +480,6 @@ pub mod giftcards_handlers {
             );
         }
     
-        // Get the giftcard to be redeemed
-        let giftcard_id = redeem_request.giftcard_id.clone();
-        let giftcard = match GIFTCARD_REFUEL_BY_ID.with(|store| store.borrow().get(&giftcard_id).clone()) {
-            Some(v) => v,
-            None => {
-                return create_response(
-                    StatusCode::NOT_FOUND,
-                    ErrorResponse::err(404, "GiftcardRefuel not found".to_string()).encode()
-                )
-            }
-        };
-    
-        // Check if giftcard is already redeemed
-        if giftcard.redeemed {
-            return create_response(
-                StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, "GiftcardRefuel already redeemed".to_string()).encode()
-            );
-        }
-    
         // Convert ICP principal to Principal
         let recipient_principal = match Principal::from_text(&redeem_request.icp_principal) {
             Ok(p) => p,
@@ -495,6 +491,36 @@ pub mod giftcards_handlers {
             }
         };
     
+        // Reserve the giftcard before any await so a concurrent redeem cannot spend it too
+        let giftcard_id = redeem_request.giftcard_id.clone();
+        let giftcard = match reserve_giftcard_refuel(&giftcard_id) {
+            Ok(v) => v,
+            Err(GiftcardReservationError::NotFound) => {
+                return create_response(
+                    StatusCode::NOT_FOUND,
+                    ErrorResponse::err(404, "GiftcardRefuel not found".to_string()).encode()
+                )
+            },
+            Err(GiftcardReservationError::AlreadyRedeemed) => {
+                return create_response(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::err(400, "GiftcardRefuel already redeemed".to_string()).encode()
+                )
+            },
+            Err(GiftcardReservationError::InProgress) => {
+                return create_response(
+                    StatusCode::CONFLICT,
+                    ErrorResponse::err(409, "GiftcardRefuel redemption already in progress".to_string()).encode()
+                )
+            },
+            Err(GiftcardReservationError::NeedsReview) => {
+                return create_response(
+                    StatusCode::CONFLICT,
+                    ErrorResponse::err(409, "GiftcardRefuel needs owner review before it can be redeemed".to_string()).encode()
+                )
+            },
+        };
+    
         // Generate a unique redeem code using timestamp
         let redeem_code = format!("REDEEM_{}", ic_cdk::api::time());
         let current_time = ic_cdk::api::time() / 1_000_000;
@@ -503,11 +529,7 @@ pub mod giftcards_handlers {
         match deposit_cycles(recipient_principal, giftcard.gas_cycles_included).await {
             Ok(_) => {
                 // Update giftcard as redeemed
-                GIFTCARD_REFUEL_BY_ID.with(|store| {
-                    let mut giftcard = giftcard.clone();
-                    giftcard.redeemed = true;
-                    store.borrow_mut().insert(giftcard_id.clone(), giftcard);
-                });
+                finish_giftcard_refuel_redemption(&giftcard_id, Ok(()));
     
                 // Store the redemption history
                 let user_id = format_user_id(&redeem_request.icp_principal);
@@ -551,6 +573,8 @@ pub mod giftcards_handlers {
                 )
             },
             Err(e) => {
+                // Release the giftcard, the cycles were refunded to the factory
+                finish_giftcard_refuel_redemption(&giftcard_id, Err(e.clone()));
                 create_response(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     ErrorResponse::err(500, format!("Failed to deposit cycles: {}", e)).encode()
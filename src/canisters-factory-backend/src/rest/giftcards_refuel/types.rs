@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     core::{
         state::giftcards_refuel::types::{FactoryRefuelHistoryRecord, GiftcardRefuel, GiftcardRefuelID}, 
-        types::{ICPPrincipalString, IDPrefix, UserID}
+        types::{GiftcardRedemptionStatus, ICPPrincipalString, IDPrefix, UserID}
     }, 
     rest::types::{
             validate_external_id, validate_external_payload, validate_icp_principal, validate_id_string, validate_user_id, ApiResponse, ValidationError
@@ -152,6 +152,9 @@ pub struct UpdateGiftcardRefuelRequestBody {
     pub gas_cycles_included: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_id: Option<String>,
+    // Settles a NEEDS_REVIEW giftcard as AVAILABLE or REDEEMED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redemption_status: Option<GiftcardRedemptionStatus>,
 }
 impl UpdateGiftcardRefuelRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -10,6 +10,7 @@ pub mod giftcards_handlers {
     use crate::core::state::giftcards_spawnorg::types::GiftcardSpawnOrgID;
     use crate::core::state::giftcards_spawnorg::types::GiftcardSpawnOrg;
     use crate::core::state::giftcards_spawnorg::types::GiftcardSpawnOrgIDVec;
+    use crate::core::state::giftcards_spawnorg::state::state::{finish_giftcard_spawnorg_redemption, record_giftcard_spawnorg_canister, reserve_giftcard_spawnorg};
     use crate::rest::giftcards_spawnorg::types::RedeemGiftcardSpawnOrgData;
     use crate::rest::giftcards_spawnorg::types::RedeemGiftcardSpawnOrgResult;
     use crate::rest::giftcards_spawnorg::types::SpawnInitArgs;
@@ -20,7 +21,7 @@ pub mod giftcards_handlers {
                     state::state::{HISTORICAL_GIFTCARDS_SPAWNORGS, OWNER_ID, USER_TO_GIFTCARDS_SPAWNORG_HASHTABLE, GIFTCARD_SPAWNORG_BY_ID},
                     
                 }, 
-            types::{IDPrefix, UserID}
+            types::{GiftcardRedemptionStatus, GiftcardReservationError, IDPrefix, UserID}
         }, 
         debug_log, 
         rest::{
@@ -282,6 +283,11 @@ pub mod giftcards_handlers {
                 timestamp_ms: current_time,
                 external_id: create_req.external_id,
                 redeemed: false,
+                redemption_status: GiftcardRedemptionStatus::Available,
+                reserved_at_ms: 0,
+                last_redeem_error: None,
+                spawned_canister_id: None,
+                spawned_owner_icp_principal: None,
                 disk_auth_json: create_req.disk_auth_json,
             };
     
@@ -375,6 +381,18 @@ pub mod giftcards_handlers {
             if let Some(disk_auth_json) = update_req.disk_auth_json {
                 giftcard.disk_auth_json = Some(disk_auth_json);
             }
+            if let Some(target_status) = update_req.redemption_status {
+                match giftcard.redemption_status.resolve_review(target_status) {
+                    Ok(status) => {
+                        giftcard.redemption_status = status;
+                        giftcard.redeemed = status == GiftcardRedemptionStatus::Redeemed;
+                    },
+                    Err(e) => return create_response(
+                        StatusCode::CONFLICT,
+                        ErrorResponse::err(409, e).encode()
+                    ),
+                }
+            }
 
             // Update the giftcard in GIFTCARD_SPAWNORG_BY_ID
             GIFTCARD_SPAWNORG_BY_ID.with(|store| {
@@ -510,26 +528,36 @@ pub mod giftcards_handlers {
             );
         }
     
-        // Get the giftcard to be redeemed
+        // Reserve the giftcard before any await so a concurrent redeem cannot spend it too
         let giftcard_id = redeem_request.giftcard_id.clone();
-        let giftcard = match GIFTCARD_SPAWNORG_BY_ID.with(|store| store.borrow().get(&giftcard_id).clone()) {
-            Some(v) => v,
-            None => {
+        let giftcard = match reserve_giftcard_spawnorg(&giftcard_id) {
+            Ok(v) => v,
+            Err(GiftcardReservationError::NotFound) => {
                 return create_response(
                     StatusCode::NOT_FOUND,
                     ErrorResponse::err(404, "GiftcardSpawnOrg not found".to_string()).encode()
                 )
-            }
+            },
+            Err(GiftcardReservationError::AlreadyRedeemed) => {
+                return create_response(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::err(400, "GiftcardSpawnOrg already redeemed".to_string()).encode()
+                )
+            },
+            Err(GiftcardReservationError::InProgress) => {
+                return create_response(
+                    StatusCode::CONFLICT,
+                    ErrorResponse::err(409, "GiftcardSpawnOrg redemption already in progress".to_string()).encode()
+                )
+            },
+            Err(GiftcardReservationError::NeedsReview) => {
+                return create_response(
+                    StatusCode::CONFLICT,
+                    ErrorResponse::err(409, "GiftcardSpawnOrg needs owner review before it can be redeemed".to_string()).encode()
+                )
+            },
         };
     
-        // Check if giftcard is already redeemed
-        if giftcard.redeemed {
-            return create_response(
-                StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, "GiftcardSpawnOrg already redeemed".to_string()).encode()
-            );
-        }
-    
         // Generate a unique redeem code using timestamp
         let redeem_code = format!("REDEEM_{}", ic_cdk::api::time());
     
@@ -540,21 +568,55 @@ pub mod giftcards_handlers {
         let current_time = ic_cdk::api::time() / 1_000_000;
         let time_iso = format_iso8601(current_time);
         
-        // Deploy the canister using IC management canister
-        let deployed_canister = match deploy_drive_canister(
+        // Reuse the canister of an earlier attempt whose install failed, its cycles are already spent
+        let drive_canister_id = match giftcard.spawned_canister_id.clone() {
+            Some(canister_id) => {
+                if giftcard.spawned_owner_icp_principal.as_deref() != Some(redeem_request.owner_icp_principal.as_str()) {
+                    finish_giftcard_spawnorg_redemption(&giftcard_id, Err(format!("Install retry of canister {} must use the original owner principal", canister_id)));
+                    return create_response(
+                        StatusCode::BAD_REQUEST,
+                        ErrorResponse::err(400, "Install retry must use the original owner_icp_principal".to_string()).encode()
+                    )
+                }
+                canister_id
+            },
+            None => match create_drive_canister(
+                redeem_request.owner_icp_principal.clone(),
+                giftcard.gas_cycles_included,
+            ).await {
+                Ok(canister_id) => {
+                    // Persisted before the install await, so a trap or failure past here keeps the canister
+                    record_giftcard_spawnorg_canister(&giftcard_id, &canister_id, &redeem_request.owner_icp_principal);
+                    canister_id
+                },
+                Err(e) => {
+                    // Nothing was created, release the giftcard so it can be redeemed again
+                    finish_giftcard_spawnorg_redemption(&giftcard_id, Err(e.clone()));
+                    return create_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        ErrorResponse::err(500, format!("Failed to deploy canister: {}", e)).encode()
+                    )
+                }
+            },
+        };
+
+        // Install the drive wasm on the canister
+        let deployed_canister = match install_drive_canister(
+            drive_canister_id.clone(),
             redeem_request.owner_icp_principal.clone(),
             redeem_request.organization_name.clone(),
             redeem_request.owner_name.clone(),
             redeem_code.clone(),
             Some(format!("giftcard {} was redeemed to spawn drive with {} cycles, owned by {}, on timestamp_ms {} {}", 
                 giftcard_id.0, giftcard.gas_cycles_included, owner_id.0, current_time, time_iso)),
-            giftcard.gas_cycles_included,
         ).await {
             Ok(canister_id) => canister_id,
             Err(e) => {
+                // The card keeps its canister, redeeming it again only retries the install
+                finish_giftcard_spawnorg_redemption(&giftcard_id, Err(e.clone()));
                 return create_response(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    ErrorResponse::err(500, format!("Failed to deploy canister: {}", e)).encode()
+                    ErrorResponse::err(500, format!("Failed to install drive on canister {}, redeem again to retry: {}", drive_canister_id, e)).encode()
                 )
             }
         };
@@ -590,11 +652,7 @@ pub mod giftcards_handlers {
         };
     
         // Update giftcard as redeemed
-        GIFTCARD_SPAWNORG_BY_ID.with(|store| {
-            let mut giftcard = giftcard.clone();
-            giftcard.redeemed = true;
-            store.borrow_mut().insert(giftcard_id.clone(), giftcard);
-        });
+        finish_giftcard_spawnorg_redemption(&giftcard_id, Ok(()));
     
         // Store the deployment history
         crate::core::state::giftcards_spawnorg::state::state::DEPLOYMENTS_BY_GIFTCARD_SPAWNORG_ID.with(|records| {
@@ -634,20 +692,13 @@ pub mod giftcards_handlers {
     }
     
     
-    // Modified deploy_drive_canister function to use chunked code installation
-    async fn deploy_drive_canister(
-        owner_icp_principal: String, 
-        title: Option<String>, 
-        owner_name: Option<String>,
-        spawn_redeem_code: String,
-        note: Option<String>,
+    // Creates an empty drive canister funded with the giftcard cycles
+    async fn create_drive_canister(
+        owner_icp_principal: String,
         cycles: u64
     ) -> Result<String, String> {
-        use ic_cdk::api::management_canister::main::{
-            create_canister, install_chunked_code, CanisterInstallMode, CreateCanisterArgument, 
-            InstallChunkedCodeArgument, UploadChunkArgument,
-        };
-        use candid::{Encode, Principal};
+        use ic_cdk::api::management_canister::main::{create_canister, CreateCanisterArgument};
+        use candid::Principal;
 
         // Convert owner ID to Principal
         let owner_principal = match Principal::from_text(&owner_icp_principal) {
@@ -685,102 +736,121 @@ pub mod giftcards_handlers {
         // Create the canister
         match create_canister(create_canister_arg, cycles_to_use).await {
             Ok((canister_id_record,)) => {
-                let drive_canister_id = canister_id_record.canister_id;
+                debug_log!("canister id deployed: {}", canister_id_record.canister_id);
+                Ok(canister_id_record.canister_id.to_string())
+            },
+            Err(e) => {
+                debug_log!("Failed to create canister: {:?}", e);
+                Err(format!("Failed to create canister: {:?}", e))
+            }
+        }
+    }
 
-                debug_log!("canister id deployed: {}", drive_canister_id);
-                
-                // Read WASM module
-                const DRIVE_WASM: &[u8] = include_bytes!("../../../../../target/wasm32-unknown-unknown/release/canisters_official_backend.wasm");
-                
-                // Split WASM into chunks of 1MB each to stay safely under the 2MB limit
-                const CHUNK_SIZE: usize = 1_000_000; // 1MB
-                
-                debug_log!("WASM module size: {} bytes", DRIVE_WASM.len());
-                
-                // Prepare chunks
-                let mut chunks: Vec<Vec<u8>> = Vec::new();
-                let mut chunk_hashes = Vec::new();
-                
-                let mut offset = 0;
-                while offset < DRIVE_WASM.len() {
-                    let end = std::cmp::min(offset + CHUNK_SIZE, DRIVE_WASM.len());
-                    let chunk = DRIVE_WASM[offset..end].to_vec();
-                    
-                    // Upload chunk
-                    debug_log!("Uploading chunk {} of size {} bytes", chunks.len(), chunk.len());
-                    
-                    let upload_arg = UploadChunkArgument {
-                        canister_id: drive_canister_id,
-                        chunk: chunk.clone(),
-                    };
-                    
-                    match ic_cdk::api::management_canister::main::upload_chunk(upload_arg).await {
-                        Ok((chunk_hash,)) => {
-                            chunk_hashes.push(chunk_hash);
-                            chunks.push(chunk);
-                            debug_log!("Chunk uploaded successfully");
-                        },
-                        Err(e) => {
-                            debug_log!("Failed to upload chunk: {:?}", e);
-                            return Err(format!("Failed to upload WASM chunk: {:?}", e));
-                        }
-                    }
-                    
-                    offset = end;
+    // Installs the drive wasm on a created canister using chunked code installation
+    async fn install_drive_canister(
+        canister_id: String,
+        owner_icp_principal: String, 
+        title: Option<String>, 
+        owner_name: Option<String>,
+        spawn_redeem_code: String,
+        note: Option<String>,
+    ) -> Result<String, String> {
+        use ic_cdk::api::management_canister::main::{
+            install_chunked_code, CanisterInstallMode, InstallChunkedCodeArgument, UploadChunkArgument,
+        };
+        use candid::{Encode, Principal};
+
+        let drive_canister_id = match Principal::from_text(&canister_id) {
+            Ok(p) => p,
+            Err(_) => return Err(format!("Invalid drive canister id {}", canister_id)),
+        };
+
+        // Read WASM module
+        const DRIVE_WASM: &[u8] = include_bytes!("../../../../../target/wasm32-unknown-unknown/release/canisters_official_backend.wasm");
+        
+        // Split WASM into chunks of 1MB each to stay safely under the 2MB limit
+        const CHUNK_SIZE: usize = 1_000_000; // 1MB
+        
+        debug_log!("WASM module size: {} bytes", DRIVE_WASM.len());
+        
+        // Prepare chunks
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
+        let mut chunk_hashes = Vec::new();
+        
+        let mut offset = 0;
+        while offset < DRIVE_WASM.len() {
+            let end = std::cmp::min(offset + CHUNK_SIZE, DRIVE_WASM.len());
+            let chunk = DRIVE_WASM[offset..end].to_vec();
+            
+            // Upload chunk
+            debug_log!("Uploading chunk {} of size {} bytes", chunks.len(), chunk.len());
+            
+            let upload_arg = UploadChunkArgument {
+                canister_id: drive_canister_id,
+                chunk: chunk.clone(),
+            };
+            
+            match ic_cdk::api::management_canister::main::upload_chunk(upload_arg).await {
+                Ok((chunk_hash,)) => {
+                    chunk_hashes.push(chunk_hash);
+                    chunks.push(chunk);
+                    debug_log!("Chunk uploaded successfully");
+                },
+                Err(e) => {
+                    debug_log!("Failed to upload chunk: {:?}", e);
+                    return Err(format!("Failed to upload WASM chunk: {:?}", e));
                 }
-                
-                debug_log!("All chunks uploaded, total chunks: {}", chunks.len());
-                
-                // Calculate WASM module hash (SHA-256)
-                use sha2::{Sha256, Digest};
-                let mut hasher = Sha256::new();
-                hasher.update(DRIVE_WASM);
-                let wasm_module_hash = hasher.finalize().to_vec();
-                
-                // Create SpawnInitArgs for the canister
-                let init_args = SpawnInitArgs {
-                    owner: owner_icp_principal,
-                    title,
-                    owner_name,
-                    note,
-                    spawn_redeem_code: Some(spawn_redeem_code),
-                };
+            }
+            
+            offset = end;
+        }
+        
+        debug_log!("All chunks uploaded, total chunks: {}", chunks.len());
+        
+        // Calculate WASM module hash (SHA-256)
+        use sha2::{Sha256, Digest};
+        let mut hasher = Sha256::new();
+        hasher.update(DRIVE_WASM);
+        let wasm_module_hash = hasher.finalize().to_vec();
+        
+        // Create SpawnInitArgs for the canister
+        let init_args = SpawnInitArgs {
+            owner: owner_icp_principal,
+            title,
+            owner_name,
+            note,
+            spawn_redeem_code: Some(spawn_redeem_code),
+        };
 
-                // Encode initialization arguments
-                let arg = match Encode!(&Option::<SpawnInitArgs>::Some(init_args)) {
-                    Ok(a) => a,
-                    Err(e) => return Err(format!("Failed to encode init arguments: {:?}", e)),
-                };
-                
-                debug_log!("Encoded initialization arguments");
-
-                // Install chunked code arguments
-                let install_chunked_code_arg = InstallChunkedCodeArgument {
-                    mode: CanisterInstallMode::Install,
-                    target_canister: drive_canister_id,
-                    store_canister: None, // Use the target canister as the storage canister
-                    chunk_hashes_list: chunk_hashes,
-                    wasm_module_hash,
-                    arg,
-                };
+        // Encode initialization arguments
+        let arg = match Encode!(&Option::<SpawnInitArgs>::Some(init_args)) {
+            Ok(a) => a,
+            Err(e) => return Err(format!("Failed to encode init arguments: {:?}", e)),
+        };
+        
+        debug_log!("Encoded initialization arguments");
+
+        // Install chunked code arguments
+        let install_chunked_code_arg = InstallChunkedCodeArgument {
+            mode: CanisterInstallMode::Install,
+            target_canister: drive_canister_id,
+            store_canister: None, // Use the target canister as the storage canister
+            chunk_hashes_list: chunk_hashes,
+            wasm_module_hash,
+            arg,
+        };
 
-                debug_log!("Installing chunked code...");
+        debug_log!("Installing chunked code...");
 
-                // Install the chunked code
-                match install_chunked_code(install_chunked_code_arg).await {
-                    Ok(()) => {
-                        debug_log!("Code installed successfully");
-                        Ok(drive_canister_id.to_string())
-                    },
-                    Err(e) => {
-                        debug_log!("Failed to install chunked code: {:?}", e);
-                        Err(format!("Failed to install chunked code: {:?}", e))
-                    }
-                }
+        // Install the chunked code
+        match install_chunked_code(install_chunked_code_arg).await {
+            Ok(()) => {
+                debug_log!("Code installed successfully");
+                Ok(drive_canister_id.to_string())
             },
             Err(e) => {
-                debug_log!("Failed to create canister: {:?}", e);
-                Err(format!("Failed to create canister: {:?}", e))
+                debug_log!("Failed to install chunked code: {:?}", e);
+                Err(format!("Failed to install chunked code: {:?}", e))
             }
         }
     }
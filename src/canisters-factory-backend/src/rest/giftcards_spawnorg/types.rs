@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     core::{
         state::giftcards_spawnorg::types::{DriveID, DriveRESTUrlEndpoint, FactorySpawnHistoryRecord, GiftcardSpawnOrg, GiftcardSpawnOrgID}, 
-        types::{ICPPrincipalString, IDPrefix, UserID}
+        types::{GiftcardRedemptionStatus, ICPPrincipalString, IDPrefix, UserID}
     }, 
     rest::types::{
             validate_external_id, validate_external_payload, validate_icp_principal, validate_id_string, validate_user_id, ApiResponse, ValidationError
@@ -154,6 +154,9 @@ pub struct UpdateGiftcardSpawnOrgRequestBody {
     pub external_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disk_auth_json: Option<String>,
+    // Settles a NEEDS_REVIEW giftcard as AVAILABLE or REDEEMED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redemption_status: Option<GiftcardRedemptionStatus>,
 }
 impl UpdateGiftcardSpawnOrgRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
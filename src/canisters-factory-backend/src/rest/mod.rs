@@ -10,4 +10,6 @@ pub mod types;
 pub mod templates;  
 pub mod api_keys;
 pub mod giftcards_spawnorg;
-pub mod giftcards_refuel;
\ No newline at end of file
+pub mod giftcards_refuel;
+pub mod drive_upgrades;
+pub mod topups;
\ No newline at end of file
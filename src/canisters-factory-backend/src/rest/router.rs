@@ -32,6 +32,8 @@ pub fn init_routes() {
     crate::rest::api_keys::route::init_routes();
     crate::rest::giftcards_spawnorg::route::init_routes();
     crate::rest::giftcards_refuel::route::init_routes();
+    crate::rest::drive_upgrades::route::init_routes();
+    crate::rest::topups::route::init_routes();
 
     debug_log!("Initializing routes...");
 
@@ -0,0 +1,340 @@
+// src/rest/topups/handler.rs
+
+pub mod topups_handlers {
+    use crate::{
+        core::{
+            api::topups::run_drive_topup_sweep,
+            state::{
+                giftcards_refuel::{
+                    state::state::{finish_giftcard_refuel_redemption, reserve_giftcard_refuel, DEPLOYMENTS_BY_GIFTCARD_REFUEL_ID},
+                    types::{FactoryRefuelHistoryRecord, GiftcardRefuelID},
+                },
+                giftcards_spawnorg::{
+                    state::state::{DRIVE_TO_GIFTCARD_SPAWNORG_HASHTABLE, OWNER_ID},
+                    types::DriveID,
+                },
+                topups::{
+                    state::state::{credit_drive_topup, get_drive_topup_subscription, DRIVE_TOPUP_SUBSCRIPTIONS},
+                    types::DriveTopupSubscription,
+                },
+            },
+            types::{GiftcardReservationError, ICPPrincipalString, IDPrefix, PublicKeyICP},
+        },
+        debug_log,
+        rest::{
+            auth::{authenticate_request, create_auth_error_response},
+            topups::types::{
+                DeleteDriveTopupRequestBody, DeleteDriveTopupResponse, DeletedDriveTopupData, DriveTopupResponse, ErrorResponse, FundDriveTopupRequestBody, FundDriveTopupResponse, FundDriveTopupResult, ListDriveTopupsResponse, ListDriveTopupsResponseData, UpsertDriveTopupRequestBody
+            },
+        },
+    };
+    use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
+    use matchit::Params;
+
+    pub async fn upsert_drive_topup_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Only owner can manage top-up subscriptions
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let upsert_request = match serde_json::from_slice::<UpsertDriveTopupRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = upsert_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        // Only drives spawned by this factory, it needs to be their controller to read balances
+        let drive_id = DriveID(upsert_request.drive_id);
+        let registered = DRIVE_TO_GIFTCARD_SPAWNORG_HASHTABLE.with(|map| map.borrow().contains_key(&drive_id));
+        if !registered {
+            return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, format!("Drive {} was not spawned by this factory", drive_id)).encode()
+            );
+        }
+
+        // Updating keeps the prepaid balance and history
+        let subscription = match get_drive_topup_subscription(&drive_id) {
+            Some(existing) => DriveTopupSubscription {
+                min_cycles: upsert_request.min_cycles,
+                topup_cycles: upsert_request.topup_cycles,
+                active: upsert_request.active,
+                ..existing
+            },
+            None => DriveTopupSubscription {
+                canister_id: drive_id.0.trim_start_matches(IDPrefix::Drive.as_str()).to_string(),
+                drive_id: drive_id.clone(),
+                min_cycles: upsert_request.min_cycles,
+                topup_cycles: upsert_request.topup_cycles,
+                prepaid_cycles: 0,
+                active: upsert_request.active,
+                created_at_ms: ic_cdk::api::time() / 1_000_000,
+                last_checked_at_ms: 0,
+                last_balance: None,
+                last_topup_at_ms: None,
+                total_topped_up_cycles: 0,
+                last_error: None,
+            },
+        };
+
+        DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            store.borrow_mut().insert(drive_id, subscription.clone());
+        });
+
+        create_response(
+            StatusCode::OK,
+            DriveTopupResponse::ok(&subscription).encode()
+        )
+    }
+
+    pub async fn get_drive_topup_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let drive_id = DriveID(params.get("drive_id").unwrap().to_string());
+        match get_drive_topup_subscription(&drive_id) {
+            Some(subscription) => create_response(
+                StatusCode::OK,
+                DriveTopupResponse::ok(&subscription).encode()
+            ),
+            None => create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Top-up subscription not found".to_string()).encode()
+            ),
+        }
+    }
+
+    pub async fn list_drive_topups_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let mut items: Vec<DriveTopupSubscription> = DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            store.borrow().iter().map(|(_, subscription)| subscription).collect()
+        });
+        items.sort_by(|a, b| b.created_at_ms.cmp(&a.created_at_ms));
+
+        create_response(
+            StatusCode::OK,
+            ListDriveTopupsResponse::ok(&ListDriveTopupsResponseData {
+                total: items.len(),
+                items,
+            }).encode()
+        )
+    }
+
+    pub async fn delete_drive_topup_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        let delete_request = match serde_json::from_slice::<DeleteDriveTopupRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = delete_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let drive_id = DriveID(delete_request.drive_id);
+        let removed = DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| store.borrow_mut().remove(&drive_id));
+
+        create_response(
+            StatusCode::OK,
+            DeleteDriveTopupResponse::ok(&DeletedDriveTopupData {
+                drive_id,
+                deleted: removed.is_some(),
+                forfeited_prepaid_cycles: removed.map_or(0, |subscription| subscription.prepaid_cycles),
+            }).encode()
+        )
+    }
+
+    // Public like the refuel redeem route, the giftcard id is the bearer credential
+    pub async fn fund_drive_topup_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        let fund_request = match serde_json::from_slice::<FundDriveTopupRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = fund_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(
+                    400,
+                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
+                ).encode()
+            );
+        }
+
+        let drive_id = DriveID(fund_request.drive_id);
+        let subscription = match get_drive_topup_subscription(&drive_id) {
+            Some(subscription) => subscription,
+            None => return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Top-up subscription not found".to_string()).encode()
+            ),
+        };
+
+        let giftcard_id = GiftcardRefuelID(fund_request.giftcard_id);
+        let giftcard = match reserve_giftcard_refuel(&giftcard_id) {
+            Ok(v) => v,
+            Err(GiftcardReservationError::NotFound) => return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "GiftcardRefuel not found".to_string()).encode()
+            ),
+            Err(GiftcardReservationError::AlreadyRedeemed) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "GiftcardRefuel already redeemed".to_string()).encode()
+            ),
+            Err(GiftcardReservationError::InProgress) => return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "GiftcardRefuel redemption already in progress".to_string()).encode()
+            ),
+            Err(GiftcardReservationError::NeedsReview) => return create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, "GiftcardRefuel needs owner review before it can be redeemed".to_string()).encode()
+            ),
+        };
+
+        // No await from here on, the credit and the redemption land in the same message
+        let current_time = ic_cdk::api::time() / 1_000_000;
+        let subscription = match credit_drive_topup(&drive_id, giftcard.gas_cycles_included) {
+            Some(subscription) => subscription,
+            None => {
+                finish_giftcard_refuel_redemption(&giftcard_id, Err("Top-up subscription not found".to_string()));
+                return create_response(
+                    StatusCode::NOT_FOUND,
+                    ErrorResponse::err(404, "Top-up subscription not found".to_string()).encode()
+                );
+            }
+        };
+        finish_giftcard_refuel_redemption(&giftcard_id, Ok(()));
+
+        let history_record = FactoryRefuelHistoryRecord {
+            note: format!("Redeemed giftcard {} into the top-up balance of drive {}, credited {} cycles", giftcard_id, drive_id, giftcard.gas_cycles_included),
+            giftcard_id: giftcard_id.clone(),
+            gas_cycles_included: giftcard.gas_cycles_included,
+            timestamp_ms: current_time,
+            icp_principal: ICPPrincipalString(PublicKeyICP(subscription.canister_id.clone())),
+        };
+        DEPLOYMENTS_BY_GIFTCARD_REFUEL_ID.with(|records| {
+            records.borrow_mut().insert(giftcard_id.clone(), history_record);
+        });
+        debug_log!("Credited {} cycles to the top-up balance of drive {}", giftcard.gas_cycles_included, drive_id);
+
+        create_response(
+            StatusCode::OK,
+            FundDriveTopupResponse::ok(&FundDriveTopupResult {
+                drive_id,
+                giftcard_id: giftcard_id.0,
+                credited_cycles: giftcard.gas_cycles_included,
+                prepaid_cycles: subscription.prepaid_cycles,
+                timestamp_ms: current_time,
+            }).encode()
+        )
+    }
+
+    pub async fn sweep_drive_topups_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, _params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_auth_error_response();
+        }
+
+        // Runs the hourly sweep now, skipped if one is already in flight
+        run_drive_topup_sweep().await;
+
+        let mut items: Vec<DriveTopupSubscription> = DRIVE_TOPUP_SUBSCRIPTIONS.with(|store| {
+            store.borrow().iter().map(|(_, subscription)| subscription).collect()
+        });
+        items.sort_by(|a, b| b.created_at_ms.cmp(&a.created_at_ms));
+
+        create_response(
+            StatusCode::OK,
+            ListDriveTopupsResponse::ok(&ListDriveTopupsResponseData {
+                total: items.len(),
+                items,
+            }).encode()
+        )
+    }
+
+    fn create_response(status_code: StatusCode, body: Vec<u8>) -> HttpResponse<'static> {
+        HttpResponse::builder()
+            .with_status_code(status_code)
+            .with_headers(vec![
+                ("content-type".to_string(), "application/json".to_string()),
+                (
+                    "strict-transport-security".to_string(),
+                    "max-age=31536000; includeSubDomains".to_string(),
+                ),
+                ("x-content-type-options".to_string(), "nosniff".to_string()),
+                ("referrer-policy".to_string(), "no-referrer".to_string()),
+                (
+                    "cache-control".to_string(),
+                    "no-store, max-age=0".to_string(),
+                ),
+                ("pragma".to_string(), "no-cache".to_string()),
+            ])
+            .with_body(body)
+            .build()
+    }
+}
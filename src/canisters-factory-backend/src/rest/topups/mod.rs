@@ -0,0 +1,4 @@
+// src/rest/topups/mod.rs
+pub mod route;
+pub mod handler;
+pub mod types;
\ No newline at end of file
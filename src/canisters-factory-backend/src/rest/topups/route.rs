@@ -0,0 +1,56 @@
+// src/rest/topups/route.rs
+use crate::debug_log;
+use crate::rest::router::{self, genroute};
+use crate::rest::types::RouteHandler;
+
+
+// ROUTE_PREFIX
+pub const DRIVE_TOPUP_UPSERT_PATH: &str =            genroute!("/topups/upsert");
+pub const DRIVE_TOPUP_GET_PATH: &str =               genroute!("/topups/get/{drive_id}");
+pub const DRIVE_TOPUP_LIST_PATH: &str =              genroute!("/topups/list");
+pub const DRIVE_TOPUP_DELETE_PATH: &str =            genroute!("/topups/delete");
+pub const DRIVE_TOPUP_FUND_PATH: &str =              genroute!("/topups/fund");
+pub const DRIVE_TOPUP_SWEEP_PATH: &str =             genroute!("/topups/sweep");
+
+type HandlerEntry = (&'static str, &'static str, RouteHandler);
+
+pub fn init_routes() {
+    let routes: &[HandlerEntry] = &[
+        (
+            "POST",
+            DRIVE_TOPUP_UPSERT_PATH,
+            |req, params| Box::pin(crate::rest::topups::handler::topups_handlers::upsert_drive_topup_handler(req, params)),
+        ),
+        (
+            "GET",
+            DRIVE_TOPUP_GET_PATH,
+            |req, params| Box::pin(crate::rest::topups::handler::topups_handlers::get_drive_topup_handler(req, params)),
+        ),
+        (
+            "GET",
+            DRIVE_TOPUP_LIST_PATH,
+            |req, params| Box::pin(crate::rest::topups::handler::topups_handlers::list_drive_topups_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_TOPUP_DELETE_PATH,
+            |req, params| Box::pin(crate::rest::topups::handler::topups_handlers::delete_drive_topup_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_TOPUP_FUND_PATH,
+            |req, params| Box::pin(crate::rest::topups::handler::topups_handlers::fund_drive_topup_handler(req, params)),
+        ),
+        (
+            "POST",
+            DRIVE_TOPUP_SWEEP_PATH,
+            |req, params| Box::pin(crate::rest::topups::handler::topups_handlers::sweep_drive_topups_handler(req, params)),
+        ),
+    ];
+
+    for &(method, path, handler) in routes {
+        debug_log!("Registering {} route: {}", method, path);
+        router::insert_route(method, path, handler);
+    }
+
+}
@@ -0,0 +1,125 @@
+// src/rest/topups/types.rs
+
+use serde::{Deserialize, Serialize};
+use crate::{
+    core::{
+        state::{giftcards_spawnorg::types::DriveID, topups::types::DriveTopupSubscription},
+        types::IDPrefix,
+    },
+    rest::types::{validate_id_string, ApiResponse, ValidationError},
+};
+
+
+fn validate_drive_id(drive_id: &str) -> Result<(), ValidationError> {
+    validate_id_string(drive_id, "drive_id")?;
+    if !drive_id.starts_with(IDPrefix::Drive.as_str()) {
+        return Err(ValidationError {
+            field: "drive_id".to_string(),
+            message: format!("Drive ID must start with '{}'", IDPrefix::Drive.as_str()),
+        });
+    }
+    Ok(())
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpsertDriveTopupRequestBody {
+    pub drive_id: String,
+    pub min_cycles: u64,
+    pub topup_cycles: u64,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl UpsertDriveTopupRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_drive_id(&self.drive_id)?;
+
+        if self.min_cycles == 0 {
+            return Err(ValidationError {
+                field: "min_cycles".to_string(),
+                message: "Minimum cycles must be greater than 0".to_string(),
+            });
+        }
+
+        if self.topup_cycles == 0 {
+            return Err(ValidationError {
+                field: "topup_cycles".to_string(),
+                message: "Top-up cycles must be greater than 0".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeleteDriveTopupRequestBody {
+    pub drive_id: String,
+}
+impl DeleteDriveTopupRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_drive_id(&self.drive_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletedDriveTopupData {
+    pub drive_id: DriveID,
+    pub deleted: bool,
+    // Prepaid cycles stay with the factory once the subscription is gone
+    pub forfeited_prepaid_cycles: u64,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FundDriveTopupRequestBody {
+    pub drive_id: String,
+    pub giftcard_id: String,
+}
+impl FundDriveTopupRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_drive_id(&self.drive_id)?;
+
+        validate_id_string(&self.giftcard_id, "giftcard_id")?;
+        if !self.giftcard_id.starts_with(IDPrefix::GiftcardRefuel.as_str()) {
+            return Err(ValidationError {
+                field: "giftcard_id".to_string(),
+                message: format!("Giftcard ID must start with '{}'", IDPrefix::GiftcardRefuel.as_str()),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FundDriveTopupResult {
+    pub drive_id: DriveID,
+    pub giftcard_id: String,
+    pub credited_cycles: u64,
+    pub prepaid_cycles: u64,
+    pub timestamp_ms: u64,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListDriveTopupsResponseData {
+    pub items: Vec<DriveTopupSubscription>,
+    pub total: usize,
+}
+
+
+pub type DriveTopupResponse<'a> = ApiResponse<'a, DriveTopupSubscription>;
+pub type DeleteDriveTopupResponse<'a> = ApiResponse<'a, DeletedDriveTopupData>;
+pub type FundDriveTopupResponse<'a> = ApiResponse<'a, FundDriveTopupResult>;
+pub type ListDriveTopupsResponse<'a> = ApiResponse<'a, ListDriveTopupsResponseData>;
+pub type ErrorResponse<'a> = ApiResponse<'a, ()>;
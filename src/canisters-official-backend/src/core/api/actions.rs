@@ -1,20 +1,58 @@
 // src/core/api/actions.rs
 use std::result::Result;
-use crate::{core::{state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::{DriveFullFilePath, FileID, FolderID, PathTranslationResponse, ShareTrackID, ShareTrackResourceID}}, drives::{state::state::{update_external_id_mapping, DRIVE_ID, OWNER_ID, URL_ENDPOINT}, types::{ExternalID, ExternalPayload}}, permissions::types::{DirectoryPermissionType, PermissionGranteeID}, webhooks::types::{WebhookAltIndexID, WebhookEventLabel}}, types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{directory::types::{CreateFileResponse, CreateFolderResponse, DeleteFileResponse, DeleteFolderResponse, DirectoryAction, DirectoryActionEnum, DirectoryActionPayload, DirectoryActionResult, DirectoryResourceID, GetFileResponse, GetFolderResponse, UpdateFileResponse}, webhooks::types::{DirectoryWebhookData, FileWebhookData, FolderWebhookData, ShareTrackingWebhookData}}};
-use super::{drive::drive::{copy_file, copy_folder, create_file, create_folder, delete_file, delete_folder, get_file_by_id, get_folder_by_id, move_file, move_folder, rename_file, rename_folder, restore_from_trash}, internals::drive_internals::{get_destination_folder, translate_path_to_id}, permissions::{self, directory::{check_directory_permissions, derive_directory_breadcrumbs, preview_directory_permissions}}, uuid::{decode_share_track_hash, generate_share_track_hash, ShareTrackHash}, webhooks::directory::{fire_directory_webhook, get_active_file_webhooks, get_active_folder_webhooks}};
+use crate::rest::types::ErrorCode;
+use crate::{core::{state::{directory::{state::state::{DirectoryJournal, file_uuid_to_metadata, file_version_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid}, types::{DriveFullFilePath, FileID, FolderID, PathTranslationResponse, ShareTrackID, ShareTrackResourceID}}, drives::{state::state::{update_external_id_mapping, DRIVE_ID, OWNER_ID, URL_ENDPOINT}, types::{ExternalID, ExternalPayload}}, permissions::types::{DirectoryPermissionType, PermissionGranteeID}, search::{state::state::index_search_resource, types::SearchResultResourceID}, webhooks::types::{WebhookAltIndexID, WebhookEventLabel}}, types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{directory::types::{CreateFileResponse, CreateFolderResponse, DeleteFileResponse, DeleteFolderResponse, DirectoryAction, DirectoryActionEnum, DirectoryActionPayload, DirectoryActionResult, DirectoryResourceID, GetFileResponse, GetFileVersionResponse, GetFolderResponse, ListFileVersionsResponse, RestoreFileVersionResponse, UpdateFileResponse}, webhooks::types::{DirectoryWebhookData, FileWebhookData, FolderWebhookData, ShareTrackingWebhookData}}};
+use super::{drive::drive::{copy_file, copy_folder, create_file, create_folder, delete_file, delete_folder, get_file_by_id, get_folder_by_id, move_file, move_folder, rename_file, rename_folder, restore_from_trash}, internals::drive_internals::{get_destination_folder, sanitize_file_path, translate_path_to_id}, permissions::{self, directory::{check_directory_permissions, derive_directory_breadcrumbs, preview_directory_permissions}}, uuid::{decode_share_track_hash, generate_share_track_hash, ShareTrackHash}, versions::versions::{apply_version_retention, archive_version_content, get_version_content_url, is_current_version, is_restoring, list_file_versions, restore_file_version}, webhooks::{directory::{fire_directory_webhook, fire_file_viewed_webhooks, get_active_file_webhooks, get_active_folder_webhooks}, filters::WebhookFilterContext}};
 
 
 #[derive(Debug, Clone)]
 pub struct DirectoryActionErrorInfo {
-    pub code: i32,
+    pub code: ErrorCode,
     pub message: String,
 }
 
-pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<DirectoryActionResult, DirectoryActionErrorInfo> {
+/// Runs one directory action under its own undo journal so a failure midway leaves no partial mutations behind.
+/// On success the journal commits, or is handed to `batch` when the action is part of an atomic batch.
+pub async fn pipe_action(action: DirectoryAction, user_id: UserID, batch: Option<&mut DirectoryJournal>) -> Result<DirectoryActionResult, DirectoryActionErrorInfo> {
+    let mut journal = DirectoryJournal::new();
+    let result = journal.record(apply_action(action, user_id)).await;
+    match &result {
+        Ok(_) => match batch {
+            Some(batch) => batch.absorb(journal),
+            None => journal.commit(),
+        },
+        Err(error_info) => {
+            debug_log!("Directory action failed with {:?}, rolling back: {}", error_info.code, error_info.message);
+            let conflicts = rollback_directory_action(journal);
+            if conflicts > 0 {
+                return Err(DirectoryActionErrorInfo {
+                    code: error_info.code,
+                    message: format!("{} ({} rolled back entries were changed by another request and kept)", error_info.message, conflicts),
+                });
+            }
+        },
+    }
+    result
+}
+
+/// Restores everything the journal recorded and refreshes the search documents of touched folders and files.
+/// Returns how many entries were left alone because another request changed them meanwhile.
+pub fn rollback_directory_action(journal: DirectoryJournal) -> usize {
+    let rollback = journal.rollback();
+    for folder_id in rollback.touched_folders {
+        index_search_resource(SearchResultResourceID::Folder(folder_id));
+    }
+    for file_id in rollback.touched_files {
+        index_search_resource(SearchResultResourceID::File(file_id));
+    }
+    rollback.conflicts
+}
+
+async fn apply_action(action: DirectoryAction, user_id: UserID) -> Result<DirectoryActionResult, DirectoryActionErrorInfo> {
     
     if let Err(validation_error) = action.validate_body() {
         return Err(DirectoryActionErrorInfo {
-            code: 400,
+            code: ErrorCode::ValidationFailed,
             message: format!("Validation error: {} - {}", validation_error.field, validation_error.message),
         });
     }
@@ -27,7 +65,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -35,15 +73,11 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // First try to get file_id either from resource_id or resource_path
                     let file_id = payload.id;
 
-                    // Get webhooks for both event types and combine them
-                    let webhooks_file = get_active_file_webhooks(&file_id, WebhookEventLabel::FileViewed);
-                    let webhooks_subfile = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileViewed);
-                  
                     // Get file metadata to use for permission check
                     let file = match get_file_by_id(file_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("File not found: {}", e),
                         }),
                     };
@@ -60,75 +94,14 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // User needs at least View permission to get file details
                     if !is_owner && !user_permissions.contains(&DirectoryPermissionType::View) {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to view this file".to_string(), 
                         });
                     }
 
                     // let your_permissions = preview_directory_permissions(&resource_id, &user_id);
 
-                    let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
-                        file: Some(file.clone()),
-                    }); 
-
-                    fire_directory_webhook(
-                        WebhookEventLabel::FileViewed,
-                        webhooks_file,
-                        Some(before_snap_file.clone()),
-                        Some(before_snap_file.clone()),
-                        Some("File viewed".to_string()),
-                    );
-                    fire_directory_webhook(
-                        WebhookEventLabel::SubfileViewed,
-                        webhooks_subfile,
-                        Some(before_snap_file.clone()),
-                        Some(before_snap_file),
-                        Some("Subfile viewed".to_string()),
-                    );
-
-
-                    let mut share_tracking_origin_id = ShareTrackID(String::new());
-                    let mut share_tracking_origin_user = UserID(String::new());
-                    if let Some(share_track_hash) = &payload.share_track_hash {
-                        if !share_track_hash.is_empty() {
-                            let share_track_hash = ShareTrackHash(share_track_hash.clone());
-                            let (share_track_id, from_user_id) = decode_share_track_hash(&share_track_hash);
-                            share_tracking_origin_id = share_track_id;
-                            share_tracking_origin_user = from_user_id;
-                        }
-                    }
-                    // generate_share_track_hash 
-                    let (my_share_track_id, my_share_track_hash) = generate_share_track_hash(&user_id);
-                    let webhooks_file_shared = get_active_file_webhooks(&file_id, WebhookEventLabel::FileShared);
-                    let webhooks_subfile_shared = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileShared);
-                    let share_tracking_payload = ShareTrackingWebhookData {
-                        id: my_share_track_id.clone(),
-                        hash: my_share_track_hash.clone(),
-                        origin_id: Some(share_tracking_origin_id),
-                        origin_hash: Some(ShareTrackHash(payload.share_track_hash.unwrap_or(String::new()))),
-                        from_user: Some(share_tracking_origin_user.clone()),
-                        to_user: Some(user_id.clone()),
-                        resource_id: ShareTrackResourceID::File(file_id.clone()),
-                        resource_name: file.name.clone(),
-                        drive_id: DRIVE_ID.with(|id| id.clone()),
-                        timestamp_ms: ic_cdk::api::time() / 1_000_000,
-                        host_url: URL_ENDPOINT.with(|url| url.borrow().get().clone()),
-                        metadata: None
-                    };
-                    fire_directory_webhook(
-                        WebhookEventLabel::FileShared,
-                        webhooks_file_shared,
-                        None,
-                        Some(DirectoryWebhookData::ShareTracking(share_tracking_payload.clone())),
-                        Some("Tracked file share".to_string()),
-                    );
-                    fire_directory_webhook(
-                        WebhookEventLabel::SubfileShared,
-                        webhooks_subfile_shared,
-                        None,
-                        Some(DirectoryWebhookData::ShareTracking(share_tracking_payload)),
-                        Some("Tracked subfile share".to_string()),
-                    );
+                    fire_file_viewed_webhooks(&file, Some(&user_id), payload.share_track_hash.clone());
 
                     let breadcrumbs = derive_directory_breadcrumbs(
                         resource_id,
@@ -142,7 +115,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     }))
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for GET_FILE action".to_string(),
                 }),
             }
@@ -155,7 +128,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -167,14 +140,15 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let folder = match get_folder_by_id(folder_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Folder not found: {}", e),
                         }),
                     };
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_folder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderViewed);
-                    let webhooks_subfolder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderViewed);
+                    let filter_context = WebhookFilterContext::for_folder(&folder_id, &user_id);
+                    let webhooks_folder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderViewed, &filter_context);
+                    let webhooks_subfolder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderViewed, &filter_context);
 
                     // Check if user has View permission on the folder
                     let resource_id = DirectoryResourceID::Folder(folder_id.clone());
@@ -187,7 +161,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
         
                     if !is_owner && !user_permissions.contains(&DirectoryPermissionType::View) {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to view this folder".to_string(),
                         });
                     }
@@ -227,8 +201,9 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     }
                     // generate_share_track_hash 
                     let (my_share_track_id, my_share_track_hash) = generate_share_track_hash(&user_id);
-                    let webhooks_folder_shared = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderShared);
-                    let webhooks_subfolder_shared = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderShared);
+                    let filter_context = WebhookFilterContext::for_folder(&folder_id, &user_id);
+                    let webhooks_folder_shared = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderShared, &filter_context);
+                    let webhooks_subfolder_shared = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderShared, &filter_context);
                     let share_tracking_payload = ShareTrackingWebhookData {
                         id: my_share_track_id.clone(),
                         hash: my_share_track_hash.clone(),
@@ -271,7 +246,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     }))
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for GET_FOLDER action".to_string(),
                 }),
             }
@@ -284,7 +259,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -293,8 +268,9 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let parent_folder_id = payload.parent_folder_uuid;
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_file = get_active_file_webhooks(&FileID(WebhookAltIndexID::file_created_slug().to_string()), WebhookEventLabel::FileCreated);
-                    let webhooks_subfile = get_active_folder_webhooks(&parent_folder_id, WebhookEventLabel::SubfileCreated);
+                    let filter_context = WebhookFilterContext::for_new_child(&parent_folder_id, &payload.name, true, &user_id);
+                    let webhooks_file = get_active_file_webhooks(&FileID(WebhookAltIndexID::file_created_slug().to_string()), WebhookEventLabel::FileCreated, &filter_context);
+                    let webhooks_subfile = get_active_folder_webhooks(&parent_folder_id, WebhookEventLabel::SubfileCreated, &filter_context);
 
                     // Check if user has Upload, Edit, or Manage permission on the parent folder
                     let parent_resource_id = DirectoryResourceID::Folder(parent_folder_id.clone());
@@ -320,7 +296,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                        !user_permissions.contains(&DirectoryPermissionType::Edit) &&
                        !user_permissions.contains(&DirectoryPermissionType::Manage) && !has_edit_permission {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to create files in this folder".to_string(),
                         });
                     }
@@ -329,7 +305,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let parent_folder = match get_folder_by_id(parent_folder_id.clone()) {
                         Ok(folder) => folder,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Parent folder not found: {}", e),
                         }),
                     };
@@ -337,6 +313,18 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // Construct the full file path
                     let full_directory_path = format!("{}{}", parent_folder.full_directory_path.0, payload.name);
 
+                    // Whatever currently sits at this path, in case it gets replaced by a new version
+                    let replaced_file = full_file_path_to_uuid
+                        .get(&DriveFullFilePath(sanitize_file_path(&full_directory_path)))
+                        .and_then(|existing_id| file_uuid_to_metadata.get(&existing_id));
+                    if let Some(replaced_file) = &replaced_file {
+                        if is_restoring(&replaced_file.id) {
+                            return Err(DirectoryActionErrorInfo {
+                                code: ErrorCode::Conflict,
+                                message: "File is being restored to an older version, try again".to_string(),
+                            });
+                        }
+                    }
         
                     // Create file using the drive API
                     match create_file(
@@ -357,6 +345,23 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     ) {
                         Ok((file_metadata, upload_response)) => {
 
+                            // A new version went on top of the old file, archive the old bytes
+                            // before the client can upload over them
+                            if let Some(replaced_file) = replaced_file {
+                                if file_metadata.prior_version.as_ref() == Some(&replaced_file.version_id) {
+                                    // Returning the error rolls the new version back, so the client
+                                    // never gets to upload over bytes that were not archived
+                                    if let Err(e) = archive_version_content(&replaced_file).await {
+                                        debug_log!("Failed to archive version {}: {}", replaced_file.version_id, e);
+                                        return Err(DirectoryActionErrorInfo {
+                                            code: ErrorCode::InternalError,
+                                            message: format!("Failed to archive the current version: {}", e),
+                                        });
+                                    }
+                                    apply_version_retention(&file_metadata.id);
+                                }
+                            }
+
                             let after_snap_file = DirectoryWebhookData::File(FileWebhookData {
                                 file: Some(file_metadata.clone()),
                             });
@@ -383,13 +388,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             }))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: e.code,
                             message: format!("Failed to create file: {}", e),
                         })
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for CREATE_FILE action".to_string(),
                 })
             }
@@ -402,7 +407,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -413,8 +418,9 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let parent_folder_id = payload.parent_folder_uuid;
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_folder = get_active_folder_webhooks(&FolderID(WebhookAltIndexID::folder_created_slug().to_string()), WebhookEventLabel::FolderCreated);
-                    let webhooks_subfolder = get_active_folder_webhooks(&parent_folder_id, WebhookEventLabel::SubfolderCreated);
+                    let filter_context = WebhookFilterContext::for_new_child(&parent_folder_id, &payload.name, false, &user_id);
+                    let webhooks_folder = get_active_folder_webhooks(&FolderID(WebhookAltIndexID::folder_created_slug().to_string()), WebhookEventLabel::FolderCreated, &filter_context);
+                    let webhooks_subfolder = get_active_folder_webhooks(&parent_folder_id, WebhookEventLabel::SubfolderCreated, &filter_context);
 
         
                     // Check if user has Upload, Edit, or Manage permission on the parent folder
@@ -429,7 +435,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                        !user_permissions.contains(&DirectoryPermissionType::Edit) &&
                        !user_permissions.contains(&DirectoryPermissionType::Manage) {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to create folders here".to_string(),
                         });
                     }
@@ -438,7 +444,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let parent_folder = match get_folder_by_id(parent_folder_id.clone()) {
                         Ok(folder) => folder,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Parent folder not found: {}", e),
                         }),
                     };
@@ -486,20 +492,14 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                                 folder: folder.cast_fe(&user_id).await,
                             }))
                         },
-                        Err(e) => match e.as_str() {
-                            "Folder already exists" => Err(DirectoryActionErrorInfo {
-                                code: 409,
-                                message: "A folder with this name already exists".to_string(),
-                            }),
-                            _ => Err(DirectoryActionErrorInfo {
-                                code: 500,
-                                message: format!("Failed to create folder: {}", e),
-                            })
-                        }
+                        Err(e) => Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::InternalError,
+                            message: format!("Failed to create folder: {}", e),
+                        })
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for CREATE_FOLDER action".to_string(),
                 })
             }
@@ -512,7 +512,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -524,14 +524,15 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let file = match get_file_by_id(file_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("File not found: {}", e),
                         }),
                     };
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_file = get_active_file_webhooks(&file_id, WebhookEventLabel::FileUpdated);
-                    let webhooks_subfile = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileUpdated);
+                    let filter_context = WebhookFilterContext::for_file(&file_id, &user_id);
+                    let webhooks_file = get_active_file_webhooks(&file_id, WebhookEventLabel::FileUpdated, &filter_context);
+                    let webhooks_subfile = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileUpdated, &filter_context);
         
                     let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
                         file: Some(file.clone()),
@@ -560,7 +561,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                     if !is_owner && !is_creator_with_upload && !has_edit_permission {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to edit this file".to_string(),
                         });
                     }
@@ -572,7 +573,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             match rename_file(file_id.clone(), new_name) {
                                 Ok(_) => (),
                                 Err(e) => return Err(DirectoryActionErrorInfo {
-                                    code: 500,
+                                    code: e.code,
                                     message: format!("Failed to rename file: {}", e),
                                 })
                             }
@@ -649,13 +650,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             }))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to get updated file metadata: {}", e),
                         })
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for UPDATE_FILE action".to_string(),
                 })
             }
@@ -668,7 +669,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -680,14 +681,15 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let folder = match get_folder_by_id(folder_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Folder not found: {}", e),
                         }),
                     };
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_folder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderUpdated);
-                    let webhooks_subfolder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderUpdated);
+                    let filter_context = WebhookFilterContext::for_folder(&folder_id, &user_id);
+                    let webhooks_folder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderUpdated, &filter_context);
+                    let webhooks_subfolder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderUpdated, &filter_context);
                     let before_snap_folder = DirectoryWebhookData::Folder(FolderWebhookData {
                         folder: Some(folder.clone()),
                     });    
@@ -697,7 +699,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                         DirectoryResourceID::Folder(parent_id)
                     } else {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "Cannot edit root folder".to_string(),
                         });
                     };
@@ -722,7 +724,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                     if !is_owner && !is_creator_with_upload && !has_edit_permission {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to edit this folder".to_string(),
                         });
                     }
@@ -733,7 +735,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             match rename_folder(folder_id.clone(), new_name) {
                                 Ok(_) => (),
                                 Err(e) => return Err(DirectoryActionErrorInfo {
-                                    code: 500,
+                                    code: e.code,
                                     message: format!("Failed to rename folder: {}", e),
                                 })
                             }
@@ -800,13 +802,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             Ok(DirectoryActionResult::UpdateFolder(updated_folder.cast_fe(&user_id).await))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to get updated folder metadata: {}", e),
                         })
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for UPDATE_FOLDER action".to_string(),
                 })
             }
@@ -819,7 +821,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -831,14 +833,15 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let file = match get_file_by_id(file_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("File not found: {}", e),
                         }),
                     };
                     
                     // Get webhooks for both event types and combine them
-                    let webhooks_file = get_active_file_webhooks(&file_id, WebhookEventLabel::FileDeleted);
-                    let webhooks_subfile = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileDeleted);
+                    let filter_context = WebhookFilterContext::for_file(&file_id, &user_id);
+                    let webhooks_file = get_active_file_webhooks(&file_id, WebhookEventLabel::FileDeleted, &filter_context);
+                    let webhooks_subfile = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileDeleted, &filter_context);
         
                     let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
                         file: Some(file.clone()),
@@ -867,7 +870,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                     if !is_owner && !is_creator_with_upload && !has_delete_permission {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to delete this file".to_string(),
                         });
                     }
@@ -896,13 +899,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                         )
                     },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to delete file: {}", e),
                         })
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for DELETE_FILE action".to_string(),
                 })
             }
@@ -915,7 +918,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -927,14 +930,15 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let folder = match get_folder_by_id(folder_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Folder not found: {}", e),
                         }),
                     };
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_folder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderDeleted);
-                    let webhooks_subfolder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderDeleted);
+                    let filter_context = WebhookFilterContext::for_folder(&folder_id, &user_id);
+                    let webhooks_folder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::FolderDeleted, &filter_context);
+                    let webhooks_subfolder = get_active_folder_webhooks(&folder_id, WebhookEventLabel::SubfolderDeleted, &filter_context);
                     let before_snap_folder = DirectoryWebhookData::Folder(FolderWebhookData {
                         folder: Some(folder.clone()),
                     });    
@@ -945,7 +949,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                         DirectoryResourceID::Folder(parent_id)
                     } else {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "Cannot delete root folder".to_string(),
                         });
                     };
@@ -969,7 +973,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                     if !is_owner && !is_creator_with_upload && !has_delete_permission {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to delete this folder".to_string(),
                         });
                     }
@@ -1005,13 +1009,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             }))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to delete folder: {}", e),
                         })
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for DELETE_FOLDER action".to_string(),
                 })
             }
@@ -1024,7 +1028,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -1037,13 +1041,14 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let source_file = match get_file_by_id(file_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Source file not found: {}", e),
                         }),
                     };
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_file = get_active_file_webhooks(&FileID(WebhookAltIndexID::file_created_slug().to_string()), WebhookEventLabel::FileCreated);
+                    let filter_context = WebhookFilterContext::for_file(&file_id, &user_id);
+                    let webhooks_file = get_active_file_webhooks(&FileID(WebhookAltIndexID::file_created_slug().to_string()), WebhookEventLabel::FileCreated, &filter_context);
                     let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
                         file: Some(source_file.clone()),
                     });
@@ -1058,7 +1063,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
         
                     if !user_permissions.contains(&DirectoryPermissionType::View) && !is_owner {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to view this file".to_string(),
                         });
                     }
@@ -1075,11 +1080,11 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     ) {
                         Ok(folder) => folder,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Destination folder not found: {}", e),
                         }),
                     };
-                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfileCreated);
+                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfileCreated, &filter_context);
         
                     // Check if user has Upload/Edit/Manage permission on destination folder
                     let dest_resource_id = DirectoryResourceID::Folder(destination_folder.id.clone());
@@ -1093,7 +1098,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                        !dest_permissions.contains(&DirectoryPermissionType::Edit) &&
                        !dest_permissions.contains(&DirectoryPermissionType::Manage) {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to copy files to this folder".to_string(),
                         });
                     }
@@ -1121,13 +1126,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             Ok(DirectoryActionResult::CopyFile(file.cast_fe(&user_id).await))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to copy file: {}", e),
                         }),
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for COPY_FILE action".to_string(),
                 }),
             }
@@ -1140,7 +1145,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -1152,13 +1157,14 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let source_folder = match get_folder_by_id(folder_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Source folder not found: {}", e),
                         }),
                     };
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_folder = get_active_folder_webhooks(&&FolderID(WebhookAltIndexID::folder_created_slug().to_string()), WebhookEventLabel::FolderCreated);
+                    let filter_context = WebhookFilterContext::for_folder(&folder_id, &user_id);
+                    let webhooks_folder = get_active_folder_webhooks(&&FolderID(WebhookAltIndexID::folder_created_slug().to_string()), WebhookEventLabel::FolderCreated, &filter_context);
                     let before_snap_folder = DirectoryWebhookData::Folder(FolderWebhookData {
                         folder: Some(source_folder.clone()),
                     });
@@ -1173,7 +1179,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
         
                     if !user_permissions.contains(&DirectoryPermissionType::View) && !is_owner {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to view this folder".to_string(),
                         });
                     }
@@ -1189,12 +1195,12 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     ) {
                         Ok(folder) => folder,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Destination folder not found: {}", e),
                         }),
                     };
         
-                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfolderCreated);
+                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfolderCreated, &filter_context);
         
                     // Check if user has Upload/Edit/Manage permission on destination folder
                     let dest_resource_id = DirectoryResourceID::Folder(destination_folder.id.clone());
@@ -1207,7 +1213,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                        !dest_permissions.contains(&DirectoryPermissionType::Edit) &&
                        !dest_permissions.contains(&DirectoryPermissionType::Manage) {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to copy folders to this location".to_string(),
                         });
                     }
@@ -1235,13 +1241,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             Ok(DirectoryActionResult::CopyFolder(folder.cast_fe(&user_id).await))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to copy folder: {}", e),
                         }),
                     }
                 },
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for COPY_FOLDER action".to_string(),
                 }),
             }
@@ -1254,7 +1260,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -1267,14 +1273,15 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let file = match get_file_by_id(file_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("File not found: {}", e),
                         }),
                     };
 
 
                     // Get webhooks for both event types and combine them
-                    let webhooks_file = get_active_file_webhooks(&FileID(WebhookAltIndexID::file_created_slug().to_string()), WebhookEventLabel::FileCreated);
+                    let filter_context = WebhookFilterContext::for_file(&file_id, &user_id);
+                    let webhooks_file = get_active_file_webhooks(&FileID(WebhookAltIndexID::file_created_slug().to_string()), WebhookEventLabel::FileCreated, &filter_context);
                     
                     let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
                         file: Some(file.clone()),
@@ -1299,7 +1306,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                     if !is_owner && !is_creator_with_upload && !has_move_permission {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to move this file from its current location".to_string(),
                         });
                     }
@@ -1315,11 +1322,11 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     ) {
                         Ok(folder) => folder,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Destination folder not found: {}", e),
                         }),
                     };
-                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfileCreated);
+                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfileCreated, &filter_context);
         
         
                     // Check destination folder permissions
@@ -1333,7 +1340,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                        !dest_permissions.contains(&DirectoryPermissionType::Edit) &&
                        !dest_permissions.contains(&DirectoryPermissionType::Manage) && !is_owner {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to move files to the destination folder".to_string(),
                         });
                     }
@@ -1360,13 +1367,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             Ok(DirectoryActionResult::MoveFile(file.cast_fe(&user_id).await))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to move file: {}", e),
                         }),
                     }
                 }
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for MOVE_FILE action".to_string(),
                 })
             }
@@ -1379,7 +1386,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -1392,12 +1399,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     let folder = match get_folder_by_id(folder_id.clone()) {
                         Ok(f) => f,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Folder not found: {}", e),
                         }),
                     };
                     // Get webhooks for both event types and combine them
-                    let webhooks_folder = get_active_folder_webhooks(&&FolderID(WebhookAltIndexID::folder_created_slug().to_string()), WebhookEventLabel::FolderCreated);
+                    let filter_context = WebhookFilterContext::for_folder(&folder_id, &user_id);
+                    let webhooks_folder = get_active_folder_webhooks(&&FolderID(WebhookAltIndexID::folder_created_slug().to_string()), WebhookEventLabel::FolderCreated, &filter_context);
                     let before_snap_folder = DirectoryWebhookData::Folder(FolderWebhookData {
                         folder: Some(folder.clone()),
                     });
@@ -1405,7 +1413,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // Prevent moving root folder
                     if folder.parent_folder_uuid.is_none() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "Cannot move root folder".to_string(),
                         });
                     }
@@ -1428,7 +1436,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
         
                     if !is_creator_with_upload && !has_move_permission && !is_owner {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to move this folder from its current location".to_string(),
                         });
                     }
@@ -1444,12 +1452,12 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     ) {
                         Ok(folder) => folder,
                         Err(e) => return Err(DirectoryActionErrorInfo {
-                            code: 404,
+                            code: ErrorCode::NotFound,
                             message: format!("Destination folder not found: {}", e),
                         }),
                     };
         
-                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfolderCreated);
+                    let webhooks_subfolder = get_active_folder_webhooks(&destination_folder.id, WebhookEventLabel::SubfolderCreated, &filter_context);
         
                     // Check destination folder permissions
                     let dest_resource_id = DirectoryResourceID::Folder(destination_folder.id.clone());
@@ -1464,7 +1472,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                        !dest_permissions.contains(&DirectoryPermissionType::Edit) &&
                        !dest_permissions.contains(&DirectoryPermissionType::Manage) {
                         return Err(DirectoryActionErrorInfo {
-                            code: 403,
+                            code: ErrorCode::PermissionDenied,
                             message: "You don't have permission to move folders to the destination folder".to_string(),
                         });
                     }
@@ -1491,13 +1499,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             Ok(DirectoryActionResult::MoveFolder(folder.cast_fe(&user_id).await))
                         },
                         Err(e) => Err(DirectoryActionErrorInfo {
-                            code: 500,
+                            code: ErrorCode::InternalError,
                             message: format!("Failed to move folder: {}", e),
                         }),
                     }
                 }
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for MOVE_FOLDER action".to_string(),
                 })
             }
@@ -1510,7 +1518,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     // validate payload
                     if let Err(validation_error) = payload.validate_body() {
                         return Err(DirectoryActionErrorInfo {
-                            code: 400,
+                            code: ErrorCode::ValidationFailed,
                             message: format!("Validation error: {}", validation_error.message),
                         });
                     }
@@ -1524,21 +1532,25 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     } else {
                         None
                     };
-                    let webhooks_restore_trash = get_active_folder_webhooks(&&FolderID(WebhookAltIndexID::restore_trash_slug().to_string()), WebhookEventLabel::DriveRestoreTrash);
+                    let filter_context = match &folder_id {
+                        Some(folder_id) => WebhookFilterContext::for_folder(folder_id, &user_id),
+                        None => WebhookFilterContext::for_file(&FileID(resource_id.to_string()), &user_id),
+                    };
+                    let webhooks_restore_trash = get_active_folder_webhooks(&&FolderID(WebhookAltIndexID::restore_trash_slug().to_string()), WebhookEventLabel::DriveRestoreTrash, &filter_context);
         
                     if let Some(folder_id) = folder_id {
                         // Get folder metadata
                         let folder = folder_uuid_to_metadata
                             .get(&folder_id)
                             .ok_or_else(|| DirectoryActionErrorInfo {
-                                code: 404,
+                                code: ErrorCode::NotFound,
                                 message: "Folder not found".to_string(),
                             })?;
 
                         // Verify folder is actually in trash
                         if folder.restore_trash_prior_folder_uuid.is_none() {
                             return Err(DirectoryActionErrorInfo {
-                                code: 400,
+                                code: ErrorCode::InvalidRequest,
                                 message: "Folder is not in trash".to_string(),
                             });
                         }
@@ -1564,7 +1576,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                         if !is_owner && !is_creator_with_upload && !has_restore_permission {
                             return Err(DirectoryActionErrorInfo {
-                                code: 403,
+                                code: ErrorCode::PermissionDenied,
                                 message: "You don't have permission to restore this folder".to_string(),
                             });
                         }
@@ -1579,7 +1591,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                                         })
                                     },
                                     Err(e) => return Err(DirectoryActionErrorInfo {
-                                        code: 500,
+                                        code: ErrorCode::InternalError,
                                         message: format!("Failed to get updated folder metadata: {}", e),
                                     })
                                 };
@@ -1594,7 +1606,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                                 Ok(result)
                             },
                             Err(e) => Err(DirectoryActionErrorInfo {
-                                code: 500,
+                                code: ErrorCode::InternalError,
                                 message: format!("Failed to restore folder from trash: {}", e),
                             })
                         }
@@ -1612,14 +1624,14 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                             let file = file_uuid_to_metadata
                                 .get(&file_id)
                                 .ok_or_else(|| DirectoryActionErrorInfo {
-                                    code: 404,
+                                    code: ErrorCode::NotFound,
                                     message: "File not found".to_string(),
                                 })?;
             
                             // Verify file is actually in trash
                             if file.restore_trash_prior_folder_uuid.is_none() {
                                 return Err(DirectoryActionErrorInfo {
-                                    code: 400,
+                                    code: ErrorCode::InvalidRequest,
                                     message: "File is not in trash".to_string(),
                                 });
                             }
@@ -1644,7 +1656,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
 
                             if !is_owner && !is_creator_with_upload && !has_restore_permission {
                                 return Err(DirectoryActionErrorInfo {
-                                    code: 403,
+                                    code: ErrorCode::PermissionDenied,
                                     message: "You don't have permission to restore this file".to_string(),
                                 });
                             }
@@ -1659,7 +1671,7 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                                             })
                                         },
                                         Err(e) => return Err(DirectoryActionErrorInfo {
-                                            code: 500,
+                                            code: ErrorCode::InternalError,
                                             message: format!("Failed to get updated file metadata: {}", e),
                                         })
                                     };
@@ -1674,13 +1686,13 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                                     Ok(result)
                                 },
                                 Err(e) => Err(DirectoryActionErrorInfo {
-                                    code: 500,
+                                    code: ErrorCode::InternalError,
                                     message: format!("Failed to restore file from trash: {}", e),
                                 })
                             }
                         } else {
                             Err(DirectoryActionErrorInfo {
-                                code: 400,
+                                code: ErrorCode::InvalidRequest,
                                 message: "Invalid resource ID".to_string(),
                             })
                         }
@@ -1689,10 +1701,211 @@ pub async fn pipe_action(action: DirectoryAction, user_id: UserID) -> Result<Dir
                     }
                 }
                 _ => Err(DirectoryActionErrorInfo {
-                    code: 400,
+                    code: ErrorCode::InvalidRequest,
                     message: "Invalid payload for RESTORE_TRASH action".to_string(),
                 })
             }
         }
+
+        DirectoryActionEnum::ListFileVersions => {
+            match action.payload {
+                DirectoryActionPayload::ListFileVersions(payload) => {
+
+                    // validate payload
+                    if let Err(validation_error) = payload.validate_body() {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::ValidationFailed,
+                            message: format!("Validation error: {}", validation_error.message),
+                        });
+                    }
+
+                    let file_id = payload.id;
+                    if let Err(e) = get_file_by_id(file_id.clone()) {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::NotFound,
+                            message: format!("File not found: {}", e),
+                        });
+                    }
+
+                    // Version history is visible to anyone who can view the file
+                    let user_permissions = check_directory_permissions(
+                        DirectoryResourceID::File(file_id.clone()),
+                        PermissionGranteeID::User(user_id.clone())
+                    ).await;
+                    let is_owner = OWNER_ID.with(|owner_id| user_id == *owner_id.borrow().get());
+
+                    if !is_owner && !user_permissions.contains(&DirectoryPermissionType::View) {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::PermissionDenied,
+                            message: "You don't have permission to view this file".to_string(),
+                        });
+                    }
+
+                    let mut versions = Vec::new();
+                    for version in list_file_versions(&file_id) {
+                        versions.push(version.cast_fe(&user_id).await);
+                    }
+
+                    Ok(DirectoryActionResult::ListFileVersions(ListFileVersionsResponse {
+                        file_id,
+                        versions,
+                    }))
+                },
+                _ => Err(DirectoryActionErrorInfo {
+                    code: ErrorCode::InvalidRequest,
+                    message: "Invalid payload for LIST_FILE_VERSIONS action".to_string(),
+                })
+            }
+        },
+
+        DirectoryActionEnum::GetFileVersion => {
+            match action.payload {
+                DirectoryActionPayload::GetFileVersion(payload) => {
+
+                    // validate payload
+                    if let Err(validation_error) = payload.validate_body() {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::ValidationFailed,
+                            message: format!("Validation error: {}", validation_error.message),
+                        });
+                    }
+
+                    let version = match file_version_to_metadata.get(&payload.version_id) {
+                        Some(version) => version,
+                        None => return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::NotFound,
+                            message: "File version not found".to_string(),
+                        }),
+                    };
+
+                    // The current record is the source of truth for the head version
+                    let is_current = is_current_version(&version);
+                    let version = match is_current {
+                        true => get_file_by_id(version.id.clone()).unwrap_or(version),
+                        false => version,
+                    };
+
+                    let user_permissions = check_directory_permissions(
+                        DirectoryResourceID::File(version.id.clone()),
+                        PermissionGranteeID::User(user_id.clone())
+                    ).await;
+                    let is_owner = OWNER_ID.with(|owner_id| user_id == *owner_id.borrow().get());
+
+                    if !is_owner && !user_permissions.contains(&DirectoryPermissionType::View) {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::PermissionDenied,
+                            message: "You don't have permission to view this file".to_string(),
+                        });
+                    }
+
+                    let content_url = match get_version_content_url(&version) {
+                        Ok(url) => url,
+                        Err(e) => return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::InternalError,
+                            message: format!("Failed to get file version content: {}", e),
+                        }),
+                    };
+
+                    Ok(DirectoryActionResult::GetFileVersion(GetFileVersionResponse {
+                        version: version.cast_fe(&user_id).await,
+                        is_current,
+                        content_url,
+                    }))
+                },
+                _ => Err(DirectoryActionErrorInfo {
+                    code: ErrorCode::InvalidRequest,
+                    message: "Invalid payload for GET_FILE_VERSION action".to_string(),
+                })
+            }
+        },
+
+        DirectoryActionEnum::RestoreFileVersion => {
+            match action.payload {
+                DirectoryActionPayload::RestoreFileVersion(payload) => {
+
+                    // validate payload
+                    if let Err(validation_error) = payload.validate_body() {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::ValidationFailed,
+                            message: format!("Validation error: {}", validation_error.message),
+                        });
+                    }
+
+                    let file_id = match file_version_to_metadata.get(&payload.version_id) {
+                        Some(version) => version.id,
+                        None => return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::NotFound,
+                            message: "File version not found".to_string(),
+                        }),
+                    };
+                    let file = match get_file_by_id(file_id.clone()) {
+                        Ok(f) => f,
+                        Err(e) => return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::NotFound,
+                            message: format!("File not found: {}", e),
+                        }),
+                    };
+
+                    // Restoring rewrites the file's content, same bar as editing it
+                    let user_permissions = check_directory_permissions(
+                        DirectoryResourceID::File(file_id.clone()),
+                        PermissionGranteeID::User(user_id.clone())
+                    ).await;
+                    let is_owner = OWNER_ID.with(|owner_id| user_id == *owner_id.borrow().get());
+
+                    if !is_owner && !user_permissions.contains(&DirectoryPermissionType::Edit) &&
+                       !user_permissions.contains(&DirectoryPermissionType::Manage) {
+                        return Err(DirectoryActionErrorInfo {
+                            code: ErrorCode::PermissionDenied,
+                            message: "You don't have permission to edit this file".to_string(),
+                        });
+                    }
+
+                    let filter_context = WebhookFilterContext::for_file(&file_id, &user_id);
+                    let webhooks_file = get_active_file_webhooks(&file_id, WebhookEventLabel::FileUpdated, &filter_context);
+                    let webhooks_subfile = get_active_file_webhooks(&file_id, WebhookEventLabel::SubfileUpdated, &filter_context);
+
+                    let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
+                        file: Some(file),
+                    });
+
+                    match restore_file_version(&file_id, &payload.version_id, &user_id).await {
+                        Ok((restored_file, pruned_versions)) => {
+                            let after_snap_file = DirectoryWebhookData::File(FileWebhookData {
+                                file: Some(restored_file.clone()),
+                            });
+                            fire_directory_webhook(
+                                WebhookEventLabel::FileUpdated,
+                                webhooks_file,
+                                Some(before_snap_file.clone()),
+                                Some(after_snap_file.clone()),
+                                Some("File version restored".to_string()),
+                            );
+                            fire_directory_webhook(
+                                WebhookEventLabel::SubfileUpdated,
+                                webhooks_subfile,
+                                Some(before_snap_file),
+                                Some(after_snap_file),
+                                Some("Subfile version restored".to_string()),
+                            );
+
+                            Ok(DirectoryActionResult::RestoreFileVersion(RestoreFileVersionResponse {
+                                file: restored_file.cast_fe(&user_id).await,
+                                restored_from: payload.version_id,
+                                pruned_versions,
+                            }))
+                        },
+                        Err(e) => Err(DirectoryActionErrorInfo {
+                            code: e.code(),
+                            message: format!("Failed to restore file version: {}", e.message()),
+                        })
+                    }
+                },
+                _ => Err(DirectoryActionErrorInfo {
+                    code: ErrorCode::InvalidRequest,
+                    message: "Invalid payload for RESTORE_FILE_VERSION action".to_string(),
+                })
+            }
+        },
     }
 }
\ No newline at end of file
@@ -0,0 +1,791 @@
+// src/core/api/archive.rs
+
+// Encrypted cold archives of an entire drive.
+//
+// An archive is a numbered list of parts. Part 0 is the header: a magic, the format
+// version and a random salt in the clear, then the sealed ColdArchiveHeader. The state
+// parts follow, holding the cbor ColdArchivePayload (EntireState plus raw_storage chunk
+// records) split into segments, and then the content parts with the raw chunk bytes,
+// one content after another, each split into segments.
+//
+// EntireState leaves out some tables the drive still needs, those travel in
+// ColdArchiveExtras. The tables in COLD_ARCHIVE_EXCLUDED_TABLES are left behind on purpose.
+//
+// Every part is deflated and sealed with ChaCha20-Poly1305. The key is derived from a
+// BIP39 phrase and the salt, the nonce is the part index, and the salt and index are
+// authenticated with the part, so parts cannot be swapped between positions or archives.
+//
+// Sessions live on the heap: an upgrade drops exports and imports in flight.
+pub mod archive {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use bip39::{Language, Mnemonic};
+    use chacha20poly1305::{aead::{Aead, KeyInit, Payload}, ChaCha20Poly1305, Key, Nonce};
+    use hmac::{Hmac, Mac};
+    use serde::{Deserialize, Serialize};
+    use sha2::Sha256;
+
+    use crate::{
+        core::{
+            api::replay::diff::{apply_entire_state, snapshot_entire_state, EntireState},
+            state::{
+                cycles::{state::state::CYCLES_MONITOR_CONFIG, types::CyclesMonitorConfig},
+                directory::{
+                    state::state::{file_uuid_to_metadata, file_version_to_metadata},
+                    types::{FileRecord, FileVersionID},
+                },
+                drives::{
+                    state::state::{ALLOWED_DOMAINS, CANISTER_ID, DRIVE_ID, DRIVE_STATE_CHECKSUM, DRIVE_STATE_TIMESTAMP_NS, OWNER_ID, URL_ENDPOINT},
+                    types::{DriveID, InboxNotifID, StateChecksum},
+                },
+                group_invites::{state::state::INVITES_BY_TIME_LIST, types::GroupInviteID},
+                groups::{state::state::DEFAULT_EVERYONE_GROUP, types::GroupID},
+                idempotency::{state::state::IDEMPOTENCY_CONFIG, types::IdempotencyConfig},
+                inbox::{
+                    state::state::{INBOX_NOTIFS_BY_ID_HASHTABLE, INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE},
+                    types::{InboxNotif, InboxNotifIDList, InboxRecipientID},
+                },
+                labels::{
+                    state::{LABELS_BY_ID_HASHTABLE, LABELS_BY_TIME_LIST, LABELS_BY_VALUE_HASHTABLE},
+                    types::{Label, LabelID, LabelStringValue},
+                },
+                rate_limits::{state::state::RATE_LIMIT_CONFIG, types::RateLimitConfig},
+                raw_storage::{
+                    state::{discard_unreferenced_content, hash_chunk_data, link_chunk, stage_chunk_content, store_filename, CHUNKS, CHUNK_CONTENT, FILE_META},
+                    types::{ChunkId, FileChunk},
+                },
+            },
+            types::UserID,
+        },
+        debug_log,
+    };
+
+    pub const COLD_ARCHIVE_MAGIC: &[u8; 8] = b"OFXARCH\0";
+    pub const COLD_ARCHIVE_FORMAT_VERSION: u8 = 1;
+    // Plaintext bytes per part, small enough to stay under the response limit once base64 encoded
+    pub const COLD_ARCHIVE_SEGMENT_BYTES: usize = 512 * 1024;
+    const COLD_ARCHIVE_SALT_BYTES: usize = 16;
+    const COLD_ARCHIVE_KEY_CONTEXT: &[u8] = b"officex-cold-archive-v1";
+
+    // Stable tables an archive does not carry, with the reason. Everything else is in
+    // EntireState, ColdArchiveExtras or the raw_storage records of the payload.
+    pub const COLD_ARCHIVE_EXCLUDED_TABLES: &[(&str, &str)] = &[
+        ("AUDIT_ENTRIES_BY_SEQUENCE", "history of this canister, the import is audited on it instead"),
+        ("IDEMPOTENCY_RECORDS", "replay cache of requests made to this canister, expires on its own"),
+        ("CYCLES_SAMPLES", "cycle balance history of this canister"),
+        ("CYCLES_ALERT_STATE", "alert bookkeeping for the cycle balance of this canister"),
+        ("WEBHOOK_DELIVERIES_BY_ID_HASHTABLE", "deliveries in flight from this canister"),
+        ("WEBHOOK_DELIVERY_QUEUE", "deliveries in flight from this canister"),
+        ("WEBHOOK_DEAD_LETTERS_HASHTABLE", "failed deliveries from this canister"),
+        ("WEBHOOK_DELIVERY_SEQUENCE", "delivery counter of this canister"),
+        ("TRANSFER_OWNER_ID", "pending ownership transfer, the archive carries the owner"),
+        ("UPLOAD_SESSIONS", "unfinished uploads, their chunks are not linked to a file"),
+        ("FILE_CHUNKS", "rebuilt from the chunk records on import"),
+        ("CHUNK_REFCOUNTS", "rebuilt from the chunk records on import"),
+        ("trashed_by_time", "sweep index, rebuilt from the folder and file records"),
+        ("expiring_by_time", "sweep index, rebuilt from the folder and file records"),
+        ("sweep_indexes_built", "sweep index flag of this canister"),
+    ];
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ColdArchiveHeader {
+        pub format_version: u8,
+        pub archive_id: String, // hex of the salt
+        pub drive_id: DriveID,
+        pub canister_id: String,
+        pub owner_id: UserID,
+        pub state_checksum: String,
+        pub state_timestamp_ns: u64,
+        pub created_at_ms: u64,
+        pub payload_size: u64,
+        pub payload_sha256: String,
+        pub state_parts: u32,
+        pub content_parts: u32,
+        pub content_bytes: u64,
+        // Names from COLD_ARCHIVE_EXCLUDED_TABLES, so a reader knows what is not in the archive
+        #[serde(default)]
+        pub excluded_tables: Vec<String>,
+    }
+
+    impl ColdArchiveHeader {
+        pub fn total_parts(&self) -> u32 {
+            1 + self.state_parts + self.content_parts
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ColdArchiveContent {
+        pub hash: String, // sha256 hex of the bytes
+        pub size: u64,
+    }
+
+    // Drive tables that EntireState does not cover, kept out of it so state diffs stay as they were
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct ColdArchiveExtras {
+        pub file_versions: Vec<(FileVersionID, FileRecord)>,
+        pub labels_by_id: Vec<(LabelID, Label)>,
+        pub labels_by_value: Vec<(LabelStringValue, LabelID)>,
+        pub labels_by_time: Vec<LabelID>,
+        pub inbox_notifs_by_id: Vec<(InboxNotifID, InboxNotif)>,
+        pub inbox_notifs_by_recipient: Vec<(InboxRecipientID, InboxNotifIDList)>,
+        pub invites_by_time: Vec<GroupInviteID>,
+        pub default_everyone_group: Option<GroupID>,
+        pub allowed_domains: String,
+        pub rate_limit_config: Option<RateLimitConfig>,
+        pub idempotency_config: Option<IdempotencyConfig>,
+        pub cycles_monitor_config: Option<CyclesMonitorConfig>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ColdArchivePayload {
+        pub state: EntireState,
+        #[serde(default)]
+        pub extras: ColdArchiveExtras,
+        // Always carry a content_hash and no data, legacy chunks are hashed on export
+        pub chunks: Vec<FileChunk>,
+        pub file_meta: Vec<(String, String)>,
+        // In the order their parts follow the state parts
+        pub contents: Vec<ColdArchiveContent>,
+    }
+
+    #[derive(Debug, Clone, Serialize)]
+    pub struct ColdArchiveImportProgress {
+        pub archive_id: String,
+        pub total_parts: u32,
+        pub received_parts: u32,
+        pub state_verified: bool,
+        pub verified_contents: u32,
+        pub total_contents: u32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ColdArchiveError {
+        InvalidSeedPhrase,
+        NotFound,
+        NotFresh,
+        Integrity(String),
+        OutOfOrder(String),
+        Incomplete(String),
+        Unavailable(String),
+    }
+
+    impl ColdArchiveError {
+        pub fn message(&self) -> String {
+            match self {
+                ColdArchiveError::InvalidSeedPhrase => "Invalid mnemonic seed phrase".to_string(),
+                ColdArchiveError::NotFound => "Archive session not found".to_string(),
+                ColdArchiveError::NotFresh => "Archives can only be imported into a drive without files".to_string(),
+                ColdArchiveError::Integrity(msg)
+                | ColdArchiveError::OutOfOrder(msg)
+                | ColdArchiveError::Incomplete(msg)
+                | ColdArchiveError::Unavailable(msg) => msg.clone(),
+            }
+        }
+    }
+
+    // Where the bytes of an exported content come from
+    #[derive(Debug, Clone)]
+    struct ContentSource {
+        hash: String,
+        legacy_chunk: Option<ChunkId>, // chunk stored before dedup, bytes live on the record
+    }
+
+    #[derive(Debug, Clone)]
+    struct ContentSegment {
+        content: usize,
+        offset: usize,
+        len: usize,
+    }
+
+    struct ColdArchiveExport {
+        salt: Vec<u8>,
+        key: [u8; 32],
+        header: ColdArchiveHeader,
+        header_part: Vec<u8>,
+        payload: Vec<u8>,
+        sources: Vec<ContentSource>,
+        segments: Vec<ContentSegment>,
+    }
+
+    struct ColdArchiveImport {
+        salt: Vec<u8>,
+        key: [u8; 32],
+        header: ColdArchiveHeader,
+        received: Vec<bool>,
+        state_segments: Vec<Option<Vec<u8>>>,
+        payload: Option<ColdArchivePayload>,
+        // part index -> (content, segment within the content)
+        part_contents: HashMap<u32, (usize, usize)>,
+        pending: HashMap<usize, Vec<Option<Vec<u8>>>>,
+        verified: Vec<bool>,
+    }
+
+    thread_local! {
+        // One export and one import at a time, a new one replaces the previous
+        static COLD_ARCHIVE_EXPORT: RefCell<Option<ColdArchiveExport>> = RefCell::new(None);
+        static COLD_ARCHIVE_IMPORT: RefCell<Option<ColdArchiveImport>> = RefCell::new(None);
+    }
+
+    pub fn derive_archive_key(seed_phrase: &str, salt: &[u8]) -> Result<[u8; 32], ColdArchiveError> {
+        let mnemonic = Mnemonic::parse_in(Language::English, seed_phrase)
+            .map_err(|_| ColdArchiveError::InvalidSeedPhrase)?;
+        let seed = mnemonic.to_seed("");
+
+        type HmacSha256 = Hmac<Sha256>;
+        let mut mac = HmacSha256::new_from_slice(&seed)
+            .expect("HMAC can take key of any size");
+        mac.update(COLD_ARCHIVE_KEY_CONTEXT);
+        mac.update(salt);
+        Ok(mac.finalize().into_bytes().into())
+    }
+
+    // 24 word phrase from the management canister's randomness
+    pub async fn generate_archive_seed_phrase() -> Result<String, String> {
+        let entropy = random_bytes().await?;
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)
+            .map_err(|e| format!("Failed to generate seed phrase: {:?}", e))?;
+        Ok(mnemonic.to_string())
+    }
+
+    async fn random_bytes() -> Result<Vec<u8>, String> {
+        ic_cdk::api::management_canister::main::raw_rand()
+            .await
+            .map(|(bytes,)| bytes)
+            .map_err(|(code, msg)| format!("Failed to get randomness: {:?} {}", code, msg))
+    }
+
+    fn part_nonce(index: u32) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[8..].copy_from_slice(&index.to_be_bytes());
+        nonce
+    }
+
+    fn part_aad(salt: &[u8], index: u32) -> Vec<u8> {
+        let mut aad = salt.to_vec();
+        aad.extend_from_slice(&index.to_be_bytes());
+        aad
+    }
+
+    fn seal_part(key: &[u8; 32], salt: &[u8], index: u32, plaintext: &[u8]) -> Vec<u8> {
+        let compressed = miniz_oxide::deflate::compress_to_vec(plaintext, 6);
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+        cipher
+            .encrypt(
+                Nonce::from_slice(&part_nonce(index)),
+                Payload { msg: &compressed, aad: &part_aad(salt, index) },
+            )
+            .expect("Encrypting an in-memory buffer cannot fail")
+    }
+
+    fn open_part(key: &[u8; 32], salt: &[u8], index: u32, sealed: &[u8]) -> Result<Vec<u8>, ColdArchiveError> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+        let compressed = cipher
+            .decrypt(
+                Nonce::from_slice(&part_nonce(index)),
+                Payload { msg: sealed, aad: &part_aad(salt, index) },
+            )
+            .map_err(|_| ColdArchiveError::Integrity(format!("Part {} failed to decrypt, wrong seed phrase or corrupted part", index)))?;
+        miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, COLD_ARCHIVE_SEGMENT_BYTES)
+            .map_err(|_| ColdArchiveError::Integrity(format!("Part {} failed to decompress", index)))
+    }
+
+    fn segment_count(len: usize) -> usize {
+        len.div_ceil(COLD_ARCHIVE_SEGMENT_BYTES).max(1)
+    }
+
+    fn now_ms() -> u64 {
+        ic_cdk::api::time() / 1_000_000
+    }
+
+    // Captures the state and the raw chunk records, content bytes are read when their parts are fetched.
+    // Content is addressed by hash, so it cannot change underneath the export, only disappear.
+    pub async fn start_cold_archive_export(seed_phrase: &str) -> Result<ColdArchiveHeader, ColdArchiveError> {
+        // Validate before the await so a typo does not cost a raw_rand call
+        Mnemonic::parse_in(Language::English, seed_phrase).map_err(|_| ColdArchiveError::InvalidSeedPhrase)?;
+        let salt: Vec<u8> = random_bytes().await
+            .map_err(ColdArchiveError::Unavailable)?
+            .into_iter()
+            .take(COLD_ARCHIVE_SALT_BYTES)
+            .collect();
+        let key = derive_archive_key(seed_phrase, &salt)?;
+
+        // Everything below runs without an await, so it is one point in time
+        let mut sources: Vec<ContentSource> = Vec::new();
+        let mut contents: Vec<ColdArchiveContent> = Vec::new();
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut chunks: Vec<FileChunk> = CHUNKS.with(|store| store.borrow().iter().map(|(_, chunk)| chunk).collect());
+        for chunk in chunks.iter_mut() {
+            let hash = match &chunk.content_hash {
+                Some(hash) => hash.clone(),
+                None => hash_chunk_data(&chunk.data),
+            };
+            if !seen.contains_key(&hash) {
+                let size = match &chunk.content_hash {
+                    Some(hash) => match CHUNK_CONTENT.with(|store| store.borrow().get(hash)) {
+                        Some(content) => content.0.len(),
+                        None => return Err(ColdArchiveError::Unavailable(format!("Content of chunk {} is missing", chunk.id))),
+                    },
+                    None => chunk.data.len(),
+                };
+                seen.insert(hash.clone(), contents.len());
+                sources.push(ContentSource {
+                    hash: hash.clone(),
+                    legacy_chunk: if chunk.content_hash.is_none() { Some(chunk.id.clone()) } else { None },
+                });
+                contents.push(ColdArchiveContent { hash: hash.clone(), size: size as u64 });
+            }
+            chunk.content_hash = Some(hash);
+            chunk.data = Vec::new();
+        }
+        // Import relinks chunks in this order, which keeps FILE_CHUNKS ordered by index
+        chunks.sort_by(|a, b| a.file_id.cmp(&b.file_id).then(a.chunk_index.cmp(&b.chunk_index)));
+
+        let file_meta: Vec<(String, String)> = FILE_META.with(|store| store.borrow().iter().collect());
+
+        let mut segments: Vec<ContentSegment> = Vec::new();
+        let mut content_bytes: u64 = 0;
+        for (content, entry) in contents.iter().enumerate() {
+            let size = entry.size as usize;
+            content_bytes += entry.size;
+            for segment in 0..segment_count(size) {
+                let offset = segment * COLD_ARCHIVE_SEGMENT_BYTES;
+                segments.push(ContentSegment {
+                    content,
+                    offset,
+                    len: (size - offset).min(COLD_ARCHIVE_SEGMENT_BYTES),
+                });
+            }
+        }
+
+        let payload = ColdArchivePayload {
+            state: snapshot_entire_state(),
+            extras: snapshot_cold_archive_extras(),
+            chunks,
+            file_meta,
+            contents,
+        };
+        let mut payload_bytes = vec![];
+        ciborium::ser::into_writer(&payload, &mut payload_bytes)
+            .map_err(|e| ColdArchiveError::Unavailable(format!("Failed to serialize state: {}", e)))?;
+
+        let header = ColdArchiveHeader {
+            format_version: COLD_ARCHIVE_FORMAT_VERSION,
+            archive_id: hex::encode(&salt),
+            drive_id: DRIVE_ID.with(|id| id.clone()),
+            canister_id: CANISTER_ID.with(|id| id.0.clone()),
+            owner_id: OWNER_ID.with(|owner| owner.borrow().get().clone()),
+            state_checksum: DRIVE_STATE_CHECKSUM.with(|cs| cs.borrow().get().0.clone()),
+            state_timestamp_ns: DRIVE_STATE_TIMESTAMP_NS.with(|ts| *ts.borrow().get()),
+            created_at_ms: now_ms(),
+            payload_size: payload_bytes.len() as u64,
+            payload_sha256: hash_chunk_data(&payload_bytes),
+            state_parts: segment_count(payload_bytes.len()) as u32,
+            content_parts: segments.len() as u32,
+            content_bytes,
+            excluded_tables: COLD_ARCHIVE_EXCLUDED_TABLES.iter().map(|(name, _)| name.to_string()).collect(),
+        };
+
+        let mut header_bytes = vec![];
+        ciborium::ser::into_writer(&header, &mut header_bytes)
+            .map_err(|e| ColdArchiveError::Unavailable(format!("Failed to serialize header: {}", e)))?;
+        let mut header_part = COLD_ARCHIVE_MAGIC.to_vec();
+        header_part.push(COLD_ARCHIVE_FORMAT_VERSION);
+        header_part.extend_from_slice(&salt);
+        header_part.extend_from_slice(&seal_part(&key, &salt, 0, &header_bytes));
+
+        debug_log!("Started cold archive export {} with {} parts", header.archive_id, header.total_parts());
+        COLD_ARCHIVE_EXPORT.with(|export| {
+            *export.borrow_mut() = Some(ColdArchiveExport {
+                salt,
+                key,
+                header: header.clone(),
+                header_part,
+                payload: payload_bytes,
+                sources,
+                segments,
+            });
+        });
+        Ok(header)
+    }
+
+    pub fn read_cold_archive_export_part(archive_id: &str, index: u32) -> Result<(Vec<u8>, u32), ColdArchiveError> {
+        COLD_ARCHIVE_EXPORT.with(|export| {
+            let export = export.borrow();
+            let export = match export.as_ref() {
+                Some(export) if export.header.archive_id == archive_id => export,
+                _ => return Err(ColdArchiveError::NotFound),
+            };
+            let total_parts = export.header.total_parts();
+            if index >= total_parts {
+                return Err(ColdArchiveError::OutOfOrder(format!("Part index must be less than {}", total_parts)));
+            }
+            if index == 0 {
+                return Ok((export.header_part.clone(), total_parts));
+            }
+
+            let plaintext: Vec<u8> = if index <= export.header.state_parts {
+                let offset = (index as usize - 1) * COLD_ARCHIVE_SEGMENT_BYTES;
+                let end = (offset + COLD_ARCHIVE_SEGMENT_BYTES).min(export.payload.len());
+                export.payload[offset..end].to_vec()
+            } else {
+                let segment = &export.segments[(index - 1 - export.header.state_parts) as usize];
+                let source = &export.sources[segment.content];
+                let bytes = match &source.legacy_chunk {
+                    Some(chunk_id) => CHUNKS.with(|store| store.borrow().get(chunk_id)).map(|chunk| chunk.data),
+                    None => CHUNK_CONTENT.with(|store| store.borrow().get(&source.hash)).map(|content| content.0),
+                };
+                match bytes {
+                    Some(bytes) if bytes.len() >= segment.offset + segment.len => bytes[segment.offset..segment.offset + segment.len].to_vec(),
+                    _ => return Err(ColdArchiveError::Unavailable(format!(
+                        "Content {} was deleted after the export started, start a new export", source.hash
+                    ))),
+                }
+            };
+            Ok((seal_part(&export.key, &export.salt, index, &plaintext), total_parts))
+        })
+    }
+
+    fn snapshot_cold_archive_extras() -> ColdArchiveExtras {
+        ColdArchiveExtras {
+            file_versions: file_version_to_metadata.with(|map| map.iter().collect()),
+            labels_by_id: LABELS_BY_ID_HASHTABLE.with(|map| map.borrow().iter().collect()),
+            labels_by_value: LABELS_BY_VALUE_HASHTABLE.with(|map| map.borrow().iter().collect()),
+            labels_by_time: LABELS_BY_TIME_LIST.with(|list| list.borrow().iter().collect()),
+            inbox_notifs_by_id: INBOX_NOTIFS_BY_ID_HASHTABLE.with(|map| map.borrow().iter().collect()),
+            inbox_notifs_by_recipient: INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|map| map.borrow().iter().collect()),
+            invites_by_time: INVITES_BY_TIME_LIST.with(|list| list.borrow().iter().collect()),
+            default_everyone_group: Some(DEFAULT_EVERYONE_GROUP.with(|group| group.borrow().get().clone())),
+            allowed_domains: ALLOWED_DOMAINS.with(|domains| domains.borrow().get().clone()),
+            rate_limit_config: Some(RATE_LIMIT_CONFIG.with(|config| config.borrow().get().clone())),
+            idempotency_config: Some(IDEMPOTENCY_CONFIG.with(|config| config.borrow().get().clone())),
+            cycles_monitor_config: Some(CYCLES_MONITOR_CONFIG.with(|config| config.borrow().get().clone())),
+        }
+    }
+
+    // Replaces each table like apply_entire_state does. Configs missing from an older
+    // archive keep the values of this drive.
+    fn apply_cold_archive_extras(extras: ColdArchiveExtras) {
+        file_version_to_metadata.with_mut(|map| {
+            for key in map.keys().collect::<Vec<_>>() {
+                map.remove(&key);
+            }
+            for (key, value) in extras.file_versions {
+                map.insert(key, value);
+            }
+        });
+        LABELS_BY_ID_HASHTABLE.with(|map| {
+            let mut map = map.borrow_mut();
+            for key in map.keys().collect::<Vec<_>>() {
+                map.remove(&key);
+            }
+            for (key, value) in extras.labels_by_id {
+                map.insert(key, value);
+            }
+        });
+        LABELS_BY_VALUE_HASHTABLE.with(|map| {
+            let mut map = map.borrow_mut();
+            for key in map.keys().collect::<Vec<_>>() {
+                map.remove(&key);
+            }
+            for (key, value) in extras.labels_by_value {
+                map.insert(key, value);
+            }
+        });
+        LABELS_BY_TIME_LIST.with(|list| {
+            let list = list.borrow_mut();
+            while list.pop().is_some() {}
+            for value in extras.labels_by_time {
+                list.push(&value).expect("Failed to push to LABELS_BY_TIME_LIST");
+            }
+        });
+        INBOX_NOTIFS_BY_ID_HASHTABLE.with(|map| {
+            let mut map = map.borrow_mut();
+            for key in map.keys().collect::<Vec<_>>() {
+                map.remove(&key);
+            }
+            for (key, value) in extras.inbox_notifs_by_id {
+                map.insert(key, value);
+            }
+        });
+        INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|map| {
+            let mut map = map.borrow_mut();
+            for key in map.keys().collect::<Vec<_>>() {
+                map.remove(&key);
+            }
+            for (key, value) in extras.inbox_notifs_by_recipient {
+                map.insert(key, value);
+            }
+        });
+        INVITES_BY_TIME_LIST.with(|list| {
+            let list = list.borrow_mut();
+            while list.pop().is_some() {}
+            for value in extras.invites_by_time {
+                list.push(&value).expect("Failed to push to INVITES_BY_TIME_LIST");
+            }
+        });
+        if let Some(group_id) = extras.default_everyone_group {
+            DEFAULT_EVERYONE_GROUP.with(|group| {
+                group.borrow_mut().set(group_id).expect("Failed to set DEFAULT_EVERYONE_GROUP");
+            });
+        }
+        ALLOWED_DOMAINS.with(|domains| {
+            domains.borrow_mut().set(extras.allowed_domains).expect("Failed to set ALLOWED_DOMAINS");
+        });
+        if let Some(config) = extras.rate_limit_config {
+            RATE_LIMIT_CONFIG.with(|store| {
+                store.borrow_mut().set(config).expect("Failed to set RATE_LIMIT_CONFIG");
+            });
+        }
+        if let Some(config) = extras.idempotency_config {
+            IDEMPOTENCY_CONFIG.with(|store| {
+                store.borrow_mut().set(config).expect("Failed to set IDEMPOTENCY_CONFIG");
+            });
+        }
+        if let Some(config) = extras.cycles_monitor_config {
+            CYCLES_MONITOR_CONFIG.with(|store| {
+                store.borrow_mut().set(config).expect("Failed to set CYCLES_MONITOR_CONFIG");
+            });
+        }
+    }
+
+    // Fresh means no file records and no raw chunks, as on a newly spawned drive
+    pub fn is_fresh_drive() -> bool {
+        let no_files = file_uuid_to_metadata.with(|map| map.is_empty());
+        let no_chunks = CHUNKS.with(|store| store.borrow().is_empty());
+        no_files && no_chunks
+    }
+
+    pub fn start_cold_archive_import(seed_phrase: &str, header_part: &[u8]) -> Result<ColdArchiveHeader, ColdArchiveError> {
+        if !is_fresh_drive() {
+            return Err(ColdArchiveError::NotFresh);
+        }
+
+        let prefix_len = COLD_ARCHIVE_MAGIC.len() + 1 + COLD_ARCHIVE_SALT_BYTES;
+        if header_part.len() <= prefix_len || &header_part[..COLD_ARCHIVE_MAGIC.len()] != COLD_ARCHIVE_MAGIC {
+            return Err(ColdArchiveError::Integrity("Part 0 is not a cold archive header".to_string()));
+        }
+        let format_version = header_part[COLD_ARCHIVE_MAGIC.len()];
+        if format_version != COLD_ARCHIVE_FORMAT_VERSION {
+            return Err(ColdArchiveError::Integrity(format!("Unsupported archive format version {}", format_version)));
+        }
+        let salt = header_part[COLD_ARCHIVE_MAGIC.len() + 1..prefix_len].to_vec();
+        let key = derive_archive_key(seed_phrase, &salt)?;
+        let header_bytes = open_part(&key, &salt, 0, &header_part[prefix_len..])?;
+        let header: ColdArchiveHeader = ciborium::de::from_reader(header_bytes.as_slice())
+            .map_err(|_| ColdArchiveError::Integrity("Archive header is malformed".to_string()))?;
+        if header.archive_id != hex::encode(&salt) {
+            return Err(ColdArchiveError::Integrity("Archive header does not match its salt".to_string()));
+        }
+
+        // Content left behind by an abandoned import, nothing references it on a fresh drive
+        discard_unreferenced_content(None);
+
+        let mut received = vec![false; header.total_parts() as usize];
+        received[0] = true;
+        debug_log!("Started cold archive import {} of drive {}", header.archive_id, header.drive_id);
+        COLD_ARCHIVE_IMPORT.with(|import| {
+            *import.borrow_mut() = Some(ColdArchiveImport {
+                salt,
+                key,
+                received,
+                state_segments: vec![None; header.state_parts as usize],
+                payload: None,
+                part_contents: HashMap::new(),
+                pending: HashMap::new(),
+                verified: Vec::new(),
+                header: header.clone(),
+            });
+        });
+        Ok(header)
+    }
+
+    // Parts are verified as they arrive. State parts must all arrive before any content part,
+    // since the payload lists which content each later part belongs to.
+    pub fn receive_cold_archive_part(archive_id: &str, index: u32, sealed: &[u8]) -> Result<ColdArchiveImportProgress, ColdArchiveError> {
+        COLD_ARCHIVE_IMPORT.with(|import| {
+            let mut import = import.borrow_mut();
+            let import = match import.as_mut() {
+                Some(import) if import.header.archive_id == archive_id => import,
+                _ => return Err(ColdArchiveError::NotFound),
+            };
+            let total_parts = import.header.total_parts();
+            if index == 0 || index >= total_parts {
+                return Err(ColdArchiveError::OutOfOrder(format!("Part index must be between 1 and {}", total_parts - 1)));
+            }
+            if import.received[index as usize] {
+                return Ok(import_progress(import));
+            }
+
+            let plaintext = open_part(&import.key, &import.salt, index, sealed)?;
+
+            if index <= import.header.state_parts {
+                import.state_segments[index as usize - 1] = Some(plaintext);
+                if import.state_segments.iter().all(|segment| segment.is_some()) {
+                    // The hash covers the whole payload, so a mismatch cannot be pinned on one part.
+                    // Drop them all so every state part can be uploaded again.
+                    if let Err(e) = verify_state_segments(import) {
+                        for segment in 1..=import.header.state_parts {
+                            import.state_segments[segment as usize - 1] = None;
+                            import.received[segment as usize] = false;
+                        }
+                        return Err(e);
+                    }
+                }
+                import.received[index as usize] = true;
+                return Ok(import_progress(import));
+            }
+
+            let (content, segment) = match import.part_contents.get(&index) {
+                Some(position) => *position,
+                None => return Err(ColdArchiveError::OutOfOrder("Upload every state part before the content parts".to_string())),
+            };
+            let payload = import.payload.as_ref().expect("part_contents is only filled once the payload is verified");
+            let entry = payload.contents[content].clone();
+            let segments_total = segment_count(entry.size as usize);
+
+            let buffer = import.pending.entry(content).or_insert_with(|| vec![None; segments_total]);
+            buffer[segment] = Some(plaintext);
+            if buffer.iter().all(|part| part.is_some()) {
+                let bytes: Vec<u8> = import.pending.remove(&content)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .collect();
+                if bytes.len() as u64 != entry.size || hash_chunk_data(&bytes) != entry.hash {
+                    // Like the state parts, the bad segment can't be told apart, so every part of
+                    // this content counts as missing again and gets uploaded once more
+                    for (part, (part_content, _)) in import.part_contents.iter() {
+                        if *part_content == content {
+                            import.received[*part as usize] = false;
+                        }
+                    }
+                    return Err(ColdArchiveError::Integrity(format!("Content {} does not match its hash", entry.hash)));
+                }
+                stage_chunk_content(&entry.hash, bytes);
+                import.verified[content] = true;
+            }
+            import.received[index as usize] = true;
+            Ok(import_progress(import))
+        })
+    }
+
+    fn verify_state_segments(import: &mut ColdArchiveImport) -> Result<(), ColdArchiveError> {
+        let payload_bytes: Vec<u8> = import.state_segments.iter().flatten().flatten().copied().collect();
+        if payload_bytes.len() as u64 != import.header.payload_size || hash_chunk_data(&payload_bytes) != import.header.payload_sha256 {
+            return Err(ColdArchiveError::Integrity("Archived state does not match the header".to_string()));
+        }
+        let payload: ColdArchivePayload = ciborium::de::from_reader(payload_bytes.as_slice())
+            .map_err(|_| ColdArchiveError::Integrity("Archived state is malformed".to_string()))?;
+
+        let mut index = import.header.state_parts + 1;
+        for (content, entry) in payload.contents.iter().enumerate() {
+            for segment in 0..segment_count(entry.size as usize) {
+                import.part_contents.insert(index, (content, segment));
+                index += 1;
+            }
+        }
+        if index != import.header.total_parts() {
+            return Err(ColdArchiveError::Integrity("Archived contents do not match the header".to_string()));
+        }
+
+        import.verified = vec![false; payload.contents.len()];
+        import.payload = Some(payload);
+        // The verified payload holds the same bytes
+        import.state_segments.iter_mut().for_each(|segment| *segment = Some(Vec::new()));
+        Ok(())
+    }
+
+    fn import_progress(import: &ColdArchiveImport) -> ColdArchiveImportProgress {
+        ColdArchiveImportProgress {
+            archive_id: import.header.archive_id.clone(),
+            total_parts: import.header.total_parts(),
+            received_parts: import.received.iter().filter(|received| **received).count() as u32,
+            state_verified: import.payload.is_some(),
+            verified_contents: import.verified.iter().filter(|verified| **verified).count() as u32,
+            total_contents: import.payload.as_ref().map_or(0, |payload| payload.contents.len() as u32),
+        }
+    }
+
+    pub fn get_cold_archive_import_progress(archive_id: &str) -> Result<ColdArchiveImportProgress, ColdArchiveError> {
+        COLD_ARCHIVE_IMPORT.with(|import| match import.borrow().as_ref() {
+            Some(import) if import.header.archive_id == archive_id => Ok(import_progress(import)),
+            _ => Err(ColdArchiveError::NotFound),
+        })
+    }
+
+    // Replaces the drive's state with the archive once every part arrived and checked out.
+    // The drive keeps its own url endpoint, everything else comes from the archive.
+    pub fn activate_cold_archive_import(archive_id: &str) -> Result<ColdArchiveHeader, ColdArchiveError> {
+        let (header, payload) = COLD_ARCHIVE_IMPORT.with(|import| {
+            let import = import.borrow();
+            let import = match import.as_ref() {
+                Some(import) if import.header.archive_id == archive_id => import,
+                _ => return Err(ColdArchiveError::NotFound),
+            };
+            let missing = import.received.iter().filter(|received| !**received).count();
+            if missing > 0 {
+                return Err(ColdArchiveError::Incomplete(format!("{} parts have not been uploaded yet", missing)));
+            }
+            let payload = import.payload.clone().ok_or_else(|| ColdArchiveError::Incomplete("Archived state is not verified".to_string()))?;
+            if import.verified.iter().any(|verified| !*verified) {
+                return Err(ColdArchiveError::Incomplete("Some contents are not verified".to_string()));
+            }
+            Ok((import.header.clone(), payload))
+        })?;
+
+        if !is_fresh_drive() {
+            return Err(ColdArchiveError::NotFresh);
+        }
+        // Every chunk must point at content that was verified and staged
+        for chunk in &payload.chunks {
+            let hash = chunk.content_hash.clone().unwrap_or_default();
+            if !CHUNK_CONTENT.with(|store| store.borrow().contains_key(&hash)) {
+                return Err(ColdArchiveError::Integrity(format!("Chunk {} points at content missing from the archive", chunk.id)));
+            }
+        }
+
+        let url_endpoint = URL_ENDPOINT.with(|url| url.borrow().get().clone());
+        apply_entire_state(payload.state);
+        apply_cold_archive_extras(payload.extras);
+        URL_ENDPOINT.with(|url| {
+            url.borrow_mut().set(url_endpoint).expect("Failed to keep URL_ENDPOINT");
+        });
+
+        for chunk in payload.chunks {
+            link_chunk(chunk);
+        }
+        for (file_id, filename) in payload.file_meta {
+            store_filename(&file_id, &filename);
+        }
+
+        DRIVE_STATE_CHECKSUM.with(|cs| {
+            cs.borrow_mut().set(StateChecksum(header.state_checksum.clone())).expect("Failed to set DRIVE_STATE_CHECKSUM");
+        });
+        DRIVE_STATE_TIMESTAMP_NS.with(|ts| {
+            ts.borrow_mut().set(header.state_timestamp_ns).expect("Failed to set DRIVE_STATE_TIMESTAMP_NS");
+        });
+
+        COLD_ARCHIVE_IMPORT.with(|import| *import.borrow_mut() = None);
+        debug_log!("Activated cold archive {} of drive {}", header.archive_id, header.drive_id);
+        Ok(header)
+    }
+
+    pub fn abort_cold_archive_import(archive_id: &str) -> Result<(), ColdArchiveError> {
+        let staged: Vec<String> = COLD_ARCHIVE_IMPORT.with(|import| {
+            let mut import = import.borrow_mut();
+            match import.as_ref() {
+                Some(session) if session.header.archive_id == archive_id => {
+                    let staged = session.payload.as_ref()
+                        .map(|payload| payload.contents.iter().map(|entry| entry.hash.clone()).collect())
+                        .unwrap_or_default();
+                    *import = None;
+                    Ok(staged)
+                },
+                _ => Err(ColdArchiveError::NotFound),
+            }
+        })?;
+        discard_unreferenced_content(Some(staged.as_slice()));
+        Ok(())
+    }
+}
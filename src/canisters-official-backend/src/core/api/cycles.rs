@@ -0,0 +1,64 @@
+// src/core/api/cycles.rs
+
+// Timer driven cycles balance monitoring.
+//
+// Every tick records the balance as a sample, so the burn rate derived from the history
+// includes what update calls spent, not just the idle burn canister_status reports.
+// drive.low_cycles fires once when the drive crosses a threshold and is re-armed after
+// the balance recovers (eg. after a top-up).
+pub mod cycles {
+    use std::time::Duration;
+
+    use crate::{
+        core::{
+            api::webhooks::cycles::fire_low_cycles_webhook,
+            state::cycles::{
+                state::state::{cycles_report, record_cycles_sample, CYCLES_ALERT_STATE, CYCLES_MONITOR_CONFIG},
+                types::{CyclesAlertState, CyclesReport},
+            },
+        },
+        debug_log,
+    };
+
+    pub const CYCLES_SAMPLE_TICK_SECONDS: u64 = 60 * 60; // hourly
+
+    pub fn start_cycles_monitor_timer() {
+        // First sample right away so the history starts at install / upgrade time
+        ic_cdk_timers::set_timer(Duration::ZERO, || {
+            sample_cycles();
+        });
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(CYCLES_SAMPLE_TICK_SECONDS),
+            || {
+                sample_cycles();
+            },
+        );
+    }
+
+    pub fn sample_cycles() -> CyclesReport {
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let balance = ic_cdk::api::canister_balance();
+        record_cycles_sample(now_ms, balance);
+
+        let report = cycles_report(now_ms, balance);
+        let alerted = CYCLES_ALERT_STATE.with(|state| state.borrow().get().low_cycles_alerted_at_ms.is_some());
+
+        if report.low_cycles && !alerted {
+            debug_log!("Cycles balance {} is low, burning {} per day", report.balance, report.burn_rate_per_day);
+            let config = CYCLES_MONITOR_CONFIG.with(|config| config.borrow().get().clone());
+            fire_low_cycles_webhook(&report, &config);
+            set_low_cycles_alert(Some(now_ms));
+        } else if !report.low_cycles && alerted {
+            set_low_cycles_alert(None);
+        }
+        report
+    }
+
+    fn set_low_cycles_alert(alerted_at_ms: Option<u64>) {
+        CYCLES_ALERT_STATE.with(|state| {
+            state.borrow_mut()
+                .set(CyclesAlertState { low_cycles_alerted_at_ms: alerted_at_ms })
+                .expect("Failed to update CYCLES_ALERT_STATE");
+        });
+    }
+}
@@ -16,6 +16,19 @@ pub fn generate_s3_view_url(
     expires_in: Option<u64>,
     download_filename: Option<&str>,
     disk_id: DiskID
+) -> String {
+    // Construct the S3 key using the same format as upload
+    let drive_id = DRIVE_ID.with(|id| id.clone());
+    let s3_key = format!("{}/{}/{}/{}.{}", drive_id, disk_id, file_id, file_id, file_extension);
+
+    generate_s3_view_url_for_key(&s3_key, auth, expires_in)
+}
+
+// Presigned GET for any object key in the bucket, eg. archived file versions
+pub fn generate_s3_view_url_for_key(
+    s3_key: &str,
+    auth: &AwsBucketAuth,
+    expires_in: Option<u64>,
 ) -> String {
     let DEFAULT_EXPIRATION: u64 = 60 * 60 * 24; // 24 hours
     let current_time = ic_cdk::api::time();
@@ -33,10 +46,6 @@ pub fn generate_s3_view_url(
     // Host construction
     let host = format!("{}.s3.{}.amazonaws.com", auth.bucket, auth.region);
 
-    // Construct the S3 key using the same format as upload
-    let drive_id = DRIVE_ID.with(|id| id.clone());
-    let s3_key = format!("{}/{}/{}/{}.{}", drive_id, disk_id, file_id, file_id, file_extension);
-
     // Create content disposition string if filename provided
     let content_disposition = Some("inline".to_string());
     // let content_disposition = download_filename.map(|filename| {
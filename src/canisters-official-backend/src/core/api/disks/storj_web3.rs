@@ -30,6 +30,21 @@ pub fn generate_storj_view_url(
     expires_in: Option<u64>,
     download_filename: Option<&str>,
     disk_id: DiskID
+) -> String {
+    // Build the S3 key as before.
+    let drive_id = DRIVE_ID.with(|id| id.clone());
+    let s3_key = format!("{}/{}/{}/{}.{}", drive_id, disk_id, file_id, file_id, file_extension);
+
+    generate_storj_view_url_for_key(&s3_key, auth, expires_in)
+}
+
+//
+// VIEW URL: Presigned GET for any object key in the bucket, eg. archived file versions.
+//
+pub fn generate_storj_view_url_for_key(
+    s3_key: &str,
+    auth: &AwsBucketAuth,
+    expires_in: Option<u64>,
 ) -> String {
     let DEFAULT_EXPIRATION: u64 = 60 * 60 * 24; // 24 hours
     let current_time = ic_cdk::api::time();
@@ -45,9 +60,6 @@ pub fn generate_storj_view_url(
     let endpoint = auth.endpoint.trim_end_matches('/');
     let host = extract_host(endpoint);
 
-    // Build the S3 key as before.
-    let drive_id = DRIVE_ID.with(|id| id.clone());
-    let s3_key = format!("{}/{}/{}/{}.{}", drive_id, disk_id, file_id, file_id, file_extension);
     // For path‐style, the canonical URI includes the bucket.
     let canonical_uri = format!("/{}/{}", auth.bucket, s3_key);
 
@@ -5,13 +5,13 @@ pub mod drive {
     use crate::{
         core::{
             api::{
-                disks::{aws_s3::{copy_s3_object, generate_s3_upload_url}, storj_web3::generate_storj_upload_url}, internals::drive_internals::{ensure_folder_structure, fetch_root_shortcuts_of_user, format_file_asset_path, resolve_naming_conflict, sanitize_file_path, split_path, translate_path_to_id, update_folder_file_uuids, update_subfolder_paths}, permissions::directory::{check_directory_permissions, derive_directory_breadcrumbs, preview_directory_permissions}, types::DirectoryError, uuid::{generate_uuidv4, mark_claimed_uuid}
+                disks::{aws_s3::{copy_s3_object, generate_s3_upload_url}, storj_web3::generate_storj_upload_url}, internals::drive_internals::{ensure_folder_structure, fetch_root_shortcuts_of_user, format_file_asset_path, resolve_naming_conflict, sanitize_file_path, split_path, translate_path_to_id, update_folder_file_uuids, update_subfolder_paths}, permissions::directory::{check_directory_permissions, derive_directory_breadcrumbs, preview_directory_permissions}, types::{DirectoryError, DriveError}, uuid::{generate_uuidv4, mark_claimed_uuid}
             },
             state::{
                 directory::{
                     state::state::{file_uuid_to_metadata, file_version_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid, full_folder_path_to_uuid},
                     types::{DriveFullFilePath, FileID, FileRecord, FileVersionID, FolderID, FolderRecord}
-                }, disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskID, DiskTypeEnum}}, drives::{state::state::{update_external_id_mapping, DRIVE_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::PermissionGranteeID, raw_storage::types::UploadStatus
+                }, disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskID, DiskTypeEnum}}, drives::{state::state::{update_external_id_mapping, DRIVE_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::PermissionGranteeID, raw_storage::{state::copy_file_data, types::UploadStatus}, search::{state::state::{index_search_folder_tree, index_search_resource}, types::SearchResultResourceID}
             }, types::{ClientSuggestedUUID, ICPPrincipalString, IDPrefix, PublicKeyICP, UserID},
         }, debug_log, rest::{directory::types::{DirectoryActionResult, DirectoryListResponse, DirectoryResourceID, DiskUploadResponse, FileConflictResolutionEnum, ListDirectoryRequest, RestoreTrashPayload, RestoreTrashResponse}, webhooks::types::SortDirection}
     };
@@ -146,7 +146,7 @@ pub mod drive {
         external_payload: Option<ExternalPayload>,
         raw_url: Option<String>,
         notes: Option<String>,
-    ) -> Result<(FileRecord, DiskUploadResponse), String> {
+    ) -> Result<(FileRecord, DiskUploadResponse), DriveError> {
         let sanitized_file_path: String = sanitize_file_path(&file_path);
         let (folder_path, file_name) = split_path(&sanitized_file_path);
         
@@ -165,7 +165,7 @@ pub mod drive {
         if final_name.is_empty() && final_path.is_empty() {
             if let Some(existing_uuid) = full_file_path_to_uuid.get(&DriveFullFilePath(sanitized_file_path.clone())) {
                 // For KEEP_ORIGINAL we just return Err since we don't want to generate an upload URL
-                return Err("File already exists and resolution is KEEP_ORIGINAL".to_string());
+                return Err(DriveError::name_conflict("File already exists and resolution is KEEP_ORIGINAL"));
             }
         }
         
@@ -179,7 +179,7 @@ pub mod drive {
 
 
         if disk.disk_type != DiskTypeEnum::AwsBucket && disk.disk_type != DiskTypeEnum::StorjWeb3 && disk.disk_type != DiskTypeEnum::IcpCanister {
-            return Err("Only S3 buckets, Storj & ICP Canisters are supported for file uploads".to_string());
+            return Err("Only S3 buckets, Storj & ICP Canisters are supported for file uploads".to_string().into());
         }
         
         let full_directory_path = final_path;
@@ -292,7 +292,7 @@ pub mod drive {
     
                                 Ok((existing_file, upload_response))
                             },
-                            Err(e) => Err(e)
+                            Err(e) => Err(e.into())
                         };
                     }
                     (existing_file.file_version + 1, Some(existing_file.version_id.clone()))
@@ -344,6 +344,7 @@ pub mod drive {
             drive_id: DRIVE_ID.with(|id| id.clone()),
             expires_at,
             restore_trash_prior_folder_uuid: None,
+            trashed_at_ms: None,
             has_sovereign_permissions: has_sovereign_permissions.unwrap_or(false),
             shortcut_to,
             upload_status,
@@ -431,7 +432,7 @@ pub mod drive {
                 return Err(format!(
                     "Unsupported disk type for generating an upload URL: {:?}",
                     file_metadata.disk_type
-                ));
+                ).into());
             }
         };
         
@@ -442,6 +443,7 @@ pub mod drive {
             external_id,
             Some(file_id_to_use.0.clone())
         );
+        index_search_resource(SearchResultResourceID::File(file_id_to_use.clone()));
     
         Ok((file_metadata, upload_response))
     }
@@ -605,7 +607,7 @@ pub mod drive {
             .ok_or_else(|| "Folder not found".to_string())
     }
 
-    pub fn rename_folder(folder_id: FolderID, new_name: String) -> Result<FolderID, String> {
+    pub fn rename_folder(folder_id: FolderID, new_name: String) -> Result<FolderID, DriveError> {
         // Get current folder metadata
         let folder = folder_uuid_to_metadata
             .get(&folder_id)
@@ -620,7 +622,7 @@ pub mod drive {
         // Split the path into storage and folder parts
         let parts: Vec<&str> = path_string.splitn(2, "::").collect();
         if parts.len() != 2 {
-            return Err("Invalid folder structure".to_string());
+            return Err("Invalid folder structure".to_string().into());
         }
     
         let storage_part = parts[0].to_string();
@@ -631,7 +633,7 @@ pub mod drive {
         let (parent_path, _current_folder_name) = match path_parts.len() {
             2 => (path_parts[1].to_string(), path_parts[0].to_string()),
             1 => (String::new(), path_parts[0].to_string()),
-            _ => return Err("Invalid folder structure".to_string()),
+            _ => return Err("Invalid folder structure".to_string().into()),
         };
     
         // Construct the new folder path
@@ -643,7 +645,7 @@ pub mod drive {
     
         // Check if a folder with the new path already exists
         if full_folder_path_to_uuid.contains_key(&DriveFullFilePath(new_folder_path.clone())) {
-            return Err("A folder with the new name already exists in the parent directory".to_string());
+            return Err(DriveError::name_conflict("A folder with the new name already exists in the parent directory"));
         }
     
         // Update folder metadata using with_mut
@@ -681,15 +683,18 @@ pub mod drive {
                 });
             } else {
                 ic_cdk::println!("Parent folder not found for path: {}", parent_full_path);
-                return Err("Parent folder not found".to_string());
+                return Err("Parent folder not found".to_string().into());
             }
         }
     
+        // Every path below the folder changed along with its name
+        index_search_folder_tree(&folder_id);
+
         ic_cdk::println!("Folder renamed successfully");
         Ok(folder_id)
     }
     
-    pub fn rename_file(file_id: FileID, new_name: String) -> Result<FileID, String> {
+    pub fn rename_file(file_id: FileID, new_name: String) -> Result<FileID, DriveError> {
         ic_cdk::println!(
             "Attempting to rename file. File ID: {}, New Name: {}",
             file_id,
@@ -710,7 +715,7 @@ pub mod drive {
         // Split the path into storage part and the rest
         let parts: Vec<&str> = path_string.splitn(2, "::").collect();
         if parts.len() != 2 {
-            return Err("Invalid file structure".to_string());
+            return Err("Invalid file structure".to_string().into());
         }
     
         let storage_part = parts[0].to_string();
@@ -729,7 +734,7 @@ pub mod drive {
         // Check if a file with the new name already exists
         if full_file_path_to_uuid.contains_key(&DriveFullFilePath(new_path.clone())) {
             ic_cdk::println!("Error: A file with this name already exists");
-            return Err("A file with this name already exists".to_string());
+            return Err(DriveError::name_conflict("A file with this name already exists"));
         }
     
         // Update file metadata
@@ -760,6 +765,8 @@ pub mod drive {
         );
         full_file_path_to_uuid.insert(DriveFullFilePath(new_path), file_id.clone());
     
+        index_search_resource(SearchResultResourceID::File(file_id.clone()));
+
         ic_cdk::println!("File renamed successfully");
         Ok(file_id)
     }
@@ -831,7 +838,7 @@ pub mod drive {
                     }
                 });
             }
-
+            index_search_resource(SearchResultResourceID::Folder(folder_id.clone()));
             
             Ok(DriveFullFilePath("".to_string()))
         } else {
@@ -958,10 +965,10 @@ pub mod drive {
             // Only attempt to delete from storage if we can get disk info and auth
             match disk_type {
                 DiskTypeEnum::IcpCanister => {
-                    // For ICP canister storage, spawn a detached task to delete the raw data
-                    // This allows the main function to return quickly
+                    // For ICP canister storage, the raw data goes once the directory action commits,
+                    // a rolled back delete still has its bytes
                     let file_id_clone = file_id.0.clone();
-                    ic_cdk::spawn(async move {
+                    crate::core::state::directory::state::state::after_directory_commit(move || {
                         match crate::core::state::raw_storage::state::delete_file_data(&file_id_clone) {
                             Ok(_) => (),
                             Err(e) => ic_cdk::println!("Warning: Failed to delete file data from canister: {}", e),
@@ -985,25 +992,31 @@ pub mod drive {
                                 // Attempt to delete based on disk type
                                 match disk_type {
                                     DiskTypeEnum::AwsBucket => {
-                                        // Spawn this as a detached future to avoid blocking
+                                        // Spawned detached once the directory action commits, a rolled
+                                        // back delete still has its object
                                         let auth_clone = auth.clone();
                                         let s3_key_clone = s3_key.clone();
-                                        ic_cdk::spawn(async move {
-                                            match crate::core::api::disks::aws_s3::delete_s3_object(&s3_key_clone, &auth_clone).await {
-                                                Ok(_) => (),
-                                                Err(e) => ic_cdk::println!("Warning: Failed to delete S3 object: {}", e),
-                                            }
+                                        crate::core::state::directory::state::state::after_directory_commit(move || {
+                                            ic_cdk::spawn(async move {
+                                                match crate::core::api::disks::aws_s3::delete_s3_object(&s3_key_clone, &auth_clone).await {
+                                                    Ok(_) => (),
+                                                    Err(e) => ic_cdk::println!("Warning: Failed to delete S3 object: {}", e),
+                                                }
+                                            });
                                         });
                                     },
                                     DiskTypeEnum::StorjWeb3 => {
-                                        // Spawn this as a detached future to avoid blocking
+                                        // Spawned detached once the directory action commits, a rolled
+                                        // back delete still has its object
                                         let auth_clone = auth.clone();
                                         let s3_key_clone = s3_key.clone();
-                                        ic_cdk::spawn(async move {
-                                            match crate::core::api::disks::storj_web3::delete_storj_object(&s3_key_clone, &auth_clone).await {
-                                                Ok(_) => (),
-                                                Err(e) => ic_cdk::println!("Warning: Failed to delete Storj object: {}", e),
-                                            }
+                                        crate::core::state::directory::state::state::after_directory_commit(move || {
+                                            ic_cdk::spawn(async move {
+                                                match crate::core::api::disks::storj_web3::delete_storj_object(&s3_key_clone, &auth_clone).await {
+                                                    Ok(_) => (),
+                                                    Err(e) => ic_cdk::println!("Warning: Failed to delete Storj object: {}", e),
+                                                }
+                                            });
                                         });
                                     },
                                     _ => {
@@ -1025,6 +1038,9 @@ pub mod drive {
                 },
             }
             
+            // Archived versions go with the file
+            crate::core::api::versions::versions::purge_file_versions(file_id);
+
             // Handle version chain
             if let Some(prior_id) = &file.prior_version {
                 file_version_to_metadata.with_mut(|map| {
@@ -1062,6 +1078,7 @@ pub mod drive {
                 None,
                 Some(file_id.0.clone())
             );
+            index_search_resource(SearchResultResourceID::File(file_id.clone()));
     
             Ok(DriveFullFilePath("".to_string()))
         } else {
@@ -1167,6 +1184,13 @@ pub mod drive {
             });
         }
 
+        // Canister disks only link the chunks, the content itself is shared by hash
+        if source_file.disk_type == DiskTypeEnum::IcpCanister {
+            if let Err(e) = copy_file_data(&source_file.id.0, &new_file_uuid.0) {
+                debug_log!("copy_file: no raw data copied for {}: {}", source_file.id, e);
+            }
+        }
+
 
         // Create new metadata for the copy
         let mut new_file_metadata = source_file.clone();
@@ -1179,6 +1203,7 @@ pub mod drive {
         new_file_metadata.next_version = None;
         new_file_metadata.created_at = ic_cdk::api::time() / 1_000_000;
         new_file_metadata.last_updated_date_ms = ic_cdk::api::time() / 1_000_000;
+        new_file_metadata.trashed_at_ms = trashed_at_in(destination_folder);
         new_file_metadata.raw_url = format_file_asset_path(new_file_uuid.clone(), new_file_metadata.extension.clone());
 
         // Update metadata maps
@@ -1193,6 +1218,7 @@ pub mod drive {
                 map.insert(destination_folder.id.clone(), folder);
             }
         });
+        index_search_resource(SearchResultResourceID::File(new_file_uuid.clone()));
 
         Ok(new_file_metadata)
     }
@@ -1237,6 +1263,7 @@ pub mod drive {
         new_folder_metadata.file_uuids = Vec::new(); // Will be populated while copying files
         new_folder_metadata.created_at = ic_cdk::api::time() / 1_000_000;
         new_folder_metadata.last_updated_date_ms = ic_cdk::api::time() / 1_000_000;
+        new_folder_metadata.trashed_at_ms = trashed_at_in(destination_folder);
     
         // Update metadata maps
         folder_uuid_to_metadata.insert(new_folder_uuid.clone(), new_folder_metadata.clone());
@@ -1274,10 +1301,20 @@ pub mod drive {
                 });
             }
         }
+        // Copied subfolders and files were indexed by their own copy calls
+        index_search_resource(SearchResultResourceID::Folder(new_folder_uuid.clone()));
     
         Ok(new_folder_metadata)
     }
     
+    // Items placed directly in a disk's .trash start their retention clock now, anywhere else they have none
+    fn trashed_at_in(destination_folder: &FolderRecord) -> Option<u64> {
+        match destination_folder.full_directory_path.0.ends_with("::.trash/") {
+            true => Some(ic_cdk::api::time() / 1_000_000),
+            false => None,
+        }
+    }
+
     pub fn move_file(
         file_id: &FileID,
         destination_folder: &FolderRecord,
@@ -1319,6 +1356,7 @@ pub mod drive {
                 file.parent_folder_uuid = destination_folder.id.clone();
                 file.full_directory_path = DriveFullFilePath(final_path.clone());
                 file.last_updated_date_ms = ic_cdk::api::time() / 1_000_000;
+                file.trashed_at_ms = trashed_at_in(destination_folder);
                 map.insert(file_id.clone(), file);
             }
         });
@@ -1343,6 +1381,7 @@ pub mod drive {
                 map.insert(destination_folder.id.clone(), folder);
             }
         });
+        index_search_resource(SearchResultResourceID::File(file_id.clone()));
     
         Ok(file_uuid_to_metadata.get(file_id).unwrap().clone())
     }
@@ -1395,6 +1434,7 @@ pub mod drive {
                 folder.parent_folder_uuid = Some(destination_folder.id.clone());
                 folder.full_directory_path = DriveFullFilePath(final_path.clone());
                 folder.last_updated_date_ms = ic_cdk::api::time() / 1_000_000;
+                folder.trashed_at_ms = trashed_at_in(destination_folder);
                 map.insert(folder_id.clone(), folder);
             }
         });
@@ -1432,6 +1472,9 @@ pub mod drive {
             .get(folder_id)
             .ok_or_else(|| "Failed to retrieve updated folder metadata".to_string())?;
         debug_log!("move_folder: Finished moving folder. New metadata: {:?}", updated_folder);
+
+        // Every path below the folder changed with the move
+        index_search_folder_tree(folder_id);
     
         Ok(updated_folder.clone())
     }
@@ -3,7 +3,7 @@ pub mod drive_internals {
     use std::collections::{HashSet, VecDeque};
 
     use crate::{
-        core::{api::{drive::drive::get_folder_by_id, helpers::get_appropriate_url_endpoint, permissions::directory::derive_breadcrumb_visibility_previews, types::{DirectoryError, DirectoryIDError}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid, full_folder_path_to_uuid}, types::{DriveFullFilePath, FileID, FolderID, FolderRecord, PathTranslationResponse}}, disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskID, DiskTypeEnum}}, drives::{state::state::DRIVE_ID, types::{DriveID, ExternalID, ExternalPayload}}, group_invites::{state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, types::GroupInviteeID}, groups::{state::state::GROUPS_BY_ID_HASHTABLE, types::GroupID}, permissions::{state::state::{DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE, DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE}, types::{DirectoryPermission, DirectoryPermissionType, PermissionGranteeID, PlaceholderPermissionGranteeID, PUBLIC_GRANTEE_ID}}}, types::{ClientSuggestedUUID, ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::directory::types::{DirectoryListResponse, DirectoryResourceID, FileConflictResolutionEnum, FilePathBreadcrumb, ListDirectoryRequest}, 
+        core::{api::{drive::drive::get_folder_by_id, helpers::get_appropriate_url_endpoint, permissions::directory::derive_breadcrumb_visibility_previews, types::{DirectoryError, DirectoryIDError}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid, full_folder_path_to_uuid}, types::{DriveFullFilePath, FileID, FolderID, FolderRecord, PathTranslationResponse}}, disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskID, DiskTypeEnum}}, drives::{state::state::DRIVE_ID, types::{DriveID, ExternalID, ExternalPayload}}, group_invites::{state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, types::GroupInviteeID}, groups::{state::state::GROUPS_BY_ID_HASHTABLE, types::GroupID}, search::{state::state::index_search_resource, types::SearchResultResourceID}, permissions::{state::state::{DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE, DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE}, types::{DirectoryPermission, DirectoryPermissionType, PermissionGranteeID, PlaceholderPermissionGranteeID, PUBLIC_GRANTEE_ID}}}, types::{ClientSuggestedUUID, ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::directory::types::{DirectoryListResponse, DirectoryResourceID, FileConflictResolutionEnum, FilePathBreadcrumb, ListDirectoryRequest}, 
         
     };
     
@@ -38,6 +38,7 @@ pub mod drive_internals {
                 name: String::new(),
                 parent_folder_uuid: None,
                 restore_trash_prior_folder_uuid: None,
+                trashed_at_ms: None,
                 subfolder_uuids: Vec::new(),
                 file_uuids: Vec::new(),
                 full_directory_path: root_path.clone(),
@@ -72,6 +73,7 @@ pub mod drive_internals {
                 name: ".trash".to_string(),
                 parent_folder_uuid: Some(root_uuid.clone()),
                 restore_trash_prior_folder_uuid: None,
+                trashed_at_ms: None,
                 subfolder_uuids: Vec::new(),
                 file_uuids: Vec::new(),
                 full_directory_path: trash_path.clone(),
@@ -222,6 +224,7 @@ folder_uuid_to_metadata.with_mut(|map| {
                     drive_id: drive_id.clone(),
                     expires_at: -1,
                     restore_trash_prior_folder_uuid: None,
+                    trashed_at_ms: None,
                     shortcut_to: if is_final_folder {
                         shortcut_to.clone()
                     } else {
@@ -266,6 +269,7 @@ folder_uuid_to_metadata.with_mut(|map| {
                         map.insert(parent_uuid.clone(), parent_folder);
                     }
                 });
+                index_search_resource(SearchResultResourceID::Folder(new_folder_uuid.clone()));
 
                 parent_uuid = new_folder_uuid;
             } else {
@@ -7,4 +7,10 @@ pub mod actions;
 pub mod permissions;
 pub mod webhooks;
 pub mod replay;
-pub mod helpers;
\ No newline at end of file
+pub mod helpers;
+pub mod versions;
+pub mod trash;
+pub mod uploads;
+pub mod cycles;
+pub mod archive;
+pub mod streaming;
\ No newline at end of file
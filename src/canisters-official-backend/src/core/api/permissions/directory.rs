@@ -2,7 +2,9 @@
 
 use std::collections::{HashSet, VecDeque};
 
-use crate::{core::{api::{internals::drive_internals::is_user_in_group, types::DirectoryIDError}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::{DriveFullFilePath, FileID, FolderID}}, disks::state::state::DISKS_BY_ID_HASHTABLE, drives::state::state::OWNER_ID, groups::{state::state::is_user_on_group, types::GroupID}, permissions::{state::{helpers::{get_directory_permission_by_id, get_directory_permission_ids_for_resource}, state::{DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE}}, types::{BreadcrumbVisibilityPreview, DirectoryPermission, DirectoryPermissionType, PermissionGranteeID, PlaceholderPermissionGranteeID, PUBLIC_GRANTEE_ID}}}, types::UserID}, rest::directory::types::{DirectoryResourceID, DirectoryResourcePermissionFE, FilePathBreadcrumb}};
+use sha2::{Digest, Sha256};
+
+use crate::{core::{api::{internals::drive_internals::is_user_in_group, types::DirectoryIDError, uuid::generate_uuidv4}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::{DriveFullFilePath, FileID, FolderID}}, disks::state::state::DISKS_BY_ID_HASHTABLE, drives::state::state::OWNER_ID, groups::{state::state::is_user_on_group, types::GroupID}, permissions::{state::{helpers::{get_directory_permission_by_id, get_directory_permission_ids_for_resource}, state::{DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE}}, types::{BreadcrumbVisibilityPreview, DirectoryPermission, DirectoryPermissionType, PermissionGranteeID, PermissionMetadata, PermissionMetadataContent, PlaceholderPermissionGranteeID, PUBLIC_GRANTEE_ID}}}, types::{IDPrefix, UserID}}, debug_log, rest::directory::types::{DirectoryResourceID, DirectoryResourcePermissionFE, FilePathBreadcrumb}};
 
 
 // Check if a user can CRUD the permission record
@@ -50,11 +52,37 @@ pub fn can_user_access_directory_permission(
     false
 }
 
+// How PUBLIC grants carrying a DirectoryPassword are treated while collecting permissions
+#[derive(Clone, Copy)]
+enum DirectoryPasswordGate<'a> {
+    Ignore,                   // grant applies regardless of its password
+    Require(Option<&'a str>), // grant only applies when the supplied password matches
+}
+
 // check what kind of permission a specific user has on a specific resource
 pub async fn check_directory_permissions(
     resource_id: DirectoryResourceID,
     grantee_id: PermissionGranteeID,
 ) -> Vec<DirectoryPermissionType> {
+    collect_directory_permissions(resource_id, grantee_id, DirectoryPasswordGate::Ignore).await
+}
+
+// Same as check_directory_permissions, except password protected PUBLIC grants only count
+// when `password` matches their DirectoryPassword metadata. Used where a resource is served
+// to whoever holds the link, like the raw asset route.
+pub async fn check_directory_permissions_with_password(
+    resource_id: DirectoryResourceID,
+    grantee_id: PermissionGranteeID,
+    password: Option<&str>,
+) -> Vec<DirectoryPermissionType> {
+    collect_directory_permissions(resource_id, grantee_id, DirectoryPasswordGate::Require(password)).await
+}
+
+async fn collect_directory_permissions(
+    resource_id: DirectoryResourceID,
+    grantee_id: PermissionGranteeID,
+    password_gate: DirectoryPasswordGate<'_>,
+) -> Vec<DirectoryPermissionType> {
 
     let is_owner = OWNER_ID.with(|owner_id| UserID(grantee_id.to_string()) == *owner_id.borrow().get());
 
@@ -78,7 +106,8 @@ pub async fn check_directory_permissions(
         let resource_permissions = check_directory_resource_permissions(
             &resource, 
             &grantee_id,
-            resource != resource_id
+            resource != resource_id,
+            password_gate,
         ).await;
         all_permissions.extend(resource_permissions);
     }
@@ -146,6 +175,7 @@ async fn check_directory_resource_permissions(
     resource_id: &DirectoryResourceID,
     grantee_id: &PermissionGranteeID,
     is_parent_for_inheritance: bool,
+    password_gate: DirectoryPasswordGate<'_>,
 ) -> HashSet<DirectoryPermissionType> {
     let mut permissions_set = HashSet::new();
     
@@ -185,7 +215,7 @@ async fn check_directory_resource_permissions(
 
         // Check if permission applies to this grantee
         let applies = match &permission_granted_to {
-            PermissionGranteeID::Public => true,
+            PermissionGranteeID::Public => public_password_matches(&permission, password_gate),
             PermissionGranteeID::User(permission_user_id) => {
                 if let PermissionGranteeID::User(request_grantee_id) = grantee_id {
                     permission_user_id.0 == request_grantee_id.0
@@ -221,6 +251,93 @@ async fn check_directory_resource_permissions(
     permissions_set
 }
 
+fn public_password_matches(permission: &DirectoryPermission, password_gate: DirectoryPasswordGate<'_>) -> bool {
+    let required = match &permission.metadata {
+        Some(metadata) => match &metadata.content {
+            PermissionMetadataContent::DirectoryPassword(password) if !password.is_empty() => password,
+            _ => return true,
+        },
+        None => return true,
+    };
+    match password_gate {
+        DirectoryPasswordGate::Ignore => true,
+        DirectoryPasswordGate::Require(supplied) => supplied.map_or(false, |supplied| directory_password_matches(required, supplied)),
+    }
+}
+
+// Directory passwords are stored as "sha256$<salt hex>$<sha256(salt || password) hex>"
+const DIRECTORY_PASSWORD_HASH_PREFIX: &str = "sha256$";
+
+fn salted_password_digest(salt: &[u8], password: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(password.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+fn parse_directory_password_hash(stored: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (salt, digest) = stored.strip_prefix(DIRECTORY_PASSWORD_HASH_PREFIX)?.split_once('$')?;
+    let salt = hex::decode(salt).ok()?;
+    let digest = hex::decode(digest).ok()?;
+    (salt.len() == 16 && digest.len() == 32).then_some((salt, digest))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+pub fn hash_directory_password(password: &str) -> String {
+    // The salt only has to be unique, so it comes from the uuid generator instead of raw_rand
+    let mut hasher = Sha256::new();
+    hasher.update(generate_uuidv4(IDPrefix::DirectoryPermission).as_bytes());
+    let salt = hasher.finalize()[..16].to_vec();
+    format!("{}{}${}", DIRECTORY_PASSWORD_HASH_PREFIX, hex::encode(&salt), hex::encode(salted_password_digest(&salt, password)))
+}
+
+// Stored values that are not a hash are plaintext from before hashing, eg. in an older archive
+pub fn directory_password_matches(stored: &str, supplied: &str) -> bool {
+    match parse_directory_password_hash(stored) {
+        Some((salt, digest)) => constant_time_eq(&salted_password_digest(&salt, supplied), &digest),
+        None => constant_time_eq(&salted_password_digest(&[], stored), &salted_password_digest(&[], supplied)),
+    }
+}
+
+/// Replaces a plaintext DirectoryPassword in request metadata with its salted hash
+pub fn hash_directory_password_metadata(metadata: Option<PermissionMetadata>) -> Option<PermissionMetadata> {
+    metadata.map(|metadata| match metadata.content {
+        PermissionMetadataContent::DirectoryPassword(password) if !password.is_empty() => PermissionMetadata {
+            metadata_type: metadata.metadata_type,
+            content: PermissionMetadataContent::DirectoryPassword(hash_directory_password(&password)),
+        },
+        _ => metadata,
+    })
+}
+
+/// Hashes the plaintext directory passwords stored before they were hashed. Runs on every upgrade,
+/// permissions that already hold a hash are left alone.
+pub fn migrate_directory_password_hashes() -> usize {
+    let plaintext: Vec<DirectoryPermission> = DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
+        permissions.borrow().iter()
+            .map(|(_, permission)| permission)
+            .filter(|permission| matches!(
+                permission.metadata.as_ref().map(|metadata| &metadata.content),
+                Some(PermissionMetadataContent::DirectoryPassword(password)) if !password.is_empty() && parse_directory_password_hash(password).is_none()
+            ))
+            .collect()
+    });
+    let migrated = plaintext.len();
+    for mut permission in plaintext {
+        permission.metadata = hash_directory_password_metadata(permission.metadata.take());
+        DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
+            permissions.borrow_mut().insert(permission.id.clone(), permission);
+        });
+    }
+    if migrated > 0 {
+        debug_log!("migrate_directory_password_hashes: hashed {} directory passwords", migrated);
+    }
+    migrated
+}
+
 pub async fn has_directory_manage_permission(user_id: &UserID, resource_id: &DirectoryResourceID) -> bool {
     // Use our existing check_directory_permissions which already handles inheritance
     let permissions = check_directory_permissions(
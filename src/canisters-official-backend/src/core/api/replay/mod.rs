@@ -1 +1,2 @@
-pub mod diff;
\ No newline at end of file
+pub mod diff;
+pub mod snapshot;
\ No newline at end of file
@@ -0,0 +1,655 @@
+// src/core/api/replay/snapshot.rs
+
+// Paged snapshots for drives whose state does not fit in one response.
+//
+// A snapshot session only remembers the owner and the checksum and timestamp of the drive
+// when it started. Every page is read straight from the stable tables: map tables resume
+// after the last key of the previous page, list tables after its last position, and plain
+// values come as a single page. Nothing is copied onto the heap between pages, so once the
+// drive changes the session is dropped and the next page answers DriveChanged.
+//
+// Tables cover EntireState (in the JSON shape of /organization/snapshot) and the extra
+// tables a cold archive carries. snapshot_excluded_tables() lists what is left out.
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    ops::Bound,
+};
+
+use ic_stable_structures::{memory_manager::VirtualMemory, DefaultMemoryImpl, StableBTreeMap, StableVec, Storable};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::core::{
+    api::archive::archive::COLD_ARCHIVE_EXCLUDED_TABLES,
+    state::{
+        api_keys::state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE, USERS_APIKEYS_HASHTABLE},
+        contacts::state::state::{CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE, CONTACTS_BY_ID_HASHTABLE, CONTACTS_BY_TIME_LIST, HISTORY_SUPERSWAP_USERID},
+        cycles::state::state::CYCLES_MONITOR_CONFIG,
+        directory::state::state::{file_uuid_to_metadata, file_version_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid, full_folder_path_to_uuid},
+        disks::state::state::{DISKS_BY_ID_HASHTABLE, DISKS_BY_TIME_LIST},
+        drives::state::state::{
+            ALLOWED_DOMAINS, CANISTER_ID, DRIVES_BY_ID_HASHTABLE, DRIVES_BY_TIME_LIST, DRIVE_ID, DRIVE_STATE_CHECKSUM,
+            DRIVE_STATE_TIMESTAMP_NS, EXTERNAL_ID_MAPPINGS, NONCE_UUID_GENERATED, OWNER_ID, RECENT_DEPLOYMENTS,
+            SPAWN_NOTE, SPAWN_REDEEM_CODE, URL_ENDPOINT, UUID_CLAIMED, VERSION,
+        },
+        group_invites::{
+            state::state::{INVITES_BY_ID_HASHTABLE, INVITES_BY_TIME_LIST, USERS_INVITES_LIST_HASHTABLE},
+            types::GroupInviteeID,
+        },
+        groups::state::state::{DEFAULT_EVERYONE_GROUP, GROUPS_BY_ID_HASHTABLE, GROUPS_BY_TIME_LIST},
+        idempotency::state::state::IDEMPOTENCY_CONFIG,
+        inbox::state::state::{INBOX_NOTIFS_BY_ID_HASHTABLE, INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE},
+        labels::state::{LABELS_BY_ID_HASHTABLE, LABELS_BY_TIME_LIST, LABELS_BY_VALUE_HASHTABLE},
+        permissions::{
+            state::state::{
+                DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE, DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE,
+                DIRECTORY_PERMISSIONS_BY_TIME_LIST, SYSTEM_GRANTEE_PERMISSIONS_HASHTABLE, SYSTEM_PERMISSIONS_BY_ID_HASHTABLE,
+                SYSTEM_PERMISSIONS_BY_RESOURCE_HASHTABLE, SYSTEM_PERMISSIONS_BY_TIME_LIST,
+            },
+            types::{PermissionGranteeID, SystemResourceID},
+        },
+        purchases::state::state::{PURCHASES_BY_ID_HASHTABLE, PURCHASES_BY_TIME_LIST, PURCHASES_BY_VENDOR_ID_HASHTABLE},
+        rate_limits::state::state::RATE_LIMIT_CONFIG,
+        webhooks::state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE, WEBHOOKS_BY_TIME_LIST},
+    },
+    types::{IDPrefix, UserID},
+};
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+pub const SNAPSHOT_SESSION_TTL_MS: u64 = 60 * 60 * 1000;
+pub const MAX_SNAPSHOT_SESSIONS: usize = 3;
+pub const DEFAULT_SNAPSHOT_PAGE_SIZE: usize = 500;
+pub const MAX_SNAPSHOT_PAGE_SIZE: usize = 5000;
+// Serialized size a page stops growing at, well under the response limit
+pub const MAX_SNAPSHOT_PAGE_BYTES: usize = 1_000_000;
+
+// Stable tables that no snapshot page covers, on top of the ones a cold archive leaves out
+pub const SNAPSHOT_ONLY_EXCLUDED_TABLES: &[(&str, &str)] = &[
+    ("CHUNKS", "raw file records, exported with /organization/archive/export"),
+    ("CHUNK_CONTENT", "raw file bytes, exported with /organization/archive/export"),
+    ("FILE_META", "raw file names, exported with /organization/archive/export"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SnapshotTableKind {
+    Map,
+    List,
+    Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotTableInfo {
+    pub name: String,
+    pub kind: SnapshotTableKind,
+    pub total: usize,
+}
+
+// Point in time the snapshot was taken at, repeated on every page
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotMarker {
+    pub snapshot_id: String,
+    pub state_checksum: String,
+    pub state_timestamp_ns: u64,
+    pub created_at_ms: u64,
+    pub expires_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotPage {
+    pub marker: SnapshotMarker,
+    pub table: String,
+    pub kind: SnapshotTableKind,
+    pub total: usize,
+    pub offset: usize,
+    // An object for map tables, an array for list tables, the value itself otherwise
+    pub items: Value,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotPageError {
+    NotFound,
+    InvalidCursor,
+    // The checksum or timestamp moved since the snapshot started, the session is gone
+    DriveChanged,
+}
+
+struct SnapshotSession {
+    marker: SnapshotMarker,
+    owner: UserID,
+    // Only cursors handed out by this session are accepted, so their keys always decode
+    issued_cursors: HashSet<String>,
+}
+
+// One page worth of a table
+struct SnapshotRead {
+    items: Value,
+    count: usize,
+    // Where the next page resumes, None once the table is exhausted
+    resume: Option<Vec<u8>>,
+}
+
+struct SnapshotTable {
+    name: &'static str,
+    kind: SnapshotTableKind,
+    total: fn() -> usize,
+    read: fn(Option<&[u8]>, usize) -> SnapshotRead,
+}
+
+thread_local! {
+    static SNAPSHOT_SESSIONS: RefCell<Vec<SnapshotSession>> = RefCell::new(Vec::new());
+    static SNAPSHOT_SEQUENCE: Cell<u64> = Cell::new(0);
+}
+
+fn now_ms() -> u64 {
+    ic_cdk::api::time() / 1_000_000
+}
+
+// Cursors are "{table}:{offset}:{resume position in hex}", opaque to clients
+fn encode_cursor(table: usize, offset: usize, resume: &[u8]) -> String {
+    format!("{}:{}:{}", table, offset, hex::encode(resume))
+}
+
+fn decode_cursor(cursor: &str) -> Option<(usize, usize, Vec<u8>)> {
+    let mut parts = cursor.splitn(3, ':');
+    let table = parts.next()?.parse().ok()?;
+    let offset = parts.next()?.parse().ok()?;
+    let resume = hex::decode(parts.next()?).ok()?;
+    Some((table, offset, resume))
+}
+
+// Not generate_uuidv4, which would write to the state being snapshotted
+fn next_snapshot_id() -> String {
+    let sequence = SNAPSHOT_SEQUENCE.with(|seq| {
+        seq.set(seq.get() + 1);
+        seq.get()
+    });
+    format!("{}{}_{}", IDPrefix::Snapshot.as_str(), ic_cdk::api::time(), sequence)
+}
+
+fn json_size(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(0, |v| v.len())
+}
+
+// Entries strictly after `after`, in key order
+fn read_map<K, V>(
+    map: &StableBTreeMap<K, V, Memory>,
+    after: Option<&[u8]>,
+    page_size: usize,
+    entry: impl Fn(&K, V) -> (String, Value),
+) -> SnapshotRead
+where
+    K: Storable + Ord + Clone,
+    V: Storable,
+{
+    let start = match after {
+        Some(bytes) => Bound::Excluded(K::from_bytes(Cow::Borrowed(bytes))),
+        None => Bound::Unbounded,
+    };
+    let mut items = serde_json::Map::new();
+    let mut count = 0;
+    let mut bytes = 0;
+    let mut last: Option<K> = None;
+    let mut more = false;
+    for (key, value) in map.range((start, Bound::Unbounded)) {
+        if count >= page_size {
+            more = true;
+            break;
+        }
+        let (name, value) = entry(&key, value);
+        let size = name.len() + json_size(&value);
+        if count > 0 && bytes + size > MAX_SNAPSHOT_PAGE_BYTES {
+            more = true;
+            break;
+        }
+        bytes += size;
+        items.insert(name, value);
+        count += 1;
+        last = Some(key);
+    }
+    SnapshotRead {
+        items: Value::Object(items),
+        count,
+        resume: if more { last.map(|key| key.to_bytes().into_owned()) } else { None },
+    }
+}
+
+// Items from position `after` on, the position is a big endian u64
+fn read_list(len: u64, after: Option<&[u8]>, page_size: usize, item: impl Fn(u64) -> Option<Value>) -> SnapshotRead {
+    let mut index = after
+        .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+        .map_or(0, u64::from_be_bytes);
+    let mut items = Vec::new();
+    let mut bytes = 0;
+    while index < len && items.len() < page_size {
+        if let Some(value) = item(index) {
+            let size = json_size(&value);
+            if !items.is_empty() && bytes + size > MAX_SNAPSHOT_PAGE_BYTES {
+                break;
+            }
+            bytes += size;
+            items.push(value);
+        }
+        index += 1;
+    }
+    SnapshotRead {
+        count: items.len(),
+        items: Value::Array(items),
+        resume: if index < len { Some(index.to_be_bytes().to_vec()) } else { None },
+    }
+}
+
+fn read_vec<T: Storable>(vec: &StableVec<T, Memory>, after: Option<&[u8]>, page_size: usize, item: impl Fn(T) -> Value) -> SnapshotRead {
+    read_list(vec.len(), after, page_size, |index| vec.get(index).map(&item))
+}
+
+fn read_value(value: Value) -> SnapshotRead {
+    SnapshotRead { items: value, count: 1, resume: None }
+}
+
+fn grantee_key(grantee: &PermissionGranteeID) -> String {
+    match grantee {
+        PermissionGranteeID::User(user_id) => format!("user:{}", user_id.0),
+        PermissionGranteeID::Group(group_id) => format!("group:{}", group_id.0),
+        PermissionGranteeID::Public => "public".to_string(),
+        PermissionGranteeID::PlaceholderDirectoryPermissionGrantee(_) => "placeholder".to_string(),
+    }
+}
+
+fn invitee_key(invitee: &GroupInviteeID) -> String {
+    match invitee {
+        GroupInviteeID::User(user_id) => format!("user:{}", user_id.0),
+        GroupInviteeID::PlaceholderGroupInvitee(_) | GroupInviteeID::Public => "public".to_string(),
+    }
+}
+
+fn ids<T>(items: &[T], id: impl Fn(&T) -> String) -> Value {
+    json!(items.iter().map(id).collect::<Vec<String>>())
+}
+
+// The order here is the order pages come in, cursors refer to tables by position
+fn snapshot_tables() -> Vec<SnapshotTable> {
+    use SnapshotTableKind::{List, Map, Value as Plain};
+    vec![
+        // About
+        SnapshotTable { name: "DRIVE_ID", kind: Plain, total: || 1, read: |_, _| read_value(DRIVE_ID.with(|id| json!(id))) },
+        SnapshotTable { name: "CANISTER_ID", kind: Plain, total: || 1, read: |_, _| read_value(CANISTER_ID.with(|id| json!(id))) },
+        SnapshotTable { name: "VERSION", kind: Plain, total: || 1, read: |_, _| read_value(VERSION.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "OWNER_ID", kind: Plain, total: || 1, read: |_, _| read_value(OWNER_ID.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "URL_ENDPOINT", kind: Plain, total: || 1, read: |_, _| read_value(URL_ENDPOINT.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "DRIVE_STATE_TIMESTAMP_NS", kind: Plain, total: || 1, read: |_, _| read_value(DRIVE_STATE_TIMESTAMP_NS.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "SPAWN_REDEEM_CODE", kind: Plain, total: || 1, read: |_, _| read_value(SPAWN_REDEEM_CODE.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "SPAWN_NOTE", kind: Plain, total: || 1, read: |_, _| read_value(SPAWN_NOTE.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "NONCE_UUID_GENERATED", kind: Plain, total: || 1, read: |_, _| read_value(NONCE_UUID_GENERATED.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable {
+            name: "RECENT_DEPLOYMENTS", kind: List,
+            total: || RECENT_DEPLOYMENTS.with(|v| v.borrow().len() as usize),
+            read: |after, size| RECENT_DEPLOYMENTS.with(|v| read_vec(&v.borrow(), after, size, |record| json!(record))),
+        },
+        SnapshotTable {
+            name: "EXTERNAL_ID_MAPPINGS", kind: Map,
+            total: || EXTERNAL_ID_MAPPINGS.with(|m| m.borrow().len() as usize),
+            read: |after, size| EXTERNAL_ID_MAPPINGS.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v.items)))),
+        },
+        SnapshotTable {
+            name: "UUID_CLAIMED", kind: Map,
+            total: || UUID_CLAIMED.with(|m| m.borrow().len() as usize),
+            read: |after, size| UUID_CLAIMED.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.clone(), json!(v)))),
+        },
+        // Api Keys
+        SnapshotTable {
+            name: "APIKEYS_BY_VALUE_HASHTABLE", kind: Map,
+            total: || APIKEYS_BY_VALUE_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| APIKEYS_BY_VALUE_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v.0)))),
+        },
+        SnapshotTable {
+            name: "APIKEYS_BY_ID_HASHTABLE", kind: Map,
+            total: || APIKEYS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| APIKEYS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "USERS_APIKEYS_HASHTABLE", kind: Map,
+            total: || USERS_APIKEYS_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| USERS_APIKEYS_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), ids(&v.keys, |id| id.0.clone())))),
+        },
+        // Contacts
+        SnapshotTable {
+            name: "CONTACTS_BY_ID_HASHTABLE", kind: Map,
+            total: || CONTACTS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| CONTACTS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE", kind: Map,
+            total: || CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.0.clone(), json!(v.0)))),
+        },
+        SnapshotTable {
+            name: "CONTACTS_BY_TIME_LIST", kind: List,
+            total: || CONTACTS_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| CONTACTS_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        SnapshotTable {
+            name: "HISTORY_SUPERSWAP_USERID", kind: Map,
+            total: || HISTORY_SUPERSWAP_USERID.with(|m| m.borrow().len() as usize),
+            read: |after, size| HISTORY_SUPERSWAP_USERID.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v.0)))),
+        },
+        // Directory
+        SnapshotTable {
+            name: "folder_uuid_to_metadata", kind: Map,
+            total: || folder_uuid_to_metadata.with(|m| m.len() as usize),
+            read: |after, size| folder_uuid_to_metadata.with(|m| read_map(m, after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "file_uuid_to_metadata", kind: Map,
+            total: || file_uuid_to_metadata.with(|m| m.len() as usize),
+            read: |after, size| file_uuid_to_metadata.with(|m| read_map(m, after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "file_version_to_metadata", kind: Map,
+            total: || file_version_to_metadata.with(|m| m.len() as usize),
+            read: |after, size| file_version_to_metadata.with(|m| read_map(m, after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "full_folder_path_to_uuid", kind: Map,
+            total: || full_folder_path_to_uuid.with(|m| m.len() as usize),
+            read: |after, size| full_folder_path_to_uuid.with(|m| read_map(m, after, size, |k, v| (k.0.clone(), json!(v.0)))),
+        },
+        SnapshotTable {
+            name: "full_file_path_to_uuid", kind: Map,
+            total: || full_file_path_to_uuid.with(|m| m.len() as usize),
+            read: |after, size| full_file_path_to_uuid.with(|m| read_map(m, after, size, |k, v| (k.0.clone(), json!(v.0)))),
+        },
+        // Disks
+        SnapshotTable {
+            name: "DISKS_BY_ID_HASHTABLE", kind: Map,
+            total: || DISKS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| DISKS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "DISKS_BY_TIME_LIST", kind: List,
+            total: || DISKS_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| DISKS_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        // Drives
+        SnapshotTable {
+            name: "DRIVES_BY_ID_HASHTABLE", kind: Map,
+            total: || DRIVES_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| DRIVES_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "DRIVES_BY_TIME_LIST", kind: List,
+            total: || DRIVES_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| DRIVES_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        SnapshotTable { name: "ALLOWED_DOMAINS", kind: Plain, total: || 1, read: |_, _| read_value(ALLOWED_DOMAINS.with(|v| json!(v.borrow().get()))) },
+        // Permissions
+        SnapshotTable {
+            name: "DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE", kind: Map,
+            total: || DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE", kind: Map,
+            total: || DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                (format!("resource:{}", k), ids(&v.permissions, |id| id.0.clone()))
+            })),
+        },
+        SnapshotTable {
+            name: "DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE", kind: Map,
+            total: || DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                (grantee_key(k), ids(&v.permissions, |id| id.0.clone()))
+            })),
+        },
+        SnapshotTable {
+            name: "DIRECTORY_PERMISSIONS_BY_TIME_LIST", kind: List,
+            total: || DIRECTORY_PERMISSIONS_BY_TIME_LIST.with(|list| list.borrow().permissions.len()),
+            read: |after, size| DIRECTORY_PERMISSIONS_BY_TIME_LIST.with(|list| {
+                let list = list.borrow();
+                read_list(list.permissions.len() as u64, after, size, |index| list.permissions.get(index as usize).map(|id| json!(id.0)))
+            }),
+        },
+        SnapshotTable {
+            name: "SYSTEM_PERMISSIONS_BY_ID_HASHTABLE", kind: Map,
+            total: || SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "SYSTEM_PERMISSIONS_BY_RESOURCE_HASHTABLE", kind: Map,
+            total: || SYSTEM_PERMISSIONS_BY_RESOURCE_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| SYSTEM_PERMISSIONS_BY_RESOURCE_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                let resource_key = match k {
+                    SystemResourceID::Table(table) => format!("table:{:?}", table),
+                    SystemResourceID::Record(record) => format!("record:{:?}", record),
+                };
+                (resource_key, ids(&v.permissions, |id| id.0.clone()))
+            })),
+        },
+        SnapshotTable {
+            name: "SYSTEM_GRANTEE_PERMISSIONS_HASHTABLE", kind: Map,
+            total: || SYSTEM_GRANTEE_PERMISSIONS_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| SYSTEM_GRANTEE_PERMISSIONS_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                (grantee_key(k), ids(&v.permissions, |id| id.0.clone()))
+            })),
+        },
+        SnapshotTable {
+            name: "SYSTEM_PERMISSIONS_BY_TIME_LIST", kind: List,
+            total: || SYSTEM_PERMISSIONS_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| SYSTEM_PERMISSIONS_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        // Group Invites
+        SnapshotTable {
+            name: "INVITES_BY_ID_HASHTABLE", kind: Map,
+            total: || INVITES_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| INVITES_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "INVITES_BY_TIME_LIST", kind: List,
+            total: || INVITES_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| INVITES_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        SnapshotTable {
+            name: "USERS_INVITES_LIST_HASHTABLE", kind: Map,
+            total: || USERS_INVITES_LIST_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| USERS_INVITES_LIST_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                (invitee_key(k), ids(&v.invites, |id| id.0.clone()))
+            })),
+        },
+        // Groups
+        SnapshotTable {
+            name: "GROUPS_BY_ID_HASHTABLE", kind: Map,
+            total: || GROUPS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| GROUPS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "GROUPS_BY_TIME_LIST", kind: List,
+            total: || GROUPS_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| GROUPS_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        SnapshotTable { name: "DEFAULT_EVERYONE_GROUP", kind: Plain, total: || 1, read: |_, _| read_value(DEFAULT_EVERYONE_GROUP.with(|v| json!(v.borrow().get().0))) },
+        // Labels
+        SnapshotTable {
+            name: "LABELS_BY_ID_HASHTABLE", kind: Map,
+            total: || LABELS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| LABELS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "LABELS_BY_VALUE_HASHTABLE", kind: Map,
+            total: || LABELS_BY_VALUE_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| LABELS_BY_VALUE_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v.0)))),
+        },
+        SnapshotTable {
+            name: "LABELS_BY_TIME_LIST", kind: List,
+            total: || LABELS_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| LABELS_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        // Inbox
+        SnapshotTable {
+            name: "INBOX_NOTIFS_BY_ID_HASHTABLE", kind: Map,
+            total: || INBOX_NOTIFS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| INBOX_NOTIFS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE", kind: Map,
+            total: || INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        // Webhooks
+        SnapshotTable {
+            name: "WEBHOOKS_BY_ALT_INDEX_HASHTABLE", kind: Map,
+            total: || WEBHOOKS_BY_ALT_INDEX_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| WEBHOOKS_BY_ALT_INDEX_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                (k.0.clone(), ids(&v.webhooks, |id| id.0.clone()))
+            })),
+        },
+        SnapshotTable {
+            name: "WEBHOOKS_BY_ID_HASHTABLE", kind: Map,
+            total: || WEBHOOKS_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| WEBHOOKS_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "WEBHOOKS_BY_TIME_LIST", kind: List,
+            total: || WEBHOOKS_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| WEBHOOKS_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        // Purchases
+        SnapshotTable {
+            name: "PURCHASES_BY_ID_HASHTABLE", kind: Map,
+            total: || PURCHASES_BY_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| PURCHASES_BY_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| (k.0.clone(), json!(v)))),
+        },
+        SnapshotTable {
+            name: "PURCHASES_BY_TIME_LIST", kind: List,
+            total: || PURCHASES_BY_TIME_LIST.with(|v| v.borrow().len() as usize),
+            read: |after, size| PURCHASES_BY_TIME_LIST.with(|v| read_vec(&v.borrow(), after, size, |id| json!(id.0))),
+        },
+        SnapshotTable {
+            name: "PURCHASES_BY_VENDOR_ID_HASHTABLE", kind: Map,
+            total: || PURCHASES_BY_VENDOR_ID_HASHTABLE.with(|m| m.borrow().len() as usize),
+            read: |after, size| PURCHASES_BY_VENDOR_ID_HASHTABLE.with(|m| read_map(&m.borrow(), after, size, |k, v| {
+                (k.0.clone(), ids(&v.purchases, |id| id.0.clone()))
+            })),
+        },
+        // Configs
+        SnapshotTable { name: "RATE_LIMIT_CONFIG", kind: Plain, total: || 1, read: |_, _| read_value(RATE_LIMIT_CONFIG.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "IDEMPOTENCY_CONFIG", kind: Plain, total: || 1, read: |_, _| read_value(IDEMPOTENCY_CONFIG.with(|v| json!(v.borrow().get()))) },
+        SnapshotTable { name: "CYCLES_MONITOR_CONFIG", kind: Plain, total: || 1, read: |_, _| read_value(CYCLES_MONITOR_CONFIG.with(|v| json!(v.borrow().get()))) },
+    ]
+}
+
+/// Names of the stable tables no snapshot page covers
+pub fn snapshot_excluded_tables() -> Vec<String> {
+    COLD_ARCHIVE_EXCLUDED_TABLES
+        .iter()
+        .chain(SNAPSHOT_ONLY_EXCLUDED_TABLES.iter())
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+fn current_marker_state() -> (String, u64) {
+    (
+        DRIVE_STATE_CHECKSUM.with(|cs| cs.borrow().get().0.clone()),
+        DRIVE_STATE_TIMESTAMP_NS.with(|ts| *ts.borrow().get()),
+    )
+}
+
+pub fn start_paged_snapshot(requester: &UserID) -> (SnapshotMarker, Vec<SnapshotTableInfo>) {
+    let now = now_ms();
+    let (state_checksum, state_timestamp_ns) = current_marker_state();
+    let marker = SnapshotMarker {
+        snapshot_id: next_snapshot_id(),
+        state_checksum,
+        state_timestamp_ns,
+        created_at_ms: now,
+        expires_at_ms: now + SNAPSHOT_SESSION_TTL_MS,
+    };
+    let table_infos = snapshot_tables()
+        .into_iter()
+        .map(|table| SnapshotTableInfo {
+            name: table.name.to_string(),
+            kind: table.kind,
+            total: (table.total)(),
+        })
+        .collect();
+
+    SNAPSHOT_SESSIONS.with(|sessions| {
+        let mut sessions = sessions.borrow_mut();
+        sessions.retain(|session| session.marker.expires_at_ms > now);
+        // Oldest first, so the one dropped to make room is the oldest
+        while sessions.len() >= MAX_SNAPSHOT_SESSIONS {
+            sessions.remove(0);
+        }
+        sessions.push(SnapshotSession {
+            marker: marker.clone(),
+            owner: requester.clone(),
+            issued_cursors: HashSet::new(),
+        });
+    });
+    (marker, table_infos)
+}
+
+// Without a cursor the first page of the first table is returned. Empty tables still get
+// a page, so clients see every table name.
+pub fn read_snapshot_page(requester: &UserID, snapshot_id: &str, cursor: Option<&str>, page_size: usize) -> Result<SnapshotPage, SnapshotPageError> {
+    let now = now_ms();
+    let page_size = page_size.clamp(1, MAX_SNAPSHOT_PAGE_SIZE);
+    let (current_checksum, current_timestamp_ns) = current_marker_state();
+
+    SNAPSHOT_SESSIONS.with(|sessions| {
+        let mut sessions = sessions.borrow_mut();
+        let position = sessions
+            .iter()
+            .position(|session| session.marker.snapshot_id == snapshot_id && session.owner == *requester && session.marker.expires_at_ms > now)
+            .ok_or(SnapshotPageError::NotFound)?;
+
+        // Pages are read live, so they only belong together while the drive stands still
+        if sessions[position].marker.state_checksum != current_checksum || sessions[position].marker.state_timestamp_ns != current_timestamp_ns {
+            sessions.remove(position);
+            return Err(SnapshotPageError::DriveChanged);
+        }
+        let session = &mut sessions[position];
+
+        let (table_index, offset, resume) = match cursor {
+            Some(cursor) if session.issued_cursors.contains(cursor) => decode_cursor(cursor).ok_or(SnapshotPageError::InvalidCursor)?,
+            Some(_) => return Err(SnapshotPageError::InvalidCursor),
+            None => (0, 0, Vec::new()),
+        };
+        let tables = snapshot_tables();
+        let table = tables.get(table_index).ok_or(SnapshotPageError::InvalidCursor)?;
+        let after = if resume.is_empty() { None } else { Some(resume.as_slice()) };
+        let read = (table.read)(after, page_size);
+
+        let next_cursor = match read.resume {
+            Some(resume) => Some(encode_cursor(table_index, offset + read.count, &resume)),
+            None if table_index + 1 < tables.len() => Some(encode_cursor(table_index + 1, 0, &[])),
+            None => None,
+        };
+        if let Some(next_cursor) = &next_cursor {
+            session.issued_cursors.insert(next_cursor.clone());
+        }
+
+        Ok(SnapshotPage {
+            marker: session.marker.clone(),
+            table: table.name.to_string(),
+            kind: table.kind.clone(),
+            total: (table.total)(),
+            offset,
+            items: read.items,
+            next_cursor,
+        })
+    })
+}
+
+pub fn end_paged_snapshot(requester: &UserID, snapshot_id: &str) -> bool {
+    SNAPSHOT_SESSIONS.with(|sessions| {
+        let mut sessions = sessions.borrow_mut();
+        let before = sessions.len();
+        sessions.retain(|session| !(session.marker.snapshot_id == snapshot_id && session.owner == *requester));
+        sessions.len() < before
+    })
+}
@@ -0,0 +1,174 @@
+// src/core/api/streaming.rs
+
+// Canister stored files larger than one response are answered with the first window and
+// a streaming strategy, the HTTP gateway then pulls the remaining windows through the
+// http_request_streaming_callback query. That query carries no credentials, so every
+// streamed response gets a grant: an unguessable token tied to the file version that
+// passed the permission check, which stops working once the file changes or it expires.
+pub mod streaming {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use candid::{CandidType, Func};
+    use ic_http_certification::HttpResponse;
+    use serde::Deserialize;
+    use sha2::{Digest, Sha256};
+
+    use crate::{
+        core::{
+            api::uuid::generate_uuidv4,
+            state::{
+                directory::{state::state::file_uuid_to_metadata, types::{FileID, FileRecord, FileVersionID}},
+                raw_storage::{state::{get_file_chunk_records, read_file_range}, types::CHUNK_SIZE},
+            },
+            types::IDPrefix,
+        },
+        debug_log,
+    };
+
+    // Bytes per response, the first window goes out with the response itself
+    pub const STREAMING_WINDOW_BYTES: u64 = CHUNK_SIZE as u64;
+    pub const STREAMING_GRANT_TTL_MS: u64 = 60 * 60 * 1000; // 1 hour
+
+    #[derive(CandidType, Deserialize, Clone, Debug)]
+    pub struct StreamingCallbackToken {
+        pub grant: String,
+        pub window: u64,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Debug)]
+    pub enum StreamingStrategy {
+        Callback {
+            callback: Func,
+            token: StreamingCallbackToken,
+        },
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Debug)]
+    pub struct StreamingCallbackHttpResponse {
+        pub body: Vec<u8>,
+        pub token: Option<StreamingCallbackToken>,
+    }
+
+    // http_request_update's response, the certification crate's type has no streaming_strategy
+    #[derive(CandidType, Deserialize, Clone, Debug)]
+    pub struct StreamingHttpResponse {
+        pub status_code: u16,
+        pub headers: Vec<(String, String)>,
+        pub body: Vec<u8>,
+        pub upgrade: Option<bool>,
+        pub streaming_strategy: Option<StreamingStrategy>,
+    }
+
+    struct StreamingGrant {
+        file_id: FileID,
+        version_id: FileVersionID,
+        total_size: u64,
+        expires_at_ms: u64,
+    }
+
+    thread_local! {
+        // Seeded from raw_rand once per canister lifetime, grants are derived from it
+        static STREAMING_SECRET: RefCell<Option<Vec<u8>>> = RefCell::new(None);
+        // Heap only, streams in progress during an upgrade have to be restarted
+        static STREAMING_GRANTS: RefCell<HashMap<String, StreamingGrant>> = RefCell::new(HashMap::new());
+        // Set by the handler that answered the current request, taken by http_request_update
+        static PENDING_STREAMING_STRATEGY: RefCell<Option<StreamingStrategy>> = RefCell::new(None);
+    }
+
+    pub async fn ensure_streaming_secret() -> Result<(), String> {
+        if STREAMING_SECRET.with(|secret| secret.borrow().is_some()) {
+            return Ok(());
+        }
+        let (bytes,) = ic_cdk::api::management_canister::main::raw_rand()
+            .await
+            .map_err(|(code, msg)| format!("Failed to get randomness: {:?} {}", code, msg))?;
+        STREAMING_SECRET.with(|secret| {
+            secret.borrow_mut().get_or_insert(bytes);
+        });
+        Ok(())
+    }
+
+    /// Issues a grant for the windows after the first and queues the strategy for the
+    /// current response. Returns false when no secret is seeded yet.
+    pub fn start_streaming(file: &FileRecord, total_size: u64) -> bool {
+        let secret = match STREAMING_SECRET.with(|secret| secret.borrow().clone()) {
+            Some(secret) => secret,
+            None => return false,
+        };
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+
+        let mut hasher = Sha256::new();
+        hasher.update(&secret);
+        hasher.update(generate_uuidv4(IDPrefix::File).as_bytes());
+        let grant = hex::encode(hasher.finalize());
+
+        STREAMING_GRANTS.with(|grants| {
+            let mut grants = grants.borrow_mut();
+            grants.retain(|_, grant| grant.expires_at_ms > now_ms);
+            grants.insert(grant.clone(), StreamingGrant {
+                file_id: file.id.clone(),
+                version_id: file.version_id.clone(),
+                total_size,
+                expires_at_ms: now_ms + STREAMING_GRANT_TTL_MS,
+            });
+        });
+        PENDING_STREAMING_STRATEGY.with(|pending| {
+            *pending.borrow_mut() = Some(StreamingStrategy::Callback {
+                callback: Func {
+                    principal: ic_cdk::api::id(),
+                    method: "http_request_streaming_callback".to_string(),
+                },
+                token: StreamingCallbackToken { grant, window: 1 },
+            });
+        });
+        true
+    }
+
+    pub fn clear_streaming_strategy() {
+        PENDING_STREAMING_STRATEGY.with(|pending| pending.borrow_mut().take());
+    }
+
+    pub fn into_streaming_response(response: HttpResponse<'static>) -> StreamingHttpResponse {
+        StreamingHttpResponse {
+            status_code: response.status_code().as_u16(),
+            headers: response.headers().to_vec(),
+            body: response.body().to_vec(),
+            upgrade: response.upgrade(),
+            streaming_strategy: PENDING_STREAMING_STRATEGY.with(|pending| pending.borrow_mut().take()),
+        }
+    }
+
+    /// Returns the window named by the token and the token for the next one, if any
+    pub fn streaming_callback(token: StreamingCallbackToken) -> Result<StreamingCallbackHttpResponse, String> {
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let (file_id, version_id, total_size) = STREAMING_GRANTS.with(|grants| {
+            grants.borrow().get(&token.grant)
+                .filter(|grant| grant.expires_at_ms > now_ms)
+                .map(|grant| (grant.file_id.clone(), grant.version_id.clone(), grant.total_size))
+        }).ok_or_else(|| "Streaming token expired".to_string())?;
+
+        // A new version, a move to another disk or a re-upload ends the stream
+        let unchanged = file_uuid_to_metadata.get(&file_id)
+            .map_or(false, |file| file.version_id == version_id)
+            && get_file_chunk_records(&file_id.0).iter().map(|c| c.size as u64).sum::<u64>() == total_size;
+        if !unchanged {
+            return Err("File changed while streaming".to_string());
+        }
+
+        let start = token.window * STREAMING_WINDOW_BYTES;
+        if start >= total_size {
+            return Ok(StreamingCallbackHttpResponse { body: Vec::new(), token: None });
+        }
+        let end = (start + STREAMING_WINDOW_BYTES).min(total_size) - 1;
+        debug_log!("streaming_callback: {} bytes {}-{}/{}", file_id, start, end, total_size);
+        let body = read_file_range(&file_id.0, start, end)
+            .map_err(|index| format!("Chunk {} is missing", index))?;
+
+        let next = if end + 1 < total_size {
+            Some(StreamingCallbackToken { grant: token.grant, window: token.window + 1 })
+        } else {
+            None
+        };
+        Ok(StreamingCallbackHttpResponse { body, token: next })
+    }
+}
@@ -0,0 +1,198 @@
+// src/core/api/trash.rs
+
+// Timer driven purge of trash and expired records.
+//
+// Items sitting directly in a disk's .trash folder are permanently deleted once they have
+// been there longer than the disk's trash_retention_days, counted from trashed_at_ms (set
+// when they are moved in, so later edits don't reset it). Any file or folder whose expires_at
+// has passed is permanently deleted wherever it lives. Both are read from the sweep indexes
+// in directory state, so a tick only visits what is due. Permanent deletion goes through
+// delete_file / delete_folder, which also drop the backing raw_storage chunks or S3 / Storj objects.
+// Items that fail to purge or can't be deleted leave the indexes until their record changes again.
+pub mod trash {
+    use std::time::Duration;
+
+    use crate::{
+        core::{
+            api::{
+                drive::drive::{delete_file, delete_folder},
+                webhooks::{directory::{fire_directory_webhook, get_active_file_webhooks, get_active_folder_webhooks}, filters::WebhookFilterContext}
+            },
+            state::{
+                directory::{state::state::{drop_from_sweep_indexes, drop_sweep_entry, expiring_before, file_uuid_to_metadata, folder_uuid_to_metadata, trashed_before}, types::{DirectorySweepResource, FileID, FolderID}},
+                disks::{state::state::DISKS_BY_ID_HASHTABLE, types::Disk},
+                webhooks::types::WebhookEventLabel
+            },
+            types::UserID
+        },
+        debug_log,
+        rest::webhooks::types::{DirectoryWebhookData, FileWebhookData, FolderWebhookData},
+    };
+
+    pub const TRASH_SWEEP_TICK_SECONDS: u64 = 60 * 60; // hourly
+    pub const TRASH_SWEEP_BATCH_SIZE: usize = 100; // top level items purged per tick
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    pub fn start_trash_sweeper_timer() {
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(TRASH_SWEEP_TICK_SECONDS),
+            sweep_trash,
+        );
+    }
+
+    /// Permanently deletes up to `TRASH_SWEEP_BATCH_SIZE` expired items, the rest wait for the next tick.
+    pub fn sweep_trash() {
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let (folder_ids, file_ids) = collect_sweep_candidates(now_ms);
+        if folder_ids.is_empty() && file_ids.is_empty() {
+            return;
+        }
+        debug_log!("sweep_trash: purging {} folders and {} files", folder_ids.len(), file_ids.len());
+
+        // Folders first, their files are deleted along with them. Whatever fails to purge leaves
+        // the indexes, otherwise it would stay at their head and hold up everything due after it.
+        for folder_id in folder_ids {
+            if !purge_folder(&folder_id) {
+                drop_from_sweep_indexes(&DirectorySweepResource::Folder(folder_id));
+            }
+        }
+        for file_id in file_ids {
+            if file_uuid_to_metadata.contains_key(&file_id) && !purge_file(&file_id) {
+                drop_from_sweep_indexes(&DirectorySweepResource::File(file_id));
+            }
+        }
+    }
+
+    fn collect_sweep_candidates(now_ms: u64) -> (Vec<FolderID>, Vec<FileID>) {
+        let mut folder_ids: Vec<FolderID> = Vec::new();
+        let mut file_ids: Vec<FileID> = Vec::new();
+
+        let disks: Vec<Disk> = DISKS_BY_ID_HASHTABLE.with(|map| {
+            map.borrow().iter().map(|(_, disk)| disk).collect()
+        });
+        for disk in disks {
+            // Trash that outlived its disk's retention, then records past their expires_at, trashed or not
+            let mut due = match disk.trash_retention_days {
+                Some(days) => trashed_before(&disk.id, now_ms.saturating_sub(days as u64 * DAY_MS), TRASH_SWEEP_BATCH_SIZE),
+                None => Vec::new(),
+            };
+            due.extend(expiring_before(&disk.id, now_ms + 1, TRASH_SWEEP_BATCH_SIZE));
+
+            for key in due {
+                if folder_ids.len() + file_ids.len() >= TRASH_SWEEP_BATCH_SIZE {
+                    return (folder_ids, file_ids);
+                }
+                match &key.resource {
+                    DirectorySweepResource::Folder(folder_id) => match folder_uuid_to_metadata.get(folder_id) {
+                        // Root and .trash folders can't be deleted, so they leave the indexes
+                        Some(folder) if folder.parent_folder_uuid.is_none() || *folder_id == disk.trash_folder => {
+                            drop_from_sweep_indexes(&key.resource);
+                        },
+                        Some(_) => {
+                            if !folder_ids.contains(folder_id) {
+                                folder_ids.push(folder_id.clone());
+                            }
+                        },
+                        None => drop_sweep_entry(&key),
+                    },
+                    DirectorySweepResource::File(file_id) => match file_uuid_to_metadata.contains_key(file_id) {
+                        true => {
+                            if !file_ids.contains(file_id) {
+                                file_ids.push(file_id.clone());
+                            }
+                        },
+                        false => drop_sweep_entry(&key),
+                    },
+                }
+            }
+        }
+
+        (folder_ids, file_ids)
+    }
+
+    // The sweeper acts on its own, so there is no actor for webhook filters to match
+    fn sweeper_filter_context(mut context: WebhookFilterContext) -> WebhookFilterContext {
+        context.actor = None;
+        context
+    }
+
+    // True once the file is gone
+    fn purge_file(file_id: &FileID) -> bool {
+        let file = match file_uuid_to_metadata.get(file_id) {
+            Some(file) => file,
+            None => return true,
+        };
+
+        let filter_context = sweeper_filter_context(WebhookFilterContext::for_file(file_id, &UserID(String::new())));
+        let webhooks_file = get_active_file_webhooks(file_id, WebhookEventLabel::FileDeleted, &filter_context);
+        let webhooks_subfile = get_active_file_webhooks(file_id, WebhookEventLabel::SubfileDeleted, &filter_context);
+        let before_snap_file = DirectoryWebhookData::File(FileWebhookData {
+            file: Some(file),
+        });
+
+        match delete_file(file_id, true) {
+            Ok(_) => {
+                fire_directory_webhook(
+                    WebhookEventLabel::FileDeleted,
+                    webhooks_file,
+                    Some(before_snap_file.clone()),
+                    None,
+                    Some("File purged".to_string()),
+                );
+                fire_directory_webhook(
+                    WebhookEventLabel::SubfileDeleted,
+                    webhooks_subfile,
+                    Some(before_snap_file),
+                    None,
+                    Some("Subfile purged".to_string()),
+                );
+                true
+            },
+            Err(e) => {
+                ic_cdk::println!("Warning: Failed to purge file {}: {}", file_id, e);
+                false
+            },
+        }
+    }
+
+    // True once the folder is gone
+    fn purge_folder(folder_id: &FolderID) -> bool {
+        let folder = match folder_uuid_to_metadata.get(folder_id) {
+            Some(folder) => folder,
+            None => return true,
+        };
+
+        let filter_context = sweeper_filter_context(WebhookFilterContext::for_folder(folder_id, &UserID(String::new())));
+        let webhooks_folder = get_active_folder_webhooks(folder_id, WebhookEventLabel::FolderDeleted, &filter_context);
+        let webhooks_subfolder = get_active_folder_webhooks(folder_id, WebhookEventLabel::SubfolderDeleted, &filter_context);
+        let before_snap_folder = DirectoryWebhookData::Folder(FolderWebhookData {
+            folder: Some(folder),
+        });
+
+        let mut deleted_files = Vec::new();
+        let mut deleted_folders = Vec::new();
+        match delete_folder(folder_id, &mut deleted_folders, &mut deleted_files, true) {
+            Ok(_) => {
+                fire_directory_webhook(
+                    WebhookEventLabel::FolderDeleted,
+                    webhooks_folder,
+                    Some(before_snap_folder.clone()),
+                    None,
+                    Some("Folder purged".to_string()),
+                );
+                fire_directory_webhook(
+                    WebhookEventLabel::SubfolderDeleted,
+                    webhooks_subfolder,
+                    Some(before_snap_folder),
+                    None,
+                    Some("Subfolder purged".to_string()),
+                );
+                true
+            },
+            Err(e) => {
+                ic_cdk::println!("Warning: Failed to purge folder {}: {}", folder_id, e);
+                false
+            },
+        }
+    }
+}
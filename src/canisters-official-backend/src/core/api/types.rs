@@ -1,3 +1,7 @@
+use std::fmt;
+
+use crate::rest::types::ErrorCode;
+
 #[derive(Debug)]
 pub enum DirectoryError {
     FolderNotFound(String),
@@ -9,4 +13,28 @@ pub enum DirectoryIDError {
     InvalidPrefix,
     MalformedID,
     UnknownType,
-}
\ No newline at end of file
+}
+/// Error from a drive operation, typed so callers can tell a name clash from other failures
+#[derive(Debug, Clone)]
+pub struct DriveError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl DriveError {
+    pub fn name_conflict(message: &str) -> Self {
+        DriveError { code: ErrorCode::NameConflict, message: message.to_string() }
+    }
+}
+
+impl From<String> for DriveError {
+    fn from(message: String) -> Self {
+        DriveError { code: ErrorCode::InternalError, message }
+    }
+}
+
+impl fmt::Display for DriveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
@@ -0,0 +1,118 @@
+// src/core/api/uploads.rs
+
+// Raw chunk uploads to IcpCanister disks. Every received chunk refreshes the file's
+// upload session, which lets clients ask what already arrived after a dropped connection.
+// Sessions that stay quiet longer than UPLOAD_SESSION_TIMEOUT_MS are treated as abandoned,
+// their chunks are freed and the file goes back to Queued so the upload can start over.
+pub mod uploads {
+    use std::time::Duration;
+
+    use crate::{
+        core::state::{
+            directory::{state::state::file_uuid_to_metadata, types::FileID},
+            raw_storage::{
+                state::{absorb_received_chunks, chunk_sha256, delete_file_data, end_upload_session, UPLOAD_SESSIONS},
+                types::{FileChunk, RunningSha256, UploadSession, UploadStatus}
+            },
+        },
+        debug_log,
+    };
+
+    pub const UPLOAD_SESSION_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000; // 24 hours without a chunk
+    pub const UPLOAD_CLEANUP_TICK_SECONDS: u64 = 60 * 60; // hourly
+
+    pub fn start_upload_cleanup_timer() {
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(UPLOAD_CLEANUP_TICK_SECONDS),
+            cleanup_abandoned_uploads,
+        );
+    }
+
+    pub fn session_expires_at(session: &UploadSession) -> u64 {
+        session.last_chunk_at + UPLOAD_SESSION_TIMEOUT_MS
+    }
+
+    /// Frees the chunks of uploads that never completed and resets their files to Queued
+    pub fn cleanup_abandoned_uploads() {
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let expired: Vec<UploadSession> = UPLOAD_SESSIONS.with(|sessions| {
+            sessions.borrow()
+                .iter()
+                .map(|(_, session)| session)
+                .filter(|session| session_expires_at(session) <= now_ms)
+                .collect()
+        });
+
+        for session in expired {
+            let file_id = FileID(session.file_id.clone());
+            match file_uuid_to_metadata.get(&file_id) {
+                // Finished meanwhile, the data is the file's content now
+                Some(file) if file.upload_status == UploadStatus::Completed => {
+                    end_upload_session(&session.file_id);
+                },
+                Some(mut file) => {
+                    debug_log!("cleanup_abandoned_uploads: discarding upload of {}", file_id);
+                    let _ = delete_file_data(&session.file_id);
+                    end_upload_session(&session.file_id);
+                    file.upload_status = UploadStatus::Queued;
+                    file_uuid_to_metadata.insert(file_id, file);
+                },
+                // The file was deleted, only the orphaned chunks are left
+                None => {
+                    let _ = delete_file_data(&session.file_id);
+                    end_upload_session(&session.file_id);
+                },
+            }
+        }
+    }
+
+    /// Chunk indexes below total_chunks that have not been received
+    pub fn missing_chunk_indexes(chunks: &[FileChunk], total_chunks: u32) -> Vec<u32> {
+        (0..total_chunks)
+            .filter(|index| !chunks.iter().any(|chunk| chunk.chunk_index == *index))
+            .collect()
+    }
+
+    /// Checks the received chunks (ordered by index) against the client's checksums.
+    /// The whole file sha256 comes from the session's running hash, which absorbed the chunks
+    /// as they arrived. Only chunks it has not reached yet are read here.
+    pub fn verify_upload(
+        chunks: &[FileChunk],
+        session: Option<&UploadSession>,
+        chunk_sha256s: Option<&Vec<String>>,
+        file_sha256: Option<&String>,
+    ) -> Result<Option<String>, String> {
+        if let Some(expected) = chunk_sha256s {
+            if expected.len() != chunks.len() {
+                return Err(format!("Expected {} chunk checksums, {} chunks were received", expected.len(), chunks.len()));
+            }
+            let mismatched: Vec<String> = chunks.iter()
+                .zip(expected.iter())
+                .filter(|(chunk, expected)| !chunk_sha256(chunk).eq_ignore_ascii_case(expected))
+                .map(|(chunk, _)| chunk.chunk_index.to_string())
+                .collect();
+            if !mismatched.is_empty() {
+                return Err(format!("Checksum mismatch for chunks: {}", mismatched.join(", ")));
+            }
+        }
+
+        let expected = match file_sha256 {
+            Some(expected) => expected,
+            None => return Ok(None),
+        };
+        // Sessions from before the running hash start from the first chunk
+        let mut running = session
+            .and_then(|session| session.running_sha256.clone())
+            .unwrap_or_else(RunningSha256::new);
+        let file_id = chunks.first().map(|chunk| chunk.file_id.clone()).unwrap_or_default();
+        absorb_received_chunks(&file_id, chunks.len() as u32, &mut running);
+        if running.next_chunk_index != chunks.len() as u32 {
+            return Err(format!("Chunk {} is missing its content", running.next_chunk_index));
+        }
+        let actual = running.finalize();
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("File checksum mismatch, received content hashes to {}", actual));
+        }
+        Ok(Some(actual))
+    }
+}
@@ -0,0 +1,396 @@
+// src/core/api/versions.rs
+
+// File version history. The current version of a file keeps its bytes at the usual
+// per file slot ({file_id}.{ext} in buckets, the file_id in canister raw_storage).
+// When a version is superseded its bytes are archived next to it under its own
+// FileVersionID, so older versions stay downloadable and restorable.
+pub mod versions {
+    use std::{cell::RefCell, collections::HashSet};
+
+    use crate::{
+        core::{
+            api::{
+                disks::{aws_s3::{copy_s3_object, delete_s3_object, generate_s3_view_url_for_key}, storj_web3::{copy_storj_object, delete_storj_object, generate_storj_view_url_for_key}},
+                helpers::get_appropriate_url_endpoint, internals::drive_internals::format_file_asset_path, uuid::generate_uuidv4
+            },
+            state::{
+                directory::{state::state::{after_directory_commit, file_uuid_to_metadata, file_version_to_metadata}, types::{FileID, FileRecord, FileVersionID}},
+                disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskTypeEnum}},
+                drives::state::state::DRIVE_ID,
+                raw_storage::{state::{copy_file_data, delete_file_data}, types::UploadStatus}
+            },
+            types::{IDPrefix, UserID}
+        },
+        debug_log,
+    };
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+    const VERSION_VIEW_URL_EXPIRES_IN: u64 = 60 * 60; // 1 hour
+    const MAX_VERSION_CHAIN: usize = 10_000; // guards against a corrupted prior_version loop
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum RestoreFileVersionError {
+        NotFound(String),
+        // The request can't apply to the file as it is, eg. restoring the current version
+        Invalid(String),
+        // Another upload or restore replaced the current version while this one was copying
+        Conflict(String),
+        Failed(String),
+    }
+
+    impl RestoreFileVersionError {
+        pub fn code(&self) -> i32 {
+            match self {
+                RestoreFileVersionError::NotFound(_) => 404,
+                RestoreFileVersionError::Invalid(_) => 400,
+                RestoreFileVersionError::Conflict(_) => 409,
+                RestoreFileVersionError::Failed(_) => 500,
+            }
+        }
+
+        pub fn message(&self) -> &str {
+            match self {
+                RestoreFileVersionError::NotFound(message)
+                | RestoreFileVersionError::Invalid(message)
+                | RestoreFileVersionError::Conflict(message)
+                | RestoreFileVersionError::Failed(message) => message,
+            }
+        }
+    }
+
+    // The id a version's bytes are stored under, in raw_storage and in bucket object names
+    fn version_storage_id(version: &FileRecord, is_current: bool) -> String {
+        match is_current {
+            true => version.id.0.clone(),
+            false => version.version_id.0.clone(),
+        }
+    }
+
+    fn version_object_key(version: &FileRecord, is_current: bool) -> String {
+        let drive_id = DRIVE_ID.with(|id| id.clone());
+        format!(
+            "{}/{}/{}/{}.{}",
+            drive_id,
+            version.disk_id,
+            version.id.0,
+            version_storage_id(version, is_current),
+            version.extension
+        )
+    }
+
+    // Files created with an external raw_url have no bytes on the disk for us to manage
+    fn is_stored_on_disk(version: &FileRecord) -> bool {
+        version.raw_url == format_file_asset_path(version.id.clone(), version.extension.clone())
+    }
+
+    pub fn is_current_version(version: &FileRecord) -> bool {
+        file_uuid_to_metadata
+            .get(&version.id)
+            .map_or(false, |file| file.version_id == version.version_id)
+    }
+
+    fn get_bucket_auth(version: &FileRecord) -> Result<AwsBucketAuth, String> {
+        let disk = DISKS_BY_ID_HASHTABLE.with(|map| {
+            map.borrow()
+                .get(&version.disk_id)
+                .map(|d| d.clone())
+        }).ok_or_else(|| "Disk not found".to_string())?;
+
+        serde_json::from_str(&disk.auth_json
+            .ok_or_else(|| "Missing bucket credentials".to_string())?
+        ).map_err(|_| "Invalid bucket credentials format".to_string())
+    }
+
+    async fn copy_version_content(
+        source: &FileRecord,
+        source_is_current: bool,
+        destination: &FileRecord,
+        destination_is_current: bool,
+    ) -> Result<(), String> {
+        match source.disk_type {
+            DiskTypeEnum::IcpCanister => copy_file_data(
+                &version_storage_id(source, source_is_current),
+                &version_storage_id(destination, destination_is_current),
+            ),
+            DiskTypeEnum::AwsBucket => {
+                let auth = get_bucket_auth(source)?;
+                copy_s3_object(
+                    &version_object_key(source, source_is_current),
+                    &version_object_key(destination, destination_is_current),
+                    &auth,
+                ).await
+            },
+            DiskTypeEnum::StorjWeb3 => {
+                let auth = get_bucket_auth(source)?;
+                copy_storj_object(
+                    &version_object_key(source, source_is_current),
+                    &version_object_key(destination, destination_is_current),
+                    &auth,
+                ).await
+            },
+            _ => Err(format!("File versions are not supported on disk type {}", source.disk_type)),
+        }
+    }
+
+    /// Copies the bytes currently at the file's slot into the archive slot of `version`.
+    /// Must run before anything new is written to the file's slot.
+    pub async fn archive_version_content(version: &FileRecord) -> Result<(), String> {
+        if !is_stored_on_disk(version) {
+            return Ok(());
+        }
+        copy_version_content(version, true, version, false).await
+    }
+
+    // Drops the bytes of an archived (not current) version, cloud deletes run detached after commit
+    fn delete_version_content(version: &FileRecord) {
+        if !is_stored_on_disk(version) {
+            return;
+        }
+        match version.disk_type {
+            DiskTypeEnum::IcpCanister => {
+                if let Err(e) = delete_file_data(&version.version_id.0) {
+                    debug_log!("delete_version_content: nothing stored for {}: {}", version.version_id, e);
+                }
+            },
+            DiskTypeEnum::AwsBucket | DiskTypeEnum::StorjWeb3 => {
+                let auth = match get_bucket_auth(version) {
+                    Ok(auth) => auth,
+                    Err(e) => {
+                        ic_cdk::println!("Warning: Cannot delete version {}: {}", version.version_id, e);
+                        return;
+                    }
+                };
+                let object_key = version_object_key(version, false);
+                let disk_type = version.disk_type;
+                // Like canister bytes, the object only goes once the directory action commits
+                after_directory_commit(move || {
+                    ic_cdk::spawn(async move {
+                        let result = match disk_type {
+                            DiskTypeEnum::AwsBucket => delete_s3_object(&object_key, &auth).await,
+                            _ => delete_storj_object(&object_key, &auth).await,
+                        };
+                        if let Err(e) = result {
+                            ic_cdk::println!("Warning: Failed to delete archived version {}: {}", object_key, e);
+                        }
+                    });
+                });
+            },
+            _ => (),
+        }
+    }
+
+    /// All versions of a file, newest (current) first
+    pub fn list_file_versions(file_id: &FileID) -> Vec<FileRecord> {
+        let current = match file_uuid_to_metadata.get(file_id) {
+            Some(file) => file,
+            None => return Vec::new(),
+        };
+
+        let mut cursor = current.prior_version.clone();
+        let mut versions = vec![current];
+        while let Some(version_id) = cursor {
+            if versions.len() >= MAX_VERSION_CHAIN {
+                break;
+            }
+            match file_version_to_metadata.get(&version_id) {
+                Some(version) if version.id == *file_id => {
+                    cursor = version.prior_version.clone();
+                    versions.push(version);
+                },
+                _ => break,
+            }
+        }
+        versions
+    }
+
+    /// Where the bytes of a version can be fetched from. Bucket disks get a short lived
+    /// presigned url, canister disks point at raw_download with the version's storage id.
+    pub fn get_version_content_url(version: &FileRecord) -> Result<String, String> {
+        if !is_stored_on_disk(version) {
+            return Ok(version.raw_url.clone());
+        }
+        let is_current = is_current_version(version);
+        match version.disk_type {
+            DiskTypeEnum::IcpCanister => Ok(format!(
+                "{}/v1/drive/{}/directory/raw_download/meta?file_id={}",
+                get_appropriate_url_endpoint(),
+                DRIVE_ID.with(|id| id.clone()),
+                version_storage_id(version, is_current)
+            )),
+            DiskTypeEnum::AwsBucket => Ok(generate_s3_view_url_for_key(
+                &version_object_key(version, is_current),
+                &get_bucket_auth(version)?,
+                Some(VERSION_VIEW_URL_EXPIRES_IN),
+            )),
+            DiskTypeEnum::StorjWeb3 => Ok(generate_storj_view_url_for_key(
+                &version_object_key(version, is_current),
+                &get_bucket_auth(version)?,
+                Some(VERSION_VIEW_URL_EXPIRES_IN),
+            )),
+            _ => Err(format!("File versions are not supported on disk type {}", version.disk_type)),
+        }
+    }
+
+    /// Applies the disk's retention policy to a file's history, returning the pruned versions.
+    /// A version's age is counted from when it was superseded, not from when it was uploaded.
+    pub fn apply_version_retention(file_id: &FileID) -> Vec<FileVersionID> {
+        let versions = list_file_versions(file_id);
+        let current = match versions.first() {
+            Some(version) => version.clone(),
+            None => return Vec::new(),
+        };
+        let retention = match DISKS_BY_ID_HASHTABLE.with(|map| {
+            map.borrow().get(&current.disk_id).and_then(|disk| disk.version_retention.clone())
+        }) {
+            Some(retention) => retention,
+            None => return Vec::new(),
+        };
+
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let cutoff_ms = retention.keep_days.map(|days| now_ms.saturating_sub(days as u64 * DAY_MS));
+        let first_pruned = (1..versions.len()).find(|index| {
+            retention.keep_versions.map_or(false, |keep| *index >= keep as usize)
+                || cutoff_ms.map_or(false, |cutoff| versions[index - 1].created_at < cutoff)
+        });
+        let first_pruned = match first_pruned {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+
+        let pruned: Vec<FileVersionID> = versions[first_pruned..].iter().map(|version| {
+            delete_version_content(version);
+            file_version_to_metadata.remove(&version.version_id);
+            version.version_id.clone()
+        }).collect();
+
+        // The oldest surviving version no longer has a history behind it
+        let oldest_kept = &versions[first_pruned - 1];
+        file_version_to_metadata.with_mut(|map| {
+            if let Some(mut version) = map.get(&oldest_kept.version_id) {
+                version.prior_version = None;
+                map.insert(oldest_kept.version_id.clone(), version);
+            }
+        });
+        if first_pruned == 1 {
+            file_uuid_to_metadata.with_mut(|map| {
+                if let Some(mut file) = map.get(file_id) {
+                    file.prior_version = None;
+                    map.insert(file_id.clone(), file);
+                }
+            });
+        }
+
+        debug_log!("apply_version_retention: pruned {} versions of {}", pruned.len(), file_id);
+        pruned
+    }
+
+    /// Removes every archived version of a file, used when the file itself is permanently deleted
+    pub fn purge_file_versions(file_id: &FileID) {
+        for version in list_file_versions(file_id).iter().skip(1) {
+            delete_version_content(version);
+            file_version_to_metadata.remove(&version.version_id);
+        }
+    }
+
+    thread_local! {
+        // Files with a restore in flight, new versions on top of them are refused until it settles
+        static RESTORING_FILES: RefCell<HashSet<FileID>> = RefCell::new(HashSet::new());
+    }
+
+    pub fn is_restoring(file_id: &FileID) -> bool {
+        RESTORING_FILES.with(|files| files.borrow().contains(file_id))
+    }
+
+    /// Makes an older version current again. The current version is archived and the
+    /// restored bytes become a new version on top, so the restore itself can be undone.
+    /// Returns the new current record along with any versions retention pruned afterwards.
+    pub async fn restore_file_version(file_id: &FileID, version_id: &FileVersionID, user_id: &UserID) -> Result<(FileRecord, Vec<FileVersionID>), RestoreFileVersionError> {
+        if !RESTORING_FILES.with(|files| files.borrow_mut().insert(file_id.clone())) {
+            return Err(RestoreFileVersionError::Conflict("Another restore of this file is in progress".to_string()));
+        }
+        let result = restore_file_version_locked(file_id, version_id, user_id).await;
+        RESTORING_FILES.with(|files| files.borrow_mut().remove(file_id));
+        result
+    }
+
+    async fn restore_file_version_locked(file_id: &FileID, version_id: &FileVersionID, user_id: &UserID) -> Result<(FileRecord, Vec<FileVersionID>), RestoreFileVersionError> {
+        let current = file_uuid_to_metadata
+            .get(file_id)
+            .ok_or_else(|| RestoreFileVersionError::NotFound("File not found".to_string()))?;
+        let target = file_version_to_metadata
+            .get(version_id)
+            .filter(|version| version.id == current.id)
+            .ok_or_else(|| RestoreFileVersionError::NotFound("Version not found for this file".to_string()))?;
+
+        if target.version_id == current.version_id {
+            return Err(RestoreFileVersionError::Invalid("Version is already the current version".to_string()));
+        }
+        if current.upload_status != UploadStatus::Completed {
+            return Err(RestoreFileVersionError::Conflict("Cannot restore a version while an upload is in progress".to_string()));
+        }
+
+        archive_version_content(&current).await.map_err(RestoreFileVersionError::Failed)?;
+
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let new_version_id = FileVersionID(generate_uuidv4(IDPrefix::FileVersion));
+
+        let mut restored = current.clone();
+        restored.file_version = current.file_version + 1;
+        restored.version_id = new_version_id.clone();
+        restored.prior_version = Some(current.version_id.clone());
+        restored.next_version = None;
+        restored.extension = target.extension.clone();
+        restored.file_size = target.file_size;
+        restored.raw_url = match is_stored_on_disk(&target) {
+            true => format_file_asset_path(restored.id.clone(), restored.extension.clone()),
+            false => target.raw_url.clone(),
+        };
+        restored.upload_status = UploadStatus::Completed;
+        restored.created_at = now_ms;
+        restored.last_updated_date_ms = now_ms;
+        restored.last_updated_by = user_id.clone();
+
+        // Stage the bytes in the new version's own archive slot, which is where they would be
+        // archived to anyway, so nothing touches the live slot before the check below
+        if is_stored_on_disk(&target) {
+            copy_version_content(&target, false, &restored, false).await.map_err(RestoreFileVersionError::Failed)?;
+        }
+
+        // The copies awaited, make sure nobody replaced or deleted the file meanwhile
+        let current = match current_version_unchanged(file_id, &current) {
+            Ok(current) => current,
+            Err(e) => {
+                delete_version_content(&restored);
+                return Err(e);
+            }
+        };
+
+        // Only now promote the staged bytes onto the live slot. New versions are refused while
+        // the file is in RESTORING_FILES, so the promotion can't land on someone else's upload.
+        if is_stored_on_disk(&restored) {
+            if let Err(e) = copy_version_content(&restored, false, &restored, true).await {
+                delete_version_content(&restored);
+                return Err(RestoreFileVersionError::Failed(e));
+            }
+        }
+        let current = current_version_unchanged(file_id, &current)?;
+
+        let mut superseded = current.clone();
+        superseded.next_version = Some(new_version_id.clone());
+        file_version_to_metadata.insert(superseded.version_id.clone(), superseded);
+        file_version_to_metadata.insert(new_version_id, restored.clone());
+        file_uuid_to_metadata.insert(file_id.clone(), restored.clone());
+
+        let pruned = apply_version_retention(file_id);
+
+        Ok((restored, pruned))
+    }
+
+    // Re-reads the file after an await, failing if its current version is no longer `expected`
+    fn current_version_unchanged(file_id: &FileID, expected: &FileRecord) -> Result<FileRecord, RestoreFileVersionError> {
+        file_uuid_to_metadata
+            .get(file_id)
+            .filter(|file| file.version_id == expected.version_id)
+            .ok_or_else(|| RestoreFileVersionError::Conflict("File changed while restoring, try again".to_string()))
+    }
+}
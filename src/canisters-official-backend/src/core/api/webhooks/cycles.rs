@@ -0,0 +1,48 @@
+// src/core/api/webhooks/cycles.rs
+
+use crate::core::api::webhooks::deliveries::dispatch_webhook_event;
+use crate::core::state::cycles::types::{CyclesMonitorConfig, CyclesReport};
+use crate::core::state::drives::state::state::DRIVE_ID;
+use crate::core::state::webhooks::{state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE}, types::{Webhook, WebhookAltIndexID, WebhookEventLabel}};
+use crate::rest::webhooks::types::{LowCyclesWebhookData, WebhookEventData, WebhookEventPayload, WebhookResourceData};
+
+pub fn get_active_low_cycles_webhooks() -> Vec<Webhook> {
+    let webhook_ids = WEBHOOKS_BY_ALT_INDEX_HASHTABLE.with(|store| {
+        store.borrow()
+            .get(&WebhookAltIndexID::low_cycles_slug())
+            .unwrap_or_default()
+    });
+
+    WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
+        let store = store.borrow();
+        webhook_ids.webhooks.into_iter()
+            .filter_map(|id| store.get(&id))
+            .filter(|webhook| webhook.active && webhook.event == WebhookEventLabel::DriveLowCycles)
+            .collect()
+    })
+}
+
+pub fn fire_low_cycles_webhook(report: &CyclesReport, config: &CyclesMonitorConfig) {
+    let timestamp_ms = ic_cdk::api::time() / 1_000_000;
+    let data = LowCyclesWebhookData {
+        drive_id: DRIVE_ID.with(|id| id.clone()),
+        report: report.clone(),
+        config: config.clone(),
+    };
+
+    for webhook in get_active_low_cycles_webhooks() {
+        let payload = WebhookEventPayload {
+            event: WebhookEventLabel::DriveLowCycles.to_string(),
+            timestamp_ms,
+            nonce: timestamp_ms,
+            notes: None,
+            webhook_id: webhook.id.clone(),
+            webhook_alt_index: webhook.alt_index.clone(),
+            payload: WebhookEventData {
+                before: None,
+                after: Some(WebhookResourceData::LowCycles(data.clone())),
+            },
+        };
+        dispatch_webhook_event(&webhook, &payload);
+    }
+}
@@ -0,0 +1,423 @@
+// src/core/api/webhooks/deliveries.rs
+
+use std::ops::Bound;
+use std::time::Duration;
+
+use crate::{core::{api::uuid::generate_uuidv4, state::webhooks::{state::state::{WEBHOOKS_BY_ID_HASHTABLE, WEBHOOK_DEAD_LETTERS_HASHTABLE, WEBHOOK_DELIVERIES_BY_ID_HASHTABLE, WEBHOOK_DELIVERY_QUEUE, WEBHOOK_DELIVERY_SEQUENCE}, types::{Webhook, WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryID, WebhookDeliveryStatus, WebhookID}}, types::IDPrefix}, debug_log, rest::webhooks::types::WebhookEventPayload};
+use ic_cdk::{api::management_canister::http_request::{
+    http_request,
+    HttpMethod,
+    HttpHeader,
+    HttpResponse,
+    CanisterHttpRequestArgument,
+    TransformArgs,
+    TransformContext
+}};
+use ic_cdk::spawn;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use num_traits::cast::ToPrimitive;
+use serde_json;
+
+// How many times a delivery is attempted before it lands in the dead-letter table
+pub const WEBHOOK_DELIVERY_MAX_ATTEMPTS: u32 = 8;
+// Backoff doubles from the base on every failed attempt, up to the max
+pub const WEBHOOK_DELIVERY_BASE_BACKOFF_MS: u64 = 30 * 1000;
+pub const WEBHOOK_DELIVERY_MAX_BACKOFF_MS: u64 = 6 * 60 * 60 * 1000;
+// While an attempt is in flight the delivery is leased so the timer doesn't pick it up twice
+pub const WEBHOOK_DELIVERY_LEASE_MS: u64 = 10 * 60 * 1000;
+pub const WEBHOOK_DELIVERY_TICK_SECONDS: u64 = 60;
+pub const WEBHOOK_DELIVERY_BATCH_SIZE: usize = 20;
+pub const WEBHOOK_DEAD_LETTER_MAX_ENTRIES: u64 = 1000;
+pub const WEBHOOK_DELIVERY_CYCLES: u128 = 100_000_000_000;
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookDeliveryRetryError {
+    NotFound,
+    // Only failed and dead-lettered deliveries can be retried
+    NotRetryable(WebhookDeliveryStatus),
+}
+
+/// Persists a delivery for this webhook and spawns its first attempt right away when
+/// nothing older is queued for the same webhook. Failed attempts are retried by the
+/// delivery timer, see `start_webhook_delivery_timer`.
+pub fn dispatch_webhook_event(webhook: &Webhook, payload: &WebhookEventPayload) {
+    if enqueue_webhook_delivery(webhook, payload).is_some() {
+        spawn_queue_head_attempt(&webhook.id);
+    }
+}
+
+/// Same as `dispatch_webhook_event` but awaits the first attempt, for callers that
+/// need the request to have gone out before they respond. If older deliveries are still
+/// queued for the webhook, the event waits behind them and is sent by the timer.
+pub async fn dispatch_webhook_event_now(webhook: &Webhook, payload: &WebhookEventPayload) {
+    if let Some(delivery_id) = enqueue_webhook_delivery(webhook, payload) {
+        if queue_head(&webhook.id).as_ref() == Some(&delivery_id) {
+            attempt_webhook_delivery(delivery_id).await;
+        }
+    }
+}
+
+/// HMAC-SHA256 over `{timestamp}.{body}`, hex encoded.
+pub fn sign_webhook_payload(secret: &str, timestamp_ms: u64, body: &str) -> String {
+    type HmacSha256 = Hmac<Sha256>;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(timestamp_ms.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Timestamp and signature headers for an attempt. The signature header carries one
+/// `v1=` entry per active secret, so during a rotation overlap receivers holding either
+/// secret can verify. Webhooks without a secret only get the timestamp.
+fn webhook_signature_headers(webhook: &Webhook, timestamp_ms: u64, body: &str) -> Vec<HttpHeader> {
+    let mut headers = vec![
+        HttpHeader {
+            name: WEBHOOK_TIMESTAMP_HEADER.to_string(),
+            value: timestamp_ms.to_string(),
+        },
+    ];
+
+    let mut signatures = vec![];
+    if !webhook.signature.is_empty() {
+        signatures.push(format!("v1={}", sign_webhook_payload(&webhook.signature, timestamp_ms, body)));
+    }
+    if let (Some(previous), Some(expires_at)) = (&webhook.previous_signature, webhook.previous_signature_expires_at) {
+        if timestamp_ms < expires_at && !previous.is_empty() {
+            signatures.push(format!("v1={}", sign_webhook_payload(previous, timestamp_ms, body)));
+        }
+    }
+    if !signatures.is_empty() {
+        headers.push(HttpHeader {
+            name: WEBHOOK_SIGNATURE_HEADER.to_string(),
+            value: signatures.join(","),
+        });
+    }
+
+    headers
+}
+
+pub fn enqueue_webhook_delivery(webhook: &Webhook, payload: &WebhookEventPayload) -> Option<WebhookDeliveryID> {
+    let body = match serde_json::to_string(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            debug_log!("Failed to serialize webhook payload for {}: {}", webhook.id, e);
+            return None;
+        }
+    };
+
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+    let delivery = WebhookDelivery {
+        id: WebhookDeliveryID(generate_uuidv4(IDPrefix::WebhookDelivery)),
+        webhook_id: webhook.id.clone(),
+        url: webhook.url.clone(),
+        event: payload.event.clone(),
+        body,
+        status: WebhookDeliveryStatus::Pending,
+        attempts: vec![],
+        retries_remaining: WEBHOOK_DELIVERY_MAX_ATTEMPTS,
+        next_attempt_at: now_ms,
+        queue_sequence: next_queue_sequence(),
+        created_at: now_ms,
+        last_updated_at: now_ms,
+    };
+
+    WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+        store.borrow_mut().insert(delivery.id.clone(), delivery.clone());
+    });
+    add_to_queue(&delivery);
+
+    Some(delivery.id)
+}
+
+/// Sends one attempt of the delivery. Only the oldest queued delivery of a webhook is ever
+/// attempted, so a receiver sees its events in the order they were fired, and only once it
+/// is due, so an attempt already in flight (leased) or backing off is never doubled.
+pub async fn attempt_webhook_delivery(delivery_id: WebhookDeliveryID) {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+
+    let mut delivery = match WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| store.borrow().get(&delivery_id)) {
+        Some(delivery) => delivery,
+        None => return,
+    };
+    if delivery.next_attempt_at > now_ms || queue_head(&delivery.webhook_id).as_ref() != Some(&delivery.id) {
+        return;
+    }
+
+    let webhook = match WEBHOOKS_BY_ID_HASHTABLE.with(|store| store.borrow().get(&delivery.webhook_id)) {
+        Some(webhook) => webhook,
+        None => {
+            delivery.attempts.push(WebhookDeliveryAttempt {
+                attempted_at: now_ms,
+                status_code: None,
+                error: Some("Webhook no longer exists".to_string()),
+            });
+            move_to_dead_letter(delivery);
+            return;
+        }
+    };
+
+    // Lease the delivery before awaiting so the timer and retries skip it. A trap after the
+    // await leaves it InFlight, and the timer picks it up again once the lease runs out.
+    delivery.status = WebhookDeliveryStatus::InFlight;
+    delivery.next_attempt_at = now_ms + WEBHOOK_DELIVERY_LEASE_MS;
+    WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+        store.borrow_mut().insert(delivery.id.clone(), delivery.clone());
+    });
+
+    // Signed per attempt so the timestamp reflects when the request was actually sent
+    let mut headers = vec![
+        HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/json".to_string(),
+        },
+    ];
+    headers.extend(webhook_signature_headers(&webhook, now_ms, &delivery.body));
+
+    let request = CanisterHttpRequestArgument {
+        url: delivery.url.clone(),
+        method: HttpMethod::POST,
+        headers,
+        body: Some(delivery.body.clone().into_bytes()),
+        max_response_bytes: Some(2048),
+        transform: Some(TransformContext::from_name("transform_webhook_response".to_string(), vec![])),
+    };
+
+    let attempt = match http_request(request, WEBHOOK_DELIVERY_CYCLES).await {
+        Ok((response,)) => {
+            let status_u16: u16 = response.status.0.to_u64()
+                .and_then(|n| if n <= u16::MAX as u64 { Some(n as u16) } else { None })
+                .unwrap_or(500);
+            WebhookDeliveryAttempt {
+                attempted_at: now_ms,
+                status_code: Some(status_u16),
+                error: if (200..300).contains(&status_u16) {
+                    None
+                } else {
+                    Some(format!("Receiver responded with status {}", status_u16))
+                },
+            }
+        },
+        Err((code, msg)) => WebhookDeliveryAttempt {
+            attempted_at: now_ms,
+            status_code: None,
+            error: Some(format!("HTTP request failed: {:?} - {}", code, msg)),
+        },
+    };
+
+    // Re-read in case the delivery was purged while we were awaiting
+    let mut delivery = match WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| store.borrow().get(&delivery_id)) {
+        Some(delivery) => delivery,
+        None => return,
+    };
+    let succeeded = attempt.error.is_none();
+    delivery.attempts.push(attempt);
+    delivery.last_updated_at = ic_cdk::api::time() / 1_000_000;
+
+    if succeeded {
+        WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+            store.borrow_mut().remove(&delivery.id);
+        });
+        remove_from_queue(&delivery);
+        // The next event for this receiver was waiting on this one
+        spawn_queue_head_attempt(&delivery.webhook_id);
+        return;
+    }
+
+    delivery.retries_remaining = delivery.retries_remaining.saturating_sub(1);
+    if delivery.retries_remaining == 0 {
+        debug_log!("Webhook delivery {} exhausted its retries, moving to dead letters", delivery.id);
+        let webhook_id = delivery.webhook_id.clone();
+        move_to_dead_letter(delivery);
+        spawn_queue_head_attempt(&webhook_id);
+        return;
+    }
+
+    let failed_attempts = WEBHOOK_DELIVERY_MAX_ATTEMPTS - delivery.retries_remaining;
+    delivery.status = WebhookDeliveryStatus::Failed;
+    delivery.next_attempt_at = delivery.last_updated_at + backoff_ms(failed_attempts);
+    WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+        store.borrow_mut().insert(delivery.id.clone(), delivery);
+    });
+}
+
+fn backoff_ms(failed_attempts: u32) -> u64 {
+    let exponent = failed_attempts.saturating_sub(1).min(20);
+    WEBHOOK_DELIVERY_BASE_BACKOFF_MS
+        .saturating_mul(1u64 << exponent)
+        .min(WEBHOOK_DELIVERY_MAX_BACKOFF_MS)
+}
+
+/// Spawns an attempt for the oldest queued delivery of every webhook whose head is due,
+/// at most `WEBHOOK_DELIVERY_BATCH_SIZE` per tick. Deliveries behind a head wait for it.
+pub fn process_webhook_delivery_queue() {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+
+    let mut due_ids: Vec<WebhookDeliveryID> = vec![];
+    let mut after: Option<WebhookID> = None;
+    while due_ids.len() < WEBHOOK_DELIVERY_BATCH_SIZE {
+        // Jump straight to the next webhook's head instead of walking its whole backlog
+        let head = WEBHOOK_DELIVERY_QUEUE.with(|queue| {
+            let queue = queue.borrow();
+            let mut range = match &after {
+                Some(webhook_id) => queue.range((Bound::Excluded((webhook_id.clone(), u64::MAX)), Bound::Unbounded)),
+                None => queue.range(..),
+            };
+            range.next()
+        });
+        let ((webhook_id, _), delivery_id) = match head {
+            Some(head) => head,
+            None => break,
+        };
+
+        let is_due = WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+            store.borrow().get(&delivery_id).map_or(false, |delivery| delivery.next_attempt_at <= now_ms)
+        });
+        if is_due {
+            due_ids.push(delivery_id);
+        }
+        after = Some(webhook_id);
+    }
+
+    for delivery_id in due_ids {
+        spawn(async move {
+            attempt_webhook_delivery(delivery_id).await;
+        });
+    }
+}
+
+pub fn start_webhook_delivery_timer() {
+    ic_cdk_timers::set_timer_interval(
+        Duration::from_secs(WEBHOOK_DELIVERY_TICK_SECONDS),
+        process_webhook_delivery_queue,
+    );
+}
+
+/// Puts a dead-lettered delivery back at the end of its webhook's queue with a fresh set of
+/// retries, or makes a failed delivery due now instead of waiting out its backoff. Pending and
+/// in-flight deliveries are refused, retrying them would send the event twice.
+pub fn retry_webhook_delivery(delivery_id: &WebhookDeliveryID) -> Result<WebhookDelivery, WebhookDeliveryRetryError> {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+
+    let mut delivery = if let Some(queued) = WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| store.borrow().get(delivery_id)) {
+        if queued.status != WebhookDeliveryStatus::Failed {
+            return Err(WebhookDeliveryRetryError::NotRetryable(queued.status));
+        }
+        queued
+    } else {
+        let mut dead = WEBHOOK_DEAD_LETTERS_HASHTABLE.with(|store| store.borrow_mut().remove(delivery_id))
+            .ok_or(WebhookDeliveryRetryError::NotFound)?;
+        dead.status = WebhookDeliveryStatus::Pending;
+        dead.retries_remaining = WEBHOOK_DELIVERY_MAX_ATTEMPTS;
+        dead.queue_sequence = next_queue_sequence();
+        add_to_queue(&dead);
+        dead
+    };
+
+    delivery.next_attempt_at = now_ms;
+    delivery.last_updated_at = now_ms;
+    WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+        store.borrow_mut().insert(delivery.id.clone(), delivery.clone());
+    });
+    spawn_queue_head_attempt(&delivery.webhook_id);
+
+    Ok(delivery)
+}
+
+/// Drops all queued deliveries of a webhook, used when the webhook itself is deleted.
+/// Dead letters are kept so the history stays inspectable.
+pub fn purge_webhook_deliveries(webhook_id: &WebhookID) {
+    let queued: Vec<((WebhookID, u64), WebhookDeliveryID)> = WEBHOOK_DELIVERY_QUEUE.with(|queue| {
+        queue.borrow().range((webhook_id.clone(), 0)..=(webhook_id.clone(), u64::MAX)).collect()
+    });
+    WEBHOOK_DELIVERY_QUEUE.with(|queue| {
+        let mut queue = queue.borrow_mut();
+        for (key, _) in queued.iter() {
+            queue.remove(key);
+        }
+    });
+    WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+        let mut store = store.borrow_mut();
+        for (_, delivery_id) in queued.iter() {
+            store.remove(delivery_id);
+        }
+    });
+}
+
+pub fn strip_webhook_response(args: TransformArgs) -> HttpResponse {
+    // Only the status code matters, and headers/body differ between replicas
+    HttpResponse {
+        status: args.response.status,
+        headers: vec![],
+        body: vec![],
+    }
+}
+
+fn move_to_dead_letter(mut delivery: WebhookDelivery) {
+    delivery.status = WebhookDeliveryStatus::DeadLetter;
+    delivery.last_updated_at = ic_cdk::api::time() / 1_000_000;
+
+    WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+        store.borrow_mut().remove(&delivery.id);
+    });
+    remove_from_queue(&delivery);
+
+    WEBHOOK_DEAD_LETTERS_HASHTABLE.with(|store| {
+        let mut store = store.borrow_mut();
+        store.insert(delivery.id.clone(), delivery);
+
+        // Evict the oldest dead letters beyond the retention cap
+        while store.len() > WEBHOOK_DEAD_LETTER_MAX_ENTRIES {
+            let oldest = store.iter()
+                .min_by_key(|(_, d)| d.last_updated_at)
+                .map(|(id, _)| id);
+            match oldest {
+                Some(id) => { store.remove(&id); },
+                None => break,
+            }
+        }
+    });
+}
+
+fn next_queue_sequence() -> u64 {
+    WEBHOOK_DELIVERY_SEQUENCE.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let sequence = *cell.get();
+        cell.set(sequence + 1).expect("Failed to update WEBHOOK_DELIVERY_SEQUENCE");
+        sequence
+    })
+}
+
+fn queue_head(webhook_id: &WebhookID) -> Option<WebhookDeliveryID> {
+    WEBHOOK_DELIVERY_QUEUE.with(|queue| {
+        queue.borrow()
+            .range((webhook_id.clone(), 0)..=(webhook_id.clone(), u64::MAX))
+            .next()
+            .map(|(_, delivery_id)| delivery_id)
+    })
+}
+
+/// Attempts the webhook's oldest queued delivery if it is due, see `attempt_webhook_delivery`
+fn spawn_queue_head_attempt(webhook_id: &WebhookID) {
+    if let Some(delivery_id) = queue_head(webhook_id) {
+        spawn(async move {
+            attempt_webhook_delivery(delivery_id).await;
+        });
+    }
+}
+
+fn add_to_queue(delivery: &WebhookDelivery) {
+    WEBHOOK_DELIVERY_QUEUE.with(|queue| {
+        queue.borrow_mut().insert((delivery.webhook_id.clone(), delivery.queue_sequence), delivery.id.clone());
+    });
+}
+
+fn remove_from_queue(delivery: &WebhookDelivery) {
+    WEBHOOK_DELIVERY_QUEUE.with(|queue| {
+        queue.borrow_mut().remove(&(delivery.webhook_id.clone(), delivery.queue_sequence));
+    });
+}
@@ -1,24 +1,21 @@
 // src/core/api/webhooks/directory.rs
 
-use crate::{core::state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::{FileID, FolderID}}, group_invites::types::GroupInvite, groups::{state::state::GROUPS_BY_ID_HASHTABLE, types::{Group, GroupID}}, webhooks::{state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE}, types::{Webhook, WebhookAltIndexID, WebhookEventLabel, WebhookIDList}}}, rest::webhooks::types::{DirectoryWebhookData, FileWebhookData, FolderWebhookData}};
+use crate::{core::{api::uuid::{decode_share_track_hash, generate_share_track_hash, ShareTrackHash}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::{FileID, FileRecord, FolderID, ShareTrackID, ShareTrackResourceID}}, drives::state::state::{DRIVE_ID, URL_ENDPOINT}, group_invites::types::GroupInvite, groups::{state::state::GROUPS_BY_ID_HASHTABLE, types::{Group, GroupID}}, webhooks::{state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE}, types::{Webhook, WebhookAltIndexID, WebhookEventLabel, WebhookIDList}}}, types::UserID}, rest::webhooks::types::{DirectoryWebhookData, FileWebhookData, FolderWebhookData, ShareTrackingWebhookData}};
 use crate::rest::webhooks::types::{
     WebhookEventPayload, 
     WebhookEventData, 
     WebhookResourceData,
     GroupInviteWebhookData
 };
-use ic_cdk::{api::management_canister::http_request::{
-    http_request, 
-    HttpMethod,
-    HttpHeader,
-    CanisterHttpRequestArgument
-}};
-use ic_cdk::spawn;
+use crate::core::api::webhooks::deliveries::{dispatch_webhook_event};
+use crate::core::state::directory::state::state::after_directory_commit;
+use crate::core::api::webhooks::filters::{webhook_passes_filters, WebhookFilterContext};
 use serde_json;
 
 pub fn get_active_file_webhooks(
     file_id: &FileID, 
     event: WebhookEventLabel,
+    filter_context: &WebhookFilterContext,
 ) -> Vec<Webhook> {
     let mut all_webhooks = Vec::new();
     
@@ -37,7 +34,7 @@ pub fn get_active_file_webhooks(
         all_webhooks.extend(
             webhook_ids.webhooks.into_iter()
                 .filter_map(|id| store.get(&id).clone())
-                .filter(|webhook| webhook.active && webhook.event == event)
+                .filter(|webhook| webhook.active && webhook.event == event && webhook_passes_filters(webhook, filter_context))
         );
     });
 
@@ -98,7 +95,7 @@ pub fn get_active_file_webhooks(
                 all_webhooks.extend(
                     parent_webhook_ids.webhooks.into_iter()
                         .filter_map(|id| store.get(&id).clone())
-                        .filter(|webhook| webhook.active && webhook.event == event)
+                        .filter(|webhook| webhook.active && webhook.event == event && webhook_passes_filters(webhook, filter_context))
                 );
             });
 
@@ -116,6 +113,7 @@ pub fn get_active_file_webhooks(
 pub fn get_active_folder_webhooks(
     folder_id: &FolderID, 
     event: WebhookEventLabel,
+    filter_context: &WebhookFilterContext,
 ) -> Vec<Webhook> {
     let mut all_webhooks = Vec::new();
     
@@ -134,7 +132,7 @@ pub fn get_active_folder_webhooks(
         all_webhooks.extend(
             webhook_ids.webhooks.into_iter()
                 .filter_map(|id| store.get(&id).clone())
-                .filter(|webhook| webhook.active && webhook.event == event)
+                .filter(|webhook| webhook.active && webhook.event == event && webhook_passes_filters(webhook, filter_context))
         );
     });
 
@@ -196,7 +194,7 @@ pub fn get_active_folder_webhooks(
                     parent_webhook_ids.webhooks
                         .into_iter()
                         .filter_map(|id| store.get(&id).clone())
-                        .filter(|webhook| webhook.active && webhook.event == event)
+                        .filter(|webhook| webhook.active && webhook.event == event && webhook_passes_filters(webhook, filter_context))
                 );
             });
 
@@ -212,6 +210,71 @@ pub fn get_active_folder_webhooks(
 }
 
 
+/// Fires file.viewed / subfile.viewed and the share tracking file.shared / subfile.shared events.
+/// `viewer` is None for anonymous views through a public link, `share_track_hash` is the
+/// hash the viewer arrived with, if any.
+pub fn fire_file_viewed_webhooks(file: &FileRecord, viewer: Option<&UserID>, share_track_hash: Option<String>) {
+    let mut filter_context = WebhookFilterContext::for_file(&file.id, &viewer.cloned().unwrap_or(UserID(String::new())));
+    filter_context.actor = viewer.cloned();
+
+    let webhooks_file = get_active_file_webhooks(&file.id, WebhookEventLabel::FileViewed, &filter_context);
+    let webhooks_subfile = get_active_file_webhooks(&file.id, WebhookEventLabel::SubfileViewed, &filter_context);
+    let snap_file = DirectoryWebhookData::File(FileWebhookData {
+        file: Some(file.clone()),
+    });
+    fire_directory_webhook(
+        WebhookEventLabel::FileViewed,
+        webhooks_file,
+        Some(snap_file.clone()),
+        Some(snap_file.clone()),
+        Some("File viewed".to_string()),
+    );
+    fire_directory_webhook(
+        WebhookEventLabel::SubfileViewed,
+        webhooks_subfile,
+        Some(snap_file.clone()),
+        Some(snap_file),
+        Some("Subfile viewed".to_string()),
+    );
+
+    let share_track_hash = share_track_hash.unwrap_or_default();
+    let (origin_id, origin_user) = match share_track_hash.is_empty() {
+        true => (ShareTrackID(String::new()), UserID(String::new())),
+        false => decode_share_track_hash(&ShareTrackHash(share_track_hash.clone())),
+    };
+    // Anonymous viewers pass the link on in the name of whoever shared it with them
+    let (my_share_track_id, my_share_track_hash) = generate_share_track_hash(viewer.unwrap_or(&origin_user));
+    let webhooks_file_shared = get_active_file_webhooks(&file.id, WebhookEventLabel::FileShared, &filter_context);
+    let webhooks_subfile_shared = get_active_file_webhooks(&file.id, WebhookEventLabel::SubfileShared, &filter_context);
+    let share_tracking_payload = ShareTrackingWebhookData {
+        id: my_share_track_id,
+        hash: my_share_track_hash,
+        origin_id: Some(origin_id),
+        origin_hash: Some(ShareTrackHash(share_track_hash)),
+        from_user: Some(origin_user),
+        to_user: viewer.cloned(),
+        resource_id: ShareTrackResourceID::File(file.id.clone()),
+        resource_name: file.name.clone(),
+        drive_id: DRIVE_ID.with(|id| id.clone()),
+        timestamp_ms: ic_cdk::api::time() / 1_000_000,
+        host_url: URL_ENDPOINT.with(|url| url.borrow().get().clone()),
+        metadata: None
+    };
+    fire_directory_webhook(
+        WebhookEventLabel::FileShared,
+        webhooks_file_shared,
+        None,
+        Some(DirectoryWebhookData::ShareTracking(share_tracking_payload.clone())),
+        Some("Tracked file share".to_string()),
+    );
+    fire_directory_webhook(
+        WebhookEventLabel::SubfileShared,
+        webhooks_subfile_shared,
+        None,
+        Some(DirectoryWebhookData::ShareTracking(share_tracking_payload)),
+        Some("Tracked subfile share".to_string()),
+    );
+}
 
 pub fn fire_directory_webhook(
     event: WebhookEventLabel,
@@ -221,6 +284,7 @@ pub fn fire_directory_webhook(
     notes: Option<String>,
 ) {
     let timestamp_ms = ic_cdk::api::time() / 1_000_000;
+    let mut events = Vec::with_capacity(webhooks.len());
     for webhook in webhooks {
         let payload = WebhookEventPayload {
             event: event.to_string(),
@@ -247,30 +311,12 @@ pub fn fire_directory_webhook(
             },
         };
 
-        // Serialize payload for this webhook
-        if let Ok(body) = serde_json::to_vec(&payload) {
-            let request = CanisterHttpRequestArgument {
-                url: webhook.url.clone(),
-                method: HttpMethod::POST,
-                headers: vec![
-                    HttpHeader {
-                        name: "Content-Type".to_string(),
-                        value: "application/json".to_string(),
-                    },
-                    HttpHeader {
-                        name: "signature".to_string(),
-                        value: webhook.signature.clone(),
-                    },
-                ],
-                body: Some(body),
-                max_response_bytes: Some(0),
-                transform: None,
-            };
-
-            spawn(async move {
-                let cycles: u128 = 1_000_000_000;
-                let _ = http_request(request, cycles).await;
-            });
-        }
+        events.push((webhook, payload));
     }
+    // Webhooks can't be recalled, so a directory action only sends them once it has committed
+    after_directory_commit(move || {
+        for (webhook, payload) in events {
+            dispatch_webhook_event(&webhook, &payload);
+        }
+    });
 }
\ No newline at end of file
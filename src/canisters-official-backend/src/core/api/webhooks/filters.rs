@@ -0,0 +1,381 @@
+// src/core/api/webhooks/filters.rs
+
+// Webhook.filters grammar, evaluated before a webhook is fired.
+//
+//   expr  := and ("OR" and)*
+//   and   := unary (["AND"] unary)*
+//   unary := "NOT" unary | "(" expr ")" | term
+//   term  := field ":" value
+//   field := label | ext | path | actor | topic | disk
+//   value := bare word, or "double quoted" when it contains spaces or parens
+//
+// e.g. `ext:pdf AND path:"/Reports/" AND NOT actor:UserID_123`
+//
+// An empty filter matches everything. A term whose field isn't known for the event
+// (eg. `topic:` on a file event) never matches. Legacy inbox filters stored as
+// `{"topic": "..."}` JSON are still accepted and read as `topic:...`. A `path:` value
+// without a `disk::` prefix matches that path on any disk.
+
+use crate::core::{state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::{FileID, FolderID}}, disks::types::DiskTypeEnum, labels::types::LabelStringValue, webhooks::{state::state::WEBHOOKS_BY_ID_HASHTABLE, types::{Webhook, WebhookID}}}, types::UserID};
+use crate::debug_log;
+
+const MAX_FILTER_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookFilterField {
+    Label,
+    Extension,
+    PathPrefix,
+    Actor,
+    Topic,
+    DiskType,
+}
+
+impl WebhookFilterField {
+    fn parse(field: &str) -> Result<Self, String> {
+        match field.to_lowercase().as_str() {
+            "label" => Ok(Self::Label),
+            "ext" => Ok(Self::Extension),
+            "path" => Ok(Self::PathPrefix),
+            "actor" => Ok(Self::Actor),
+            "topic" => Ok(Self::Topic),
+            "disk" => Ok(Self::DiskType),
+            _ => Err(format!("Unknown filter field '{}', expected one of label, ext, path, actor, topic, disk", field)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookFilter {
+    Any,
+    Term(WebhookFilterField, String),
+    Not(Box<WebhookFilter>),
+    And(Box<WebhookFilter>, Box<WebhookFilter>),
+    Or(Box<WebhookFilter>, Box<WebhookFilter>),
+}
+
+/// What is known about the event being fired. Fields left as None fail any term on them.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookFilterContext {
+    pub labels: Vec<LabelStringValue>,
+    pub extension: Option<String>,
+    pub path: Option<String>,
+    pub actor: Option<UserID>,
+    pub topic: Option<String>,
+    pub disk_type: Option<DiskTypeEnum>,
+}
+
+impl WebhookFilterContext {
+    pub fn for_actor(actor: &UserID) -> Self {
+        WebhookFilterContext {
+            actor: Some(actor.clone()),
+            ..Default::default()
+        }
+    }
+
+    pub fn for_file(file_id: &FileID, actor: &UserID) -> Self {
+        match file_uuid_to_metadata.get(file_id) {
+            Some(file) => WebhookFilterContext {
+                labels: file.labels.clone(),
+                extension: Some(file.extension.clone()),
+                path: Some(file.full_directory_path.0.clone()),
+                actor: Some(actor.clone()),
+                topic: None,
+                disk_type: Some(file.disk_type),
+            },
+            None => Self::for_actor(actor),
+        }
+    }
+
+    pub fn for_folder(folder_id: &FolderID, actor: &UserID) -> Self {
+        match folder_uuid_to_metadata.get(folder_id) {
+            Some(folder) => WebhookFilterContext {
+                labels: folder.labels.clone(),
+                extension: None,
+                path: Some(folder.full_directory_path.0.clone()),
+                actor: Some(actor.clone()),
+                topic: None,
+                disk_type: Some(folder.disk_type),
+            },
+            None => Self::for_actor(actor),
+        }
+    }
+
+    /// For resources that don't exist yet, described by where they will be created
+    pub fn for_new_child(parent_folder_id: &FolderID, name: &str, is_file: bool, actor: &UserID) -> Self {
+        let parent = folder_uuid_to_metadata.get(parent_folder_id);
+        let extension = if is_file {
+            name.rsplit_once('.').map(|(_, ext)| ext.to_string())
+        } else {
+            None
+        };
+        WebhookFilterContext {
+            labels: vec![],
+            extension,
+            path: parent.as_ref().map(|folder| match is_file {
+                true => format!("{}{}", folder.full_directory_path.0, name),
+                false => format!("{}{}/", folder.full_directory_path.0, name),
+            }),
+            actor: Some(actor.clone()),
+            topic: None,
+            disk_type: parent.map(|folder| folder.disk_type),
+        }
+    }
+
+    pub fn for_inbox(topic: Option<&String>, actor: &UserID) -> Self {
+        WebhookFilterContext {
+            actor: Some(actor.clone()),
+            topic: topic.cloned(),
+            ..Default::default()
+        }
+    }
+}
+
+impl WebhookFilter {
+    pub fn matches(&self, context: &WebhookFilterContext) -> bool {
+        match self {
+            WebhookFilter::Any => true,
+            WebhookFilter::Not(inner) => !inner.matches(context),
+            WebhookFilter::And(left, right) => left.matches(context) && right.matches(context),
+            WebhookFilter::Or(left, right) => left.matches(context) || right.matches(context),
+            WebhookFilter::Term(field, value) => match field {
+                WebhookFilterField::Label => context.labels.iter()
+                    .any(|label| label.0.eq_ignore_ascii_case(value)),
+                WebhookFilterField::Extension => context.extension.as_ref()
+                    .map_or(false, |ext| ext.eq_ignore_ascii_case(value.trim_start_matches('.'))),
+                WebhookFilterField::PathPrefix => context.path.as_ref()
+                    .map_or(false, |path| match (value.contains("::"), path.split_once("::")) {
+                        // Stored paths are `disk::/...`, a bare `/...` value matches on every disk
+                        (false, Some((_, disk_path))) => disk_path.starts_with(value.as_str()),
+                        _ => path.starts_with(value.as_str()),
+                    }),
+                WebhookFilterField::Actor => context.actor.as_ref()
+                    .map_or(false, |actor| &actor.0 == value),
+                WebhookFilterField::Topic => context.topic.as_ref()
+                    .map_or(false, |topic| topic == value),
+                WebhookFilterField::DiskType => context.disk_type
+                    .map_or(false, |disk_type| disk_type.to_string().eq_ignore_ascii_case(value)),
+            },
+        }
+    }
+}
+
+/// Whether the webhook's filters let this event through. Filters are validated when
+/// the webhook is saved, so only free-form filters written before the filter language
+/// existed fail to parse here. Those never restricted anything, so they match everything.
+pub fn webhook_passes_filters(webhook: &Webhook, context: &WebhookFilterContext) -> bool {
+    match parse_webhook_filter(&webhook.filters) {
+        Ok(filter) => filter.matches(context),
+        Err(e) => {
+            debug_log!("Warning: webhook {} has legacy filters that don't parse ({}), matching every event", webhook.id, e);
+            true
+        }
+    }
+}
+
+/// Clears filters that don't parse so legacy webhooks keep firing on every event as they
+/// did before the filter language existed. Runs in post_upgrade, returns the webhooks changed.
+pub fn migrate_legacy_webhook_filters() -> Vec<WebhookID> {
+    let legacy: Vec<Webhook> = WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
+        store.borrow()
+            .iter()
+            .filter(|(_, webhook)| parse_webhook_filter(&webhook.filters).is_err())
+            .map(|(_, webhook)| webhook)
+            .collect()
+    });
+
+    WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
+        let mut store = store.borrow_mut();
+        legacy.into_iter()
+            .map(|mut webhook| {
+                debug_log!("Warning: clearing legacy filters {:?} on webhook {}, it now matches every event", webhook.filters, webhook.id);
+                webhook.filters = String::new();
+                let webhook_id = webhook.id.clone();
+                store.insert(webhook_id.clone(), webhook);
+                webhook_id
+            })
+            .collect()
+    })
+}
+
+pub fn parse_webhook_filter(input: &str) -> Result<WebhookFilter, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Ok(WebhookFilter::Any);
+    }
+    if trimmed.starts_with('{') {
+        return parse_legacy_json_filter(trimmed);
+    }
+
+    let tokens = tokenize(trimmed)?;
+    let mut parser = FilterParser { tokens, position: 0 };
+    let filter = parser.parse_or(0)?;
+    if parser.position < parser.tokens.len() {
+        return Err(format!("Unexpected '{}' in filter", parser.tokens[parser.position].describe()));
+    }
+    Ok(filter)
+}
+
+fn parse_legacy_json_filter(input: &str) -> Result<WebhookFilter, String> {
+    let json: serde_json::Value = serde_json::from_str(input)
+        .map_err(|e| format!("Invalid filter JSON: {}", e))?;
+    match json.get("topic").and_then(|topic| topic.as_str()) {
+        Some(topic) => Ok(WebhookFilter::Term(WebhookFilterField::Topic, topic.to_string())),
+        None => Err("Filter JSON must contain a string 'topic'".to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilterToken {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(WebhookFilterField, String),
+}
+
+impl FilterToken {
+    fn describe(&self) -> String {
+        match self {
+            FilterToken::Open => "(".to_string(),
+            FilterToken::Close => ")".to_string(),
+            FilterToken::And => "AND".to_string(),
+            FilterToken::Or => "OR".to_string(),
+            FilterToken::Not => "NOT".to_string(),
+            FilterToken::Term(_, value) => value.clone(),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<FilterToken>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '(' {
+            tokens.push(FilterToken::Open);
+            i += 1;
+            continue;
+        }
+        if c == ')' {
+            tokens.push(FilterToken::Close);
+            i += 1;
+            continue;
+        }
+
+        // Read a word up to whitespace, a paren or the start of a quoted value
+        let start = i;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' && chars[i] != '"' {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect();
+
+        match word.to_uppercase().as_str() {
+            "AND" => { tokens.push(FilterToken::And); continue; },
+            "OR" => { tokens.push(FilterToken::Or); continue; },
+            "NOT" => { tokens.push(FilterToken::Not); continue; },
+            _ => {}
+        }
+
+        let (field, bare_value) = match word.split_once(':') {
+            Some(parts) => parts,
+            None => return Err(format!("Expected field:value but found '{}'", word)),
+        };
+        let field = WebhookFilterField::parse(field)?;
+
+        let value = if bare_value.is_empty() && i < chars.len() && chars[i] == '"' {
+            i += 1;
+            let value_start = i;
+            while i < chars.len() && chars[i] != '"' {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err("Unterminated quoted value in filter".to_string());
+            }
+            let value: String = chars[value_start..i].iter().collect();
+            i += 1;
+            value
+        } else {
+            bare_value.to_string()
+        };
+
+        if value.is_empty() {
+            return Err(format!("Missing value for filter field '{}'", word.trim_end_matches(':')));
+        }
+        tokens.push(FilterToken::Term(field, value));
+    }
+
+    Ok(tokens)
+}
+
+struct FilterParser {
+    tokens: Vec<FilterToken>,
+    position: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&FilterToken> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or(&mut self, depth: usize) -> Result<WebhookFilter, String> {
+        let mut left = self.parse_and(depth)?;
+        while self.peek() == Some(&FilterToken::Or) {
+            self.position += 1;
+            let right = self.parse_and(depth)?;
+            left = WebhookFilter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self, depth: usize) -> Result<WebhookFilter, String> {
+        let mut left = self.parse_unary(depth)?;
+        loop {
+            match self.peek() {
+                Some(FilterToken::And) => {
+                    self.position += 1;
+                },
+                // Adjacent terms are implicitly ANDed
+                Some(FilterToken::Not) | Some(FilterToken::Open) | Some(FilterToken::Term(_, _)) => {},
+                _ => break,
+            }
+            let right = self.parse_unary(depth)?;
+            left = WebhookFilter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self, depth: usize) -> Result<WebhookFilter, String> {
+        if depth > MAX_FILTER_DEPTH {
+            return Err("Filter is nested too deeply".to_string());
+        }
+        match self.tokens.get(self.position).cloned() {
+            Some(FilterToken::Not) => {
+                self.position += 1;
+                Ok(WebhookFilter::Not(Box::new(self.parse_unary(depth + 1)?)))
+            },
+            Some(FilterToken::Open) => {
+                self.position += 1;
+                let inner = self.parse_or(depth + 1)?;
+                if self.peek() != Some(&FilterToken::Close) {
+                    return Err("Missing closing ')' in filter".to_string());
+                }
+                self.position += 1;
+                Ok(inner)
+            },
+            Some(FilterToken::Term(field, value)) => {
+                self.position += 1;
+                Ok(WebhookFilter::Term(field, value))
+            },
+            Some(token) => Err(format!("Unexpected '{}' in filter", token.describe())),
+            None => Err("Filter ends unexpectedly".to_string()),
+        }
+    }
+}
@@ -7,13 +7,7 @@ use crate::rest::webhooks::types::{
     WebhookResourceData,
     GroupInviteWebhookData
 };
-use ic_cdk::{api::management_canister::http_request::{
-    http_request, 
-    HttpMethod,
-    HttpHeader,
-    CanisterHttpRequestArgument
-}};
-use ic_cdk::spawn;
+use crate::core::api::webhooks::deliveries::{dispatch_webhook_event};
 use serde_json;
 
 pub fn get_active_group_invite_webhooks(group_id: &GroupID, event: WebhookEventLabel) -> Vec<Webhook> {
@@ -55,30 +49,6 @@ pub fn fire_group_invite_webhook(
                 after: after_snap.clone().map(|snap| WebhookResourceData::GroupInvite(snap)),
             },
         };
-        // Serialize payload for this webhook
-        if let Ok(body) = serde_json::to_vec(&payload) {
-            let request = CanisterHttpRequestArgument {
-                url: webhook.url.clone(),
-                method: HttpMethod::POST,
-                headers: vec![
-                    HttpHeader {
-                        name: "Content-Type".to_string(),
-                        value: "application/json".to_string(),
-                    },
-                    HttpHeader {
-                        name: "signature".to_string(),
-                        value: webhook.signature.clone(),
-                    },
-                ],
-                body: Some(body),
-                max_response_bytes: Some(0),
-                transform: None,
-            };
-
-            spawn(async move {
-                let cycles: u128 = 1_000_000_000;
-                let _ = http_request(request, cycles).await;
-            });
-        }
+        dispatch_webhook_event(&webhook, &payload);
     }
 }
\ No newline at end of file
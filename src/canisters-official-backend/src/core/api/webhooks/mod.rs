@@ -2,4 +2,7 @@ pub mod group_invites;
 pub mod directory;
 pub mod state_diffs;
 pub mod labels;
-pub mod organization;
\ No newline at end of file
+pub mod organization;
+pub mod deliveries;
+pub mod filters;
+pub mod cycles;
\ No newline at end of file
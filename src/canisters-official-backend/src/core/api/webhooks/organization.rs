@@ -3,20 +3,15 @@
 use crate::{core::{
     state::{group_invites::types::GroupInvite, groups::{state::state::GROUPS_BY_ID_HASHTABLE, types::{Group, GroupID}}, webhooks::{state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE}, types::{Webhook, WebhookAltIndexID, WebhookEventLabel, WebhookIDList}}},
     types::UserID,
-}, debug_log, rest::organization::types::InboxOrgRequestBody};
+}, rest::organization::types::InboxOrgRequestBody};
 use crate::rest::webhooks::types::{
     WebhookEventPayload, 
     WebhookEventData, 
     WebhookResourceData,
     GroupInviteWebhookData
 };
-use ic_cdk::{api::management_canister::http_request::{
-    http_request, 
-    HttpMethod,
-    HttpHeader,
-    CanisterHttpRequestArgument
-}};
-use ic_cdk::spawn;
+use crate::core::api::webhooks::deliveries::{dispatch_webhook_event, dispatch_webhook_event_now};
+use crate::core::api::webhooks::filters::{webhook_passes_filters, WebhookFilterContext};
 use serde_json;
 
 pub fn get_superswap_user_webhooks(event: WebhookEventLabel) -> Vec<Webhook> {
@@ -37,73 +32,24 @@ pub fn get_superswap_user_webhooks(event: WebhookEventLabel) -> Vec<Webhook> {
 }
 
 
-pub fn get_org_inbox_webhooks(topic: Option<&String>) -> Vec<Webhook> {
+pub fn get_org_inbox_webhooks(topic: Option<&String>, actor: &UserID) -> Vec<Webhook> {
     let webhook_ids = WEBHOOKS_BY_ALT_INDEX_HASHTABLE.with(|store| {
         store.borrow()
-            .get(&WebhookAltIndexID::superswap_user_slug())
+            .get(&WebhookAltIndexID::inbox_new_notif_slug())
             .map(|list| list.clone())  
             .unwrap_or_else(|| WebhookIDList { webhooks: Vec::new() })
     });
 
+    let filter_context = WebhookFilterContext::for_inbox(topic, actor);
 
     WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
         let store = store.borrow();
         webhook_ids.webhooks.into_iter()
             .filter_map(|id| store.get(&id).clone())
             .filter(|webhook| {
-                
-                if !webhook.active || webhook.event != WebhookEventLabel::OrganizationInboxNewNotif {
-                    return false;
-                }
-                
-                match (topic, webhook.filters.as_str()) {
-                    // No topic in request but webhook has filter - no match
-                    (None, filters) if !filters.is_empty() => {
-                        false
-                    },
-                    
-                    // Topic in request but no filter in webhook - no match
-                    (Some(_), filters) if filters.is_empty() => {
-                        false
-                    },
-                    
-                    // Both topic and filter exist - try to match
-                    (Some(request_topic), filters) if !filters.is_empty() => {
-                        
-                        match serde_json::from_str::<serde_json::Value>(filters) {
-                            Ok(filter_json) => {
-                                match filter_json.get("topic") {
-                                    Some(filter_topic) => {
-                                        match filter_topic.as_str() {
-                                            Some(filter_topic_str) => {
-                                                let matches = filter_topic_str == request_topic;
-                                                matches
-                                            },
-                                            None => {
-                                                debug_log!("Filter topic is not a string");
-                                                false
-                                            }
-                                        }
-                                    },
-                                    None => {
-                                        debug_log!("No 'topic' key in filter JSON");
-                                        false
-                                    }
-                                }
-                            },
-                            Err(e) => {
-                                debug_log!("Failed to parse filter JSON: {}", e);
-                                false
-                            }
-                        }
-                    },
-                    
-                    // No topic and no filter - catch-all case
-                    _ => {
-                        debug_log!("Catch-all case - no topic and no filter");
-                        true
-                    }
-                }
+                webhook.active
+                    && webhook.event == WebhookEventLabel::OrganizationInboxNewNotif
+                    && webhook_passes_filters(webhook, &filter_context)
             })
             .collect()
     })
@@ -144,31 +90,7 @@ pub fn fire_superswap_user_webhook(
             },
         };
         
-        // The rest of the function looks correct
-        if let Ok(body) = serde_json::to_vec(&payload) {
-            let request = CanisterHttpRequestArgument {
-                url: webhook.url.clone(),
-                method: HttpMethod::POST,
-                headers: vec![
-                    HttpHeader {
-                        name: "Content-Type".to_string(),
-                        value: "application/json".to_string(),
-                    },
-                    HttpHeader {
-                        name: "signature".to_string(),
-                        value: webhook.signature.clone(),
-                    },
-                ],
-                body: Some(body),
-                max_response_bytes: Some(0),
-                transform: None,
-            };
-
-            spawn(async move {
-                let cycles: u128 = 1_000_000_000;
-                let _ = http_request(request, cycles).await;
-            });
-        }
+        dispatch_webhook_event(&webhook, &payload);
     }
 }
 
@@ -207,28 +129,6 @@ pub async fn fire_org_inbox_new_notif_webhook(
             },
         };
         
-        if let Ok(body) = serde_json::to_vec(&payload) {
-            let request = CanisterHttpRequestArgument {
-                url: webhook.url.clone(),
-                method: HttpMethod::POST,
-                headers: vec![
-                    HttpHeader {
-                        name: "Content-Type".to_string(),
-                        value: "application/json".to_string(),
-                    },
-                    HttpHeader {
-                        name: "signature".to_string(),
-                        value: webhook.signature.clone(),
-                    },
-                ],
-                body: Some(body),
-                max_response_bytes: Some(0),
-                transform: None,
-            };
-
-            // Execute the HTTP request and await its completion
-            let cycles: u128 = 1_000_000_000;
-            let _ = http_request(request, cycles).await;
-        }
+        dispatch_webhook_event_now(&webhook, &payload).await;
     }
 }
\ No newline at end of file
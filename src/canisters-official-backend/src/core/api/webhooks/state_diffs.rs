@@ -7,13 +7,7 @@ use crate::rest::webhooks::types::{
     WebhookResourceData,
     GroupInviteWebhookData
 };
-use ic_cdk::{api::management_canister::http_request::{
-    http_request, 
-    HttpMethod,
-    HttpHeader,
-    CanisterHttpRequestArgument
-}};
-use ic_cdk::spawn;
+use crate::core::api::webhooks::deliveries::{dispatch_webhook_event};
 use serde_json;
 
 pub fn get_active_state_diff_webhooks() -> Vec<Webhook> {
@@ -72,30 +66,6 @@ pub fn fire_state_diff_webhooks(
                 }))
             },
         };
-        // Serialize payload for this webhook
-        if let Ok(body) = serde_json::to_vec(&payload) {
-            let request = CanisterHttpRequestArgument {
-                url: webhook.url.clone(),
-                method: HttpMethod::POST,
-                headers: vec![
-                    HttpHeader {
-                        name: "Content-Type".to_string(),
-                        value: "application/json".to_string(),
-                    },
-                    HttpHeader {
-                        name: "signature".to_string(),
-                        value: webhook.signature.clone(),
-                    },
-                ],
-                body: Some(body),
-                max_response_bytes: Some(0),
-                transform: None,
-            };
-
-            spawn(async move {
-                let cycles: u128 = 1_000_000_000;
-                let _ = http_request(request, cycles).await;
-            });
-        }
+        dispatch_webhook_event(&webhook, &payload);
     }
 }
\ No newline at end of file
@@ -57,6 +57,7 @@ pub mod state {
             labels: vec![],
             external_id: None,
             external_payload: None,
+            scopes: vec![],
         };
 
         debug_log!("Default admin api key: {}", default_key);
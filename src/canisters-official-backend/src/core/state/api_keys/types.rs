@@ -5,7 +5,7 @@ use candid::CandidType;
 use ic_stable_structures::{storable::Bound, Storable};
 use serde_diff::{Diff, SerdeDiff};
 use serde::{Deserialize, Serialize};
-use crate::{core::{api::permissions::system::check_system_permissions, state::{contacts::state::state::CONTACTS_BY_ID_HASHTABLE, drives::{state::state::OWNER_ID, types::{ExternalID, ExternalPayload}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, labels::types::{redact_label, LabelStringValue}}, types::UserID}, rest::api_keys::types::ApiKeyFE};
+use crate::{core::{api::permissions::system::check_system_permissions, state::{contacts::state::state::CONTACTS_BY_ID_HASHTABLE, drives::{state::state::OWNER_ID, types::{ExternalID, ExternalPayload}}, directory::types::FolderID, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, labels::types::{redact_label, LabelStringValue}}, types::UserID}, rest::api_keys::types::ApiKeyFE};
 use std::{borrow::Cow, fmt};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff, PartialOrd, Ord, CandidType)]
@@ -76,6 +76,21 @@ pub struct ApiKey {
     pub labels: Vec<LabelStringValue>,
     pub external_id: Option<ExternalID>,
     pub external_payload: Option<ExternalPayload>,
+    #[serde(default)]
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+// Restrictions narrowing what an api key can do on top of its user's permissions.
+// Every scope on a key must allow a request, a key without scopes is unrestricted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff, CandidType, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ApiKeyScope {
+    ReadOnly,
+    DirectoryOnly,
+    FolderSubtree(FolderID),
+    SystemTables(Vec<SystemTableEnum>),
+    HttpMethods(Vec<String>),
+    Routes(Vec<String>), // route prefixes after /v1/drive/{organization_id}, e.g. "/contacts"
 }
 
 impl Storable for ApiKey {
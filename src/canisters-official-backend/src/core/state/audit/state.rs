@@ -0,0 +1,161 @@
+// src/core/state/audit/state.rs
+pub mod state {
+    use std::cell::RefCell;
+
+    use ic_stable_structures::{memory_manager::MemoryId, DefaultMemoryImpl, StableBTreeMap};
+
+    use crate::{core::{api::uuid::generate_uuidv4, state::{api_keys::types::{ApiKey, AuthTypeEnum}, audit::types::{AuditActionEnum, AuditEntry, AuditEntryID}, disks::types::Disk, permissions::types::{DirectoryPermission, SystemPermission}}, types::IDPrefix}, MEMORY_MANAGER};
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+    pub const AUDIT_ENTRIES_MEMORY_ID: MemoryId = MemoryId::new(66);
+
+    // Oldest entries are dropped past this many
+    pub const AUDIT_MAX_ENTRIES: u64 = 50_000;
+    // Four fields at this many bytes plus ids and candid framing stay well inside AuditEntry's 8KB bound
+    const AUDIT_MAX_SUMMARY_BYTES: usize = 1024;
+
+    thread_local! {
+        // Keyed by a sequence number so entries stay in insertion order and double as cursors
+        pub(crate) static AUDIT_ENTRIES_BY_SEQUENCE: RefCell<StableBTreeMap<u64, AuditEntry, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(AUDIT_ENTRIES_MEMORY_ID))
+            )
+        );
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        AUDIT_ENTRIES_BY_SEQUENCE.with(|_| {});
+    }
+
+    /// Appends an entry for an action taken by `actor` through `route`, then enforces the retention cap
+    pub fn record_audit_entry(
+        actor: &ApiKey,
+        action: AuditActionEnum,
+        route: String,
+        resource_id: String,
+        before: Option<String>,
+        after: Option<String>,
+    ) {
+        let truncate = |mut summary: String| {
+            if summary.len() > AUDIT_MAX_SUMMARY_BYTES {
+                let mut end = AUDIT_MAX_SUMMARY_BYTES;
+                while !summary.is_char_boundary(end) {
+                    end -= 1;
+                }
+                summary.truncate(end);
+            }
+            summary
+        };
+
+        // Signature auth mints a sig_auth_ key on the fly, see authenticate_request
+        let auth_type = if actor.id.0.starts_with("sig_auth_") {
+            AuthTypeEnum::Signature
+        } else {
+            AuthTypeEnum::ApiKey
+        };
+
+        let entry = AuditEntry {
+            id: AuditEntryID(generate_uuidv4(IDPrefix::AuditEntry)),
+            action,
+            actor: actor.user_id.clone(),
+            auth_type,
+            route: truncate(route),
+            resource_id: truncate(resource_id),
+            before: before.map(truncate),
+            after: after.map(truncate),
+            timestamp_ms: ic_cdk::api::time() / 1_000_000,
+        };
+
+        AUDIT_ENTRIES_BY_SEQUENCE.with(|store| {
+            let mut store = store.borrow_mut();
+            let sequence = store.last_key_value().map_or(0, |(sequence, _)| sequence + 1);
+            store.insert(sequence, entry);
+
+            while store.len() > AUDIT_MAX_ENTRIES {
+                match store.first_key_value() {
+                    Some((oldest, _)) => { store.remove(&oldest); },
+                    None => break,
+                }
+            }
+        });
+    }
+
+    /// One page of entries matching `filter` starting at the `cursor` sequence number (inclusive),
+    /// oldest first unless `newest_first`. Also returns the sequence number the next page starts at.
+    pub fn list_audit_entries(
+        filter: impl Fn(&AuditEntry) -> bool,
+        cursor: Option<u64>,
+        page_size: usize,
+        newest_first: bool,
+    ) -> (Vec<AuditEntry>, Option<u64>) {
+        AUDIT_ENTRIES_BY_SEQUENCE.with(|store| {
+            let store = store.borrow();
+            let matches: Box<dyn Iterator<Item = (u64, AuditEntry)>> = match (newest_first, cursor) {
+                (false, Some(cursor)) => Box::new(store.range(cursor..)),
+                (false, None) => Box::new(store.iter()),
+                (true, Some(cursor)) => Box::new(store.range(..=cursor).rev()),
+                (true, None) => Box::new(store.iter().rev()),
+            };
+
+            let mut page = Vec::new();
+            let mut next_cursor = None;
+            for (sequence, entry) in matches.filter(|(_, entry)| filter(entry)) {
+                if page.len() == page_size {
+                    next_cursor = Some(sequence);
+                    break;
+                }
+                page.push(entry);
+            }
+            (page, next_cursor)
+        })
+    }
+
+    pub fn audit_entry_count() -> u64 {
+        AUDIT_ENTRIES_BY_SEQUENCE.with(|store| store.borrow().len())
+    }
+
+    pub fn audit_summary_directory_permission(permission: &DirectoryPermission) -> String {
+        format!(
+            "{} on {} [{}] begins {} expires {}",
+            permission.granted_to,
+            permission.resource_id,
+            permission.permission_types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "),
+            permission.begin_date_ms,
+            permission.expiry_date_ms,
+        )
+    }
+
+    pub fn audit_summary_system_permission(permission: &SystemPermission) -> String {
+        format!(
+            "{} on {} {:?} begins {} expires {}",
+            permission.granted_to,
+            permission.resource_id,
+            permission.permission_types,
+            permission.begin_date_ms,
+            permission.expiry_date_ms,
+        )
+    }
+
+    pub fn audit_summary_api_key(api_key: &ApiKey) -> String {
+        format!(
+            "{} for {} begins {} expires {} revoked {} scopes {:?}",
+            api_key.name,
+            api_key.user_id,
+            api_key.begins_at,
+            api_key.expires_at,
+            api_key.is_revoked,
+            api_key.scopes,
+        )
+    }
+
+    // Only says whether credentials are set, never what they are
+    pub fn audit_summary_disk(disk: &Disk) -> String {
+        format!(
+            "{} ({}) credentials {}",
+            disk.name,
+            disk.disk_type,
+            if disk.auth_json.as_deref().map_or(true, |auth| auth.is_empty()) { "unset" } else { "set" },
+        )
+    }
+}
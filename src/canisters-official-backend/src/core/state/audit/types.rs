@@ -0,0 +1,97 @@
+// src/core/state/audit/types.rs
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
+
+use crate::core::{state::api_keys::types::AuthTypeEnum, types::UserID};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, CandidType, PartialOrd, Ord)]
+pub struct AuditEntryID(pub String);
+
+impl fmt::Display for AuditEntryID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, CandidType)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuditActionEnum {
+    DirectoryPermissionCreate,
+    DirectoryPermissionUpdate,
+    DirectoryPermissionDelete,
+    DirectoryPermissionRedeem,
+    SystemPermissionCreate,
+    SystemPermissionUpdate,
+    SystemPermissionDelete,
+    SystemPermissionRedeem,
+    ApiKeyCreate,
+    ApiKeyUpdate,
+    ApiKeyDelete,
+    OwnershipTransfer,
+    SuperswapUser,
+    DiskCreate,
+    DiskUpdate,
+    DiskDelete,
+    FileDownload,
+    ArchiveExport,
+    ArchiveImport,
+}
+
+impl fmt::Display for AuditActionEnum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditActionEnum::DirectoryPermissionCreate => write!(f, "DIRECTORY_PERMISSION_CREATE"),
+            AuditActionEnum::DirectoryPermissionUpdate => write!(f, "DIRECTORY_PERMISSION_UPDATE"),
+            AuditActionEnum::DirectoryPermissionDelete => write!(f, "DIRECTORY_PERMISSION_DELETE"),
+            AuditActionEnum::DirectoryPermissionRedeem => write!(f, "DIRECTORY_PERMISSION_REDEEM"),
+            AuditActionEnum::SystemPermissionCreate => write!(f, "SYSTEM_PERMISSION_CREATE"),
+            AuditActionEnum::SystemPermissionUpdate => write!(f, "SYSTEM_PERMISSION_UPDATE"),
+            AuditActionEnum::SystemPermissionDelete => write!(f, "SYSTEM_PERMISSION_DELETE"),
+            AuditActionEnum::SystemPermissionRedeem => write!(f, "SYSTEM_PERMISSION_REDEEM"),
+            AuditActionEnum::ApiKeyCreate => write!(f, "API_KEY_CREATE"),
+            AuditActionEnum::ApiKeyUpdate => write!(f, "API_KEY_UPDATE"),
+            AuditActionEnum::ApiKeyDelete => write!(f, "API_KEY_DELETE"),
+            AuditActionEnum::OwnershipTransfer => write!(f, "OWNERSHIP_TRANSFER"),
+            AuditActionEnum::SuperswapUser => write!(f, "SUPERSWAP_USER"),
+            AuditActionEnum::DiskCreate => write!(f, "DISK_CREATE"),
+            AuditActionEnum::DiskUpdate => write!(f, "DISK_UPDATE"),
+            AuditActionEnum::DiskDelete => write!(f, "DISK_DELETE"),
+            AuditActionEnum::FileDownload => write!(f, "FILE_DOWNLOAD"),
+            AuditActionEnum::ArchiveExport => write!(f, "ARCHIVE_EXPORT"),
+            AuditActionEnum::ArchiveImport => write!(f, "ARCHIVE_IMPORT"),
+        }
+    }
+}
+
+// Append only, entries are never edited and only leave through the retention cap.
+// before/after are short human readable summaries that never carry secrets.
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct AuditEntry {
+    pub id: AuditEntryID,
+    pub action: AuditActionEnum,
+    pub actor: UserID,
+    pub auth_type: AuthTypeEnum,
+    pub route: String,
+    pub resource_id: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub timestamp_ms: u64,
+}
+
+impl Storable for AuditEntry {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 8 * 1024, // summaries are truncated on the way in
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
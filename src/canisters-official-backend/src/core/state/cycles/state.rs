@@ -0,0 +1,122 @@
+// src/core/state/cycles/state.rs
+pub mod state {
+    use std::cell::RefCell;
+
+    use ic_stable_structures::{memory_manager::MemoryId, DefaultMemoryImpl, StableBTreeMap, StableCell};
+
+    use crate::{core::state::cycles::types::{CyclesAlertState, CyclesMonitorConfig, CyclesReport, CyclesSample}, MEMORY_MANAGER};
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+    pub const CYCLES_SAMPLES_MEMORY_ID: MemoryId = MemoryId::new(69);
+    pub const CYCLES_MONITOR_CONFIG_MEMORY_ID: MemoryId = MemoryId::new(70);
+    pub const CYCLES_ALERT_STATE_MEMORY_ID: MemoryId = MemoryId::new(71);
+
+    // 30 days of hourly samples
+    pub const MAX_CYCLES_SAMPLES: u64 = 30 * 24;
+    // Burn rate is averaged over this window
+    const BURN_RATE_WINDOW_MS: u64 = 7 * DAY_MS;
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    thread_local! {
+        // Keyed by timestamp_ms so iteration is oldest first
+        pub(crate) static CYCLES_SAMPLES: RefCell<StableBTreeMap<u64, CyclesSample, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(CYCLES_SAMPLES_MEMORY_ID))
+            )
+        );
+
+        pub(crate) static CYCLES_MONITOR_CONFIG: RefCell<StableCell<CyclesMonitorConfig, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(CYCLES_MONITOR_CONFIG_MEMORY_ID)),
+                CyclesMonitorConfig::default()
+            ).expect("Failed to initialize CYCLES_MONITOR_CONFIG")
+        );
+
+        pub(crate) static CYCLES_ALERT_STATE: RefCell<StableCell<CyclesAlertState, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(CYCLES_ALERT_STATE_MEMORY_ID)),
+                CyclesAlertState::default()
+            ).expect("Failed to initialize CYCLES_ALERT_STATE")
+        );
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        CYCLES_SAMPLES.with(|_| {});
+        CYCLES_MONITOR_CONFIG.with(|_| {});
+        CYCLES_ALERT_STATE.with(|_| {});
+    }
+
+    /// Stores the current balance against the previous sample and drops the oldest beyond MAX_CYCLES_SAMPLES.
+    pub fn record_cycles_sample(timestamp_ms: u64, balance: u64) -> CyclesSample {
+        CYCLES_SAMPLES.with(|samples| {
+            let mut samples = samples.borrow_mut();
+            let previous = samples.last_key_value().map(|(_, sample)| sample);
+            let sample = CyclesSample {
+                timestamp_ms,
+                balance,
+                burned: previous.as_ref().map_or(0, |prev| prev.balance.saturating_sub(balance)),
+                topped_up: previous.as_ref().map_or(0, |prev| balance.saturating_sub(prev.balance)),
+            };
+            samples.insert(timestamp_ms, sample.clone());
+
+            while samples.len() > MAX_CYCLES_SAMPLES {
+                match samples.first_key_value() {
+                    Some((oldest, _)) => { samples.remove(&oldest); },
+                    None => break,
+                }
+            }
+            sample
+        })
+    }
+
+    /// Cycles burned per day, averaged over the samples of the last week
+    pub fn cycles_burn_rate_per_day(now_ms: u64) -> u64 {
+        CYCLES_SAMPLES.with(|samples| {
+            let samples = samples.borrow();
+            let since_ms = now_ms.saturating_sub(BURN_RATE_WINDOW_MS);
+            let mut window_start_ms = None;
+            let mut burned: u128 = 0;
+            for (timestamp_ms, sample) in samples.range(since_ms..) {
+                // The first sample's burn happened before the window opened
+                if window_start_ms.is_none() {
+                    window_start_ms = Some(timestamp_ms);
+                    continue;
+                }
+                burned += sample.burned as u128;
+            }
+            let elapsed_ms = match window_start_ms {
+                Some(start_ms) => now_ms.saturating_sub(start_ms),
+                None => 0,
+            };
+            if elapsed_ms == 0 {
+                return 0;
+            }
+            (burned * DAY_MS as u128 / elapsed_ms as u128).min(u64::MAX as u128) as u64
+        })
+    }
+
+    pub fn cycles_report(now_ms: u64, balance: u64) -> CyclesReport {
+        let config = CYCLES_MONITOR_CONFIG.with(|config| config.borrow().get().clone());
+        let burn_rate_per_day = cycles_burn_rate_per_day(now_ms);
+        let runway_days = if burn_rate_per_day > 0 { Some(balance / burn_rate_per_day) } else { None };
+
+        let below_balance = config.low_cycles_threshold > 0 && balance < config.low_cycles_threshold;
+        let below_runway = config.low_runway_days > 0
+            && runway_days.map_or(false, |days| days < config.low_runway_days as u64);
+
+        CyclesReport {
+            balance,
+            burn_rate_per_day,
+            runway_days,
+            low_cycles: below_balance || below_runway,
+        }
+    }
+
+    /// Newest first
+    pub fn recent_cycles_samples(limit: usize) -> Vec<CyclesSample> {
+        CYCLES_SAMPLES.with(|samples| {
+            samples.borrow().iter().rev().take(limit).map(|(_, sample)| sample).collect()
+        })
+    }
+}
@@ -0,0 +1,94 @@
+// src/core/state/cycles/types.rs
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// Balance reading taken by the cycles monitor. `burned` is the drop since the previous
+// sample, so it covers idle burn and update calls alike. A top-up in the same interval
+// shows up as `topped_up` instead and hides that interval's burn.
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct CyclesSample {
+    pub timestamp_ms: u64,
+    pub balance: u64,
+    pub burned: u64,
+    pub topped_up: u64,
+}
+
+impl Storable for CyclesSample {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 128,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+// The drive counts as low on cycles when either threshold is crossed. 0 turns a threshold off.
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct CyclesMonitorConfig {
+    pub low_cycles_threshold: u64,
+    pub low_runway_days: u32,
+}
+
+impl Default for CyclesMonitorConfig {
+    fn default() -> Self {
+        Self {
+            low_cycles_threshold: 500_000_000_000, // 0.5T
+            low_runway_days: 14,
+        }
+    }
+}
+
+impl Storable for CyclesMonitorConfig {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+// Kept in stable memory so an upgrade does not fire drive.low_cycles again
+#[derive(Debug, Clone, Default, Serialize, Deserialize, CandidType)]
+pub struct CyclesAlertState {
+    pub low_cycles_alerted_at_ms: Option<u64>,
+}
+
+impl Storable for CyclesAlertState {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 64,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct CyclesReport {
+    pub balance: u64,
+    pub burn_rate_per_day: u64,
+    pub runway_days: Option<u64>, // None until there is burn to extrapolate from
+    pub low_cycles: bool,
+}
@@ -3,14 +3,21 @@
 pub mod state {
     use std::cell::{RefCell, RefMut};
     use std::collections::HashMap;
+    use std::future::Future;
+    use std::mem::{discriminant, Discriminant};
     use std::ops::Deref;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
 
     use ic_stable_structures::memory_manager::MemoryId;
-    use ic_stable_structures::{StableBTreeMap, DefaultMemoryImpl};
+    use ic_stable_structures::{StableBTreeMap, StableCell, DefaultMemoryImpl, Storable};
 
-    use crate::core::state::directory::types::FileVersionID;
+    use crate::core::state::directory::types::{DirectorySweepKey, DirectorySweepResource, FileVersionID};
     use crate::core::state::{
-        directory::types::{DriveFullFilePath, FileRecord, FileID, FolderRecord, FolderID}
+        directory::types::{DriveFullFilePath, FileRecord, FileID, FolderRecord, FolderID},
+        disks::types::DiskID,
+        drives::{state::state::EXTERNAL_ID_MAPPINGS, types::{ExternalID, StringVec}},
+        raw_storage::{state::{discard_unreferenced_content, CHUNKS, CHUNK_REFCOUNTS, FILE_CHUNKS, FILE_META, UPLOAD_SESSIONS}, types::{ChunkId, ChunkIdList, FileChunk, UploadSession}},
     };
     use crate::MEMORY_MANAGER;
 
@@ -22,6 +29,157 @@ pub mod state {
     pub const FULL_FOLDER_PATH_TO_UUID_MEMORY_ID: MemoryId = MemoryId::new(42);
     pub const FULL_FILE_PATH_TO_UUID_MEMORY_ID: MemoryId = MemoryId::new(43);
     pub const FILE_VERSION_TO_METADATA_MEMORY_ID: MemoryId = MemoryId::new(52);
+    pub const TRASHED_BY_TIME_MEMORY_ID: MemoryId = MemoryId::new(73);
+    pub const EXPIRING_BY_TIME_MEMORY_ID: MemoryId = MemoryId::new(74);
+    pub const SWEEP_INDEXES_BUILT_MEMORY_ID: MemoryId = MemoryId::new(75);
+
+    /// Prior value of one map entry, written back if the action that touched it fails
+    pub(crate) enum DirectoryUndoEntry {
+        Folder(FolderID, Option<FolderRecord>),
+        File(FileID, Option<FileRecord>),
+        FileVersion(FileVersionID, Option<FileRecord>),
+        FolderPath(DriveFullFilePath, Option<FolderID>),
+        FilePath(DriveFullFilePath, Option<FileID>),
+        ExternalIdMapping(ExternalID, Option<StringVec>),
+        Chunk(ChunkId, Option<FileChunk>),
+        FileChunks(String, Option<ChunkIdList>),
+        FileMeta(String, Option<String>),
+        ChunkRefcount(String, Option<u64>),
+        UploadSession(String, Option<UploadSession>),
+    }
+
+    impl DirectoryUndoEntry {
+        // Which map and key the entry belongs to, so writes to the same entry can be matched up
+        fn slot(&self) -> (Discriminant<Self>, Vec<u8>) {
+            let key = match self {
+                DirectoryUndoEntry::Folder(key, _) => key.to_bytes(),
+                DirectoryUndoEntry::File(key, _) => key.to_bytes(),
+                DirectoryUndoEntry::FileVersion(key, _) => key.to_bytes(),
+                DirectoryUndoEntry::FolderPath(key, _) | DirectoryUndoEntry::FilePath(key, _) => key.to_bytes(),
+                DirectoryUndoEntry::ExternalIdMapping(key, _) => key.to_bytes(),
+                DirectoryUndoEntry::Chunk(key, _) => key.to_bytes(),
+                DirectoryUndoEntry::FileChunks(key, _)
+                | DirectoryUndoEntry::FileMeta(key, _)
+                | DirectoryUndoEntry::ChunkRefcount(key, _)
+                | DirectoryUndoEntry::UploadSession(key, _) => key.to_bytes(),
+            };
+            (discriminant(self), key.into_owned())
+        }
+
+        fn previous_bytes(&self) -> Option<Vec<u8>> {
+            match self {
+                DirectoryUndoEntry::Folder(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::File(_, previous) | DirectoryUndoEntry::FileVersion(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::FolderPath(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::FilePath(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::ExternalIdMapping(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::Chunk(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::FileChunks(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::FileMeta(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::ChunkRefcount(_, previous) => stored_bytes(previous),
+                DirectoryUndoEntry::UploadSession(_, previous) => stored_bytes(previous),
+            }
+        }
+
+        // What the entry's key holds right now
+        fn current_bytes(&self) -> Option<Vec<u8>> {
+            match self {
+                DirectoryUndoEntry::Folder(key, _) => folder_uuid_to_metadata_inner.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::File(key, _) => file_uuid_to_metadata_inner.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::FileVersion(key, _) => file_version_to_metadata_inner.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::FolderPath(key, _) => full_folder_path_to_uuid_inner.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::FilePath(key, _) => full_file_path_to_uuid_inner.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::ExternalIdMapping(key, _) => EXTERNAL_ID_MAPPINGS.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::Chunk(key, _) => CHUNKS.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::FileChunks(key, _) => FILE_CHUNKS.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::FileMeta(key, _) => FILE_META.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::ChunkRefcount(key, _) => CHUNK_REFCOUNTS.with(|map| stored_bytes(&map.borrow().get(key))),
+                DirectoryUndoEntry::UploadSession(key, _) => UPLOAD_SESSIONS.with(|map| stored_bytes(&map.borrow().get(key))),
+            }
+        }
+    }
+
+    fn stored_bytes<V: Storable>(value: &Option<V>) -> Option<Vec<u8>> {
+        value.as_ref().map(|value| value.to_bytes().into_owned())
+    }
+
+    /// Undo entries and deferred effects of one directory action, or of an atomic batch once
+    /// its actions are absorbed into it. Owned by whoever runs the action and only switched on
+    /// while the action's future is polled, so writes made by other messages during its awaits
+    /// are never captured. Each entry also keeps the value the action left behind, and rollback
+    /// leaves an entry alone once another message has written over it.
+    #[derive(Default)]
+    pub struct DirectoryJournal {
+        entries: Vec<DirectoryUndoEntry>,
+        // Value this journal left at each entry's key, in step with `entries` up to the last poll
+        written: Vec<Option<Vec<u8>>>,
+        // Effects that can't be undone, such as webhooks and freeing chunk bytes, run on commit
+        deferred: Vec<Box<dyn FnOnce()>>,
+    }
+
+    /// What a rollback did, the entries it left alone because another message changed them meanwhile are conflicts
+    pub struct DirectoryRollback {
+        pub touched_folders: Vec<FolderID>,
+        pub touched_files: Vec<FileID>,
+        pub conflicts: usize,
+    }
+
+    struct JournaledFuture<'j, F: Future> {
+        future: Pin<Box<F>>,
+        journal: &'j mut DirectoryJournal,
+    }
+
+    impl<F: Future> Future for JournaledFuture<'_, F> {
+        type Output = F::Output;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+            let this = self.get_mut();
+            let outer = DIRECTORY_UNDO_JOURNAL.with(|journal| journal.replace(Some(std::mem::take(this.journal))));
+            let poll = this.future.as_mut().poll(cx);
+            *this.journal = DIRECTORY_UNDO_JOURNAL.with(|journal| journal.replace(outer)).unwrap_or_default();
+            // Nothing else ran during the poll, so what its writes left behind is still there
+            this.journal.capture_written();
+            poll
+        }
+    }
+
+    /// Mutable view handed out by `with_mut`, records what it overwrites or removes while a journal is open
+    /// and keeps the sweep indexes in step with every write
+    pub struct JournaledMapMut<'a, K: Storable + Ord + Clone, V: Storable + Clone> {
+        map: &'a mut StableBTreeMap<K, V, Memory>,
+        record: fn(&K, Option<V>),
+        reindex: fn(&K, Option<&V>, Option<&V>),
+    }
+
+    impl<K: Storable + Ord + Clone, V: Storable + Clone> JournaledMapMut<'_, K, V> {
+        pub fn get(&self, key: &K) -> Option<V> {
+            self.map.get(key)
+        }
+
+        pub fn contains_key(&self, key: &K) -> bool {
+            self.map.contains_key(key)
+        }
+
+        pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+            self.map.keys()
+        }
+
+        pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+            let previous = self.map.insert(key.clone(), value.clone());
+            (self.reindex)(&key, previous.as_ref(), Some(&value));
+            (self.record)(&key, previous.clone());
+            previous
+        }
+
+        pub fn remove(&mut self, key: &K) -> Option<V> {
+            let previous = self.map.remove(key);
+            if previous.is_some() {
+                (self.reindex)(key, previous.as_ref(), None);
+                (self.record)(key, previous.clone());
+            }
+            previous
+        }
+    }
 
     // Wrapper types that implement Deref
     pub struct FolderMap;
@@ -36,11 +194,15 @@ pub mod state {
         }
 
         pub fn insert(&self, key: FolderID, value: FolderRecord) {
-            folder_uuid_to_metadata_inner.with(|map| map.borrow_mut().insert(key, value));
+            self.with_mut(|map| map.insert(key, value));
         }
 
-        pub fn with_mut<R>(&self, f: impl FnOnce(&mut StableBTreeMap<FolderID, FolderRecord, Memory>) -> R) -> R {
-            folder_uuid_to_metadata_inner.with(|map| f(&mut map.borrow_mut()))
+        pub fn with_mut<R>(&self, f: impl FnOnce(&mut JournaledMapMut<FolderID, FolderRecord>) -> R) -> R {
+            folder_uuid_to_metadata_inner.with(|map| f(&mut JournaledMapMut {
+                map: &mut map.borrow_mut(),
+                record: |key, previous| record_directory_undo(DirectoryUndoEntry::Folder(key.clone(), previous)),
+                reindex: reindex_folder,
+            }))
         }
     
         pub fn contains_key(&self, key: &FolderID) -> bool {
@@ -48,7 +210,7 @@ pub mod state {
         }
     
         pub fn remove(&self, key: &FolderID) -> Option<FolderRecord> {
-            folder_uuid_to_metadata_inner.with(|map| map.borrow_mut().remove(key))
+            self.with_mut(|map| map.remove(key))
         }
 
         pub fn with<R>(&self, f: impl FnOnce(&StableBTreeMap<FolderID, FolderRecord, Memory>) -> R) -> R {
@@ -62,11 +224,15 @@ pub mod state {
         }
 
         pub fn insert(&self, key: FileID, value: FileRecord) {
-            file_uuid_to_metadata_inner.with(|map| map.borrow_mut().insert(key, value));
+            self.with_mut(|map| map.insert(key, value));
         }
 
-        pub fn with_mut<R>(&self, f: impl FnOnce(&mut StableBTreeMap<FileID, FileRecord, Memory>) -> R) -> R {
-            file_uuid_to_metadata_inner.with(|map| f(&mut map.borrow_mut()))
+        pub fn with_mut<R>(&self, f: impl FnOnce(&mut JournaledMapMut<FileID, FileRecord>) -> R) -> R {
+            file_uuid_to_metadata_inner.with(|map| f(&mut JournaledMapMut {
+                map: &mut map.borrow_mut(),
+                record: |key, previous| record_directory_undo(DirectoryUndoEntry::File(key.clone(), previous)),
+                reindex: reindex_file,
+            }))
         }
     
         pub fn contains_key(&self, key: &FileID) -> bool {
@@ -74,7 +240,7 @@ pub mod state {
         }
     
         pub fn remove(&self, key: &FileID) -> Option<FileRecord> {
-            file_uuid_to_metadata_inner.with(|map| map.borrow_mut().remove(key))
+            self.with_mut(|map| map.remove(key))
         }
         
         pub fn with<R>(&self, f: impl FnOnce(&StableBTreeMap<FileID, FileRecord, Memory>) -> R) -> R {
@@ -88,11 +254,15 @@ pub mod state {
         }
 
         pub fn insert(&self, key: FileVersionID, value: FileRecord) {
-            file_version_to_metadata_inner.with(|map| map.borrow_mut().insert(key, value));
+            self.with_mut(|map| map.insert(key, value));
         }
 
-        pub fn with_mut<R>(&self, f: impl FnOnce(&mut StableBTreeMap<FileVersionID, FileRecord, Memory>) -> R) -> R {
-            file_version_to_metadata_inner.with(|map| f(&mut map.borrow_mut()))
+        pub fn with_mut<R>(&self, f: impl FnOnce(&mut JournaledMapMut<FileVersionID, FileRecord>) -> R) -> R {
+            file_version_to_metadata_inner.with(|map| f(&mut JournaledMapMut {
+                map: &mut map.borrow_mut(),
+                record: |key, previous| record_directory_undo(DirectoryUndoEntry::FileVersion(key.clone(), previous)),
+                reindex: |_, _, _| {},
+            }))
         }
     
         pub fn contains_key(&self, key: &FileVersionID) -> bool {
@@ -100,7 +270,7 @@ pub mod state {
         }
     
         pub fn remove(&self, key: &FileVersionID) -> Option<FileRecord> {
-            file_version_to_metadata_inner.with(|map| map.borrow_mut().remove(key))
+            self.with_mut(|map| map.remove(key))
         }
         
         pub fn with<R>(&self, f: impl FnOnce(&StableBTreeMap<FileVersionID, FileRecord, Memory>) -> R) -> R {
@@ -114,11 +284,15 @@ pub mod state {
         }
 
         pub fn insert(&self, key: DriveFullFilePath, value: FolderID) {
-            full_folder_path_to_uuid_inner.with(|map| map.borrow_mut().insert(key, value));
+            self.with_mut(|map| map.insert(key, value));
         }
 
-        pub fn with_mut<R>(&self, f: impl FnOnce(&mut StableBTreeMap<DriveFullFilePath, FolderID, Memory>) -> R) -> R {
-            full_folder_path_to_uuid_inner.with(|map| f(&mut map.borrow_mut()))
+        pub fn with_mut<R>(&self, f: impl FnOnce(&mut JournaledMapMut<DriveFullFilePath, FolderID>) -> R) -> R {
+            full_folder_path_to_uuid_inner.with(|map| f(&mut JournaledMapMut {
+                map: &mut map.borrow_mut(),
+                record: |key, previous| record_directory_undo(DirectoryUndoEntry::FolderPath(key.clone(), previous)),
+                reindex: |_, _, _| {},
+            }))
         }
 
         pub fn contains_key(&self, key: &DriveFullFilePath) -> bool {
@@ -126,7 +300,7 @@ pub mod state {
         }
     
         pub fn remove(&self, key: &DriveFullFilePath) -> Option<FolderID> {
-            full_folder_path_to_uuid_inner.with(|map| map.borrow_mut().remove(key))
+            self.with_mut(|map| map.remove(key))
         }
 
         pub fn with<R>(&self, f: impl FnOnce(&StableBTreeMap<DriveFullFilePath, FolderID, Memory>) -> R) -> R {
@@ -140,11 +314,15 @@ pub mod state {
         }
 
         pub fn insert(&self, key: DriveFullFilePath, value: FileID) {
-            full_file_path_to_uuid_inner.with(|map| map.borrow_mut().insert(key, value));
+            self.with_mut(|map| map.insert(key, value));
         }
 
-        pub fn with_mut<R>(&self, f: impl FnOnce(&mut StableBTreeMap<DriveFullFilePath, FileID, Memory>) -> R) -> R {
-            full_file_path_to_uuid_inner.with(|map| f(&mut map.borrow_mut()))
+        pub fn with_mut<R>(&self, f: impl FnOnce(&mut JournaledMapMut<DriveFullFilePath, FileID>) -> R) -> R {
+            full_file_path_to_uuid_inner.with(|map| f(&mut JournaledMapMut {
+                map: &mut map.borrow_mut(),
+                record: |key, previous| record_directory_undo(DirectoryUndoEntry::FilePath(key.clone(), previous)),
+                reindex: |_, _, _| {},
+            }))
         }
     
         pub fn contains_key(&self, key: &DriveFullFilePath) -> bool {
@@ -152,7 +330,7 @@ pub mod state {
         }
     
         pub fn remove(&self, key: &DriveFullFilePath) -> Option<FileID> {
-            full_file_path_to_uuid_inner.with(|map| map.borrow_mut().remove(key))
+            self.with_mut(|map| map.remove(key))
         }
 
         pub fn with<R>(&self, f: impl FnOnce(&StableBTreeMap<DriveFullFilePath, FileID, Memory>) -> R) -> R {
@@ -196,6 +374,337 @@ pub mod state {
                 MEMORY_MANAGER.with(|m| m.borrow().get(FULL_FILE_PATH_TO_UUID_MEMORY_ID))
             )
         );
+
+        // Items sitting directly in a .trash folder, by disk and trashed_at_ms
+        static trashed_by_time_inner: RefCell<StableBTreeMap<DirectorySweepKey, (), Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(TRASHED_BY_TIME_MEMORY_ID))
+            )
+        );
+
+        // Items with an expires_at, by disk and expiry
+        static expiring_by_time_inner: RefCell<StableBTreeMap<DirectorySweepKey, (), Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(EXPIRING_BY_TIME_MEMORY_ID))
+            )
+        );
+
+        // Set once the sweep indexes cover every record written before they existed
+        static sweep_indexes_built: RefCell<StableCell<bool, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(SWEEP_INDEXES_BUILT_MEMORY_ID)),
+                false
+            ).expect("Failed to initialize sweep_indexes_built")
+        );
+    }
+
+    fn expires_at_ms(expires_at: i64) -> Option<u64> {
+        match expires_at > 0 {
+            true => Some(expires_at as u64),
+            false => None,
+        }
+    }
+
+    // Moves one resource's entry in a sweep index from the old time to the new one
+    fn update_sweep_index(
+        index: &RefCell<StableBTreeMap<DirectorySweepKey, (), Memory>>,
+        resource: &DirectorySweepResource,
+        before: Option<(&DiskID, u64)>,
+        after: Option<(&DiskID, u64)>,
+    ) {
+        if before == after {
+            return;
+        }
+        let mut index = index.borrow_mut();
+        if let Some((disk_id, at_ms)) = before {
+            index.remove(&DirectorySweepKey { disk_id: disk_id.clone(), at_ms, resource: resource.clone() });
+        }
+        if let Some((disk_id, at_ms)) = after {
+            index.insert(DirectorySweepKey { disk_id: disk_id.clone(), at_ms, resource: resource.clone() }, ());
+        }
+    }
+
+    fn reindex_folder(key: &FolderID, previous: Option<&FolderRecord>, current: Option<&FolderRecord>) {
+        let resource = DirectorySweepResource::Folder(key.clone());
+        trashed_by_time_inner.with(|index| update_sweep_index(
+            index,
+            &resource,
+            previous.and_then(|folder| folder.trashed_at_ms.map(|at_ms| (&folder.disk_id, at_ms))),
+            current.and_then(|folder| folder.trashed_at_ms.map(|at_ms| (&folder.disk_id, at_ms))),
+        ));
+        expiring_by_time_inner.with(|index| update_sweep_index(
+            index,
+            &resource,
+            previous.and_then(|folder| expires_at_ms(folder.expires_at).map(|at_ms| (&folder.disk_id, at_ms))),
+            current.and_then(|folder| expires_at_ms(folder.expires_at).map(|at_ms| (&folder.disk_id, at_ms))),
+        ));
+    }
+
+    fn reindex_file(key: &FileID, previous: Option<&FileRecord>, current: Option<&FileRecord>) {
+        let resource = DirectorySweepResource::File(key.clone());
+        trashed_by_time_inner.with(|index| update_sweep_index(
+            index,
+            &resource,
+            previous.and_then(|file| file.trashed_at_ms.map(|at_ms| (&file.disk_id, at_ms))),
+            current.and_then(|file| file.trashed_at_ms.map(|at_ms| (&file.disk_id, at_ms))),
+        ));
+        expiring_by_time_inner.with(|index| update_sweep_index(
+            index,
+            &resource,
+            previous.and_then(|file| expires_at_ms(file.expires_at).map(|at_ms| (&file.disk_id, at_ms))),
+            current.and_then(|file| expires_at_ms(file.expires_at).map(|at_ms| (&file.disk_id, at_ms))),
+        ));
+    }
+
+    fn sweep_index_range(
+        index: &RefCell<StableBTreeMap<DirectorySweepKey, (), Memory>>,
+        disk_id: &DiskID,
+        before_ms: u64,
+        limit: usize,
+    ) -> Vec<DirectorySweepKey> {
+        index.borrow()
+            .range(DirectorySweepKey::lower_bound(disk_id, 0)..DirectorySweepKey::lower_bound(disk_id, before_ms))
+            .take(limit)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Oldest first, the items on a disk that went into its trash before `before_ms`
+    pub fn trashed_before(disk_id: &DiskID, before_ms: u64, limit: usize) -> Vec<DirectorySweepKey> {
+        trashed_by_time_inner.with(|index| sweep_index_range(index, disk_id, before_ms, limit))
+    }
+
+    /// Soonest first, the items on a disk whose expires_at is before `before_ms`
+    pub fn expiring_before(disk_id: &DiskID, before_ms: u64, limit: usize) -> Vec<DirectorySweepKey> {
+        expiring_by_time_inner.with(|index| sweep_index_range(index, disk_id, before_ms, limit))
+    }
+
+    /// Takes an entry out of both sweep indexes, for entries whose record is gone
+    pub fn drop_sweep_entry(key: &DirectorySweepKey) {
+        trashed_by_time_inner.with(|index| index.borrow_mut().remove(key));
+        expiring_by_time_inner.with(|index| index.borrow_mut().remove(key));
+    }
+
+    /// Takes a record out of the sweep indexes and leaves the record itself alone, so a sweep
+    /// that can't purge it moves past it. It is indexed again once its trashed_at_ms or
+    /// expires_at changes, or when the indexes are rebuilt.
+    pub fn drop_from_sweep_indexes(resource: &DirectorySweepResource) {
+        match resource {
+            DirectorySweepResource::Folder(folder_id) => {
+                if let Some(folder) = folder_uuid_to_metadata_inner.with(|map| map.borrow().get(folder_id)) {
+                    reindex_folder(folder_id, Some(&folder), None);
+                }
+            },
+            DirectorySweepResource::File(file_id) => {
+                if let Some(file) = file_uuid_to_metadata_inner.with(|map| map.borrow().get(file_id)) {
+                    reindex_file(file_id, Some(&file), None);
+                }
+            },
+        }
+    }
+
+    /// Builds both sweep indexes from the folder and file maps. Items already in a .trash folder
+    /// from before trashed_at_ms existed are stamped with their last update, the best estimate left.
+    /// Runs in post_upgrade, and only scans the maps the first time.
+    pub fn rebuild_directory_sweep_indexes() {
+        if sweep_indexes_built.with(|built| *built.borrow().get()) {
+            return;
+        }
+
+        let is_in_trash = |path: &DriveFullFilePath, is_folder: bool| match path.0.split_once("::.trash/") {
+            Some((_, rest)) => match is_folder {
+                true => !rest.is_empty() && rest.trim_end_matches('/').find('/').is_none(),
+                false => !rest.is_empty() && !rest.contains('/'),
+            },
+            None => false,
+        };
+
+        folder_uuid_to_metadata_inner.with(|map| {
+            let mut map = map.borrow_mut();
+            let folders: Vec<(FolderID, FolderRecord)> = map.iter().collect();
+            for (folder_id, mut folder) in folders {
+                if folder.trashed_at_ms.is_none() && is_in_trash(&folder.full_directory_path, true) {
+                    folder.trashed_at_ms = Some(folder.last_updated_date_ms);
+                    map.insert(folder_id.clone(), folder.clone());
+                }
+                reindex_folder(&folder_id, None, Some(&folder));
+            }
+        });
+        file_uuid_to_metadata_inner.with(|map| {
+            let mut map = map.borrow_mut();
+            let files: Vec<(FileID, FileRecord)> = map.iter().collect();
+            for (file_id, mut file) in files {
+                if file.trashed_at_ms.is_none() && is_in_trash(&file.full_directory_path, false) {
+                    file.trashed_at_ms = Some(file.last_updated_date_ms);
+                    map.insert(file_id.clone(), file.clone());
+                }
+                reindex_file(&file_id, None, Some(&file));
+            }
+        });
+
+        sweep_indexes_built.with(|built| {
+            built.borrow_mut().set(true).expect("Failed to set sweep_indexes_built");
+        });
+    }
+
+    thread_local! {
+        // The journal of the action being polled right now, None means mutations are not journaled
+        static DIRECTORY_UNDO_JOURNAL: RefCell<Option<DirectoryJournal>> = RefCell::new(None);
+    }
+
+    pub(crate) fn directory_journal_recording() -> bool {
+        DIRECTORY_UNDO_JOURNAL.with(|journal| journal.borrow().is_some())
+    }
+
+    pub(crate) fn record_directory_undo(entry: DirectoryUndoEntry) {
+        DIRECTORY_UNDO_JOURNAL.with(|journal| {
+            if let Some(journal) = journal.borrow_mut().as_mut() {
+                journal.entries.push(entry);
+            }
+        });
+    }
+
+    /// Runs `effect` once the action being journaled commits, or right away outside of one.
+    /// A rolled back action drops its effects.
+    pub fn after_directory_commit(effect: impl FnOnce() + 'static) {
+        let effect: Box<dyn FnOnce()> = Box::new(effect);
+        let effect = DIRECTORY_UNDO_JOURNAL.with(|journal| match journal.borrow_mut().as_mut() {
+            Some(journal) => {
+                journal.deferred.push(effect);
+                None
+            },
+            None => Some(effect),
+        });
+        if let Some(effect) = effect {
+            effect();
+        }
+    }
+
+    fn restore<K: Storable + Ord, V: Storable>(map: &mut StableBTreeMap<K, V, Memory>, key: K, previous: Option<V>) {
+        match previous {
+            Some(value) => { map.insert(key, value); },
+            None => { map.remove(&key); },
+        }
+    }
+
+    impl DirectoryJournal {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Awaits `future` with its directory writes recorded into this journal
+        pub async fn record<F: Future>(&mut self, future: F) -> F::Output {
+            JournaledFuture { future: Box::pin(future), journal: self }.await
+        }
+
+        /// Takes over a later journal, so rolling this one back undoes both
+        pub fn absorb(&mut self, later: DirectoryJournal) {
+            self.capture_written();
+            self.entries.extend(later.entries);
+            self.written.extend(later.written);
+            self.deferred.extend(later.deferred);
+        }
+
+        // Fills in `written` for the entries recorded since the last poll. A key written twice in
+        // one poll left the second write's previous value after the first write.
+        fn capture_written(&mut self) {
+            let start = self.written.len();
+            let mut later_previous: HashMap<(Discriminant<DirectoryUndoEntry>, Vec<u8>), Option<Vec<u8>>> = HashMap::new();
+            let mut captured: Vec<Option<Vec<u8>>> = self.entries[start..].iter().rev().map(|entry| {
+                let slot = entry.slot();
+                let written = match later_previous.get(&slot) {
+                    Some(previous) => previous.clone(),
+                    None => entry.current_bytes(),
+                };
+                later_previous.insert(slot, entry.previous_bytes());
+                written
+            }).collect();
+            captured.reverse();
+            self.written.extend(captured);
+        }
+
+        /// Keeps every recorded mutation and runs the deferred effects
+        pub fn commit(self) {
+            for effect in self.deferred {
+                effect();
+            }
+        }
+
+        /// Writes back the prior value of every entry touched, newest first, and drops the deferred effects.
+        /// An entry whose key no longer holds what this journal wrote was changed by another message
+        /// during an await, it keeps that value and counts as a conflict. Also returns the folders and
+        /// files that were touched so callers can refresh derived indexes.
+        pub fn rollback(mut self) -> DirectoryRollback {
+            self.capture_written();
+            let mut touched_folders = Vec::new();
+            let mut touched_files = Vec::new();
+            let mut touched_content = Vec::new();
+            let mut conflicts = 0;
+            // Straight to the inner maps so restoring is not journaled again
+            for (entry, written) in self.entries.into_iter().zip(self.written).rev() {
+                if entry.current_bytes() != written {
+                    ic_cdk::println!("Warning: Directory rollback left {} alone, another request changed it", String::from_utf8_lossy(&entry.slot().1));
+                    conflicts += 1;
+                    continue;
+                }
+                match entry {
+                    DirectoryUndoEntry::Folder(key, previous) => {
+                        let replaced = folder_uuid_to_metadata_inner.with(|map| match &previous {
+                            Some(value) => map.borrow_mut().insert(key.clone(), value.clone()),
+                            None => map.borrow_mut().remove(&key),
+                        });
+                        reindex_folder(&key, replaced.as_ref(), previous.as_ref());
+                        touched_folders.push(key);
+                    },
+                    DirectoryUndoEntry::File(key, previous) => {
+                        let replaced = file_uuid_to_metadata_inner.with(|map| match &previous {
+                            Some(value) => map.borrow_mut().insert(key.clone(), value.clone()),
+                            None => map.borrow_mut().remove(&key),
+                        });
+                        reindex_file(&key, replaced.as_ref(), previous.as_ref());
+                        touched_files.push(key);
+                    },
+                    DirectoryUndoEntry::FileVersion(key, previous) => {
+                        file_version_to_metadata_inner.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::FolderPath(key, previous) => {
+                        full_folder_path_to_uuid_inner.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::FilePath(key, previous) => {
+                        full_file_path_to_uuid_inner.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::ExternalIdMapping(key, previous) => {
+                        EXTERNAL_ID_MAPPINGS.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::Chunk(key, previous) => {
+                        CHUNKS.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::FileChunks(key, previous) => {
+                        FILE_CHUNKS.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::FileMeta(key, previous) => {
+                        FILE_META.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                    DirectoryUndoEntry::ChunkRefcount(key, previous) => {
+                        CHUNK_REFCOUNTS.with(|map| restore(&mut map.borrow_mut(), key.clone(), previous));
+                        touched_content.push(key);
+                    },
+                    DirectoryUndoEntry::UploadSession(key, previous) => {
+                        UPLOAD_SESSIONS.with(|map| restore(&mut map.borrow_mut(), key, previous));
+                    },
+                }
+            }
+            // Bytes first stored by the rolled back action are referenced by nothing now
+            touched_content.sort();
+            touched_content.dedup();
+            discard_unreferenced_content(Some(&touched_content));
+
+            touched_folders.sort();
+            touched_folders.dedup();
+            touched_files.sort();
+            touched_files.dedup();
+            DirectoryRollback { touched_folders, touched_files, conflicts }
+        }
     }
 
     // Public instances with original names
@@ -212,6 +721,9 @@ pub mod state {
         full_folder_path_to_uuid_inner.with(|_| {});
         full_file_path_to_uuid_inner.with(|_| {});
         file_version_to_metadata_inner.with(|_| {});
+        trashed_by_time_inner.with(|_| {});
+        expiring_by_time_inner.with(|_| {});
+        sweep_indexes_built.with(|_| {});
     }
 }
 
@@ -144,6 +144,47 @@ impl Storable for DriveClippedFilePath {
 
 
 
+// Entry in the directory sweep indexes, partitioned by disk so each disk's retention
+// can be applied to a contiguous range, then ordered by time
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, CandidType)]
+pub enum DirectorySweepResource {
+    Folder(FolderID),
+    File(FileID),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, CandidType)]
+pub struct DirectorySweepKey {
+    pub disk_id: DiskID,
+    pub at_ms: u64,
+    pub resource: DirectorySweepResource,
+}
+
+impl DirectorySweepKey {
+    /// Sorts before every entry of the disk at or after `at_ms`, for use as a range bound
+    pub fn lower_bound(disk_id: &DiskID, at_ms: u64) -> Self {
+        DirectorySweepKey {
+            disk_id: disk_id.clone(),
+            at_ms,
+            resource: DirectorySweepResource::Folder(FolderID(String::new())),
+        }
+    }
+}
+
+impl Storable for DirectorySweepKey {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 1024,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, SerdeDiff, CandidType)]
 pub struct FolderRecord {
     pub(crate) id: FolderID,
@@ -163,6 +204,8 @@ pub struct FolderRecord {
     pub(crate) expires_at: i64,
     pub(crate) drive_id: DriveID,
     pub(crate) restore_trash_prior_folder_uuid: Option<FolderID>,
+    #[serde(default)]
+    pub(crate) trashed_at_ms: Option<u64>, // unix ms, only set while the item sits directly in .trash
     pub(crate) has_sovereign_permissions: bool,
     pub(crate) shortcut_to: Option<FolderID>,
     pub(crate) external_id: Option<ExternalID>,
@@ -257,6 +300,8 @@ pub struct FileRecord {
     pub(crate) upload_status: UploadStatus,
     pub(crate) expires_at: i64,
     pub(crate) restore_trash_prior_folder_uuid: Option<FolderID>,
+    #[serde(default)]
+    pub(crate) trashed_at_ms: Option<u64>, // unix ms, only set while the item sits directly in .trash
     pub(crate) has_sovereign_permissions: bool,
     pub(crate) shortcut_to: Option<FileID>,
     pub(crate) external_id: Option<ExternalID>,
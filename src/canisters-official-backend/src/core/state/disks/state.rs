@@ -64,6 +64,8 @@ pub mod state {
             trash_folder: trash_folder,
             created_at: ic_cdk::api::time() / 1_000_000,
             endpoint: None,
+            version_retention: None,
+            trash_retention_days: None,
         };
 
         DISKS_BY_ID_HASHTABLE.with(|map| {
@@ -106,6 +108,7 @@ pub mod state {
                 drive_id: drive_id.clone(),
                 expires_at: -1,
                 restore_trash_prior_folder_uuid: None,
+                trashed_at_ms: None,
                 has_sovereign_permissions: true,
                 shortcut_to: None,
                 external_id: None,
@@ -146,6 +149,7 @@ pub mod state {
                 drive_id: drive_id.clone(),
                 expires_at: -1,
                 restore_trash_prior_folder_uuid: None,
+                trashed_at_ms: None,
                 has_sovereign_permissions: true,
                 shortcut_to: None,
                 external_id: None,
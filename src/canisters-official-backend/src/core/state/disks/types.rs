@@ -51,6 +51,18 @@ pub struct Disk {
     pub external_id: Option<ExternalID>,
     pub external_payload: Option<ExternalPayload>,
     pub endpoint: Option<String>,
+    #[serde(default)]
+    pub version_retention: Option<FileVersionRetention>,
+    #[serde(default)]
+    pub trash_retention_days: Option<u32>, // None keeps trash until it is emptied by hand
+}
+
+// How many superseded file versions a disk keeps around. Either limit prunes a version,
+// the current version is never pruned. None on the disk means keep every version.
+#[derive(Debug, Clone, Serialize, Deserialize, SerdeDiff, CandidType, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileVersionRetention {
+    pub keep_versions: Option<u32>, // counts the current version
+    pub keep_days: Option<u32>,
 }
 
 
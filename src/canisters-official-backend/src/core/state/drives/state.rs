@@ -18,6 +18,7 @@ pub mod state {
     use crate::core::state::drives::types::DriveID;
     use crate::core::state::drives::types::DriveRESTUrlEndpoint;
     use crate::core::state::drives::types::ExternalID;
+    use crate::core::state::directory::state::state::{directory_journal_recording, record_directory_undo, DirectoryUndoEntry};
     use crate::core::state::drives::types::FactorySpawnHistoryRecord;
     use crate::core::state::drives::types::SpawnRedeemCode;
     use crate::core::state::drives::types::StateChecksum;
@@ -54,6 +55,7 @@ pub mod state {
     pub const EXTERNAL_ID_MAPPINGS_MEMORY_ID: MemoryId = MemoryId::new(25);
     pub const UUID_CLAIMED_MEMORY_ID: MemoryId = MemoryId::new(26);
     pub const NONCE_UUID_MEMORY_ID: MemoryId = MemoryId::new(27);
+    pub const ALLOWED_DOMAINS_MEMORY_ID: MemoryId = MemoryId::new(64);
     
 
     thread_local! { 
@@ -153,6 +155,14 @@ pub mod state {
                 "".to_string()
             ).expect("Failed to initialize SPAWN_NOTE")
         );
+
+        // Comma separated origins allowed by CORS, empty means any origin
+        pub(crate) static ALLOWED_DOMAINS: RefCell<StableCell<String, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(ALLOWED_DOMAINS_MEMORY_ID)),
+                "".to_string()
+            ).expect("Failed to initialize ALLOWED_DOMAINS")
+        );
         
         // Convert SpawnRedeemCode to StableCell
         pub(crate) static SPAWN_REDEEM_CODE: RefCell<StableCell<SpawnRedeemCode, Memory>> = RefCell::new(
@@ -180,6 +190,7 @@ pub mod state {
         RECENT_DEPLOYMENTS.with(|_| {});
         SPAWN_NOTE.with(|_| {});
         SPAWN_REDEEM_CODE.with(|_| {});
+        ALLOWED_DOMAINS.with(|_| {});
     }
 
     pub fn init_self_drive(
@@ -257,6 +268,13 @@ pub mod state {
         }
         EXTERNAL_ID_MAPPINGS.with(|mappings| {
             let mut mappings_mut = mappings.borrow_mut();
+
+            // Directory actions roll mapping changes back along with the records they belong to
+            if directory_journal_recording() {
+                for key in old_external_id.iter().chain(new_external_id.iter()) {
+                    record_directory_undo(DirectoryUndoEntry::ExternalIdMapping(key.clone(), mappings_mut.get(key)));
+                }
+            }
             
             // Handle removal of old external ID mapping if it exists
             if let Some(old_id) = old_external_id {
@@ -39,7 +39,7 @@ impl Storable for DriveID {
 
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff, CandidType)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerdeDiff, CandidType, Ord, PartialOrd)]
 pub struct InboxNotifID(pub String);
 impl fmt::Display for InboxNotifID {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -47,6 +47,25 @@ impl fmt::Display for InboxNotifID {
     }
 }
 
+impl Storable for InboxNotifID {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256, // Adjust based on your needs
+        is_fixed_size: false,
+    };
+    
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Failed to serialize InboxNotifID");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::de::from_reader(bytes.as_ref())
+            .expect("Failed to deserialize InboxNotifID")
+    }
+}
+
 
 #[derive(Debug, Clone, Serialize, Deserialize, SerdeDiff, CandidType)]
 pub struct Drive {
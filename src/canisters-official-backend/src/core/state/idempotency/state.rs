@@ -0,0 +1,116 @@
+// src/core/state/idempotency/state.rs
+pub mod state {
+    use std::{cell::RefCell, collections::HashMap, time::Duration};
+
+    use ic_stable_structures::{memory_manager::MemoryId, DefaultMemoryImpl, StableBTreeMap, StableCell};
+
+    use crate::{core::state::idempotency::types::{IdempotencyConfig, IdempotencyLookup, IdempotencyRecord}, debug_log, MEMORY_MANAGER};
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+    pub const IDEMPOTENCY_RECORDS_MEMORY_ID: MemoryId = MemoryId::new(67);
+    pub const IDEMPOTENCY_CONFIG_MEMORY_ID: MemoryId = MemoryId::new(68);
+
+    pub const IDEMPOTENCY_PRUNE_TICK_SECONDS: u64 = 60 * 60; // hourly
+    // A request that trapped after an await never finishes, its key frees up after this
+    const IDEMPOTENCY_IN_FLIGHT_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+    thread_local! {
+        // Keyed by "{user_id}:{idempotency_key}" so users cannot replay each other's responses
+        pub(crate) static IDEMPOTENCY_RECORDS: RefCell<StableBTreeMap<String, IdempotencyRecord, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(IDEMPOTENCY_RECORDS_MEMORY_ID))
+            )
+        );
+
+        pub(crate) static IDEMPOTENCY_CONFIG: RefCell<StableCell<IdempotencyConfig, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(IDEMPOTENCY_CONFIG_MEMORY_ID)),
+                IdempotencyConfig::default()
+            ).expect("Failed to initialize IDEMPOTENCY_CONFIG")
+        );
+
+        // Keys whose first request is still running, with when it started
+        static IDEMPOTENCY_IN_FLIGHT: RefCell<HashMap<String, u64>> = RefCell::new(HashMap::new());
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        IDEMPOTENCY_RECORDS.with(|_| {});
+        IDEMPOTENCY_CONFIG.with(|_| {});
+    }
+
+    pub fn start_idempotency_prune_timer() {
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(IDEMPOTENCY_PRUNE_TICK_SECONDS),
+            || {
+                let pruned = prune_idempotency_records();
+                if pruned > 0 {
+                    debug_log!("Pruned {} expired idempotency records", pruned);
+                }
+            },
+        );
+    }
+
+    fn idempotency_window_ms() -> u64 {
+        IDEMPOTENCY_CONFIG.with(|config| config.borrow().get().window_ms)
+    }
+
+    /// Decides what to do with a request carrying an idempotency key. On a Miss the key is
+    /// marked in flight until `finish_idempotent_request` is called with the response.
+    pub fn begin_idempotent_request(scope_key: &str, request_hash: &str) -> IdempotencyLookup {
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+
+        let in_flight = IDEMPOTENCY_IN_FLIGHT.with(|in_flight| {
+            in_flight.borrow().get(scope_key)
+                .is_some_and(|started_ms| now_ms.saturating_sub(*started_ms) < IDEMPOTENCY_IN_FLIGHT_TIMEOUT_MS)
+        });
+        if in_flight {
+            return IdempotencyLookup::InFlight;
+        }
+
+        let window_ms = idempotency_window_ms();
+        let existing = IDEMPOTENCY_RECORDS.with(|records| records.borrow().get(&scope_key.to_string()));
+        match existing {
+            Some(record) if now_ms.saturating_sub(record.created_at_ms) < window_ms => {
+                if record.request_hash == request_hash {
+                    IdempotencyLookup::Replay(record)
+                } else {
+                    IdempotencyLookup::Conflict
+                }
+            },
+            _ => {
+                IDEMPOTENCY_IN_FLIGHT.with(|in_flight| in_flight.borrow_mut().insert(scope_key.to_string(), now_ms));
+                IdempotencyLookup::Miss
+            }
+        }
+    }
+
+    /// Releases the in flight mark and keeps the response for repeats, None keeps nothing (eg. server errors)
+    pub fn finish_idempotent_request(scope_key: &str, record: Option<IdempotencyRecord>) {
+        IDEMPOTENCY_IN_FLIGHT.with(|in_flight| in_flight.borrow_mut().remove(scope_key));
+        IDEMPOTENCY_RECORDS.with(|records| {
+            let mut records = records.borrow_mut();
+            match record {
+                Some(record) => { records.insert(scope_key.to_string(), record); },
+                None => { records.remove(&scope_key.to_string()); },
+            }
+        });
+    }
+
+    /// Drops records older than the window, returns how many were removed
+    pub fn prune_idempotency_records() -> usize {
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let window_ms = idempotency_window_ms();
+        IDEMPOTENCY_RECORDS.with(|records| {
+            let mut records = records.borrow_mut();
+            let expired: Vec<String> = records.iter()
+                .filter(|(_, record)| now_ms.saturating_sub(record.created_at_ms) >= window_ms)
+                .map(|(scope_key, _)| scope_key)
+                .collect();
+            for scope_key in &expired {
+                records.remove(scope_key);
+            }
+            expired.len()
+        })
+    }
+}
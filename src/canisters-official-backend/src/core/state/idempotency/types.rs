@@ -0,0 +1,65 @@
+// src/core/state/idempotency/types.rs
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+// First response to a mutating request sent with an Idempotency-Key header
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct IdempotencyRecord {
+    pub request_hash: String, // sha256 of method, url and body, a repeat must match it
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub created_at_ms: u64,
+}
+
+impl Storable for IdempotencyRecord {
+    // Bodies are whatever the route answered with
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct IdempotencyConfig {
+    pub window_ms: u64, // how long a key replays its first response
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            window_ms: 24 * 60 * 60 * 1000,
+        }
+    }
+}
+
+impl Storable for IdempotencyConfig {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+pub enum IdempotencyLookup {
+    Miss, // run the request, then hand its response to finish_idempotent_request
+    Replay(IdempotencyRecord),
+    Conflict, // same key, different request
+    InFlight, // same key, first request has not answered yet
+}
@@ -0,0 +1,132 @@
+// src/core/state/inbox/state.rs
+pub mod state {
+    use std::cell::RefCell;
+
+    use ic_stable_structures::{memory_manager::MemoryId, StableBTreeMap, DefaultMemoryImpl};
+
+    use crate::{core::state::{drives::types::InboxNotifID, inbox::types::{InboxNotif, InboxNotifIDList, InboxRecipientID}}, MEMORY_MANAGER};
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+    pub const INBOX_NOTIFS_BY_ID_MEMORY_ID: MemoryId = MemoryId::new(59);
+    pub const INBOX_NOTIFS_BY_RECIPIENT_MEMORY_ID: MemoryId = MemoryId::new(60);
+
+    // Retention limits, applied to a recipient's inbox whenever new mail arrives for it
+    pub const INBOX_MAX_NOTIFS_PER_RECIPIENT: usize = 500;
+    pub const INBOX_RETENTION_MS: u64 = 90 * 24 * 60 * 60 * 1000;
+
+    thread_local! {
+        pub(crate) static INBOX_NOTIFS_BY_ID_HASHTABLE: RefCell<StableBTreeMap<InboxNotifID, InboxNotif, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(INBOX_NOTIFS_BY_ID_MEMORY_ID))
+            )
+        );
+
+        pub(crate) static INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE: RefCell<StableBTreeMap<InboxRecipientID, InboxNotifIDList, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(INBOX_NOTIFS_BY_RECIPIENT_MEMORY_ID))
+            )
+        );
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        INBOX_NOTIFS_BY_ID_HASHTABLE.with(|_| {});
+        INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|_| {});
+    }
+
+    pub fn store_inbox_notif(notif: InboxNotif) {
+        let recipient = notif.recipient.clone();
+        let now_ms = notif.created_at;
+
+        INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| {
+            store.borrow_mut().insert(notif.id.clone(), notif.clone());
+        });
+        INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|store| {
+            let mut store = store.borrow_mut();
+            let mut list = store.get(&recipient).unwrap_or_default();
+            list.add(notif.id.clone());
+            store.insert(recipient.clone(), list);
+        });
+
+        prune_recipient_inbox(&recipient, now_ms);
+    }
+
+    pub fn remove_inbox_notif(id: &InboxNotifID) -> Option<InboxNotif> {
+        let removed = INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| store.borrow_mut().remove(id))?;
+
+        INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|store| {
+            let mut store = store.borrow_mut();
+            if let Some(mut list) = store.get(&removed.recipient) {
+                list.remove(id);
+                if list.is_empty() {
+                    store.remove(&removed.recipient);
+                } else {
+                    store.insert(removed.recipient.clone(), list);
+                }
+            }
+        });
+
+        Some(removed)
+    }
+
+    pub fn get_recipient_inbox_notifs(recipient: &InboxRecipientID) -> Vec<InboxNotif> {
+        let ids = INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|store| {
+            store.borrow().get(recipient).unwrap_or_default()
+        });
+
+        INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| {
+            let store = store.borrow();
+            ids.notifs.iter()
+                .filter_map(|id| store.get(id))
+                .collect()
+        })
+    }
+
+    pub fn get_inbox_recipients() -> Vec<InboxRecipientID> {
+        INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|store| {
+            store.borrow().iter().map(|(recipient, _)| recipient).collect()
+        })
+    }
+
+    // Drops mail older than the retention window, then the oldest beyond the per recipient cap
+    fn prune_recipient_inbox(recipient: &InboxRecipientID, now_ms: u64) {
+        let list = match INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|store| store.borrow().get(recipient)) {
+            Some(list) => list,
+            None => return,
+        };
+
+        let cutoff_ms = now_ms.saturating_sub(INBOX_RETENTION_MS);
+        let mut expired: Vec<InboxNotifID> = INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| {
+            let store = store.borrow();
+            list.notifs.iter()
+                .filter(|id| store.get(id).map_or(true, |notif| notif.created_at < cutoff_ms))
+                .cloned()
+                .collect()
+        });
+
+        let remaining = list.notifs.len() - expired.len();
+        if remaining > INBOX_MAX_NOTIFS_PER_RECIPIENT {
+            let overflow = remaining - INBOX_MAX_NOTIFS_PER_RECIPIENT;
+            expired.extend(
+                list.notifs.iter()
+                    .filter(|id| !expired.contains(id))
+                    .take(overflow)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        for id in expired.iter() {
+            if remove_inbox_notif(id).is_none() {
+                // Dangling id without a record, clean it out of the list directly
+                INBOX_NOTIFS_BY_RECIPIENT_HASHTABLE.with(|store| {
+                    let mut store = store.borrow_mut();
+                    if let Some(mut list) = store.get(recipient) {
+                        list.remove(id);
+                        store.insert(recipient.clone(), list);
+                    }
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// src/core/state/inbox/types.rs
+use std::{borrow::Cow, fmt};
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Serialize, Deserialize};
+use crate::core::{state::{drives::types::InboxNotifID, groups::types::GroupID}, types::{IDPrefix, UserID}};
+
+
+// Either a UserID or a GroupID, kept as a plain string so it can key the stable map
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, CandidType, Ord, PartialOrd)]
+pub struct InboxRecipientID(pub String);
+
+impl InboxRecipientID {
+    pub fn user(user_id: &UserID) -> Self {
+        InboxRecipientID(user_id.0.clone())
+    }
+
+    pub fn group(group_id: &GroupID) -> Self {
+        InboxRecipientID(group_id.0.clone())
+    }
+
+    pub fn as_group(&self) -> Option<GroupID> {
+        if self.0.starts_with(IDPrefix::Group.as_str()) {
+            Some(GroupID(self.0.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.0.starts_with(IDPrefix::User.as_str()) || self.0.starts_with(IDPrefix::Group.as_str())
+    }
+}
+
+impl fmt::Display for InboxRecipientID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Storable for InboxRecipientID {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct InboxNotif {
+    pub id: InboxNotifID,
+    pub recipient: InboxRecipientID,
+    pub sender: UserID,
+    pub topic: Option<String>,
+    pub payload: Option<String>, // raw JSON as sent to /organization/inbox
+    pub read_at: Option<u64>, // unix ms
+    pub created_at: u64, // unix ms
+}
+
+impl Storable for InboxNotif {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 128 * 1024, // payload is capped at 64kb on the way in
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+
+// Oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType, Default)]
+pub struct InboxNotifIDList {
+    pub notifs: Vec<InboxNotifID>,
+}
+
+impl InboxNotifIDList {
+    pub fn new() -> Self {
+        Self { notifs: Vec::new() }
+    }
+
+    pub fn add(&mut self, id: InboxNotifID) {
+        if !self.notifs.contains(&id) {
+            self.notifs.push(id);
+        }
+    }
+
+    pub fn remove(&mut self, id: &InboxNotifID) {
+        self.notifs.retain(|existing| existing != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifs.is_empty()
+    }
+}
+
+impl Storable for InboxNotifIDList {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256 * 256, // holds up to INBOX_MAX_NOTIFS_PER_RECIPIENT ids
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
@@ -14,3 +14,8 @@ pub mod permissions;
 pub mod search;
 pub mod labels;
 pub mod purchases;
+pub mod inbox;
+pub mod rate_limits;
+pub mod audit;
+pub mod idempotency;
+pub mod cycles;
\ No newline at end of file
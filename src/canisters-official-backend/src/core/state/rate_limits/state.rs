@@ -0,0 +1,85 @@
+// src/core/state/rate_limits/state.rs
+pub mod state {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use ic_stable_structures::{memory_manager::MemoryId, DefaultMemoryImpl, StableCell};
+
+    use crate::{core::state::rate_limits::types::{RateLimitConfig, RateLimitRouteGroup, RateLimitRule}, MEMORY_MANAGER};
+
+    type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
+    pub const RATE_LIMIT_CONFIG_MEMORY_ID: MemoryId = MemoryId::new(65);
+
+    // Idle buckets are dropped once this many are held, they would be full again anyway
+    const MAX_RATE_LIMIT_BUCKETS: usize = 10_000;
+
+    struct TokenBucket {
+        tokens: f64,
+        last_refill_ms: u64,
+    }
+
+    thread_local! {
+        pub(crate) static RATE_LIMIT_CONFIG: RefCell<StableCell<RateLimitConfig, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(RATE_LIMIT_CONFIG_MEMORY_ID)),
+                RateLimitConfig::default()
+            ).expect("Failed to initialize RATE_LIMIT_CONFIG")
+        );
+
+        // Buckets live on the heap only, an upgrade simply refills everyone
+        static RATE_LIMIT_BUCKETS: RefCell<HashMap<(String, RateLimitRouteGroup), TokenBucket>> = RefCell::new(HashMap::new());
+    }
+
+    pub fn initialize() {
+        // Force thread_locals in this module to initialize
+        RATE_LIMIT_CONFIG.with(|_| {});
+    }
+
+    pub fn rate_limit_rule(group: RateLimitRouteGroup) -> Option<RateLimitRule> {
+        RATE_LIMIT_CONFIG.with(|config| {
+            config.borrow().get().rules.iter().find(|rule| rule.group == group).cloned()
+        })
+    }
+
+    /// Takes one token from every subject's bucket for the group (e.g. the api key and its user).
+    /// Nothing is taken unless all buckets have a token, Err carries the seconds until they do.
+    pub fn consume_rate_limit(subjects: &[String], group: RateLimitRouteGroup) -> Result<(), u64> {
+        let rule = match rate_limit_rule(group) {
+            Some(rule) if rule.burst > 0 && rule.per_minute > 0 => rule,
+            _ => return Ok(()),
+        };
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let ms_per_token = 60_000.0 / rule.per_minute as f64;
+
+        RATE_LIMIT_BUCKETS.with(|buckets| {
+            let mut buckets = buckets.borrow_mut();
+
+            if buckets.len() > MAX_RATE_LIMIT_BUCKETS {
+                buckets.retain(|_, bucket| now_ms.saturating_sub(bucket.last_refill_ms) < 10 * 60 * 1000);
+            }
+
+            let mut retry_after_ms = 0.0_f64;
+            for subject in subjects {
+                let bucket = buckets.entry((subject.clone(), group)).or_insert(TokenBucket {
+                    tokens: rule.burst as f64,
+                    last_refill_ms: now_ms,
+                });
+                let elapsed_ms = now_ms.saturating_sub(bucket.last_refill_ms) as f64;
+                bucket.tokens = (bucket.tokens + elapsed_ms / ms_per_token).min(rule.burst as f64);
+                bucket.last_refill_ms = now_ms;
+                if bucket.tokens < 1.0 {
+                    retry_after_ms = retry_after_ms.max((1.0 - bucket.tokens) * ms_per_token);
+                }
+            }
+
+            if retry_after_ms > 0.0 {
+                return Err(((retry_after_ms / 1000.0).ceil() as u64).max(1));
+            }
+            for subject in subjects {
+                if let Some(bucket) = buckets.get_mut(&(subject.clone(), group)) {
+                    bucket.tokens -= 1.0;
+                }
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,104 @@
+// src/core/state/rate_limits/types.rs
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt};
+
+// Routes sharing a token bucket. Anything not listed falls into Default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, CandidType)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitRouteGroup {
+    DirectoryAction,
+    Search,
+    Reindex,
+    Snapshot,
+    SnapshotPart, // pages and parts of a snapshot or archive that is already running
+    Replay,
+    Webhooks,
+    Default,
+}
+
+impl RateLimitRouteGroup {
+    /// Group for a path relative to `/v1/drive/{organization_id}`
+    pub fn for_path(path: &str) -> Self {
+        match path {
+            "/directory/action" => RateLimitRouteGroup::DirectoryAction,
+            "/organization/search" => RateLimitRouteGroup::Search,
+            "/organization/reindex" => RateLimitRouteGroup::Reindex,
+            "/organization/snapshot" => RateLimitRouteGroup::Snapshot,
+            "/organization/snapshot/start" => RateLimitRouteGroup::Snapshot,
+            "/organization/snapshot/page" => RateLimitRouteGroup::SnapshotPart,
+            "/organization/snapshot/end" => RateLimitRouteGroup::SnapshotPart,
+            "/organization/archive/export" => RateLimitRouteGroup::Snapshot,
+            "/organization/archive/export/part" => RateLimitRouteGroup::SnapshotPart,
+            "/organization/archive/import" => RateLimitRouteGroup::Snapshot,
+            "/organization/archive/import/part" => RateLimitRouteGroup::SnapshotPart,
+            "/organization/replay" => RateLimitRouteGroup::Replay,
+            _ if path.starts_with("/webhooks/") => RateLimitRouteGroup::Webhooks,
+            _ => RateLimitRouteGroup::Default,
+        }
+    }
+}
+
+impl fmt::Display for RateLimitRouteGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitRouteGroup::DirectoryAction => write!(f, "DIRECTORY_ACTION"),
+            RateLimitRouteGroup::Search => write!(f, "SEARCH"),
+            RateLimitRouteGroup::Reindex => write!(f, "REINDEX"),
+            RateLimitRouteGroup::Snapshot => write!(f, "SNAPSHOT"),
+            RateLimitRouteGroup::SnapshotPart => write!(f, "SNAPSHOT_PART"),
+            RateLimitRouteGroup::Replay => write!(f, "REPLAY"),
+            RateLimitRouteGroup::Webhooks => write!(f, "WEBHOOKS"),
+            RateLimitRouteGroup::Default => write!(f, "DEFAULT"),
+        }
+    }
+}
+
+// Token bucket holding up to `burst` requests, refilled at `per_minute`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct RateLimitRule {
+    pub group: RateLimitRouteGroup,
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+// Groups without a rule are not rate limited
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+pub struct RateLimitConfig {
+    pub rules: Vec<RateLimitRule>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let rule = |group, burst, per_minute| RateLimitRule { group, burst, per_minute };
+        Self {
+            rules: vec![
+                rule(RateLimitRouteGroup::DirectoryAction, 60, 60),
+                rule(RateLimitRouteGroup::Search, 60, 60),
+                rule(RateLimitRouteGroup::Reindex, 2, 1),
+                rule(RateLimitRouteGroup::Snapshot, 5, 2),
+                rule(RateLimitRouteGroup::SnapshotPart, 600, 600),
+                rule(RateLimitRouteGroup::Replay, 5, 2),
+                rule(RateLimitRouteGroup::Webhooks, 30, 30),
+                rule(RateLimitRouteGroup::Default, 300, 300),
+            ],
+        }
+    }
+}
+
+impl Storable for RateLimitConfig {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 16 * 1024,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
@@ -7,9 +7,10 @@ use ic_stable_structures::{
 use std::borrow::Cow;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
-use crate::{core::state::raw_storage::types::{ChunkId, FileChunk}, debug_log, MEMORY_MANAGER};
+use sha2::{Digest, Sha256};
+use crate::{core::state::{directory::state::state::{after_directory_commit, directory_journal_recording, record_directory_undo, DirectoryUndoEntry}, raw_storage::types::{ChunkId, FileChunk}}, debug_log, MEMORY_MANAGER};
 
-use super::types::{ChunkIdList, CHUNK_SIZE};
+use super::types::{ChunkContent, ChunkIdList, RunningSha256, UploadSession, CHUNK_SIZE};
 
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -17,6 +18,9 @@ type Memory = VirtualMemory<DefaultMemoryImpl>;
 const CHUNKS_MEMORY_ID: MemoryId = MemoryId::new(1);
 const FILE_CHUNKS_MEMORY_ID: MemoryId = MemoryId::new(2);
 const FILE_META_MEMORY_ID: MemoryId = MemoryId::new(3);
+const CHUNK_CONTENT_MEMORY_ID: MemoryId = MemoryId::new(61);
+const CHUNK_REFCOUNTS_MEMORY_ID: MemoryId = MemoryId::new(62);
+const UPLOAD_SESSIONS_MEMORY_ID: MemoryId = MemoryId::new(63);
 
 // Implement Storable for our types
 impl Storable for ChunkId {
@@ -72,6 +76,27 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(FILE_META_MEMORY_ID))
         )
     );
+
+    // Chunk bytes keyed by their sha256 hex, stored once no matter how many files share them
+    pub(crate) static CHUNK_CONTENT: RefCell<StableBTreeMap<String, ChunkContent, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(CHUNK_CONTENT_MEMORY_ID))
+        )
+    );
+
+    // How many FileChunks point at each entry of CHUNK_CONTENT
+    pub(crate) static CHUNK_REFCOUNTS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(CHUNK_REFCOUNTS_MEMORY_ID))
+        )
+    );
+
+    // Raw uploads that have received chunks but were not completed yet, keyed by file_id
+    pub(crate) static UPLOAD_SESSIONS: RefCell<StableBTreeMap<String, UploadSession, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(UPLOAD_SESSIONS_MEMORY_ID))
+        )
+    );
 }
 
 
@@ -80,26 +105,152 @@ pub fn initialize() {
     CHUNKS.with(|_| {});
     FILE_CHUNKS.with(|_| {});
     FILE_META.with(|_| {});
+    CHUNK_CONTENT.with(|_| {});
+    CHUNK_REFCOUNTS.with(|_| {});
+    UPLOAD_SESSIONS.with(|_| {});
 }
 
-pub fn store_chunk(chunk: FileChunk) {
+// Writes made while a directory action runs are journaled, so rolling the action back restores them
+fn journal_chunk(chunk_id: &ChunkId) {
+    if directory_journal_recording() {
+        let previous = CHUNKS.with(|chunks| chunks.borrow().get(chunk_id));
+        record_directory_undo(DirectoryUndoEntry::Chunk(chunk_id.clone(), previous));
+    }
+}
+
+fn journal_file_chunks(file_id: &str) {
+    if directory_journal_recording() {
+        let previous = FILE_CHUNKS.with(|file_chunks| file_chunks.borrow().get(&file_id.to_string()));
+        record_directory_undo(DirectoryUndoEntry::FileChunks(file_id.to_string(), previous));
+    }
+}
+
+fn journal_file_meta(file_id: &str) {
+    if directory_journal_recording() {
+        let previous = FILE_META.with(|fmeta| fmeta.borrow().get(&file_id.to_string()));
+        record_directory_undo(DirectoryUndoEntry::FileMeta(file_id.to_string(), previous));
+    }
+}
+
+fn journal_refcount(hash: &str) {
+    if directory_journal_recording() {
+        let previous = CHUNK_REFCOUNTS.with(|refs| refs.borrow().get(&hash.to_string()));
+        record_directory_undo(DirectoryUndoEntry::ChunkRefcount(hash.to_string(), previous));
+    }
+}
+
+fn journal_upload_session(file_id: &str) {
+    if directory_journal_recording() {
+        let previous = UPLOAD_SESSIONS.with(|sessions| sessions.borrow().get(&file_id.to_string()));
+        record_directory_undo(DirectoryUndoEntry::UploadSession(file_id.to_string(), previous));
+    }
+}
+
+pub fn hash_chunk_data(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+// Adds a reference to the content, storing the bytes only the first time they are seen
+fn retain_content(hash: &str, data: &[u8]) {
+    let count = CHUNK_REFCOUNTS.with(|refs| refs.borrow().get(&hash.to_string()).unwrap_or(0));
+    if count == 0 {
+        CHUNK_CONTENT.with(|content| {
+            content.borrow_mut().insert(hash.to_string(), ChunkContent(data.to_vec()));
+        });
+    }
+    journal_refcount(hash);
+    CHUNK_REFCOUNTS.with(|refs| {
+        refs.borrow_mut().insert(hash.to_string(), count + 1);
+    });
+}
+
+// Drops a reference to the content, freeing the bytes once nothing points at them.
+// Inside a directory action the bytes are only freed when it commits, a rollback may need them back.
+fn release_content(hash: &str) {
+    let count = CHUNK_REFCOUNTS.with(|refs| refs.borrow().get(&hash.to_string()).unwrap_or(0));
+    journal_refcount(hash);
+    if count <= 1 {
+        debug_log!("Freeing chunk content: {}", hash);
+        CHUNK_REFCOUNTS.with(|refs| {
+            refs.borrow_mut().remove(&hash.to_string());
+        });
+        let hash = hash.to_string();
+        after_directory_commit(move || discard_unreferenced_content(Some(&[hash])));
+    } else {
+        CHUNK_REFCOUNTS.with(|refs| {
+            refs.borrow_mut().insert(hash.to_string(), count - 1);
+        });
+    }
+}
+
+// Removes a chunk record and its hold on the shared content
+fn remove_chunk(chunk_id: &ChunkId) {
+    journal_chunk(chunk_id);
+    let removed = CHUNKS.with(|chunks| chunks.borrow_mut().remove(chunk_id));
+    if let Some(hash) = removed.and_then(|chunk| chunk.content_hash) {
+        release_content(&hash);
+    }
+}
+
+// Points a chunk record at content that is already stored, without touching the bytes
+pub fn link_chunk(chunk: FileChunk) {
+    if let Some(hash) = &chunk.content_hash {
+        journal_refcount(hash);
+        CHUNK_REFCOUNTS.with(|refs| {
+            let mut refs = refs.borrow_mut();
+            let count = refs.get(hash).unwrap_or(0);
+            refs.insert(hash.clone(), count + 1);
+        });
+    }
+    index_chunk(&chunk);
+    journal_chunk(&chunk.id);
     CHUNKS.with(|chunks| {
-        chunks.borrow_mut().insert(chunk.id.clone(), chunk.clone());
+        chunks.borrow_mut().insert(chunk.id.clone(), chunk);
     });
+}
 
+fn index_chunk(chunk: &FileChunk) {
+    journal_file_chunks(&chunk.file_id);
     FILE_CHUNKS.with(|file_chunks| {
         let mut map = file_chunks.borrow_mut();
-        let existing_chunks = map.get(&chunk.file_id)
+        let mut chunk_ids = map.get(&chunk.file_id)
             .map(|list| list.0.clone())
             .unwrap_or_default();
-        let mut new_chunks = existing_chunks;
-        new_chunks.push(chunk.id.clone());
-        map.insert(chunk.file_id.clone(), ChunkIdList(new_chunks));
+        if !chunk_ids.contains(&chunk.id) {
+            chunk_ids.push(chunk.id.clone());
+            map.insert(chunk.file_id.clone(), ChunkIdList(chunk_ids));
+        }
+    });
+}
+
+// Chunk bytes are content addressed, identical chunks across files are stored once.
+// Re-uploading a chunk id replaces it and releases the content it pointed at.
+// A content_hash already on the chunk is trusted, callers set it when they verified the bytes.
+pub fn store_chunk(chunk: FileChunk) {
+    let hash = chunk.content_hash.clone().unwrap_or_else(|| hash_chunk_data(&chunk.data));
+    retain_content(&hash, &chunk.data);
+    remove_chunk(&chunk.id);
+
+    index_chunk(&chunk);
+    journal_chunk(&chunk.id);
+    CHUNKS.with(|chunks| {
+        chunks.borrow_mut().insert(chunk.id.clone(), FileChunk {
+            data: Vec::new(),
+            content_hash: Some(hash),
+            ..chunk
+        });
     });
 }
 
 pub fn get_chunk(chunk_id: &ChunkId) -> Option<FileChunk> {
-    CHUNKS.with(|chunks| chunks.borrow().get(chunk_id))
+    let mut chunk = CHUNKS.with(|chunks| chunks.borrow().get(chunk_id))?;
+    // Chunks stored before deduplication still carry their own bytes
+    if let Some(hash) = &chunk.content_hash {
+        chunk.data = CHUNK_CONTENT.with(|content| content.borrow().get(hash))?.0;
+    }
+    Some(chunk)
 }
 
 pub fn get_file_chunks(file_id: &str) -> Vec<FileChunk> {
@@ -114,7 +265,77 @@ pub fn get_file_chunks(file_id: &str) -> Vec<FileChunk> {
     })
 }
 
+// Chunk records of a file ordered by index, without loading the shared content
+pub fn get_file_chunk_records(file_id: &str) -> Vec<FileChunk> {
+    let chunk_ids = FILE_CHUNKS.with(|file_chunks| {
+        file_chunks.borrow().get(&file_id.to_string())
+            .map(|chunks| chunks.0.clone())
+            .unwrap_or_default()
+    });
+    let mut chunks: Vec<FileChunk> = chunk_ids.iter()
+        .filter_map(|id| CHUNKS.with(|chunks| chunks.borrow().get(id)))
+        .collect();
+    chunks.sort_by_key(|c| c.chunk_index);
+    chunks
+}
+
+// Bytes start..=end of a file, only the chunks overlapping the span are loaded.
+// Err carries the index of a chunk whose content is missing.
+pub fn read_file_range(file_id: &str, start: u64, end: u64) -> Result<Vec<u8>, u32> {
+    let mut body = Vec::with_capacity((end - start + 1) as usize);
+    let mut offset: u64 = 0;
+    for record in get_file_chunk_records(file_id) {
+        let chunk_start = offset;
+        let chunk_end = offset + record.size as u64;
+        offset = chunk_end;
+        if chunk_end <= start {
+            continue;
+        }
+        if chunk_start > end {
+            break;
+        }
+        let chunk = get_chunk(&record.id).ok_or(record.chunk_index)?;
+        let from = start.saturating_sub(chunk_start) as usize;
+        let to = ((end + 1).min(chunk_end) - chunk_start) as usize;
+        body.extend_from_slice(&chunk.data[from..to]);
+    }
+    Ok(body)
+}
+
+// sha256 hex of a chunk's bytes, computed for chunks stored before deduplication
+pub fn chunk_sha256(chunk: &FileChunk) -> String {
+    match &chunk.content_hash {
+        Some(hash) => hash.clone(),
+        None => hash_chunk_data(&chunk.data),
+    }
+}
+
+// Stores verified content without a reference, link_chunk adds the references.
+// Used by archive imports, which receive the bytes before the chunk records.
+pub fn stage_chunk_content(hash: &str, data: Vec<u8>) {
+    CHUNK_CONTENT.with(|content| {
+        content.borrow_mut().insert(hash.to_string(), ChunkContent(data));
+    });
+}
+
+// Frees staged content that no chunk ended up referencing, all such content when `hashes` is None
+pub fn discard_unreferenced_content(hashes: Option<&[String]>) {
+    let candidates: Vec<String> = match hashes {
+        Some(hashes) => hashes.to_vec(),
+        None => CHUNK_CONTENT.with(|content| content.borrow().keys().collect()),
+    };
+    for hash in candidates {
+        let referenced = CHUNK_REFCOUNTS.with(|refs| refs.borrow().get(&hash).unwrap_or(0) > 0);
+        if !referenced {
+            CHUNK_CONTENT.with(|content| {
+                content.borrow_mut().remove(&hash);
+            });
+        }
+    }
+}
+
 pub fn store_filename(file_id: &str, filename: &str) {
+    journal_file_meta(file_id);
     FILE_META.with(|fmeta| {
         fmeta.borrow_mut().insert(file_id.to_string(), filename.to_string());
     });
@@ -135,14 +356,16 @@ pub fn delete_file_data(file_id: &str) -> Result<(), String> {
 
     debug_log!("Deleting file data for file ID: {}", file_id);
     
-    // Delete each individual chunk
+    // Delete each individual chunk, shared content is only freed by its last reference
     for chunk_id in &chunk_ids {
         debug_log!("Deleting chunk: {}", chunk_id);
-        CHUNKS.with(|chunks| {
-            chunks.borrow_mut().remove(chunk_id);
-        });
+        remove_chunk(chunk_id);
     }
     
+    journal_file_chunks(file_id);
+    journal_file_meta(file_id);
+    journal_upload_session(file_id);
+
     // Remove the file's entry from FILE_CHUNKS
     FILE_CHUNKS.with(|file_chunks| {
         debug_log!("Removing file entry from FILE_CHUNKS {}", file_id);
@@ -154,8 +377,103 @@ pub fn delete_file_data(file_id: &str) -> Result<(), String> {
         debug_log!("Removing file metadata for file ID: {}", file_id);
         fmeta.borrow_mut().remove(&file_id.to_string());
     });
+    UPLOAD_SESSIONS.with(|sessions| {
+        sessions.borrow_mut().remove(&file_id.to_string());
+    });
 
     debug_log!("File data deleted for file ID: {}", file_id);
     
     Ok(())
-}
\ No newline at end of file
+}
+
+// Links every chunk stored under `from_key` into `to_key`, replacing whatever was there.
+// Only chunk records are written, the bytes stay shared through their content hash.
+// Chunk ids follow the upload scheme of "{key}-{index}".
+pub fn copy_file_data(from_key: &str, to_key: &str) -> Result<(), String> {
+    let chunks = get_file_chunk_records(from_key);
+    if chunks.is_empty() {
+        return Err(format!("No chunks found for file ID: {}", from_key));
+    }
+
+    // Clear out the destination first so stale chunks don't linger
+    let _ = delete_file_data(to_key);
+
+    for chunk in chunks {
+        let id = ChunkId(format!("{}-{}", to_key, chunk.chunk_index));
+        match chunk.content_hash {
+            Some(_) => link_chunk(FileChunk {
+                id,
+                file_id: to_key.to_string(),
+                ..chunk
+            }),
+            // Chunks from before deduplication get hashed on the way over
+            None => store_chunk(FileChunk {
+                id,
+                file_id: to_key.to_string(),
+                ..chunk
+            }),
+        }
+    }
+
+    if let Some(filename) = FILE_META.with(|fmeta| fmeta.borrow().get(&from_key.to_string())) {
+        store_filename(to_key, &filename);
+    }
+
+    debug_log!("File data copied from {} to {}", from_key, to_key);
+
+    Ok(())
+}
+
+// Records a received chunk against the file's upload session, starting one if needed.
+// `content_changed` is set when the chunk replaced different bytes under its index, which
+// invalidates a running hash that already absorbed the old bytes.
+pub fn touch_upload_session(file_id: &str, chunk_index: u32, total_chunks: u32, content_changed: bool) {
+    let now_ms = ic_cdk::api::time() / 1_000_000;
+    let (started_at, running) = match get_upload_session(file_id) {
+        Some(session) if chunk_index != 0 => {
+            let running = session.running_sha256
+                .filter(|running| !(content_changed && chunk_index < running.next_chunk_index));
+            (session.started_at, running)
+        },
+        _ => (now_ms, None),
+    };
+    let mut running = running.unwrap_or_else(RunningSha256::new);
+    absorb_received_chunks(file_id, total_chunks, &mut running);
+    UPLOAD_SESSIONS.with(|sessions| {
+        sessions.borrow_mut().insert(file_id.to_string(), UploadSession {
+            file_id: file_id.to_string(),
+            total_chunks,
+            started_at,
+            last_chunk_at: now_ms,
+            running_sha256: Some(running),
+        });
+    });
+}
+
+// Feeds the chunks that continue the running hash without a gap. Chunks arriving
+// out of order wait in storage until the gap before them is filled.
+pub fn absorb_received_chunks(file_id: &str, total_chunks: u32, running: &mut RunningSha256) {
+    while running.next_chunk_index < total_chunks {
+        let chunk_id = ChunkId(format!("{}-{}", file_id, running.next_chunk_index));
+        match get_chunk(&chunk_id) {
+            Some(chunk) => running.update(&chunk.data),
+            None => break,
+        }
+        running.next_chunk_index += 1;
+    }
+}
+
+// sha256 hex of the bytes stored under a chunk id, without loading them
+pub fn stored_chunk_sha256(chunk_id: &ChunkId) -> Option<String> {
+    CHUNKS.with(|chunks| chunks.borrow().get(chunk_id)).map(|chunk| chunk_sha256(&chunk))
+}
+
+pub fn get_upload_session(file_id: &str) -> Option<UploadSession> {
+    UPLOAD_SESSIONS.with(|sessions| sessions.borrow().get(&file_id.to_string()))
+}
+
+pub fn end_upload_session(file_id: &str) {
+    UPLOAD_SESSIONS.with(|sessions| {
+        sessions.borrow_mut().remove(&file_id.to_string());
+    });
+}
@@ -3,6 +3,7 @@ use ic_stable_structures::{storable::Bound, Storable};
 // src/core/state/raw_storage/types.rs
 use serde::{Deserialize, Serialize};
 use serde_diff::SerdeDiff;
+use sha2::digest::generic_array::GenericArray;
 use std::{borrow::Cow, fmt};
 
 
@@ -20,8 +21,29 @@ pub struct FileChunk {
     pub id: ChunkId,
     pub file_id: String,
     pub chunk_index: u32,
-    pub data: Vec<u8>,
-    pub size: usize
+    pub data: Vec<u8>, // empty while stored when content_hash is set, filled in on read
+    pub size: usize,
+    #[serde(default)]
+    pub content_hash: Option<String>, // sha256 hex into CHUNK_CONTENT, None for chunks stored before dedup
+}
+
+// Raw bytes of a content addressed chunk, shared by every FileChunk with the same hash
+#[derive(Debug, Clone)]
+pub struct ChunkContent(pub Vec<u8>);
+
+impl Storable for ChunkContent {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: CHUNK_SIZE as u32,
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ChunkContent(bytes.into_owned())
+    }
 }
 
 pub const CHUNK_SIZE: usize = 3 * 1024 * (1024 / 2); // 1.5MB chunks
@@ -54,4 +76,96 @@ pub enum UploadStatus {
     Queued,     // File is created but no chunks uploaded yet
     Pending,    // Some chunks uploaded, not completed
     Completed,  // All chunks uploaded and verified
-}
\ No newline at end of file
+}
+// Tracks an in progress raw upload so clients can resume and abandoned uploads can be reclaimed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, CandidType)]
+pub struct UploadSession {
+    pub file_id: String,
+    pub total_chunks: u32,   // as announced by the client with each chunk
+    pub started_at: u64,     // ms, reset when chunk 0 restarts the upload
+    pub last_chunk_at: u64,  // ms
+    #[serde(default)]
+    pub running_sha256: Option<RunningSha256>,
+}
+
+// Whole file sha256 absorbed chunk by chunk in index order, so completing an upload
+// only pads the last block instead of reading the entire file back in one message
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, CandidType)]
+pub struct RunningSha256 {
+    pub next_chunk_index: u32, // chunks below this index are absorbed
+    pub state: Vec<u32>,       // the 8 sha256 state words
+    pub pending: Vec<u8>,      // tail shorter than a block, not compressed yet
+    pub length: u64,           // bytes absorbed
+}
+
+const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+impl RunningSha256 {
+    pub fn new() -> Self {
+        Self {
+            next_chunk_index: 0,
+            state: SHA256_INITIAL_STATE.to_vec(),
+            pending: Vec::new(),
+            length: 0,
+        }
+    }
+
+    fn compress(state: &mut [u32; 8], blocks: &[u8]) {
+        for block in blocks.chunks_exact(64) {
+            sha2::compress256(state, std::slice::from_ref(GenericArray::from_slice(block)));
+        }
+    }
+
+    fn state_words(&self) -> [u32; 8] {
+        self.state.as_slice().try_into().unwrap_or(SHA256_INITIAL_STATE)
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let mut state = self.state_words();
+        self.length += data.len() as u64;
+        if !self.pending.is_empty() {
+            let take = (64 - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < 64 {
+                return;
+            }
+            Self::compress(&mut state, &self.pending);
+            self.pending.clear();
+        }
+        let whole = data.len() - data.len() % 64;
+        Self::compress(&mut state, &data[..whole]);
+        self.pending.extend_from_slice(&data[whole..]);
+        self.state = state.to_vec();
+    }
+
+    // sha256 hex of everything absorbed so far
+    pub fn finalize(&self) -> String {
+        let mut state = self.state_words();
+        let mut tail = self.pending.clone();
+        tail.push(0x80);
+        while tail.len() % 64 != 56 {
+            tail.push(0);
+        }
+        tail.extend_from_slice(&(self.length * 8).to_be_bytes());
+        Self::compress(&mut state, &tail);
+        hex::encode(state.iter().flat_map(|word| word.to_be_bytes()).collect::<Vec<u8>>())
+    }
+}
+
+impl Storable for UploadSession {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut bytes = vec![];
+        ciborium::into_writer(self, &mut bytes).expect("Failed to serialize UploadSession");
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        ciborium::from_reader(&bytes[..]).expect("Failed to deserialize UploadSession")
+    }
+}
@@ -3,111 +3,351 @@
 pub mod state {
     use std::cell::RefCell;
     use std::collections::{HashMap, BTreeMap};
+    use std::ops::Bound;
     use std::sync::Arc;
+    use std::time::Duration;
 
-    use fst::{Map, MapBuilder, IntoStreamer, Streamer};
-    use fst::automaton::Subsequence;
+    use fst::{Automaton, Map, MapBuilder, IntoStreamer, Streamer};
+    use fst::automaton::{Levenshtein, Str};
+    use ic_stable_structures::{Memory, StableBTreeMap, Storable};
 
     use crate::core::api::permissions::directory::check_directory_permissions;
     use crate::core::api::permissions::system::check_system_permissions;
     use crate::core::state::directory::state::state::{file_uuid_to_metadata, folder_uuid_to_metadata};
-    use crate::core::state::directory::types::{DriveFullFilePath, FileID, FolderID};
-    use crate::core::state::disks::types::DiskID;
+    use crate::core::state::contacts::types::Contact;
+    use crate::core::state::directory::types::{FileID, FileRecord, FolderID, FolderRecord};
+    use crate::core::state::disks::types::{Disk, DiskID};
     use crate::core::state::drives::state::state::{DRIVES_BY_ID_HASHTABLE, DRIVE_ID};
-    use crate::core::state::drives::types::{DriveID, ExternalID};
-    use crate::core::state::groups::types::GroupID;
+    use crate::core::state::drives::types::{Drive, DriveID, ExternalID};
+    use crate::core::state::groups::types::{Group, GroupID};
+    use crate::core::state::labels::state::LABELS_BY_ID_HASHTABLE;
+    use crate::core::state::labels::types::{Label, LabelID, LabelStringValue};
+    use crate::core::state::permissions::state::state::{DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, SYSTEM_PERMISSIONS_BY_ID_HASHTABLE};
+    use crate::core::state::permissions::types::{DirectoryPermission, DirectoryPermissionID, SystemPermission, SystemPermissionID};
     use crate::core::state::purchases::state::state::PURCHASES_BY_ID_HASHTABLE;
-    use crate::core::state::purchases::types::PurchaseID;
+    use crate::core::state::purchases::types::{Purchase, PurchaseID};
     use crate::core::state::permissions::types::{DirectoryPermissionType, PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum};
-    use crate::core::state::search::types::{SearchResult, SearchResultResourceID, SearchCategoryEnum};
+    use crate::core::state::search::types::{SearchDocument, SearchFacets, SearchFieldEnum, SearchFilters, SearchResult, SearchResultResourceID, SearchCategoryEnum, TokenPosting};
+    use crate::core::state::webhooks::state::state::WEBHOOKS_BY_ID_HASHTABLE;
+    use crate::core::state::webhooks::types::{Webhook, WebhookID};
     use crate::core::state::contacts::state::state::{CONTACTS_BY_ID_HASHTABLE};
     use crate::core::state::disks::state::state::{DISKS_BY_ID_HASHTABLE};
     use crate::core::state::groups::state::state::{GROUPS_BY_ID_HASHTABLE};
     use crate::core::types::{IDPrefix, UserID};
+    use crate::debug_log;
     use crate::rest::directory::types::DirectoryResourceID;
     
+    pub const MAX_SEARCH_EDIT_DISTANCE: u32 = 2; // Levenshtein automata grow quickly past this
+    const MAX_TOKEN_LENGTH: usize = 64;
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+    const SEARCH_DELTA_MERGE_THRESHOLD: usize = 500; // pending changes before the delta is merged right away
+    const SEARCH_DELTA_MERGE_TICK_SECONDS: u64 = 5 * 60;
+    const REINDEX_BATCH_SIZE: usize = 500; // records read from stable memory per timer tick
 
-    // Thread-local storage for the FST search index
+    // Tables in the order a reindex walks them
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum ReindexTable {
+        Files,
+        Folders,
+        Contacts,
+        Disks,
+        Drives,
+        Groups,
+        Purchases,
+        Labels,
+        Webhooks,
+        DirectoryPermissions,
+        SystemPermissions,
+    }
+
+    const REINDEX_TABLES: [ReindexTable; 11] = [
+        ReindexTable::Files,
+        ReindexTable::Folders,
+        ReindexTable::Contacts,
+        ReindexTable::Disks,
+        ReindexTable::Drives,
+        ReindexTable::Groups,
+        ReindexTable::Purchases,
+        ReindexTable::Labels,
+        ReindexTable::Webhooks,
+        ReindexTable::DirectoryPermissions,
+        ReindexTable::SystemPermissions,
+    ];
+
+    // A rebuild in progress. Each tick reads the next batch after `after` in the current
+    // table, so a large drive is spread over many messages instead of trapping one.
+    struct ReindexJob {
+        table: usize,          // position in REINDEX_TABLES
+        after: Option<String>, // last key read from the current table
+        documents: Vec<SearchDocument>,
+        started_at_ms: u64,
+    }
+
+    /// Progress of the running rebuild, if any
+    #[derive(Debug, Clone)]
+    pub struct ReindexProgress {
+        pub started_at_ms: u64,
+        pub documents_read: usize,
+    }
+
+    // Thread-local storage for the FST search index.
+    // The FST maps every distinct token to its slot in TOKEN_POSTINGS,
+    // postings point into SEARCH_DOCUMENTS.
     thread_local! {
         static FST_INDEX: RefCell<Option<Arc<Map<Vec<u8>>>>> = RefCell::new(None);
-        static PATH_TO_ID_MAP: RefCell<HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>> = RefCell::new(HashMap::new());
+        static TOKEN_POSTINGS: RefCell<Vec<Vec<TokenPosting>>> = RefCell::new(Vec::new());
+        static SEARCH_DOCUMENTS: RefCell<Vec<SearchDocument>> = RefCell::new(Vec::new());
         static LAST_INDEX_UPDATE_MS: RefCell<u64> = RefCell::new(0);
+        // Changes since the FST was last built, keyed by resource id.
+        // Some(document) replaces the indexed copy, None hides a removed resource.
+        static SEARCH_DELTA: RefCell<HashMap<String, Option<SearchDocument>>> = RefCell::new(HashMap::new());
+        static REINDEX_JOB: RefCell<Option<ReindexJob>> = RefCell::new(None);
     }
     
-    /// Builds or rebuilds the search index for the entire drive
-    /// This is the primary function to call when you need to create or update the index
-    pub fn reindex_drive() -> Result<usize, String> {
-        // Get current time in milliseconds
-        let current_time_ms = ic_cdk::api::time() / 1_000_000; // Convert nanoseconds to milliseconds
-        
-        // Index all resources
-        let result = build_index();
-        
-        // Update the last index time if successful
-        if let Ok(count) = result {
-            // Update thread-local timestamp
-            LAST_INDEX_UPDATE_MS.with(|cell| {
-                *cell.borrow_mut() = current_time_ms;
-            });
-            
-            // Update the Drive record to store the last_indexed_ms value
-            DRIVE_ID.with(|drive_id| {
-                let drive_id_val = drive_id.clone(); // a copy of the key
-                DRIVES_BY_ID_HASHTABLE.with(|drives| {
-                    let mut map_ref = drives.borrow_mut();
-                    
-                    // Fetch a Drive (clone) from the stable map:
-                    if let Some(mut drive) = map_ref.get(&drive_id_val) {
-                        // Update the Drive in regular memory
-                        drive.last_indexed_ms = Some(current_time_ms);
-            
-                        // Reinsert into the stable map
-                        map_ref.insert(drive_id_val, drive);
-                    }
-                });
+    /// Starts rebuilding the search index for the entire drive in timer driven batches.
+    /// Returns false when a rebuild is already running, it keeps going and covers this request too.
+    /// Queries keep using the previous index until the rebuild swaps in the new one.
+    pub fn start_reindex() -> bool {
+        let started = REINDEX_JOB.with(|cell| {
+            let mut job = cell.borrow_mut();
+            if job.is_some() {
+                return false;
+            }
+            *job = Some(ReindexJob {
+                table: 0,
+                after: None,
+                documents: Vec::new(),
+                started_at_ms: ic_cdk::api::time() / 1_000_000,
             });
+            true
+        });
+        if started {
+            ic_cdk_timers::set_timer(Duration::ZERO, reindex_tick);
+        }
+        started
+    }
+
+    pub fn reindex_progress() -> Option<ReindexProgress> {
+        REINDEX_JOB.with(|cell| cell.borrow().as_ref().map(|job| ReindexProgress {
+            started_at_ms: job.started_at_ms,
+            documents_read: job.documents.len(),
+        }))
+    }
+
+    fn reindex_in_progress() -> bool {
+        REINDEX_JOB.with(|cell| cell.borrow().is_some())
+    }
 
+    fn reindex_tick() {
+        let mut job = match REINDEX_JOB.with(|cell| cell.borrow_mut().take()) {
+            Some(job) => job,
+            None => return,
+        };
+
+        let mut budget = REINDEX_BATCH_SIZE;
+        while budget > 0 && job.table < REINDEX_TABLES.len() {
+            let (read, last) = scan_table(REINDEX_TABLES[job.table], job.after.take(), budget, &mut job.documents);
+            budget -= read;
+            if read == 0 || last.is_none() {
+                job.table += 1;
+            } else {
+                job.after = last;
+            }
+        }
+
+        if job.table < REINDEX_TABLES.len() {
+            REINDEX_JOB.with(|cell| *cell.borrow_mut() = Some(job));
+            ic_cdk_timers::set_timer(Duration::ZERO, reindex_tick);
+            return;
+        }
+        if let Err(e) = finish_reindex(job.documents) {
+            debug_log!("Failed to rebuild search index: {}", e);
+        }
+    }
+
+    // Changes made while the tables were being read sit in the delta, they win over the
+    // copies the job read earlier
+    fn finish_reindex(documents: Vec<SearchDocument>) -> Result<usize, String> {
+        let documents = SEARCH_DELTA.with(|delta_cell| {
+            let delta = delta_cell.borrow();
+            let mut documents: Vec<SearchDocument> = documents.into_iter()
+                .filter(|document| !delta.contains_key(&document.resource_id.to_string()))
+                .collect();
+            documents.extend(delta.values().flatten().cloned());
+            documents
+        });
+        let count = build_fst(documents)?;
+        SEARCH_DELTA.with(|cell| cell.borrow_mut().clear());
+
+        let current_time_ms = ic_cdk::api::time() / 1_000_000;
+        LAST_INDEX_UPDATE_MS.with(|cell| {
+            *cell.borrow_mut() = current_time_ms;
+        });
+
+        // Update the Drive record to store the last_indexed_ms value
+        let drive_id = DRIVE_ID.with(|drive_id| drive_id.clone());
+        DRIVES_BY_ID_HASHTABLE.with(|drives| {
+            let mut map_ref = drives.borrow_mut();
+            if let Some(mut drive) = map_ref.get(&drive_id) {
+                drive.last_indexed_ms = Some(current_time_ms);
+                map_ref.insert(drive_id, drive);
+            }
+        });
+        Ok(count)
+    }
+
+    /// Rebuilds the index right after init/upgrade, the heap index does not survive upgrades.
+    /// Runs from timers so a large drive can never trap the upgrade itself.
+    /// Also merges the delta into the FST in the background.
+    pub fn start_search_index_timers() {
+        start_reindex();
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(SEARCH_DELTA_MERGE_TICK_SECONDS),
+            || {
+                if let Err(e) = merge_search_delta() {
+                    debug_log!("Failed to merge search delta: {}", e);
+                }
+            },
+        );
+    }
+
+    /// Refreshes one resource after it was created, renamed, moved or deleted.
+    /// Queries see the change right away through the delta, the FST catches up on the next merge.
+    pub fn index_search_resource(resource_id: SearchResultResourceID) {
+        // Until the first build starts there is nothing to patch, that build reads current state.
+        // During a rebuild the delta also catches changes to records the job already read.
+        if FST_INDEX.with(|cell| cell.borrow().is_none()) && !reindex_in_progress() {
+            return;
+        }
+
+        let document = build_document(&resource_id);
+        let pending = SEARCH_DELTA.with(|cell| {
+            let mut delta = cell.borrow_mut();
+            delta.insert(resource_id.to_string(), document);
+            delta.len()
+        });
+
+        if pending >= SEARCH_DELTA_MERGE_THRESHOLD {
+            if let Err(e) = merge_search_delta() {
+                debug_log!("Failed to merge search delta: {}", e);
+            }
+        }
+    }
+
+    /// Refreshes a folder and everything below it, their paths change with the folder
+    pub fn index_search_folder_tree(folder_id: &FolderID) {
+        let mut stack = vec![folder_id.clone()];
+        while let Some(current_folder_id) = stack.pop() {
+            if let Some(folder) = folder_uuid_to_metadata.get(&current_folder_id) {
+                for file_id in &folder.file_uuids {
+                    index_search_resource(SearchResultResourceID::File(file_id.clone()));
+                }
+                stack.extend(folder.subfolder_uuids.clone());
+            }
+            index_search_resource(SearchResultResourceID::Folder(current_folder_id));
+        }
+    }
+
+    /// Folds the delta into the FST so queries go back to a single index lookup.
+    /// Unlike a reindex nothing is read back from stable memory.
+    /// Waits while a reindex runs, the rebuild applies the delta when it finishes.
+    pub fn merge_search_delta() -> Result<usize, String> {
+        if SEARCH_DELTA.with(|cell| cell.borrow().is_empty()) || reindex_in_progress() {
+            return Ok(SEARCH_DOCUMENTS.with(|cell| cell.borrow().len()));
+        }
+
+        // On failure the delta stays, so queries keep serving the pending changes
+        let result = build_fst(current_documents());
+        if result.is_ok() {
+            SEARCH_DELTA.with(|cell| cell.borrow_mut().clear());
         }
-        
         result
     }
-    
-    /// Internal function that builds the index from all resources
-    /// This handles the actual FST construction
-    fn build_index() -> Result<usize, String> {
-        let mut builder = MapBuilder::memory();
-        let mut entries = BTreeMap::new();
-        let mut path_to_id = HashMap::new();
-    
-        // Index files
-        index_files(&mut entries, &mut path_to_id);
-        
-        // Index folders
-        index_folders(&mut entries, &mut path_to_id);
-        
-        // Index contacts
-        index_contacts(&mut entries, &mut path_to_id);
-        
-        // Index disks
-        index_disks(&mut entries, &mut path_to_id);
-        
-        // Index drives
-        index_drives(&mut entries, &mut path_to_id);
-        
-        // Index groups
-        index_groups(&mut entries, &mut path_to_id);
 
-        // Index purchases
-        index_purchases(&mut entries, &mut path_to_id);
-    
+    // Indexed documents with the delta applied on top
+    fn current_documents() -> Vec<SearchDocument> {
+        SEARCH_DELTA.with(|delta_cell| {
+            let delta = delta_cell.borrow();
+            let mut documents: Vec<SearchDocument> = SEARCH_DOCUMENTS.with(|cell| {
+                cell.borrow()
+                    .iter()
+                    .filter(|document| !delta.contains_key(&document.resource_id.to_string()))
+                    .cloned()
+                    .collect()
+            });
+            documents.extend(delta.values().flatten().cloned());
+            documents
+        })
+    }
+
+    // Reads one resource back from state, None once it is gone or deleted
+    fn build_document(resource_id: &SearchResultResourceID) -> Option<SearchDocument> {
+        match resource_id {
+            SearchResultResourceID::File(file_id) => file_uuid_to_metadata
+                .get(file_id)
+                .filter(|file| !file.deleted)
+                .map(|file| file_document(file_id, &file)),
+            SearchResultResourceID::Folder(folder_id) => folder_uuid_to_metadata
+                .get(folder_id)
+                .filter(|folder| !folder.deleted)
+                .map(|folder| folder_document(folder_id, &folder)),
+            SearchResultResourceID::Contact(contact_id) => CONTACTS_BY_ID_HASHTABLE
+                .with(|contacts| contacts.borrow().get(contact_id))
+                .map(|contact| contact_document(contact_id, &contact)),
+            SearchResultResourceID::Disk(disk_id) => DISKS_BY_ID_HASHTABLE
+                .with(|disks| disks.borrow().get(disk_id))
+                .map(|disk| disk_document(disk_id, &disk)),
+            SearchResultResourceID::Drive(drive_id) => DRIVES_BY_ID_HASHTABLE
+                .with(|drives| drives.borrow().get(drive_id))
+                .map(|drive| drive_document(drive_id, &drive)),
+            SearchResultResourceID::Group(group_id) => GROUPS_BY_ID_HASHTABLE
+                .with(|groups| groups.borrow().get(group_id))
+                .map(|group| group_document(group_id, &group)),
+            SearchResultResourceID::Purchase(purchase_id) => PURCHASES_BY_ID_HASHTABLE
+                .with(|purchases| purchases.borrow().get(purchase_id))
+                .map(|purchase| purchase_document(purchase_id, &purchase)),
+            SearchResultResourceID::Label(label_id) => LABELS_BY_ID_HASHTABLE
+                .with(|labels| labels.borrow().get(label_id))
+                .map(|label| label_document(label_id, &label)),
+            SearchResultResourceID::Webhook(webhook_id) => WEBHOOKS_BY_ID_HASHTABLE
+                .with(|webhooks| webhooks.borrow().get(webhook_id))
+                .map(|webhook| webhook_document(webhook_id, &webhook)),
+            SearchResultResourceID::DirectoryPermission(permission_id) => DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE
+                .with(|permissions| permissions.borrow().get(permission_id))
+                .map(|permission| directory_permission_document(permission_id, &permission)),
+            SearchResultResourceID::SystemPermission(permission_id) => SYSTEM_PERMISSIONS_BY_ID_HASHTABLE
+                .with(|permissions| permissions.borrow().get(permission_id))
+                .map(|permission| system_permission_document(permission_id, &permission)),
+        }
+    }
+
+    /// Builds the FST and postings from a complete set of documents and swaps them in
+    fn build_fst(documents: Vec<SearchDocument>) -> Result<usize, String> {
         // Get the total count of indexed items
-        let indexed_count = entries.len();
+        let indexed_count = documents.len();
+
+        // Invert documents into token -> postings, BTreeMap keeps tokens sorted for the FST
+        let mut token_postings: BTreeMap<String, Vec<TokenPosting>> = BTreeMap::new();
+        for (doc_index, document) in documents.iter().enumerate() {
+            for (field, text) in &document.fields {
+                for token in tokenize(text) {
+                    let postings = token_postings.entry(token).or_default();
+                    let posting = TokenPosting { doc: doc_index as u32, field: *field };
+                    if !postings.contains(&posting) {
+                        postings.push(posting);
+                    }
+                }
+            }
+        }
     
-        // Build the FST Map
-        for (key, value) in &entries {
-            if let Err(e) = builder.insert(key, *value) {
+        // Build the FST Map, each token's value is its slot in the postings list
+        let mut builder = MapBuilder::memory();
+        let mut postings_list = Vec::with_capacity(token_postings.len());
+        for (slot, (token, postings)) in token_postings.into_iter().enumerate() {
+            if let Err(e) = builder.insert(&token, slot as u64) {
                 return Err(format!("Failed to build search index: {}", e));
             }
+            postings_list.push(postings);
         }
     
         // Finish building and store in thread-local storage
@@ -118,190 +358,327 @@ pub mod state {
             *cell.borrow_mut() = Some(arc_map);
         });
         
-        PATH_TO_ID_MAP.with(|cell| {
-            *cell.borrow_mut() = path_to_id;
+        TOKEN_POSTINGS.with(|cell| {
+            *cell.borrow_mut() = postings_list;
+        });
+
+        SEARCH_DOCUMENTS.with(|cell| {
+            *cell.borrow_mut() = documents;
         });
         
         Ok(indexed_count)
     }
 
-    /// Index files
-    fn index_files(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-        file_uuid_to_metadata.with(|map| {
-            for (file_id, metadata) in map.iter() {
-                // Skip deleted files
-                if !metadata.deleted {
-                    // Normalize the path for search
-                    let normalized = normalize_path(&metadata.full_directory_path.0);
-                    
-                    path_to_id.insert(normalized.clone(), (
-                        SearchResultResourceID::File(file_id.clone()),
-                        SearchCategoryEnum::Files
-                    ));
-                    
-                    // Insert with a default score of 1
-                    entries.insert(normalized, 1u64);
-                }
-            }
-        });
+    // Reads up to `limit` records after `after` from one table.
+    // Returns how many were read and the last key, None once the table is exhausted.
+    fn scan_table(table: ReindexTable, after: Option<String>, limit: usize, documents: &mut Vec<SearchDocument>) -> (usize, Option<String>) {
+        match table {
+            ReindexTable::Files => file_uuid_to_metadata.with(|map| scan_map(map, after.map(FileID), limit, documents,
+                |id| id.0.clone(),
+                |id, file| (!file.deleted).then(|| file_document(id, file)))),
+            ReindexTable::Folders => folder_uuid_to_metadata.with(|map| scan_map(map, after.map(FolderID), limit, documents,
+                |id| id.0.clone(),
+                |id, folder| (!folder.deleted).then(|| folder_document(id, folder)))),
+            ReindexTable::Contacts => CONTACTS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(UserID), limit, documents,
+                |id| id.0.clone(),
+                |id, contact| Some(contact_document(id, contact)))),
+            ReindexTable::Disks => DISKS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(DiskID), limit, documents,
+                |id| id.0.clone(),
+                |id, disk| Some(disk_document(id, disk)))),
+            ReindexTable::Drives => DRIVES_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(DriveID), limit, documents,
+                |id| id.0.clone(),
+                |id, drive| Some(drive_document(id, drive)))),
+            ReindexTable::Groups => GROUPS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(GroupID), limit, documents,
+                |id| id.0.clone(),
+                |id, group| Some(group_document(id, group)))),
+            ReindexTable::Purchases => PURCHASES_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(PurchaseID), limit, documents,
+                |id| id.0.clone(),
+                |id, purchase| Some(purchase_document(id, purchase)))),
+            ReindexTable::Labels => LABELS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(LabelID), limit, documents,
+                |id| id.0.clone(),
+                |id, label| Some(label_document(id, label)))),
+            ReindexTable::Webhooks => WEBHOOKS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(WebhookID), limit, documents,
+                |id| id.0.clone(),
+                |id, webhook| Some(webhook_document(id, webhook)))),
+            // Directory and system permissions both land in the Permissions category
+            ReindexTable::DirectoryPermissions => DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(DirectoryPermissionID), limit, documents,
+                |id| id.0.clone(),
+                |id, permission| Some(directory_permission_document(id, permission)))),
+            ReindexTable::SystemPermissions => SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|map| scan_map(&map.borrow(), after.map(SystemPermissionID), limit, documents,
+                |id| id.0.clone(),
+                |id, permission| Some(system_permission_document(id, permission)))),
+        }
     }
 
-    /// Index folders
-    fn index_folders(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-        folder_uuid_to_metadata.with(|map| {
-            for (folder_id, metadata) in map.iter() {
-                // Skip deleted folders
-                if !metadata.deleted {
-                    // Normalize the path for search
-                    let normalized = normalize_path(&metadata.full_directory_path.0);
-                    
-                    path_to_id.insert(normalized.clone(), (
-                        SearchResultResourceID::Folder(folder_id.clone()),
-                        SearchCategoryEnum::Folders
-                    ));
-                    
-                    // Insert with a default score of 1
-                    entries.insert(normalized, 1u64);
-                }
-            }
-        });
+    fn scan_map<K, V, M>(
+        map: &StableBTreeMap<K, V, M>,
+        after: Option<K>,
+        limit: usize,
+        documents: &mut Vec<SearchDocument>,
+        key_string: impl Fn(&K) -> String,
+        document: impl Fn(&K, &V) -> Option<SearchDocument>,
+    ) -> (usize, Option<String>)
+    where
+        K: Storable + Ord + Clone,
+        V: Storable,
+        M: Memory,
+    {
+        let start = match after {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+        let mut read = 0;
+        let mut last = None;
+        for (key, value) in map.range((start, Bound::Unbounded)).take(limit) {
+            read += 1;
+            documents.extend(document(&key, &value));
+            last = Some(key);
+        }
+        (read, last.map(|key| key_string(&key)))
     }
 
-    /// Index contacts
-    fn index_contacts(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-        CONTACTS_BY_ID_HASHTABLE.with(|contacts| {
-            for (contact_id, contact) in contacts.borrow().iter() {
-                // Create a searchable string with all contact fields
-                let search_string = format!(
-                    "{}|{}|{}|{}",
-                    contact_id.0,
-                    contact.name,
-                    contact.icp_principal.0.0,
-                    contact.evm_public_address
-                );
-                
-                // Normalize for search
-                let normalized = normalize_path(&search_string);
-                
-                path_to_id.insert(normalized.clone(), (
-                    SearchResultResourceID::Contact(contact_id.clone()),
-                    SearchCategoryEnum::Contacts
-                ));
-                
-                // Insert with a default score of 1
-                entries.insert(normalized, 1u64);
-            }
-        });
+    // Filter attributes are captured with the document, so filtering never reads stable memory.
+    // Only public notes are kept, search results are visible to anyone with view access.
+    fn file_document(file_id: &FileID, file: &FileRecord) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&file.labels),
+            vec![file.notes.clone()],
+            file.external_id.clone().map(|id| id.0),
+            file.created_at,
+            file.last_updated_date_ms,
+        );
+        facets.disk_id = Some(file.disk_id.0.clone());
+        facets.extension = Some(file.extension.clone());
+        facets.file_size = Some(file.file_size);
+        facets.created_by = Some(file.created_by.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::File(file_id.clone()),
+            category: SearchCategoryEnum::Files,
+            fields: vec![
+                (SearchFieldEnum::Name, file.name.clone()),
+                (SearchFieldEnum::Path, file.full_directory_path.0.clone()),
+            ],
+            facets,
+        }
     }
 
-    /// Index disks
-    fn index_disks(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-        DISKS_BY_ID_HASHTABLE.with(|disks| {
-            for (disk_id, disk) in disks.borrow().iter() {
-                // Create a searchable string with disk id, name, and external_id
-                let search_string = format!(
-                    "{}|{}|{}",
-                    disk_id.0,
-                    disk.name,
-                    disk.external_id.clone().unwrap_or(ExternalID("".to_string()))
-                );
-                
-                // Normalize for search
-                let normalized = normalize_path(&search_string);
-                
-                path_to_id.insert(normalized.clone(), (
-                    SearchResultResourceID::Disk(disk_id.clone()),
-                    SearchCategoryEnum::Disks
-                ));
-                
-                // Insert with a default score of 1
-                entries.insert(normalized, 1u64);
-            }
-        });
+    fn folder_document(folder_id: &FolderID, folder: &FolderRecord) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&folder.labels),
+            vec![folder.notes.clone()],
+            folder.external_id.clone().map(|id| id.0),
+            folder.created_at,
+            folder.last_updated_date_ms,
+        );
+        facets.disk_id = Some(folder.disk_id.0.clone());
+        facets.created_by = Some(folder.created_by.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::Folder(folder_id.clone()),
+            category: SearchCategoryEnum::Folders,
+            fields: vec![
+                (SearchFieldEnum::Name, folder.name.clone()),
+                (SearchFieldEnum::Path, folder.full_directory_path.0.clone()),
+            ],
+            facets,
+        }
     }
 
-    /// Index drives
-    fn index_drives(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-        DRIVES_BY_ID_HASHTABLE.with(|drives| {
-            for (drive_id, drive) in drives.borrow().iter() {
-                // Create a searchable string with drive fields
-                let search_string = format!(
-                    "{}|{}|{}|{}",
-                    drive_id.0,
-                    drive.name,
-                    drive.icp_principal.0.0,
-                    drive.host_url.0
-                );
-                
-                // Normalize for search
-                let normalized = normalize_path(&search_string);
-                
-                path_to_id.insert(normalized.clone(), (
-                    SearchResultResourceID::Drive(drive_id.clone()),
-                    SearchCategoryEnum::Drives
-                ));
-                
-                // Insert with a default score of 1
-                entries.insert(normalized, 1u64);
-            }
-        });
+    fn contact_document(contact_id: &UserID, contact: &Contact) -> SearchDocument {
+        SearchDocument {
+            resource_id: SearchResultResourceID::Contact(contact_id.clone()),
+            category: SearchCategoryEnum::Contacts,
+            fields: vec![
+                (SearchFieldEnum::Name, contact.name.clone()),
+                (SearchFieldEnum::Identifier, contact_id.0.clone()),
+                (SearchFieldEnum::Identifier, contact.icp_principal.0.0.clone()),
+                (SearchFieldEnum::Identifier, contact.evm_public_address.clone()),
+            ],
+            facets: SearchFacets::new(
+                label_values(&contact.labels),
+                vec![contact.public_note.clone()],
+                contact.external_id.clone().map(|id| id.0),
+                contact.created_at,
+                contact.last_online_ms,
+            ),
+        }
     }
 
-    /// Index groups
-    fn index_groups(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-        GROUPS_BY_ID_HASHTABLE.with(|groups| {
-            for (group_id, group) in groups.borrow().iter() {
-                // Create a searchable string with group id, name, and drive_id
-                let search_string = format!(
-                    "{}|{}|{}",
-                    group_id.0,
-                    group.name,
-                    group.drive_id.0
-                );
-                
-                // Normalize for search
-                let normalized = normalize_path(&search_string);
-                
-                path_to_id.insert(normalized.clone(), (
-                    SearchResultResourceID::Group(group_id.clone()),
-                    SearchCategoryEnum::Groups
-                ));
-                
-                // Insert with a default score of 1
-                entries.insert(normalized, 1u64);
-            }
-        });
+    fn disk_document(disk_id: &DiskID, disk: &Disk) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&disk.labels),
+            vec![disk.public_note.clone()],
+            disk.external_id.clone().map(|id| id.0),
+            disk.created_at,
+            disk.created_at,
+        );
+        facets.disk_id = Some(disk.id.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::Disk(disk_id.clone()),
+            category: SearchCategoryEnum::Disks,
+            fields: vec![
+                (SearchFieldEnum::Name, disk.name.clone()),
+                (SearchFieldEnum::Identifier, disk_id.0.clone()),
+                (SearchFieldEnum::Identifier, disk.external_id.clone().unwrap_or(ExternalID("".to_string())).0),
+            ],
+            facets,
+        }
     }
 
-    fn index_purchases(entries: &mut BTreeMap<String, u64>, path_to_id: &mut HashMap<String, (SearchResultResourceID, SearchCategoryEnum)>) {
-    
-        PURCHASES_BY_ID_HASHTABLE.with(|purchases| {
-            for (purchase_id, purchase) in purchases.borrow().iter() {
-                // Create a searchable string with purchase id, name, and drive_id
-                let search_string = format!(
-                    "{}|{}|{}",
-                    purchase_id.0,
-                    purchase.title,
-                    purchase.vendor_name
-                );
-                
-                // Normalize for search
-                let normalized = normalize_path(&search_string);
-                
-                path_to_id.insert(normalized.clone(), (
-                    SearchResultResourceID::Purchase(purchase_id.clone()),
-                    SearchCategoryEnum::Purchases
-                ));
-                
-                // Insert with a default score of 1
-                entries.insert(normalized, 1u64);
-            }
-        });
+    fn drive_document(drive_id: &DriveID, drive: &Drive) -> SearchDocument {
+        SearchDocument {
+            resource_id: SearchResultResourceID::Drive(drive_id.clone()),
+            category: SearchCategoryEnum::Drives,
+            fields: vec![
+                (SearchFieldEnum::Name, drive.name.clone()),
+                (SearchFieldEnum::Identifier, drive_id.0.clone()),
+                (SearchFieldEnum::Identifier, drive.icp_principal.0.0.clone()),
+                (SearchFieldEnum::Path, drive.host_url.0.clone()),
+            ],
+            facets: SearchFacets::new(
+                label_values(&drive.labels),
+                vec![drive.public_note.clone()],
+                drive.external_id.clone().map(|id| id.0),
+                drive.created_at,
+                drive.created_at,
+            ),
+        }
+    }
+
+    fn group_document(group_id: &GroupID, group: &Group) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&group.labels),
+            vec![group.public_note.clone()],
+            group.external_id.clone().map(|id| id.0),
+            group.created_at,
+            group.last_modified_at,
+        );
+        facets.created_by = Some(group.owner.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::Group(group_id.clone()),
+            category: SearchCategoryEnum::Groups,
+            fields: vec![
+                (SearchFieldEnum::Name, group.name.clone()),
+                (SearchFieldEnum::Identifier, group_id.0.clone()),
+                (SearchFieldEnum::Identifier, group.drive_id.0.clone()),
+            ],
+            facets,
+        }
+    }
+
+    fn purchase_document(purchase_id: &PurchaseID, purchase: &Purchase) -> SearchDocument {
+        SearchDocument {
+            resource_id: SearchResultResourceID::Purchase(purchase_id.clone()),
+            category: SearchCategoryEnum::Purchases,
+            fields: vec![
+                (SearchFieldEnum::Name, purchase.title.clone()),
+                (SearchFieldEnum::Path, purchase.vendor_name.clone()),
+                (SearchFieldEnum::Identifier, purchase_id.0.clone()),
+            ],
+            facets: SearchFacets::new(
+                purchase.labels.clone(),
+                vec![Some(purchase.vendor_notes.clone())],
+                purchase.external_id.clone(),
+                purchase.created_at,
+                purchase.last_updated_at,
+            ),
+        }
+    }
+
+    fn label_document(label_id: &LabelID, label: &Label) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&label.labels),
+            vec![label.public_note.clone()],
+            label.external_id.clone().map(|id| id.0),
+            label.created_at,
+            label.last_updated_at,
+        );
+        facets.created_by = Some(label.created_by.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::Label(label_id.clone()),
+            category: SearchCategoryEnum::Labels,
+            fields: vec![
+                (SearchFieldEnum::Name, label.value.0.clone()),
+                (SearchFieldEnum::Identifier, label_id.0.clone()),
+            ],
+            facets,
+        }
     }
 
-    /// Search the index with fuzzy matching and return results sorted by relevance
-    /// Now supports filtering by categories
-    pub fn raw_query(query: &str, _max_edit_distance: u32, categories: Option<Vec<SearchCategoryEnum>>) -> Vec<SearchResult> {
+    // The receiver url is left out, it often embeds tokens and search results reach non owners
+    fn webhook_document(webhook_id: &WebhookID, webhook: &Webhook) -> SearchDocument {
+        SearchDocument {
+            resource_id: SearchResultResourceID::Webhook(webhook_id.clone()),
+            category: SearchCategoryEnum::Webhooks,
+            fields: vec![
+                (SearchFieldEnum::Name, webhook.name.clone()),
+                (SearchFieldEnum::Identifier, webhook_id.0.clone()),
+                (SearchFieldEnum::Identifier, webhook.event.to_string()),
+            ],
+            facets: SearchFacets::new(
+                label_values(&webhook.labels),
+                vec![webhook.note.clone()],
+                webhook.external_id.clone().map(|id| id.0),
+                webhook.created_at,
+                webhook.created_at,
+            ),
+        }
+    }
+
+    fn directory_permission_document(permission_id: &DirectoryPermissionID, permission: &DirectoryPermission) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&permission.labels),
+            vec![Some(permission.note.clone())],
+            permission.external_id.clone().map(|id| id.0),
+            permission.created_at,
+            permission.last_modified_at,
+        );
+        facets.created_by = Some(permission.granted_by.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::DirectoryPermission(permission_id.clone()),
+            category: SearchCategoryEnum::Permissions,
+            fields: vec![
+                (SearchFieldEnum::Path, permission.resource_path.0.clone()),
+                (SearchFieldEnum::Identifier, permission_id.0.clone()),
+                (SearchFieldEnum::Identifier, permission.resource_id.to_string()),
+                (SearchFieldEnum::Identifier, permission.granted_to.to_string()),
+            ],
+            facets,
+        }
+    }
+
+    fn system_permission_document(permission_id: &SystemPermissionID, permission: &SystemPermission) -> SearchDocument {
+        let mut facets = SearchFacets::new(
+            label_values(&permission.labels),
+            vec![Some(permission.note.clone())],
+            permission.external_id.clone().map(|id| id.0),
+            permission.created_at,
+            permission.last_modified_at,
+        );
+        facets.created_by = Some(permission.granted_by.0.clone());
+        SearchDocument {
+            resource_id: SearchResultResourceID::SystemPermission(permission_id.clone()),
+            category: SearchCategoryEnum::Permissions,
+            fields: vec![
+                (SearchFieldEnum::Identifier, permission_id.0.clone()),
+                (SearchFieldEnum::Identifier, permission.resource_id.to_string()),
+                (SearchFieldEnum::Identifier, permission.granted_to.to_string()),
+            ],
+            facets,
+        }
+    }
+
+    // How well a single query term matched a single indexed token
+    struct TermMatch {
+        token_slot: u64,
+        quality: u64,
+    }
+
+    /// Search the index with typo tolerant matching and return results sorted by relevance.
+    /// Every query word has to match a token of the document, either as a prefix or within
+    /// `max_edit_distance` edits (shorter words tolerate fewer edits).
+    /// Results can be narrowed by categories and structured filters, with filters set the
+    /// query may be empty to list every matching resource.
+    pub fn raw_query(query: &str, max_edit_distance: u32, categories: Option<Vec<SearchCategoryEnum>>, filters: Option<&SearchFilters>) -> Vec<SearchResult> {
         // Early return if index isn't built yet
         let index_option = FST_INDEX.with(|cell| cell.borrow().clone());
         let index = match index_option {
@@ -309,54 +686,210 @@ pub mod state {
             None => return Vec::new(),
         };
         
-        // Normalize the query
-        let normalized_query = normalize_for_query(query);
-        
-        // Use subsequence matching (a simpler form of fuzzy matching)
-        let subseq = Subsequence::new(&normalized_query);
-        
-        // Search the FST
-        let mut stream = index.search(subseq).into_stream();
+        // Tokenize the query the same way names and paths were indexed
+        let terms = tokenize(query);
+        let filters = filters.filter(|filters| !filters.is_empty());
+        if terms.is_empty() && filters.is_none() {
+            return Vec::new();
+        }
+        let max_edit_distance = max_edit_distance.min(MAX_SEARCH_EDIT_DISTANCE);
+
+        let scored_documents: Vec<(SearchDocument, u64)> = if terms.is_empty() {
+            // Filters only, every resource is a candidate
+            current_documents().into_iter().map(|document| (document, 0)).collect()
+        } else {
+            score_documents(&index, &terms, max_edit_distance)
+        };
+
+        let now_ms = ic_cdk::api::time() / 1_000_000;
         let mut matches = Vec::new();
-        
-        while let Some((path_bytes, score)) = stream.next() {
-            if let Ok(path) = String::from_utf8(path_bytes.to_vec()) {
-                let resource_info = PATH_TO_ID_MAP.with(|cell| {
-                    cell.borrow().get(&path).cloned()
-                });
-                
-                if let Some((resource_id, category)) = resource_info {
-                    // Filter by category if specified
-                    if let Some(ref filter_categories) = categories {
-                        if !filter_categories.contains(&SearchCategoryEnum::All) && 
-                           !filter_categories.contains(&category) {
-                            continue;
-                        }
-                    }
-                    
-                    // Generate title and preview based on resource type
-                    let (title, preview, created_at, updated_at, metadata) = generate_title_and_preview(&resource_id);
-                    
-                    matches.push(SearchResult {
-                        title,
-                        preview,
-                        score,
-                        resource_id: resource_id.to_string(),
-                        category,
-                        created_at,
-                        updated_at,
-                        metadata,
-                    });
+        for (document, base_score) in scored_documents {
+            // Filter by category if specified
+            if let Some(ref filter_categories) = categories {
+                if !filter_categories.contains(&SearchCategoryEnum::All) && 
+                   !filter_categories.contains(&document.category) {
+                    continue;
                 }
             }
+
+            if let Some(filters) = filters {
+                if !document.facets.matches(filters) {
+                    continue;
+                }
+            }
+            
+            // Generate title and preview based on resource type
+            let (title, preview, created_at, updated_at, metadata) = generate_title_and_preview(&document.resource_id);
+            
+            matches.push(SearchResult {
+                title,
+                preview,
+                score: apply_recency_boost(base_score, updated_at, now_ms),
+                resource_id: document.resource_id.to_string(),
+                category: document.category,
+                created_at,
+                updated_at,
+                metadata,
+            });
         }
         
-        // Sort by score (higher score = better match)
-        matches.sort_by(|a, b| b.score.cmp(&a.score));
+        // Sort by score (higher score = better match), ties go to the shorter title
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.title.len().cmp(&b.title.len())));
         
         matches
     }
 
+    // Scores the indexed documents and the delta, a document has to match every term
+    fn score_documents(index: &Map<Vec<u8>>, terms: &[String], max_edit_distance: u32) -> Vec<(SearchDocument, u64)> {
+        // Best score per document for each term, a document has to match every term
+        let mut doc_scores: HashMap<u32, u64> = HashMap::new();
+        for (term_index, term) in terms.iter().enumerate() {
+            let mut term_scores: HashMap<u32, u64> = HashMap::new();
+            for term_match in match_term(index, term, max_edit_distance) {
+                TOKEN_POSTINGS.with(|cell| {
+                    if let Some(postings) = cell.borrow().get(term_match.token_slot as usize) {
+                        for posting in postings {
+                            let score = term_match.quality * posting.field.weight();
+                            let best = term_scores.entry(posting.doc).or_insert(0);
+                            *best = (*best).max(score);
+                        }
+                    }
+                });
+            }
+
+            if term_index == 0 {
+                doc_scores = term_scores;
+            } else {
+                doc_scores = doc_scores.into_iter()
+                    .filter_map(|(doc, score)| term_scores.get(&doc).map(|term_score| (doc, score + term_score)))
+                    .collect();
+            }
+            if doc_scores.is_empty() {
+                break;
+            }
+        }
+
+        // Indexed copies that changed since the last merge are replaced by their delta entry
+        SEARCH_DELTA.with(|delta_cell| {
+            let delta = delta_cell.borrow();
+            let mut scored: Vec<(SearchDocument, u64)> = SEARCH_DOCUMENTS.with(|cell| {
+                let documents = cell.borrow();
+                doc_scores.into_iter()
+                    .filter_map(|(doc, score)| documents.get(doc as usize).map(|document| (document, score)))
+                    .filter(|(document, _)| !delta.contains_key(&document.resource_id.to_string()))
+                    .map(|(document, score)| (document.clone(), score))
+                    .collect()
+            });
+            for document in delta.values().flatten() {
+                if let Some(score) = score_delta_document(document, terms, max_edit_distance) {
+                    scored.push((document.clone(), score));
+                }
+            }
+            scored
+        })
+    }
+
+    // Delta documents are not in the FST, so their tokens are compared one by one with the same
+    // qualities as `match_term`. Returns None unless every term matches.
+    fn score_delta_document(document: &SearchDocument, terms: &[String], max_edit_distance: u32) -> Option<u64> {
+        let mut total = 0;
+        for term in terms {
+            let distance = allowed_edit_distance(term, max_edit_distance);
+            let mut best = 0;
+            for (field, text) in &document.fields {
+                for token in tokenize(text) {
+                    let quality = if token == *term {
+                        100
+                    } else if token.starts_with(term.as_str()) {
+                        70
+                    } else {
+                        match edit_distance(term, &token) as u32 {
+                            edits if edits > distance => 0,
+                            1 => 40,
+                            _ => 20,
+                        }
+                    };
+                    best = best.max(quality * field.weight());
+                }
+            }
+            if best == 0 {
+                return None;
+            }
+            total += best;
+        }
+        Some(total)
+    }
+
+    // Exact 100, prefix 70, then 40 for one edit and 20 for two
+    fn match_term(index: &Map<Vec<u8>>, term: &str, max_edit_distance: u32) -> Vec<TermMatch> {
+        let mut found: HashMap<u64, u64> = HashMap::new();
+
+        // Prefix matches, so results show up while the user is still typing
+        let prefix = Str::new(term).starts_with();
+        let mut stream = index.search(prefix).into_stream();
+        while let Some((token_bytes, slot)) = stream.next() {
+            let quality = if token_bytes == term.as_bytes() { 100 } else { 70 };
+            found.insert(slot, quality);
+        }
+
+        let distance = allowed_edit_distance(term, max_edit_distance);
+        if distance > 0 {
+            // Building the automaton fails for pathological terms, prefix matches still stand
+            if let Ok(automaton) = Levenshtein::new(term, distance) {
+                let mut stream = index.search(automaton).into_stream();
+                while let Some((token_bytes, slot)) = stream.next() {
+                    let token = String::from_utf8_lossy(token_bytes);
+                    let quality = match edit_distance(term, &token) {
+                        0 => 100,
+                        1 => 40,
+                        _ => 20,
+                    };
+                    let best = found.entry(slot).or_insert(0);
+                    *best = (*best).max(quality);
+                }
+            }
+        }
+
+        found.into_iter().map(|(token_slot, quality)| TermMatch { token_slot, quality }).collect()
+    }
+
+    // Short words would match almost anything with two typos
+    fn allowed_edit_distance(term: &str, max_edit_distance: u32) -> u32 {
+        let length = term.chars().count();
+        let by_length = match length {
+            0..=2 => 0,
+            3..=5 => 1,
+            _ => 2,
+        };
+        by_length.min(max_edit_distance)
+    }
+
+    // Up to +30% for something touched today, fading to a few percent after a year
+    fn apply_recency_boost(score: u64, updated_at_ms: u64, now_ms: u64) -> u64 {
+        let age_days = now_ms.saturating_sub(updated_at_ms) / DAY_MS;
+        let boost_percent = 30 * 30 / (30 + age_days);
+        score * (100 + boost_percent) / 100
+    }
+
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        let mut previous: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.iter().enumerate() {
+            let mut current = vec![i + 1; b.len() + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            previous = current;
+        }
+        previous[b.len()]
+    }
+
+    fn label_values(labels: &[LabelStringValue]) -> Vec<String> {
+        labels.iter().map(|label| label.0.clone()).collect()
+    }
+
     pub async fn filter_search_results_by_permission(
         results: &[SearchResult], 
         grantee_id: &PermissionGranteeID, 
@@ -503,6 +1036,21 @@ pub mod state {
                         false // This should not happen based on category
                     }
                 },
+                SearchCategoryEnum::Labels => can_view_system_record(
+                    SystemRecordIDEnum::Label(result.resource_id.clone()),
+                    SystemTableEnum::Labels,
+                    grantee_id,
+                ),
+                SearchCategoryEnum::Webhooks => can_view_system_record(
+                    SystemRecordIDEnum::Webhook(result.resource_id.clone()),
+                    SystemTableEnum::Webhooks,
+                    grantee_id,
+                ),
+                SearchCategoryEnum::Permissions => can_view_system_record(
+                    SystemRecordIDEnum::Permission(result.resource_id.clone()),
+                    SystemTableEnum::Permissions,
+                    grantee_id,
+                ),
                 // Handle the All category by checking the specific resource type
                 SearchCategoryEnum::All => {
                     // We need to determine the type of resource from the category or some other way
@@ -596,6 +1144,13 @@ pub mod state {
                         } else {
                             true
                         }
+                    } else if result.resource_id.starts_with(IDPrefix::LabelID.as_str()) {
+                        can_view_system_record(SystemRecordIDEnum::Label(result.resource_id.clone()), SystemTableEnum::Labels, grantee_id)
+                    } else if result.resource_id.starts_with(IDPrefix::Webhook.as_str()) {
+                        can_view_system_record(SystemRecordIDEnum::Webhook(result.resource_id.clone()), SystemTableEnum::Webhooks, grantee_id)
+                    } else if result.resource_id.starts_with(IDPrefix::DirectoryPermission.as_str())
+                        || result.resource_id.starts_with(IDPrefix::SystemPermission.as_str()) {
+                        can_view_system_record(SystemRecordIDEnum::Permission(result.resource_id.clone()), SystemTableEnum::Permissions, grantee_id)
                     } else {
                         // Unknown resource type
                         false
@@ -611,6 +1166,14 @@ pub mod state {
         filtered_results
     }
     
+    // View on the record itself, or table-wide View when there is no record permission
+    fn can_view_system_record(record: SystemRecordIDEnum, table: SystemTableEnum, grantee_id: &PermissionGranteeID) -> bool {
+        check_system_permissions(SystemResourceID::Record(record), grantee_id.clone())
+            .contains(&SystemPermissionType::View)
+            || check_system_permissions(SystemResourceID::Table(table), grantee_id.clone())
+                .contains(&SystemPermissionType::View)
+    }
+
     /// Helper function to generate title and preview for each resource type
     fn generate_title_and_preview(resource_id: &SearchResultResourceID) -> (String, String, u64, u64, Option<String>) {
         match resource_id {
@@ -760,6 +1323,38 @@ pub mod state {
                 
                 (title, preview, created_at, updated_at, None)
             },
+            SearchResultResourceID::Label(label_id) => {
+                LABELS_BY_ID_HASHTABLE.with(|labels| labels.borrow().get(label_id))
+                    .map(|label| (label.value.0.clone(), label.color.0.clone(), label.created_at, label.last_updated_at, None))
+                    .unwrap_or_default()
+            },
+            SearchResultResourceID::Webhook(webhook_id) => {
+                WEBHOOKS_BY_ID_HASHTABLE.with(|webhooks| webhooks.borrow().get(webhook_id))
+                    .map(|webhook| (webhook.name.clone(), webhook.event.to_string(), webhook.created_at, webhook.created_at, None))
+                    .unwrap_or_default()
+            },
+            SearchResultResourceID::DirectoryPermission(permission_id) => {
+                DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| permissions.borrow().get(permission_id))
+                    .map(|permission| (
+                        permission.resource_path.0.clone(),
+                        permission.granted_to.to_string(),
+                        permission.created_at,
+                        permission.last_modified_at,
+                        Some(permission.resource_id.to_string()),
+                    ))
+                    .unwrap_or_default()
+            },
+            SearchResultResourceID::SystemPermission(permission_id) => {
+                SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| permissions.borrow().get(permission_id))
+                    .map(|permission| (
+                        permission.resource_id.to_string(),
+                        permission.granted_to.to_string(),
+                        permission.created_at,
+                        permission.last_modified_at,
+                        Some(permission.resource_id.to_string()),
+                    ))
+                    .unwrap_or_default()
+            },
         }
     }
 
@@ -768,18 +1363,19 @@ pub mod state {
         LAST_INDEX_UPDATE_MS.with(|cell| *cell.borrow())
     }
 
-    /// Helper function to normalize a path for indexing
-    fn normalize_path(path: &str) -> String {
-        path.to_lowercase()
-            .trim()
-            .replace("//", "/")
-    }
-
-    /// Helper function to normalize a query for searching
-    /// This should match the same normalization approach used for indexed paths
-    fn normalize_for_query(query: &str) -> String {
-        query.to_lowercase()
-            .trim()
-            .replace(" ", "") // Remove spaces for more flexible matching
+    /// Splits names, paths and queries into lowercase words, e.g.
+    /// "Projects/Q3 Report_final.pdf" -> ["projects", "q3", "report", "final", "pdf"]
+    fn tokenize(text: &str) -> Vec<String> {
+        let mut tokens: Vec<String> = Vec::new();
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+            let token: String = word.to_lowercase().chars().take(MAX_TOKEN_LENGTH).collect();
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+        tokens
     }
 }
\ No newline at end of file
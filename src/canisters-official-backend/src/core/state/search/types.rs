@@ -2,8 +2,9 @@
 
 use core::fmt;
 
+use candid::CandidType;
 use serde::{Deserialize, Serialize};
-use crate::core::{state::{directory::types::{FileID, FolderID}, disks::types::DiskID, drives::types::DriveID, groups::types::GroupID, purchases::types::PurchaseID}, types::UserID};
+use crate::core::{state::{directory::types::{FileID, FolderID}, disks::types::DiskID, drives::types::DriveID, groups::types::GroupID, labels::types::LabelID, permissions::types::{DirectoryPermissionID, SystemPermissionID}, purchases::types::PurchaseID, webhooks::types::WebhookID}, types::UserID};
 
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -17,6 +18,9 @@ pub enum SearchCategoryEnum {
     Drives,
     Groups,
     Purchases,
+    Labels,
+    Webhooks,
+    Permissions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +32,10 @@ pub enum SearchResultResourceID {
     Drive(DriveID),
     Group(GroupID),
     Purchase(PurchaseID),
+    Label(LabelID),
+    Webhook(WebhookID),
+    DirectoryPermission(DirectoryPermissionID),
+    SystemPermission(SystemPermissionID),
 }
 // implement display
 impl fmt::Display for SearchResultResourceID {
@@ -40,6 +48,10 @@ impl fmt::Display for SearchResultResourceID {
             SearchResultResourceID::Drive(id) => write!(f, "{}", id),
             SearchResultResourceID::Group(id) => write!(f, "{}", id),
             SearchResultResourceID::Purchase(id) => write!(f, "{}", id),
+            SearchResultResourceID::Label(id) => write!(f, "{}", id),
+            SearchResultResourceID::Webhook(id) => write!(f, "{}", id),
+            SearchResultResourceID::DirectoryPermission(id) => write!(f, "{}", id),
+            SearchResultResourceID::SystemPermission(id) => write!(f, "{}", id),
         }
     }
 }
@@ -57,3 +69,118 @@ pub struct SearchResult {
     pub metadata: Option<String>,
 }
 
+
+// Structured filters applied on top of the text query, every filter that is set has to match.
+// Resources without the filtered attribute (e.g. file size on a contact) are left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, CandidType)]
+pub struct SearchFilters {
+    #[serde(default)]
+    pub labels: Vec<String>, // resource must carry all of these label values
+    pub notes: Option<String>, // case insensitive substring of the public notes
+    pub external_id: Option<String>,
+    pub created_after: Option<u64>, // unix ms, inclusive
+    pub created_before: Option<u64>,
+    pub updated_after: Option<u64>,
+    pub updated_before: Option<u64>,
+    pub disk_id: Option<String>,
+    pub extension: Option<String>, // without the leading dot
+    pub min_file_size: Option<u64>, // bytes
+    pub max_file_size: Option<u64>,
+    pub created_by: Option<String>, // UserID
+}
+
+impl SearchFilters {
+    pub fn is_empty(&self) -> bool {
+        *self == SearchFilters::default()
+    }
+}
+
+// Which part of a resource a search token came from, matches on names rank highest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SearchFieldEnum {
+    Name,
+    Path,
+    Identifier,
+}
+
+impl SearchFieldEnum {
+    pub fn weight(&self) -> u64 {
+        match self {
+            SearchFieldEnum::Name => 3,
+            SearchFieldEnum::Path => 2,
+            SearchFieldEnum::Identifier => 1,
+        }
+    }
+}
+
+// A searchable resource with the text of each field that gets tokenized
+#[derive(Debug, Clone)]
+pub struct SearchDocument {
+    pub resource_id: SearchResultResourceID,
+    pub category: SearchCategoryEnum,
+    pub fields: Vec<(SearchFieldEnum, String)>,
+    pub facets: SearchFacets,
+}
+
+// The attributes structured filters look at, None where a resource has no such attribute
+#[derive(Debug, Clone, Default)]
+pub struct SearchFacets {
+    pub labels: Vec<String>,
+    pub notes: Vec<String>,
+    pub external_id: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub disk_id: Option<String>,
+    pub extension: Option<String>,
+    pub file_size: Option<u64>,
+    pub created_by: Option<String>,
+}
+
+impl SearchFacets {
+    pub fn new(labels: Vec<String>, notes: Vec<Option<String>>, external_id: Option<String>, created_at: u64, updated_at: u64) -> Self {
+        SearchFacets {
+            labels,
+            notes: notes.into_iter().flatten().collect(),
+            external_id,
+            created_at,
+            updated_at,
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, filters: &SearchFilters) -> bool {
+        let labels_match = filters.labels.iter()
+            .all(|wanted| self.labels.iter().any(|label| label.eq_ignore_ascii_case(wanted)));
+        let notes_match = filters.notes.as_ref().map_or(true, |wanted| {
+            let wanted = wanted.to_lowercase();
+            self.notes.iter().any(|note| note.to_lowercase().contains(&wanted))
+        });
+        let external_id_match = filters.external_id.as_ref()
+            .map_or(true, |wanted| self.external_id.as_ref() == Some(wanted));
+        let created_match = filters.created_after.map_or(true, |after| self.created_at >= after)
+            && filters.created_before.map_or(true, |before| self.created_at <= before);
+        let updated_match = filters.updated_after.map_or(true, |after| self.updated_at >= after)
+            && filters.updated_before.map_or(true, |before| self.updated_at <= before);
+        let disk_match = filters.disk_id.as_ref()
+            .map_or(true, |wanted| self.disk_id.as_ref() == Some(wanted));
+        let extension_match = filters.extension.as_ref().map_or(true, |wanted| {
+            let wanted = wanted.trim_start_matches('.');
+            self.extension.as_ref().map_or(false, |extension| extension.eq_ignore_ascii_case(wanted))
+        });
+        let size_match = filters.min_file_size.map_or(true, |min| self.file_size.map_or(false, |size| size >= min))
+            && filters.max_file_size.map_or(true, |max| self.file_size.map_or(false, |size| size <= max));
+        let creator_match = filters.created_by.as_ref()
+            .map_or(true, |wanted| self.created_by.as_ref() == Some(wanted));
+
+        labels_match && notes_match && external_id_match && created_match && updated_match
+            && disk_match && extension_match && size_match && creator_match
+    }
+}
+
+// One occurrence of a token, `doc` indexes into the search documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenPosting {
+    pub doc: u32,
+    pub field: SearchFieldEnum,
+}
@@ -3,9 +3,9 @@ pub mod state {
     use std::cell::RefCell;
     use std::collections::HashMap;
 
-    use ic_stable_structures::{memory_manager::MemoryId, StableBTreeMap, StableVec, DefaultMemoryImpl};
+    use ic_stable_structures::{memory_manager::MemoryId, StableBTreeMap, StableCell, StableVec, DefaultMemoryImpl};
 
-    use crate::{core::state::webhooks::types::{Webhook, WebhookAltIndexID, WebhookID, WebhookIDList}, MEMORY_MANAGER};
+    use crate::{core::state::webhooks::types::{Webhook, WebhookAltIndexID, WebhookDelivery, WebhookDeliveryID, WebhookID, WebhookIDList}, MEMORY_MANAGER};
 
     type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
     
@@ -13,6 +13,10 @@ pub mod state {
     pub const WEBHOOKS_BY_ALT_INDEX_MEMORY_ID: MemoryId = MemoryId::new(37);
     pub const WEBHOOKS_BY_ID_MEMORY_ID: MemoryId = MemoryId::new(38);
     pub const WEBHOOKS_BY_TIME_MEMORY_ID: MemoryId = MemoryId::new(39);
+    pub const WEBHOOK_DELIVERIES_BY_ID_MEMORY_ID: MemoryId = MemoryId::new(56);
+    pub const WEBHOOK_DELIVERY_QUEUE_MEMORY_ID: MemoryId = MemoryId::new(57);
+    pub const WEBHOOK_DEAD_LETTERS_MEMORY_ID: MemoryId = MemoryId::new(58);
+    pub const WEBHOOK_DELIVERY_SEQUENCE_MEMORY_ID: MemoryId = MemoryId::new(72);
     
     thread_local! {
         // Convert HashMap<WebhookAltIndexID, Vec<WebhookID>> to StableBTreeMap<WebhookAltIndexID, WebhookIDList>
@@ -35,6 +39,35 @@ pub mod state {
                 MEMORY_MANAGER.with(|m| m.borrow().get(WEBHOOKS_BY_TIME_MEMORY_ID))
             ).expect("Failed to initialize WEBHOOKS_BY_TIME_LIST")
         );
+
+        // Pending deliveries (including their attempt history), removed once delivered
+        pub(crate) static WEBHOOK_DELIVERIES_BY_ID_HASHTABLE: RefCell<StableBTreeMap<WebhookDeliveryID, WebhookDelivery, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(WEBHOOK_DELIVERIES_BY_ID_MEMORY_ID))
+            )
+        );
+
+        // Delivery queue keyed by (webhook, sequence), so each receiver's events sort in the order they were fired
+        pub(crate) static WEBHOOK_DELIVERY_QUEUE: RefCell<StableBTreeMap<(WebhookID, u64), WebhookDeliveryID, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(WEBHOOK_DELIVERY_QUEUE_MEMORY_ID))
+            )
+        );
+
+        // Next queue sequence number, shared by all receivers
+        pub(crate) static WEBHOOK_DELIVERY_SEQUENCE: RefCell<StableCell<u64, Memory>> = RefCell::new(
+            StableCell::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(WEBHOOK_DELIVERY_SEQUENCE_MEMORY_ID)),
+                0
+            ).expect("Failed to initialize WEBHOOK_DELIVERY_SEQUENCE")
+        );
+
+        // Deliveries that exhausted their retries
+        pub(crate) static WEBHOOK_DEAD_LETTERS_HASHTABLE: RefCell<StableBTreeMap<WebhookDeliveryID, WebhookDelivery, Memory>> = RefCell::new(
+            StableBTreeMap::init(
+                MEMORY_MANAGER.with(|m| m.borrow().get(WEBHOOK_DEAD_LETTERS_MEMORY_ID))
+            )
+        );
     }
 
 
@@ -43,6 +76,10 @@ pub mod state {
         WEBHOOKS_BY_ALT_INDEX_HASHTABLE.with(|_| {});
         WEBHOOKS_BY_ID_HASHTABLE.with(|_| {});
         WEBHOOKS_BY_TIME_LIST.with(|_| {});
+        WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|_| {});
+        WEBHOOK_DELIVERY_QUEUE.with(|_| {});
+        WEBHOOK_DELIVERY_SEQUENCE.with(|_| {});
+        WEBHOOK_DEAD_LETTERS_HASHTABLE.with(|_| {});
     }
 
 }
@@ -16,6 +16,9 @@ pub struct Webhook {
     pub alt_index: WebhookAltIndexID,
     pub event: WebhookEventLabel,
     pub signature: String,
+    // Kept after a rotation so receivers can verify with either secret until it expires
+    pub previous_signature: Option<String>,
+    pub previous_signature_expires_at: Option<u64>,
     pub note: Option<String>,
     pub active: bool,
     pub filters: String,
@@ -129,6 +132,7 @@ impl WebhookAltIndexID {
     pub const STATE_DIFFS: &'static str = "STATE_DIFFS"; 
     pub const SUPERSWAP_USER: &'static str = "SUPERSWAP_USER";
     pub const INBOX_NEW_MAIL: &'static str = "INBOX_NEW_MAIL";
+    pub const LOW_CYCLES: &'static str = "LOW_CYCLES";
 
     // Helper method to create new instances
     pub fn new(id: String) -> Self {
@@ -159,6 +163,10 @@ impl WebhookAltIndexID {
     pub fn inbox_new_notif_slug() -> Self {
         WebhookAltIndexID(Self::INBOX_NEW_MAIL.to_string())
     }
+
+    pub fn low_cycles_slug() -> Self {
+        WebhookAltIndexID(Self::LOW_CYCLES.to_string())
+    }
 }
 
 
@@ -213,6 +221,8 @@ pub enum WebhookEventLabel {
     DriveRestoreTrash,
     #[serde(rename = "drive.state_diffs")]
     DriveStateDiffs,
+    #[serde(rename = "drive.low_cycles")]
+    DriveLowCycles,
     #[serde(rename = "label.added")]
     LabelAdded,
     #[serde(rename = "label.removed")]
@@ -254,6 +264,7 @@ impl std::str::FromStr for WebhookEventLabel {
             "label.removed" => Ok(Self::LabelRemoved),
             "drive.restore_trash" => Ok(Self::DriveRestoreTrash),
             "drive.state_diffs" => Ok(Self::DriveStateDiffs),
+            "drive.low_cycles" => Ok(Self::DriveLowCycles),
             "org.superswap_user" => Ok(Self::OrganizationSuperswapUser),
             "org.inbox.new_mail" => Ok(Self::OrganizationInboxNewNotif),
             _ => Err(format!("Invalid webhook event: {}", s)),
@@ -295,6 +306,7 @@ impl ToString for WebhookEventLabel {
             // drive
             Self::DriveRestoreTrash => "drive.restore_trash",
             Self::DriveStateDiffs => "drive.state_diffs",
+            Self::DriveLowCycles => "drive.low_cycles",
             // labels
             Self::LabelAdded => "label.added",
             Self::LabelRemoved => "label.removed",
@@ -387,3 +399,77 @@ impl Default for WebhookIDList {
         Self::new()
     }
 }
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, CandidType, Ord, PartialOrd)]
+pub struct WebhookDeliveryID(pub String);
+impl fmt::Display for WebhookDeliveryID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Storable for WebhookDeliveryID {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256, // Adjust based on your needs
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WebhookDeliveryStatus {
+    Pending,     // Queued and not attempted yet, removed once the receiver answers 2xx
+    InFlight,    // An attempt is awaiting the receiver, leased until next_attempt_at
+    Failed,      // The last attempt failed, retried by the timer at next_attempt_at
+    DeadLetter,  // Exhausted all retries, kept for inspection & manual retry
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct WebhookDeliveryAttempt {
+    pub attempted_at: u64,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct WebhookDelivery {
+    pub id: WebhookDeliveryID,
+    pub webhook_id: WebhookID,
+    pub url: String,
+    pub event: String,
+    pub body: String, // serialized WebhookEventPayload, replayed verbatim on every attempt
+    pub status: WebhookDeliveryStatus,
+    pub attempts: Vec<WebhookDeliveryAttempt>,
+    pub retries_remaining: u32,
+    pub next_attempt_at: u64,
+    pub queue_sequence: u64, // position in the receiver's queue, see WEBHOOK_DELIVERY_QUEUE
+    pub created_at: u64,
+    pub last_updated_at: u64,
+}
+
+impl Storable for WebhookDelivery {
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 2 * 1024 * 1024, // state diff payloads can get large
+        is_fixed_size: false,
+    };
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let bytes = candid::encode_one(self).unwrap();
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+}
@@ -149,6 +149,9 @@ pub enum IDPrefix {
     RedeemCode,
     InboxNotifID,
     Purchase,
+    WebhookDelivery,
+    AuditEntry,
+    Snapshot,
 }
 
 impl IDPrefix {
@@ -175,6 +178,9 @@ impl IDPrefix {
             IDPrefix::RedeemCode => "RedeemTokenID_",
             IDPrefix::InboxNotifID => "InboxNotifID_",
             IDPrefix::Purchase => "PurchaseID_",
+            IDPrefix::WebhookDelivery => "WebhookDeliveryID_",
+            IDPrefix::AuditEntry => "AuditEntryID_",
+            IDPrefix::Snapshot => "SnapshotID_",
         }
     }
 }
@@ -1,6 +1,7 @@
 // src/lib.rs
 use ic_cdk::*;
 use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
+use ic_cdk::api::management_canister::http_request::{HttpResponse as CanisterHttpResponse, TransformArgs};
 use core::{api::uuid::format_user_id, state::{api_keys::state::state::init_default_admin_apikey, contacts::state::state::init_default_owner_contact, disks::state::state::init_default_disks, drives::state::state::init_self_drive, groups::state::state::init_default_group}, types::UserID};
 use std::{cell::RefCell, collections::HashMap};
 use serde::{Deserialize, Serialize};
@@ -9,6 +10,7 @@ mod logger;
 mod rest;
 mod core;
 use rest::{router, types::validate_icp_principal};
+use core::api::streaming::streaming::{clear_streaming_strategy, into_streaming_response, streaming_callback, StreamingCallbackHttpResponse, StreamingCallbackToken, StreamingHttpResponse};
 use candid::{CandidType, Decode, Encode};
 
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
@@ -30,6 +32,12 @@ pub struct InitArgs {
     pub spawn_redeem_code: Option<String>,
 }
 
+// Sent by the factory when it upgrades a drive it spawned, reported by /organization/about
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct UpgradeArgs {
+    pub version: String,
+}
+
 // Track if we've already initialized to prevent double initialization
 thread_local! {
 
@@ -69,6 +77,14 @@ fn init() {
 
     debug_log!("Initializing routes...");
     router::init_routes();
+
+    // Timers do not survive upgrades, so they are started here and in post_upgrade
+    crate::core::api::webhooks::deliveries::start_webhook_delivery_timer();
+    crate::core::api::trash::trash::start_trash_sweeper_timer();
+    crate::core::api::uploads::uploads::start_upload_cleanup_timer();
+    crate::core::state::search::state::state::start_search_index_timers();
+    crate::core::state::idempotency::state::state::start_idempotency_prune_timer();
+    crate::core::api::cycles::cycles::start_cycles_monitor_timer();
     
     debug_log!("INIT FUNCTION COMPLETED");
 }
@@ -113,6 +129,11 @@ fn initialize_canister(args: Option<InitArgs>) {
                 crate::core::state::raw_storage::state::initialize();
                 crate::core::state::webhooks::state::state::initialize();
                 crate::core::state::purchases::state::state::initialize();
+                crate::core::state::inbox::state::state::initialize();
+                crate::core::state::rate_limits::state::state::initialize();
+                crate::core::state::audit::state::state::initialize();
+                crate::core::state::idempotency::state::state::initialize();
+                crate::core::state::cycles::state::state::initialize();
                 
                 // Initialize the drive with all parameters
                 init_self_drive(
@@ -168,6 +189,17 @@ fn post_upgrade() {
     
     debug_log!("Initializing routes...");
     router::init_routes();
+
+    crate::core::api::webhooks::deliveries::start_webhook_delivery_timer();
+    crate::core::api::trash::trash::start_trash_sweeper_timer();
+    crate::core::api::uploads::uploads::start_upload_cleanup_timer();
+    crate::core::state::search::state::state::start_search_index_timers();
+    crate::core::state::idempotency::state::state::start_idempotency_prune_timer();
+    crate::core::api::cycles::cycles::start_cycles_monitor_timer();
+
+    crate::core::api::webhooks::filters::migrate_legacy_webhook_filters();
+    crate::core::state::directory::state::state::rebuild_directory_sweep_indexes();
+    crate::core::api::permissions::directory::migrate_directory_password_hashes();
     
     // Then check if we need to set up state
     let already_initialized = INITIALIZED_FLAG.with(|flag_cell| {
@@ -176,6 +208,17 @@ fn post_upgrade() {
     
     if already_initialized {
         debug_log!("Canister already initialized, skipping full initialization");
+
+        // Upgrades without arguments (eg. dfx deploy) keep the previous version label
+        let upgrade_args = ic_cdk::api::call::arg_data::<(Option<UpgradeArgs>,)>(ic_cdk::api::call::ArgDecoderConfig::default()).0;
+        if let Some(upgrade_args) = upgrade_args {
+            debug_log!("Upgraded to version {}", upgrade_args.version);
+            crate::core::state::drives::state::state::VERSION.with(|version| {
+                version.borrow_mut()
+                    .set(upgrade_args.version)
+                    .expect("Failed to set VERSION in stable memory");
+            });
+        }
     } else {
          // Either use arguments from upgrade call or fallback to defaults
          let args = ic_cdk::api::call::arg_data::<(Option<InitArgs>,)>(ic_cdk::api::call::ArgDecoderConfig::default()).0;
@@ -193,6 +236,22 @@ fn http_request(req: HttpRequest) -> HttpResponse<'static> {
 }
 
 #[update]
-async fn http_request_update(req: HttpRequest<'_>) -> HttpResponse<'static> {
-    router::handle_request(req).await
+async fn http_request_update(req: HttpRequest<'_>) -> StreamingHttpResponse {
+    clear_streaming_strategy();
+    into_streaming_response(router::handle_request(req).await)
+}
+
+// The HTTP gateway pulls the windows after the first of a streamed file through here
+#[query]
+fn http_request_streaming_callback(token: StreamingCallbackToken) -> StreamingCallbackHttpResponse {
+    match streaming_callback(token) {
+        Ok(response) => response,
+        Err(e) => ic_cdk::trap(&e),
+    }
+}
+
+// Strips webhook receiver responses down to their status code so replicas reach consensus
+#[query]
+fn transform_webhook_response(args: TransformArgs) -> CanisterHttpResponse {
+    crate::core::api::webhooks::deliveries::strip_webhook_response(args)
 }
\ No newline at end of file
@@ -4,6 +4,8 @@ pub mod apikeys_handlers {
     use crate::{
         core::{api::{permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_api_key, generate_uuidv4, mark_claimed_uuid}}, state::{api_keys::{state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE, USERS_APIKEYS_HASHTABLE}, types::{ApiKey, ApiKeyID, ApiKeyIDList, ApiKeyValue}}, drives::{state::state::{update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, types::{IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{api_keys::types::{ApiKeyFE, CreateApiKeyRequestBody, CreateApiKeyResponse, DeleteApiKeyRequestBody, DeleteApiKeyResponse, DeletedApiKeyData, ErrorResponse, GetApiKeyResponse, ListApiKeysResponse, UpdateApiKeyRequestBody, UpdateApiKeyResponse}, auth::{authenticate_request, create_auth_error_response}}, 
     };
+    use crate::core::state::audit::{state::state::audit_summary_api_key, types::AuditActionEnum};
+    use crate::rest::helpers::record_audit_event;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
     use serde::Deserialize;
@@ -159,10 +161,7 @@ pub mod apikeys_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400,
-                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -182,6 +181,15 @@ pub mod apikeys_handlers {
             }
         }
 
+        // A scoped key can only hand out keys at least as restricted as itself
+        let scopes = create_req.scopes.clone().unwrap_or_default();
+        if !requester_api_key.scopes.iter().all(|scope| scopes.contains(scope)) {
+            return create_response(
+                StatusCode::FORBIDDEN,
+                ErrorResponse::err(403, "New API key must keep all scopes of the requesting key".to_string()).encode()
+            );
+        }
+
         let prestate = snapshot_prestate();
 
         // If owner and user_id provided in request, use that. Otherwise use requester's user_id
@@ -210,6 +218,7 @@ pub mod apikeys_handlers {
             labels: vec![],
             external_id: Some(ExternalID(create_req.external_id.unwrap_or("".to_string()))),
             external_payload: Some(ExternalPayload(create_req.external_payload.unwrap_or("".to_string()))),
+            scopes,
         };
         mark_claimed_uuid(&unique_id.to_string());
 
@@ -250,6 +259,8 @@ pub mod apikeys_handlers {
             Some(new_api_key.id.to_string()),
         );
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::ApiKeyCreate, &new_api_key.id, None, Some(audit_summary_api_key(&new_api_key)));
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Create API Key {}", 
@@ -280,10 +291,7 @@ pub mod apikeys_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400,
-                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -319,6 +327,16 @@ pub mod apikeys_handlers {
             }
         }
 
+        if let Some(scopes) = &update_req.scopes {
+            if !requester_api_key.scopes.iter().all(|scope| scopes.contains(scope)) {
+                return create_response(
+                    StatusCode::FORBIDDEN,
+                    ErrorResponse::err(403, "API key scopes can't be widened beyond the requesting key's scopes".to_string()).encode()
+                );
+            }
+        }
+
+        let audit_before = audit_summary_api_key(&api_key);
         let prestate = snapshot_prestate();
 
         // Update only the fields that were provided
@@ -344,6 +362,9 @@ pub mod apikeys_handlers {
         if let Some(external_payload) = update_req.external_payload.clone() {
             api_key.external_payload = Some(ExternalPayload(external_payload));
         }
+        if let Some(scopes) = update_req.scopes.clone() {
+            api_key.scopes = scopes;
+        }
 
         // Update the API key in APIKEYS_BY_ID_HASHTABLE
         APIKEYS_BY_ID_HASHTABLE.with(|store| {
@@ -363,6 +384,8 @@ pub mod apikeys_handlers {
 
         match updated_api_key {
             Some(key) => {
+                record_audit_event(request, &requester_api_key, AuditActionEnum::ApiKeyUpdate, &key.id, Some(audit_before), Some(audit_summary_api_key(&key)));
+
                 snapshot_poststate(prestate, Some(
                     format!(
                         "{}: Update API Key {}", 
@@ -411,10 +434,7 @@ pub mod apikeys_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400,
-                    format!("Validation error for field '{}': {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -496,6 +516,8 @@ pub mod apikeys_handlers {
             Some(old_internal_id),
         );
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::ApiKeyDelete, &api_key.id, Some(audit_summary_api_key(&api_key)), None);
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Delete API Key {}", 
@@ -1,7 +1,7 @@
 // src/rest/api_keys/types.rs
 
 use serde::{Deserialize, Serialize};
-use crate::{core::{api::permissions::system::check_system_permissions, state::{api_keys::types::{ApiKey, ApiKeyID, ApiKeyValue}, drives::state::state::OWNER_ID, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, labels::{state::validate_uuid4_string_with_prefix, types::{redact_label, LabelStringValue}}}, types::{ClientSuggestedUUID, IDPrefix, UserID}}, rest::types::{validate_description, validate_external_id, validate_external_payload, validate_id_string, validate_unclaimed_uuid, validate_user_id, ApiResponse, ValidationError}};
+use crate::{core::{api::permissions::system::check_system_permissions, state::{api_keys::types::{ApiKey, ApiKeyID, ApiKeyScope, ApiKeyValue}, drives::state::state::OWNER_ID, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, labels::{state::validate_uuid4_string_with_prefix, types::{redact_label, LabelStringValue}}}, types::{ClientSuggestedUUID, IDPrefix, UserID}}, rest::types::{validate_description, validate_external_id, validate_external_payload, validate_id_string, validate_short_string, validate_unclaimed_uuid, validate_user_id, ApiResponse, ValidationError}};
 
 
 
@@ -63,6 +63,8 @@ pub struct CreateApiKeyRequestBody {
     pub external_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_payload: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<ApiKeyScope>>,
 }
 impl CreateApiKeyRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -98,6 +100,10 @@ impl CreateApiKeyRequestBody {
             validate_external_payload(external_payload)?;
         }
 
+        if let Some(scopes) = &self.scopes {
+            validate_api_key_scopes(scopes)?;
+        }
+
         // Validate expires_at if provided (must be a future timestamp)
         if let Some(expires_at) = self.expires_at {
             if expires_at != -1 && expires_at <= (ic_cdk::api::time() / 1_000_000) as i64 {
@@ -161,6 +167,8 @@ pub struct UpdateApiKeyRequestBody {
     pub external_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_payload: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<ApiKeyScope>>,
 }
 impl UpdateApiKeyRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -196,6 +204,10 @@ impl UpdateApiKeyRequestBody {
             validate_external_payload(external_payload)?;
         }
 
+        if let Some(scopes) = &self.scopes {
+            validate_api_key_scopes(scopes)?;
+        }
+
         // Validate expires_at if provided (must be a future timestamp)
         if let Some(expires_at) = self.expires_at {
             if expires_at != -1 && expires_at <= (ic_cdk::api::time() / 1_000_000) as i64 {
@@ -210,7 +222,53 @@ impl UpdateApiKeyRequestBody {
     }
 }
 
+pub fn validate_api_key_scopes(scopes: &[ApiKeyScope]) -> Result<(), ValidationError> {
+    let scope_error = |message: &str| ValidationError {
+        field: "scopes".to_string(),
+        message: message.to_string(),
+    };
 
+    if scopes.len() > 16 {
+        return Err(scope_error("An API key can have at most 16 scopes"));
+    }
+    for scope in scopes {
+        match scope {
+            ApiKeyScope::ReadOnly | ApiKeyScope::DirectoryOnly => {},
+            ApiKeyScope::FolderSubtree(folder_id) => {
+                validate_id_string(&folder_id.0, "scopes")?;
+                if !folder_id.0.starts_with(IDPrefix::Folder.as_str()) {
+                    return Err(scope_error("FOLDER_SUBTREE must be a folder ID"));
+                }
+            },
+            ApiKeyScope::SystemTables(tables) => {
+                if tables.is_empty() {
+                    return Err(scope_error("SYSTEM_TABLES needs at least one table"));
+                }
+            },
+            ApiKeyScope::HttpMethods(methods) => {
+                if methods.is_empty() {
+                    return Err(scope_error("HTTP_METHODS needs at least one method"));
+                }
+                let known = ["GET", "POST", "PUT", "DELETE", "HEAD"];
+                if let Some(method) = methods.iter().find(|m| !known.contains(&m.to_uppercase().as_str())) {
+                    return Err(scope_error(&format!("Unknown HTTP method '{}'", method)));
+                }
+            },
+            ApiKeyScope::Routes(prefixes) => {
+                if prefixes.is_empty() || prefixes.len() > 32 {
+                    return Err(scope_error("ROUTES needs between 1 and 32 route prefixes"));
+                }
+                for prefix in prefixes {
+                    validate_short_string(prefix, "scopes")?;
+                    if !prefix.starts_with('/') {
+                        return Err(scope_error("ROUTES prefixes must start with '/', e.g. /contacts"));
+                    }
+                }
+            },
+        }
+    }
+    Ok(())
+}
 
 pub type UpdateApiKeyResponse<'a> = ApiResponse<'a, ApiKeyFE>;
 pub type ListApiKeysResponse<'a> = ApiResponse<'a, Vec<ApiKeyFE>>;
@@ -2,8 +2,9 @@ use candid::Principal;
 use ed25519_dalek::SigningKey;
 // src/rest/auth.rs
 use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
-use crate::{core::{api::uuid::format_user_id, state::api_keys::{state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE}, types::{ApiKey, ApiKeyID, ApiKeyValue, AuthJsonDecoded, AuthTypeEnum}}, types::UserID}, debug_log, rest::helpers::update_last_online_at};
+use crate::{core::{api::uuid::format_user_id, state::{api_keys::{state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE}, types::{ApiKey, ApiKeyID, ApiKeyScope, ApiKeyValue, AuthJsonDecoded, AuthTypeEnum}}, directory::{state::state::{file_uuid_to_metadata, file_version_to_metadata, folder_uuid_to_metadata, full_folder_path_to_uuid}, types::{DriveFullFilePath, FileID, FileVersionID, FolderID}}, permissions::types::SystemTableEnum, rate_limits::{state::state::consume_rate_limit, types::RateLimitRouteGroup}}, types::{IDPrefix, UserID}}, debug_log, rest::{directory::types::{DirectoryAction, DirectoryActionPayload, DirectoryResourceID}, helpers::update_last_online_at}};
 use crate::rest::api_keys::types::ErrorResponse;
+use crate::rest::types::ErrorCode;
 use ic_types::crypto::AlgorithmId;
 use bip39::{Mnemonic, Language};
 use tiny_keccak::{Keccak, Hasher};
@@ -12,9 +13,79 @@ use ic_crypto_standalone_sig_verifier::{
     verify_basic_sig_by_public_key,
 };
 use super::helpers::create_response;
+use std::cell::RefCell;
 
 
+/// How a request's credentials resolved. A valid key used outside its scopes is kept apart
+/// from a missing or invalid one, the router answers it with 403 instead of 401.
+#[derive(Clone)]
+pub enum AuthResult {
+    Authenticated(ApiKey),
+    OutOfScope(ApiKey),
+    Unauthenticated,
+}
+
+impl AuthResult {
+    pub fn api_key(&self) -> Option<&ApiKey> {
+        match self {
+            AuthResult::Authenticated(key) => Some(key),
+            AuthResult::OutOfScope(_) | AuthResult::Unauthenticated => None,
+        }
+    }
+}
+
+thread_local! {
+    // The router resolves credentials once per request, handlers reuse the result. Tagged with
+    // the request it belongs to, since another request can run while a handler awaits.
+    static RESOLVED_REQUEST_AUTH: RefCell<Option<(String, AuthResult)>> = RefCell::new(None);
+}
+
+fn request_auth_tag(req: &HttpRequest) -> String {
+    let authorization = req.headers().iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        .map(|(_, value)| value.as_str())
+        .unwrap_or("");
+    format!("{} {} {}", req.method().as_str(), req.url(), authorization)
+}
+
+pub fn remember_request_auth(req: &HttpRequest, auth: &AuthResult) {
+    RESOLVED_REQUEST_AUTH.with(|resolved| {
+        *resolved.borrow_mut() = Some((request_auth_tag(req), auth.clone()));
+    });
+}
+
+pub fn forget_request_auth() {
+    RESOLVED_REQUEST_AUTH.with(|resolved| resolved.borrow_mut().take());
+}
+
+pub fn resolve_request_auth(req: &HttpRequest) -> AuthResult {
+    let key = match lookup_request_api_key(req) {
+        Some(key) => key,
+        None => return AuthResult::Unauthenticated,
+    };
+    // Scoped keys are refused outright on routes outside their scopes
+    if !api_key_scopes_allow_route(&key, req.method().as_str(), &drive_relative_path(req)) {
+        debug_log!("API key {} is not scoped for this route", key.id);
+        return AuthResult::OutOfScope(key);
+    }
+    update_last_online_at(&key.user_id);
+    AuthResult::Authenticated(key)
+}
+
 pub fn authenticate_request(req: &HttpRequest) -> Option<ApiKey> {
+    let tag = request_auth_tag(req);
+    let remembered = RESOLVED_REQUEST_AUTH.with(|resolved| {
+        resolved.borrow().as_ref()
+            .filter(|(resolved_tag, _)| *resolved_tag == tag)
+            .map(|(_, auth)| auth.clone())
+    });
+    match remembered.unwrap_or_else(|| resolve_request_auth(req)) {
+        AuthResult::Authenticated(key) => Some(key),
+        AuthResult::OutOfScope(_) | AuthResult::Unauthenticated => None,
+    }
+}
+
+fn lookup_request_api_key(req: &HttpRequest) -> Option<ApiKey> {
     // // Extract the Authorization header
     // let auth_header = match req.headers().iter().find(|(k, _)| k == "authorization") {
     //     Some((_, value)) => value,
@@ -166,8 +237,6 @@ pub fn authenticate_request(req: &HttpRequest) -> Option<ApiKey> {
                     }
                     debug_log!("Successfully authenticated user: {}", computed_principal);
 
-                    update_last_online_at(&format_user_id(&computed_principal.clone()));
-
                     // Create and return an API key based on the computed principal.
                     Some(ApiKey {
                         id: ApiKeyID(format!("sig_auth_{}", now)),
@@ -182,6 +251,7 @@ pub fn authenticate_request(req: &HttpRequest) -> Option<ApiKey> {
                         labels: vec![],
                         external_id: None,
                         external_payload: None,
+                        scopes: vec![],
                     })
                 },
                 Err(e) => {
@@ -223,8 +293,6 @@ pub fn authenticate_request(req: &HttpRequest) -> Option<ApiKey> {
                     
                     // Return the key if it's valid (not expired and not revoked), and begins time is past
                     if (key.expires_at <= 0 || now < key.expires_at) && !key.is_revoked && key.begins_at <= (ic_cdk::api::time() / 1_000_000) {
-
-                        update_last_online_at(&key.user_id);
                         return Some(key);
                     }
                 }
@@ -236,22 +304,225 @@ pub fn authenticate_request(req: &HttpRequest) -> Option<ApiKey> {
     }
 }
 
+// Route actions that never change state, checked against the path segments
+const READ_ONLY_ROUTE_ACTIONS: &[&str] = &[
+    "get", "list", "check", "validate", "search", "about", "whoami",
+    "snapshot", "external_id", "status", "raw_download", "asset",
+];
+
+// Introspection routes every authenticated key may call regardless of its scopes
+const UNSCOPED_ROUTES: &[&str] = &["/organization/whoami", "/organization/about"];
+
+/// Request path relative to `/v1/drive/{organization_id}`, e.g. `/contacts/list`
+pub(crate) fn drive_relative_path(req: &HttpRequest) -> String {
+    let path = req.get_path().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_start_matches('/').splitn(4, '/').collect();
+    match segments.as_slice() {
+        ["v1", "drive", _, rest] => format!("/{}", rest),
+        ["v1", "drive", _] => "/".to_string(),
+        _ => path,
+    }
+}
+
+fn is_read_only_route(method: &str, path: &str) -> bool {
+    // Directory actions are batched, ApiKeyScope::ReadOnly is checked per action instead
+    if method.eq_ignore_ascii_case("GET") || path == "/directory/action" {
+        return true;
+    }
+    path.split('/').any(|segment| READ_ONLY_ROUTE_ACTIONS.contains(&segment))
+}
+
+fn system_table_for_route(path: &str) -> Option<SystemTableEnum> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    match (segments.next()?, segments.next()) {
+        ("drives", _) => Some(SystemTableEnum::Drives),
+        ("disks", _) => Some(SystemTableEnum::Disks),
+        ("contacts", _) => Some(SystemTableEnum::Contacts),
+        ("groups", _) => Some(SystemTableEnum::Groups),
+        ("api_keys", _) => Some(SystemTableEnum::ApiKeys),
+        ("permissions", _) => Some(SystemTableEnum::Permissions),
+        ("webhooks", _) => Some(SystemTableEnum::Webhooks),
+        ("labels", _) => Some(SystemTableEnum::Labels),
+        ("purchases", _) => Some(SystemTableEnum::Purchases),
+        ("organization", Some("inbox")) => Some(SystemTableEnum::Inbox),
+        _ => None,
+    }
+}
+
+/// Whether every scope on the key allows `method` on the drive relative `path`
+pub fn api_key_scopes_allow_route(key: &ApiKey, method: &str, path: &str) -> bool {
+    if key.scopes.is_empty() || UNSCOPED_ROUTES.contains(&path) {
+        return true;
+    }
+    key.scopes.iter().all(|scope| match scope {
+        ApiKeyScope::ReadOnly => is_read_only_route(method, path),
+        ApiKeyScope::DirectoryOnly | ApiKeyScope::FolderSubtree(_) => path.starts_with("/directory/"),
+        ApiKeyScope::SystemTables(tables) => system_table_for_route(path)
+            .map_or(false, |table| tables.contains(&table)),
+        ApiKeyScope::HttpMethods(methods) => methods.iter().any(|m| m.eq_ignore_ascii_case(method)),
+        ApiKeyScope::Routes(prefixes) => prefixes.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path == prefix || path.starts_with(&format!("{}/", prefix))
+        }),
+    })
+}
+
+fn is_in_folder_subtree(resource: &DirectoryResourceID, root: &FolderID) -> bool {
+    let mut current = match resource {
+        DirectoryResourceID::Folder(folder_id) => Some(folder_id.clone()),
+        DirectoryResourceID::File(file_id) => file_uuid_to_metadata.get(file_id).map(|file| file.parent_folder_uuid),
+    };
+    // Guards against a corrupt parent chain looping forever
+    let mut depth = 0;
+    while let Some(folder_id) = current {
+        if &folder_id == root {
+            return true;
+        }
+        depth += 1;
+        if depth > 1024 {
+            return false;
+        }
+        current = folder_uuid_to_metadata.get(&folder_id).and_then(|folder| folder.parent_folder_uuid);
+    }
+    false
+}
+
+/// Whether the key's folder subtree scopes (if any) cover the resource
+pub fn api_key_allows_directory_resource(key: &ApiKey, resource: &DirectoryResourceID) -> bool {
+    key.scopes.iter().all(|scope| match scope {
+        ApiKeyScope::FolderSubtree(root) => is_in_folder_subtree(resource, root),
+        _ => true,
+    })
+}
+
+/// Checks a single directory action against ApiKeyScope::ReadOnly and every folder it touches
+pub fn api_key_allows_directory_action(key: &ApiKey, action: &DirectoryAction) -> bool {
+    if key.scopes.is_empty() {
+        return true;
+    }
+
+    let is_read = matches!(
+        action.payload,
+        DirectoryActionPayload::GetFile(_)
+            | DirectoryActionPayload::GetFolder(_)
+            | DirectoryActionPayload::ListFileVersions(_)
+            | DirectoryActionPayload::GetFileVersion(_)
+    );
+    if !is_read && key.scopes.contains(&ApiKeyScope::ReadOnly) {
+        return false;
+    }
+
+    // Destinations given by path must already exist to be checked
+    let destination = |folder_id: &Option<FolderID>, folder_path: &Option<DriveFullFilePath>| -> Option<DirectoryResourceID> {
+        match (folder_id, folder_path) {
+            (Some(folder_id), _) => Some(DirectoryResourceID::Folder(folder_id.clone())),
+            (None, Some(folder_path)) => Some(DirectoryResourceID::Folder(
+                full_folder_path_to_uuid.get(folder_path).unwrap_or(FolderID(String::new()))
+            )),
+            (None, None) => None,
+        }
+    };
+    let version_file = |version_id: &FileVersionID| {
+        DirectoryResourceID::File(
+            file_version_to_metadata.get(version_id).map(|file| file.id).unwrap_or(FileID(String::new()))
+        )
+    };
+
+    let resources: Vec<DirectoryResourceID> = match &action.payload {
+        DirectoryActionPayload::GetFile(payload) => vec![DirectoryResourceID::File(payload.id.clone())],
+        DirectoryActionPayload::GetFolder(payload) => vec![DirectoryResourceID::Folder(payload.id.clone())],
+        DirectoryActionPayload::CreateFile(payload) => vec![DirectoryResourceID::Folder(payload.parent_folder_uuid.clone())],
+        DirectoryActionPayload::CreateFolder(payload) => vec![DirectoryResourceID::Folder(payload.parent_folder_uuid.clone())],
+        DirectoryActionPayload::UpdateFile(payload) => vec![DirectoryResourceID::File(payload.id.clone())],
+        DirectoryActionPayload::UpdateFolder(payload) => vec![DirectoryResourceID::Folder(payload.id.clone())],
+        DirectoryActionPayload::DeleteFile(payload) => vec![DirectoryResourceID::File(payload.id.clone())],
+        DirectoryActionPayload::DeleteFolder(payload) => vec![DirectoryResourceID::Folder(payload.id.clone())],
+        DirectoryActionPayload::CopyFile(payload) => std::iter::once(DirectoryResourceID::File(payload.id.clone()))
+            .chain(destination(&payload.destination_folder_id, &payload.destination_folder_path))
+            .collect(),
+        DirectoryActionPayload::CopyFolder(payload) => std::iter::once(DirectoryResourceID::Folder(payload.id.clone()))
+            .chain(destination(&payload.destination_folder_id, &payload.destination_folder_path))
+            .collect(),
+        DirectoryActionPayload::MoveFile(payload) => std::iter::once(DirectoryResourceID::File(payload.id.clone()))
+            .chain(destination(&payload.destination_folder_id, &payload.destination_folder_path))
+            .collect(),
+        DirectoryActionPayload::MoveFolder(payload) => std::iter::once(DirectoryResourceID::Folder(payload.id.clone()))
+            .chain(destination(&payload.destination_folder_id, &payload.destination_folder_path))
+            .collect(),
+        DirectoryActionPayload::RestoreTrash(payload) => {
+            // Trashed items live outside the subtree, so check where they came from
+            let prior_folder = if payload.id.starts_with(IDPrefix::Folder.as_str()) {
+                folder_uuid_to_metadata.get(&FolderID(payload.id.clone())).and_then(|folder| folder.restore_trash_prior_folder_uuid)
+            } else {
+                file_uuid_to_metadata.get(&FileID(payload.id.clone())).and_then(|file| file.restore_trash_prior_folder_uuid)
+            };
+            std::iter::once(DirectoryResourceID::Folder(prior_folder.unwrap_or(FolderID(String::new()))))
+                .chain(destination(&None, &payload.restore_to_folder_path))
+                .collect()
+        },
+        DirectoryActionPayload::ListFileVersions(payload) => vec![DirectoryResourceID::File(payload.id.clone())],
+        DirectoryActionPayload::GetFileVersion(payload) => vec![version_file(&payload.version_id)],
+        DirectoryActionPayload::RestoreFileVersion(payload) => vec![version_file(&payload.version_id)],
+    };
+
+    resources.iter().all(|resource| api_key_allows_directory_resource(key, resource))
+}
+
+/// Charges the request against its api key's and user's token buckets for the route group.
+/// Returns the 429 to send back when either bucket is empty. Unauthenticated requests
+/// aren't limited here, their handlers reject them anyway.
+pub fn rate_limit_response(req: &HttpRequest, auth: &AuthResult) -> Option<HttpResponse<'static>> {
+    if req.method().as_str().eq_ignore_ascii_case("OPTIONS") {
+        return None;
+    }
+    let key = auth.api_key()?;
+    let group = RateLimitRouteGroup::for_path(&drive_relative_path(req));
+
+    // Signature auth mints a throwaway key id per request, only its user is meaningful
+    let mut subjects = vec![key.user_id.to_string()];
+    if !key.id.0.starts_with("sig_auth_") {
+        subjects.push(key.id.to_string());
+    }
+
+    match consume_rate_limit(&subjects, group) {
+        Ok(()) => None,
+        Err(retry_after_seconds) => {
+            debug_log!("Rate limited {} on {}, retry after {}s", key.user_id, group, retry_after_seconds);
+            let mut response = create_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                String::from_utf8(ErrorResponse::err(429, format!("Rate limit exceeded for {}, retry after {} seconds", group, retry_after_seconds)).encode())
+                    .unwrap_or_else(|_| String::from("Too Many Requests")),
+            );
+            response.headers_mut().push(("Retry-After".to_string(), retry_after_seconds.to_string()));
+            Some(response)
+        }
+    }
+}
+
 pub fn create_auth_error_response() -> HttpResponse<'static> {
     let body = String::from_utf8(ErrorResponse::unauthorized().encode())
         .unwrap_or_else(|_| String::from("Unauthorized"));
     create_response(StatusCode::UNAUTHORIZED, body)
 }
 
+pub fn create_scope_error_response() -> HttpResponse<'static> {
+    let body = String::from_utf8(ErrorResponse::error(ErrorCode::PermissionDenied, "API key is not scoped for this route".to_string()).encode())
+        .unwrap_or_else(|_| String::from("Forbidden"));
+    create_response(StatusCode::FORBIDDEN, body)
+}
+
 
 
-pub fn create_raw_upload_error_response(error_msg: &str) -> HttpResponse<'static> {
-    // Use the new `unauthorized_with_message` (or whichever method you create)
-    let error_struct = ErrorResponse::err(400, error_msg.to_string());
+// Raw upload failures carry their own code, so missing files, scopes and permissions
+// answer 404 and 403 like the rest of the API instead of a blanket 400
+pub fn create_raw_upload_error_response(error_code: ErrorCode, error_msg: &str) -> HttpResponse<'static> {
+    let error_struct = ErrorResponse::error(error_code, error_msg.to_string());
 
     let body = String::from_utf8(error_struct.encode())
         .unwrap_or_else(|_| String::from("Bad Request"));
 
-    create_response(StatusCode::BAD_REQUEST, body)
+    let status = StatusCode::from_u16(error_code.status()).unwrap_or(StatusCode::BAD_REQUEST);
+    create_response(status, body)
 }
 
 
@@ -3,12 +3,14 @@
 
 pub mod contacts_handlers {
     use crate::{
-        core::{api::{permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{format_user_id, generate_api_key, generate_uuidv4, mark_claimed_uuid}, webhooks::organization::{fire_superswap_user_webhook, get_superswap_user_webhooks}}, state::{api_keys::{state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE, USERS_APIKEYS_HASHTABLE}, types::{ApiKey, ApiKeyID, ApiKeyIDList, ApiKeyValue}}, contacts::state::state::{CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE, CONTACTS_BY_ID_HASHTABLE, CONTACTS_BY_TIME_LIST}, drives::{state::state::{superswap_userid, update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, group_invites::{state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, types::{GroupInvite, GroupInviteID, GroupInviteIDList, GroupInviteeID, GroupRole}}, groups::state::state::{DEFAULT_EVERYONE_GROUP, GROUPS_BY_ID_HASHTABLE}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, webhooks::types::WebhookEventLabel}, types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, contacts::types::{ CreateContactRequestBody, CreateContactResponse, DeleteContactRequest, DeleteContactResponse, DeletedContactData, ErrorResponse, GetContactResponse, ListContactsRequestBody, ListContactsResponse, ListContactsResponseData, RedeemContactRequestBody, RedeemContactResponse, RedeemContactResponseBody, UpdateContactRequest, UpdateContactRequestBody, UpdateContactResponse}, webhooks::types::SortDirection}
+        core::{api::{permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{format_user_id, generate_api_key, generate_uuidv4, mark_claimed_uuid}, webhooks::organization::{fire_superswap_user_webhook, get_superswap_user_webhooks}}, state::{api_keys::{state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE, USERS_APIKEYS_HASHTABLE}, types::{ApiKey, ApiKeyID, ApiKeyIDList, ApiKeyValue}}, contacts::state::state::{CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE, CONTACTS_BY_ID_HASHTABLE, CONTACTS_BY_TIME_LIST}, drives::{state::state::{superswap_userid, update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, group_invites::{state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, types::{GroupInvite, GroupInviteID, GroupInviteIDList, GroupInviteeID, GroupRole}}, groups::state::state::{DEFAULT_EVERYONE_GROUP, GROUPS_BY_ID_HASHTABLE}, search::{state::state::index_search_resource, types::SearchResultResourceID}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, webhooks::types::WebhookEventLabel}, types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, contacts::types::{ CreateContactRequestBody, CreateContactResponse, DeleteContactRequest, DeleteContactResponse, DeletedContactData, ErrorResponse, GetContactResponse, ListContactsRequestBody, ListContactsResponse, ListContactsResponseData, RedeemContactRequestBody, RedeemContactResponse, RedeemContactResponseBody, UpdateContactRequest, UpdateContactRequestBody, UpdateContactResponse}, webhooks::types::SortDirection}
         
     };
     use crate::core::state::contacts::{
         types::Contact,
     };
+    use crate::core::state::audit::{state::state::audit_summary_api_key, types::AuditActionEnum};
+    use crate::rest::helpers::record_audit_event;
     use url::Url;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
@@ -122,10 +124,7 @@ pub mod contacts_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -312,10 +311,7 @@ pub mod contacts_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
         
@@ -386,6 +382,7 @@ pub mod contacts_handlers {
         CONTACTS_BY_TIME_LIST.with(|store| {
             store.borrow_mut().push(&contact_id);
         });
+        index_search_resource(SearchResultResourceID::Contact(contact_id.clone()));
 
         update_external_id_mapping(None, contact.external_id.clone(), Some(contact_id.to_string()));
 
@@ -498,10 +495,7 @@ pub mod contacts_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -577,6 +571,7 @@ pub mod contacts_handlers {
         CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE.with(|store| {
             store.borrow_mut().insert(contact.icp_principal.clone(), contact_id.clone());
         });
+        index_search_resource(SearchResultResourceID::Contact(contact_id.clone()));
 
         update_external_id_mapping(
             old_external_id,
@@ -627,10 +622,7 @@ pub mod contacts_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -665,6 +657,7 @@ pub mod contacts_handlers {
         CONTACTS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&UserID(contact_id.to_string()));
         });
+        index_search_resource(SearchResultResourceID::Contact(contact_id.clone()));
 
         CONTACTS_BY_TIME_LIST.with(|store| {
             let mut new_vec = Vec::new();
@@ -774,10 +767,7 @@ pub mod contacts_handlers {
         if let Err(validation_error) = redeem_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -819,6 +809,9 @@ pub mod contacts_handlers {
                         store_ref.insert(new_user_id.clone(), contact);
                     }
                 });
+                // The contact now lives under the new user id
+                index_search_resource(SearchResultResourceID::Contact(current_user_id.clone()));
+                index_search_resource(SearchResultResourceID::Contact(new_user_id.clone()));
 
 
                 let active_webhooks = get_superswap_user_webhooks(
@@ -834,6 +827,15 @@ pub mod contacts_handlers {
                     Some(format!("Redeem Contact - superswap {} to {}, updated {} records", current_user_id, new_user_id, update_count))
                 );
 
+                record_audit_event(
+                    request,
+                    &requester_api_key,
+                    AuditActionEnum::SuperswapUser,
+                    &current_user_id,
+                    Some(current_user_id.to_string()),
+                    Some(format!("{}, updated {} records", new_user_id, update_count)),
+                );
+
                 snapshot_poststate(prestate, Some(
                     format!(
                         "{}: Redeem Contact - superswap {} to {}, updated {} records", 
@@ -863,6 +865,7 @@ pub mod contacts_handlers {
                     labels: vec![],
                     external_id: None,
                     external_payload: None,
+                    scopes: vec![],
                 };
                 mark_claimed_uuid(&unique_id.to_string());
 
@@ -895,6 +898,7 @@ pub mod contacts_handlers {
                         store_mut.insert(new_api_key.user_id.clone(), ApiKeyIDList::with_key(new_api_key.id.clone()));
                     }
                 });
+                record_audit_event(request, &requester_api_key, AuditActionEnum::ApiKeyCreate, &new_api_key.id, None, Some(audit_summary_api_key(&new_api_key)));
                 
                 create_response(
                     StatusCode::OK,
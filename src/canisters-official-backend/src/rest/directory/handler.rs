@@ -3,14 +3,20 @@
 
 pub mod directorys_handlers {
     use crate::{
-        core::{api::{disks::{aws_s3::{generate_s3_upload_url, generate_s3_view_url}, storj_web3::generate_storj_view_url}, drive::drive::fetch_files_at_folder_path, permissions::directory::check_directory_permissions, uuid::generate_uuidv4}, state::{directory::{state::state::file_uuid_to_metadata, types::{FileID, FolderID}}, disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskID, DiskTypeEnum}}, drives::state::state::OWNER_ID, permissions::types::{DirectoryPermissionType, PermissionGranteeID}, raw_storage::{state::{delete_file_data, get_file_chunks, store_chunk, store_filename, FILE_META}, types::{ChunkId, FileChunk, UploadStatus, CHUNK_SIZE}}}, types::IDPrefix}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response, create_raw_upload_error_response}, directory::types::{ClientSideUploadRequest, ClientSideUploadResponse, CompleteUploadRequest, CompleteUploadResponse, DirectoryAction, DirectoryActionError, DirectoryActionOutcome, DirectoryActionOutcomeID, DirectoryActionRequestBody, DirectoryActionResponse, DirectoryListResponse, DirectoryResourceID, ErrorResponse, FileMetadataResponse, ListDirectoryRequest, UploadChunkRequest, UploadChunkResponse}}, 
+        core::{api::{disks::{aws_s3::{generate_s3_upload_url, generate_s3_view_url}, storj_web3::generate_storj_view_url}, drive::drive::fetch_files_at_folder_path, permissions::directory::{check_directory_permissions, check_directory_permissions_with_password}, streaming::streaming::{clear_streaming_strategy, ensure_streaming_secret, start_streaming, STREAMING_WINDOW_BYTES}, uploads::uploads::{missing_chunk_indexes, session_expires_at, verify_upload}, uuid::generate_uuidv4, webhooks::directory::fire_file_viewed_webhooks}, state::{api_keys::types::ApiKeyScope, directory::{state::state::{file_uuid_to_metadata, file_version_to_metadata}, types::{FileID, FileRecord, FileVersionID, FolderID}}, disks::{state::state::DISKS_BY_ID_HASHTABLE, types::{AwsBucketAuth, DiskID, DiskTypeEnum}}, drives::state::state::OWNER_ID, permissions::types::{DirectoryPermissionType, PermissionGranteeID}, raw_storage::{state::{chunk_sha256, delete_file_data, end_upload_session, get_file_chunk_records, get_file_chunks, get_upload_session, hash_chunk_data, read_file_range, store_chunk, store_filename, stored_chunk_sha256, touch_upload_session, FILE_META}, types::{ChunkId, FileChunk, UploadStatus, CHUNK_SIZE}}}, types::IDPrefix}, debug_log, rest::{auth::{api_key_allows_directory_action, api_key_allows_directory_resource, authenticate_request, create_auth_error_response, create_raw_upload_error_response}, directory::types::{ClientSideUploadRequest, ClientSideUploadResponse, CompleteUploadRequest, CompleteUploadResponse, DirectoryAction, DirectoryActionError, DirectoryActionOutcome, DirectoryActionOutcomeID, DirectoryActionRequestBody, DirectoryActionResponse, DirectoryListResponse, DirectoryResourceID, ErrorResponse, FileMetadataResponse, ListDirectoryRequest, UploadChunkRequest, UploadChunkResponse, UploadStatusResponse, UploadedChunkInfo, validate_sha256_hex}}, 
         
     };
     
+    use crate::core::state::audit::types::AuditActionEnum;
+    use crate::rest::helpers::record_audit_event;
+    use crate::core::api::actions::rollback_directory_action;
+    use crate::rest::types::ErrorCode;
+    use crate::core::state::directory::state::state::DirectoryJournal;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
     use serde::Deserialize;
     use urlencoding::decode;
+    use crate::rest::helpers::{content_type_for_extension, parse_range_header};
     #[derive(Deserialize, Default)]
     struct ListQueryParams {
         title: Option<String>,
@@ -37,10 +43,7 @@ pub mod directorys_handlers {
         if let Err(validation_error) = list_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -49,6 +52,12 @@ pub mod directorys_handlers {
         match list_request.clone().folder_id {
             Some(folder_id) => {
                 let resource_id = DirectoryResourceID::Folder(FolderID(folder_id.clone()));
+                if !api_key_allows_directory_resource(&requester_api_key, &resource_id) {
+                    return create_response(
+                        StatusCode::FORBIDDEN,
+                        ErrorResponse::err(403, "API key is not scoped for this directory".to_string()).encode()
+                    );
+                }
                 let user_permissions = check_directory_permissions(
                     resource_id.clone(),
                     PermissionGranteeID::User(requester_api_key.user_id.clone())
@@ -68,6 +77,13 @@ pub mod directorys_handlers {
             None => {
                 // do nothing, fetch_files_at_folder_path will just use disk_id instead
                 // debug_log!("No folder_id provided, will use disk_id instead")
+                // Folder scoped keys must name the folder they list
+                if requester_api_key.scopes.iter().any(|scope| matches!(scope, ApiKeyScope::FolderSubtree(_))) {
+                    return create_response(
+                        StatusCode::FORBIDDEN,
+                        ErrorResponse::err(403, "API key is scoped to a folder, provide folder_id".to_string()).encode()
+                    );
+                }
             }
         }
     
@@ -104,11 +120,47 @@ pub mod directorys_handlers {
             },
         };
     
+        // Each action rolls back on its own failure, an atomic batch also collects the journals of the ones that succeeded
+        let mut batch_journal = if action_batch.atomic { Some(DirectoryJournal::new()) } else { None };
+        let mut batch_failed = false;
         let mut outcomes = Vec::new();
         
         for action in action_batch.actions {
             let outcome_id = DirectoryActionOutcomeID(generate_uuidv4(IDPrefix::DirectoryActionOutcome));
-            let outcome = match crate::core::api::actions::pipe_action(action.clone(), requester_api_key.user_id.clone()).await {
+            if batch_failed {
+                outcomes.push(DirectoryActionOutcome {
+                    id: outcome_id,
+                    success: false,
+                    request: action.clone(),
+                    response: DirectoryActionResponse {
+                        result: None,
+                        error: Some(DirectoryActionError {
+                            code: 409,
+                            error_code: ErrorCode::Conflict,
+                            message: "Skipped because an earlier action in the atomic batch failed".to_string(),
+                        }),
+                    }
+                });
+                continue;
+            }
+            if !api_key_allows_directory_action(&requester_api_key, &action) {
+                batch_failed = action_batch.atomic;
+                outcomes.push(DirectoryActionOutcome {
+                    id: outcome_id,
+                    success: false,
+                    request: action.clone(),
+                    response: DirectoryActionResponse {
+                        result: None,
+                        error: Some(DirectoryActionError {
+                            code: 403,
+                            error_code: ErrorCode::PermissionDenied,
+                            message: "API key is not scoped for this action".to_string(),
+                        }),
+                    }
+                });
+                continue;
+            }
+            let outcome = match crate::core::api::actions::pipe_action(action.clone(), requester_api_key.user_id.clone(), batch_journal.as_mut()).await {
                 Ok(result) => DirectoryActionOutcome {
                     id: outcome_id,
                     success: true,
@@ -131,14 +183,40 @@ pub mod directorys_handlers {
                     response: DirectoryActionResponse {
                         result: None,
                         error: Some(DirectoryActionError {
-                            code: error_info.code,
+                            code: error_info.code.status() as i32,
+                            error_code: error_info.code,
                             message: error_info.message,
                         }),
                     }
                 },
             };
+            batch_failed = action_batch.atomic && !outcome.success;
             outcomes.push(outcome);
         }
+
+        if let Some(journal) = batch_journal {
+            if batch_failed {
+                let conflicts = rollback_directory_action(journal);
+                let message = match conflicts {
+                    0 => "Rolled back because a later action in the atomic batch failed".to_string(),
+                    _ => format!("Rolled back because a later action in the atomic batch failed, {} entries changed by another request were kept", conflicts),
+                };
+                // Actions that succeeded before the failure were undone with it
+                for outcome in outcomes.iter_mut().filter(|outcome| outcome.success) {
+                    outcome.success = false;
+                    outcome.response = DirectoryActionResponse {
+                        result: None,
+                        error: Some(DirectoryActionError {
+                            code: 409,
+                            error_code: ErrorCode::Conflict,
+                            message: message.clone(),
+                        }),
+                    };
+                }
+            } else {
+                journal.commit();
+            }
+        }
     
         create_response(
             StatusCode::OK,
@@ -158,7 +236,7 @@ pub mod directorys_handlers {
             Ok(req) => req,
             Err(_) => {
                 debug_log!("handle_upload_chunk: Failed to deserialize request");
-                return create_raw_upload_error_response("Invalid request format")
+                return create_raw_upload_error_response(ErrorCode::InvalidRequest, "Invalid request format")
             }
         };
 
@@ -174,9 +252,12 @@ pub mod directorys_handlers {
             Some(record) => record,
             None => {
                 debug_log!("handle_upload_chunk: File ID not found");
-                return create_raw_upload_error_response("File ID not found or not authorized for upload")
+                return create_raw_upload_error_response(ErrorCode::NotFound, "File ID not found")
             }
         };
+        if !api_key_allows_directory_resource(&requester_api_key, &DirectoryResourceID::File(file_id.clone())) {
+            return create_raw_upload_error_response(ErrorCode::PermissionDenied, "API key is not scoped for this file")
+        }
 
         // Check directory permissions for upload/create/update
         let resource_id = DirectoryResourceID::File(file_id.clone());
@@ -191,13 +272,13 @@ pub mod directorys_handlers {
 
         if !has_permission {
             debug_log!("handle_upload_chunk: User does not have upload/create/update permission");
-            return create_raw_upload_error_response("Not authorized to upload to this file")
+            return create_raw_upload_error_response(ErrorCode::PermissionDenied, "Not authorized to upload to this file")
         }
 
         // Check if file is in valid upload state
         if file_record.upload_status == UploadStatus::Completed {
             debug_log!("handle_upload_chunk: File upload already completed");
-            return create_raw_upload_error_response("File upload already completed")
+            return create_raw_upload_error_response(ErrorCode::Conflict, "File upload already completed")
         }
 
         // If this is chunk index 0, delete any existing file data to prepare for a fresh upload
@@ -210,25 +291,45 @@ pub mod directorys_handlers {
         }
     
         if upload_req.chunk_data.len() > CHUNK_SIZE {
-            return create_raw_upload_error_response("Chunk too large");
+            return create_raw_upload_error_response(ErrorCode::ValidationFailed, "Chunk too large");
+        }
+
+        if upload_req.chunk_index >= upload_req.total_chunks {
+            return create_raw_upload_error_response(ErrorCode::ValidationFailed, "chunk_index must be below total_chunks");
+        }
+
+        // Verify the chunk before it replaces anything that was stored under its index
+        let sha256 = hash_chunk_data(&upload_req.chunk_data);
+        if let Some(expected) = &upload_req.chunk_sha256 {
+            if let Err(e) = validate_sha256_hex(expected, "chunk_sha256") {
+                return create_raw_upload_error_response(ErrorCode::ValidationFailed, &format!("Validation error: {} - {}", e.field, e.message));
+            }
+            if !sha256.eq_ignore_ascii_case(expected) {
+                debug_log!("handle_upload_chunk: Checksum mismatch for chunk {}", upload_req.chunk_index);
+                return create_raw_upload_error_response(ErrorCode::ValidationFailed, "Chunk checksum mismatch");
+            }
         }
     
         let chunk_id = ChunkId(format!("{}-{}", upload_req.file_id, upload_req.chunk_index));
         
         let chunk = FileChunk {
             id: chunk_id.clone(),
-            file_id: upload_req.file_id,
+            file_id: upload_req.file_id.clone(),
             chunk_index: upload_req.chunk_index,
             data: upload_req.chunk_data.clone(),
-            size: upload_req.chunk_data.len()
+            size: upload_req.chunk_data.len(),
+            content_hash: Some(sha256.clone()),
         };
         debug_log!("handle_upload_chunk: Storing chunk {:?}", chunk.id);
-    
+
+        let content_changed = stored_chunk_sha256(&chunk_id).map_or(false, |previous| previous != sha256);
         store_chunk(chunk);
+        touch_upload_session(&upload_req.file_id, upload_req.chunk_index, upload_req.total_chunks, content_changed);
     
         let response = UploadChunkResponse {
             chunk_id: chunk_id.0,
-            bytes_received: upload_req.chunk_data.len()
+            bytes_received: upload_req.chunk_data.len(),
+            sha256,
         };
     
         debug_log!("handle_upload_chunk: Successfully stored chunk");
@@ -252,8 +353,11 @@ pub mod directorys_handlers {
         
         let complete_req: CompleteUploadRequest = match serde_json::from_slice(request.body()) {
             Ok(req) => req,
-            Err(_) => return create_raw_upload_error_response("Invalid request format")
+            Err(_) => return create_raw_upload_error_response(ErrorCode::InvalidRequest, "Invalid request format")
         };
+        if let Err(validation_error) = complete_req.validate_body() {
+            return create_raw_upload_error_response(ErrorCode::ValidationFailed, &format!("Validation error: {} - {}", validation_error.field, validation_error.message));
+        }
         debug_log!("handle_complete_upload: Completing upload");
         debug_log!("  file_id = {}", complete_req.file_id);
 
@@ -262,9 +366,12 @@ pub mod directorys_handlers {
             Some(record) => record,
             None => {
                 debug_log!("handle_complete_upload: File ID not found");
-                return create_raw_upload_error_response("File ID not found or not authorized for upload")
+                return create_raw_upload_error_response(ErrorCode::NotFound, "File ID not found")
             }
         };
+        if !api_key_allows_directory_resource(&requester_api_key, &DirectoryResourceID::File(file_id.clone())) {
+            return create_raw_upload_error_response(ErrorCode::PermissionDenied, "API key is not scoped for this file")
+        }
 
         // Check directory permissions for upload/create/update
         let resource_id = DirectoryResourceID::File(file_id.clone());
@@ -279,20 +386,38 @@ pub mod directorys_handlers {
 
         if !has_permission {
             debug_log!("handle_complete_upload: User does not have upload/create/update permission");
-            return create_raw_upload_error_response("Not authorized to upload to this file")
+            return create_raw_upload_error_response(ErrorCode::PermissionDenied, "Not authorized to upload to this file")
         }
 
         // Check file upload status
         if file_record.upload_status == UploadStatus::Completed {
             debug_log!("handle_complete_upload: File upload already completed");
-            return create_raw_upload_error_response("File upload already completed")
+            return create_raw_upload_error_response(ErrorCode::Conflict, "File upload already completed")
         }
 
-        store_filename(&complete_req.file_id, &complete_req.filename);
-    
-        let chunks = get_file_chunks(&complete_req.file_id);
+        let chunks = get_file_chunk_records(&complete_req.file_id);
         debug_log!("handle_complete_upload: Found {} chunks", chunks.len());
 
+        // A failed check leaves the upload pending, the client can resend chunks and retry
+        let session = get_upload_session(&complete_req.file_id);
+        if let Some(session) = &session {
+            let missing = missing_chunk_indexes(&chunks, session.total_chunks);
+            if !missing.is_empty() {
+                let missing: Vec<String> = missing.iter().map(|index| index.to_string()).collect();
+                return create_raw_upload_error_response(ErrorCode::Conflict, &format!("Missing chunks: {}", missing.join(", ")));
+            }
+        }
+        let sha256 = match verify_upload(&chunks, session.as_ref(), complete_req.chunk_sha256s.as_ref(), complete_req.file_sha256.as_ref()) {
+            Ok(sha256) => sha256,
+            Err(e) => {
+                debug_log!("handle_complete_upload: Verification failed: {}", e);
+                return create_raw_upload_error_response(ErrorCode::ValidationFailed, &e);
+            }
+        };
+
+        store_filename(&complete_req.file_id, &complete_req.filename);
+        end_upload_session(&complete_req.file_id);
+
         let total_size: usize = chunks.iter().map(|c| c.size).sum();
         debug_log!("handle_complete_upload: Total size = {} bytes", total_size);
     
@@ -300,7 +425,8 @@ pub mod directorys_handlers {
             file_id: complete_req.file_id,
             size: total_size,
             chunks: chunks.len() as u32,
-            filename: complete_req.filename
+            filename: complete_req.filename,
+            sha256,
         };
          debug_log!("handle_complete_upload: Returning final response with size={} chunks={}", response.size, response.chunks);
     
@@ -312,6 +438,74 @@ pub mod directorys_handlers {
         create_success_response(&response)
     }
 
+    /// Reports which chunks of an in progress upload are already stored, so a client can resume.
+    pub async fn handle_upload_status<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let raw_query_string = request.get_query().unwrap_or(Some("".to_string()));
+        let query_string = raw_query_string.as_deref().unwrap_or("");
+        let query_map = crate::rest::helpers::parse_query_string(query_string);
+
+        let file_id = match query_map.get("file_id") {
+            Some(fid) => fid,
+            None => return create_raw_upload_error_response(ErrorCode::InvalidRequest, "Missing file_id in query"),
+        };
+        let file_id = decode(file_id).unwrap_or_else(|_| file_id.into()).to_string();
+        debug_log!("handle_upload_status: file_id={}", file_id);
+
+        let file_record = match file_uuid_to_metadata.get(&FileID(file_id.clone())) {
+            Some(record) => record,
+            None => return create_raw_upload_error_response(ErrorCode::NotFound, "File ID not found"),
+        };
+        if !api_key_allows_directory_resource(&requester_api_key, &DirectoryResourceID::File(FileID(file_id.clone()))) {
+            return create_raw_upload_error_response(ErrorCode::PermissionDenied, "API key is not scoped for this file")
+        }
+
+        // Same permissions as uploading the chunks in the first place
+        let permissions = check_directory_permissions(
+            DirectoryResourceID::File(file_record.id.clone()),
+            PermissionGranteeID::User(requester_api_key.user_id.clone()),
+        ).await;
+
+        let has_permission = permissions.contains(&DirectoryPermissionType::Upload) ||
+                            permissions.contains(&DirectoryPermissionType::Edit) ||
+                            permissions.contains(&DirectoryPermissionType::Manage);
+
+        if !has_permission {
+            debug_log!("handle_upload_status: User does not have upload/create/update permission");
+            return create_raw_upload_error_response(ErrorCode::PermissionDenied, "Not authorized to upload to this file")
+        }
+
+        let chunks = get_file_chunk_records(&file_id);
+        let session = get_upload_session(&file_id);
+        let total_chunks = match (&session, &file_record.upload_status) {
+            (Some(session), _) => Some(session.total_chunks),
+            (None, UploadStatus::Completed) => Some(chunks.len() as u32),
+            (None, _) => None,
+        };
+
+        let response = UploadStatusResponse {
+            file_id,
+            upload_status: file_record.upload_status.clone(),
+            total_chunks,
+            missing_chunks: total_chunks.map(|total| missing_chunk_indexes(&chunks, total)).unwrap_or_default(),
+            bytes_received: chunks.iter().map(|c| c.size).sum(),
+            received_chunks: chunks.iter().map(|chunk| UploadedChunkInfo {
+                chunk_index: chunk.chunk_index,
+                size: chunk.size,
+                sha256: chunk_sha256(chunk),
+            }).collect(),
+            started_at: session.as_ref().map(|s| s.started_at),
+            last_chunk_at: session.as_ref().map(|s| s.last_chunk_at),
+            expires_at: session.as_ref().map(session_expires_at),
+        };
+
+        create_success_response(&response)
+    }
+
     /// Returns the metadata about a file: total size, total chunks, etc.
     pub async fn download_file_metadata_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
         debug_log!("download_file_metadata_handler: Handling file metadata request");
@@ -341,7 +535,13 @@ pub mod directorys_handlers {
         debug_log!("download_file_metadata_handler: file_id={}", file_id);
 
         // 3. Check directory permissions for view
-        let resource_id = DirectoryResourceID::File(FileID(file_id.clone().to_string()));
+        let resource_id = DirectoryResourceID::File(resolve_download_file_id(&file_id));
+        if !api_key_allows_directory_resource(&requester_api_key, &resource_id) {
+            return create_response(
+                StatusCode::FORBIDDEN,
+                ErrorResponse::err(403, "API key is not scoped for this file".to_string()).encode()
+            );
+        }
         let permissions = check_directory_permissions(
             resource_id.clone(),
             PermissionGranteeID::User(requester_api_key.user_id.clone()),
@@ -363,6 +563,7 @@ pub mod directorys_handlers {
                 ErrorResponse::err(404, "File not found".to_string()).encode()
             );
         }
+        record_audit_event(request, &requester_api_key, AuditActionEnum::FileDownload, &resource_id, None, None);
 
         // 5. Sort by chunk index and compute total size
         chunks.sort_by_key(|c| c.chunk_index);
@@ -419,7 +620,13 @@ pub mod directorys_handlers {
         let file_id = decode(file_id).unwrap_or_else(|_| file_id.into());
 
         // 3. Check directory permissions for view
-        let resource_id = DirectoryResourceID::File(FileID(file_id.clone().to_string()));
+        let resource_id = DirectoryResourceID::File(resolve_download_file_id(&file_id));
+        if !api_key_allows_directory_resource(&requester_api_key, &resource_id) {
+            return create_response(
+                StatusCode::FORBIDDEN,
+                ErrorResponse::err(403, "API key is not scoped for this file".to_string()).encode()
+            );
+        }
         let permissions = check_directory_permissions(
             resource_id.clone(),
             PermissionGranteeID::User(requester_api_key.user_id.clone()),
@@ -503,39 +710,52 @@ pub mod directorys_handlers {
     
         debug_log!("get_raw_url_proxy_handler: file_id={}", file_id);
 
-        // 2. Check directory permissions for view
+        let raw_query_string = request.get_query().unwrap_or(Some("".to_string()));
+        let query_map = crate::rest::helpers::parse_query_string(raw_query_string.as_deref().unwrap_or(""));
+        // Password protected public links pass the password along, like share_track_hash
+        let password = query_map.get("password").cloned().or_else(|| {
+            request.headers().iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("x-directory-password"))
+                .map(|(_, value)| value.clone())
+        });
+
+        // 2. Check directory permissions for view, either as the authenticated user
+        // (header or auth query param) or through a PUBLIC grant
         let resource_id = DirectoryResourceID::File(FileID(file_id.to_string()));
-        
-        // Try to get authenticated user if present
-        let has_permission = match authenticate_request(request) {
-            Some(api_key) => {
-                // First check if user is owner
-                let is_owner = OWNER_ID.with(|owner_id| api_key.user_id == *owner_id.borrow().get());
-                
-                if is_owner {
-                    true // Owner has full access
-                } else {
-                    // Check authenticated user's permissions
-                    let permissions = check_directory_permissions(
-                        resource_id.clone(),
-                        PermissionGranteeID::User(api_key.user_id.clone()),
-                    ).await;
-                    permissions.contains(&DirectoryPermissionType::View) || 
-                    permissions.contains(&DirectoryPermissionType::Edit) || 
-                    permissions.contains(&DirectoryPermissionType::Manage)
-                }
-            },
-            None => {
-                // Check public view permissions
-                let permissions = check_directory_permissions(
-                    resource_id.clone(),
-                    PermissionGranteeID::Public,
-                ).await;
-                permissions.contains(&DirectoryPermissionType::View)
-            }
+        // Keys scoped away from this file fall back to public access
+        let viewer_key = authenticate_request(request)
+            .filter(|api_key| api_key_allows_directory_resource(api_key, &resource_id));
+        let viewer = viewer_key.as_ref().map(|api_key| api_key.user_id.clone());
+        let grantee_id = match &viewer {
+            Some(user_id) => PermissionGranteeID::User(user_id.clone()),
+            None => PermissionGranteeID::Public,
+        };
+        let can_view = |permissions: &Vec<DirectoryPermissionType>| {
+            permissions.contains(&DirectoryPermissionType::View) ||
+            permissions.contains(&DirectoryPermissionType::Edit) ||
+            permissions.contains(&DirectoryPermissionType::Manage)
         };
 
-        if !has_permission {
+        let permissions = check_directory_permissions_with_password(
+            resource_id.clone(),
+            grantee_id.clone(),
+            password.as_deref(),
+        ).await;
+
+        if !can_view(&permissions) {
+            // Tell link holders when only the password stands in the way
+            let without_password = check_directory_permissions(resource_id.clone(), grantee_id).await;
+            if can_view(&without_password) {
+                debug_log!("get_raw_url_proxy_handler: Password required");
+                let message = match password {
+                    Some(_) => "Incorrect password for this file",
+                    None => "This file is password protected",
+                };
+                return create_response(
+                    StatusCode::UNAUTHORIZED,
+                    ErrorResponse::err(401, message.to_string()).encode()
+                )
+            }
             debug_log!("get_raw_url_proxy_handler: No view permission");
             return create_response(
                 StatusCode::FORBIDDEN,
@@ -553,6 +773,45 @@ pub mod directorys_handlers {
             ),
         };
     
+        // Ranged follow ups (video seeking, the next window of a large file) are not new views
+        let is_initial_view = request.headers().iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("range"))
+            .map_or(true, |(_, value)| value.trim().starts_with("bytes=0-"));
+        // Only counted once the content is actually handed out
+        let record_view = |file: &FileRecord| {
+            if !is_initial_view {
+                return;
+            }
+            fire_file_viewed_webhooks(file, viewer.as_ref(), query_map.get("share_track_hash").cloned());
+            // Anonymous public link views have no actor to audit
+            if let Some(api_key) = &viewer_key {
+                record_audit_event(request, api_key, AuditActionEnum::FileDownload, &resource_id, None, Some(file.name.clone()));
+            }
+        };
+
+        // Canister disks have no bucket to redirect to, the bytes are served from here
+        if file_meta.disk_type == DiskTypeEnum::IcpCanister {
+            // Only the first request after an install or upgrade waits for the randomness
+            if let Err(e) = ensure_streaming_secret().await {
+                return create_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErrorResponse::err(500, e).encode()
+                );
+            }
+            let file_meta = match file_uuid_to_metadata.get(&file_meta.id) {
+                Some(file_meta) => file_meta,
+                None => return create_response(
+                    StatusCode::NOT_FOUND,
+                    ErrorResponse::err(404, "File not found".to_string()).encode()
+                ),
+            };
+            let response = serve_canister_asset(request, &file_meta);
+            if response.status_code().is_success() {
+                record_view(&file_meta);
+            }
+            return response;
+        }
+    
         // 3. Get disk info to access AWS credentials
         let disk = DISKS_BY_ID_HASHTABLE.with(|map| {
             map.borrow().get(&file_meta.disk_id).map(|d| d.clone())
@@ -612,6 +871,7 @@ pub mod directorys_handlers {
         };
     
         debug_log!("get_raw_url_proxy_handler: Redirecting to presigned URL");
+        record_view(&file_meta);
     
         // 6. Return 302 redirect response
         HttpResponse::builder()
@@ -624,6 +884,128 @@ pub mod directorys_handlers {
             .build()
     }
 
+    // Largest body the asset route returns at once, bigger files are streamed or fetched with Range requests
+    const MAX_ASSET_RESPONSE_BYTES: u64 = STREAMING_WINDOW_BYTES;
+
+    // Serves a canister stored file with Range support. Requests without a Range header for
+    // files above MAX_ASSET_RESPONSE_BYTES get a 200 with the first window and a streaming
+    // strategy, the HTTP gateway fetches the rest through the streaming callback.
+    fn serve_canister_asset(request: &HttpRequest, file: &FileRecord) -> HttpResponse<'static> {
+        if file.upload_status != UploadStatus::Completed {
+            return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "File upload not completed".to_string()).encode()
+            );
+        }
+
+        let chunks = get_file_chunk_records(&file.id.0);
+        if chunks.is_empty() {
+            return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "File content not found".to_string()).encode()
+            );
+        }
+        let total_size: u64 = chunks.iter().map(|c| c.size as u64).sum();
+
+        // A new version of the file always gets a new version_id
+        let etag = format!("\"{}\"", file.version_id.0);
+        let header = |name: &str| request.headers().iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone());
+
+        if header("if-none-match").map_or(false, |value| value.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*")) {
+            return asset_response(StatusCode::NOT_MODIFIED, file, &etag, Vec::new(), Vec::new());
+        }
+        if total_size == 0 {
+            return asset_response(StatusCode::OK, file, &etag, Vec::new(), Vec::new());
+        }
+
+        // If-Range with a stale etag means the client's partial copy is outdated, start over
+        let range_header = header("range").filter(|_| header("if-range").map_or(true, |value| value.trim() == etag));
+        let requested = match range_header {
+            Some(value) => match parse_range_header(&value, total_size) {
+                Ok(range) => range,
+                Err(_) => return asset_response(
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    file,
+                    &etag,
+                    vec![("content-range".to_string(), format!("bytes */{}", total_size))],
+                    Vec::new(),
+                ),
+            },
+            None => None,
+        };
+
+        let (start, end, status_code) = match requested {
+            Some((start, end)) => (start, end.min(start + MAX_ASSET_RESPONSE_BYTES - 1), StatusCode::PARTIAL_CONTENT),
+            None if total_size <= MAX_ASSET_RESPONSE_BYTES => (0, total_size - 1, StatusCode::OK),
+            None => {
+                if !start_streaming(file, total_size) {
+                    return create_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        ErrorResponse::err(503, "Streaming is not ready yet, retry shortly".to_string()).encode()
+                    );
+                }
+                (0, MAX_ASSET_RESPONSE_BYTES - 1, StatusCode::OK)
+            },
+        };
+        debug_log!("serve_canister_asset: {} bytes {}-{}/{}", file.id, start, end, total_size);
+
+        // Only the chunks overlapping the window are loaded
+        let body = match read_file_range(&file.id.0, start, end) {
+            Ok(body) => body,
+            Err(index) => {
+                clear_streaming_strategy();
+                return create_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErrorResponse::err(500, format!("Chunk {} is missing", index)).encode()
+                );
+            },
+        };
+
+        let mut headers = Vec::new();
+        if status_code == StatusCode::PARTIAL_CONTENT {
+            headers.push(("content-range".to_string(), format!("bytes {}-{}/{}", start, end, total_size)));
+        }
+        asset_response(status_code, file, &etag, headers, body)
+    }
+
+    fn asset_response(status_code: StatusCode, file: &FileRecord, etag: &str, extra_headers: Vec<(String, String)>, body: Vec<u8>) -> HttpResponse<'static> {
+        let filename = format!("{}.{}", file.name, file.extension).replace('"', "");
+        // Markup and scripts would run with the drive's origin if rendered inline
+        let disposition = match file.extension.to_ascii_lowercase().as_str() {
+            "html" | "htm" | "xhtml" | "svg" | "js" | "mjs" | "xml" => "attachment",
+            _ => "inline",
+        };
+        let mut headers = vec![
+            ("content-type".to_string(), content_type_for_extension(&file.extension).to_string()),
+            ("content-disposition".to_string(), format!("{}; filename=\"{}\"", disposition, filename)),
+            ("accept-ranges".to_string(), "bytes".to_string()),
+            ("etag".to_string(), etag.to_string()),
+            // Access is permission checked per request, so only revalidated private caching
+            ("cache-control".to_string(), "private, no-cache".to_string()),
+            ("x-content-type-options".to_string(), "nosniff".to_string()),
+        ];
+        headers.extend(extra_headers);
+
+        HttpResponse::builder()
+            .with_status_code(status_code)
+            .with_headers(headers)
+            .with_body(body)
+            .build()
+    }
+
+    // Archived file versions are downloaded by their FileVersionID,
+    // permissions come from the file the version belongs to
+    fn resolve_download_file_id(id: &str) -> FileID {
+        if id.starts_with(IDPrefix::FileVersion.as_str()) {
+            if let Some(version) = file_version_to_metadata.get(&FileVersionID(id.to_string())) {
+                return version.id;
+            }
+        }
+        FileID(id.to_string())
+    }
+
     fn json_decode<T>(value: &[u8]) -> T
     where
         T: for<'de> Deserialize<'de>,
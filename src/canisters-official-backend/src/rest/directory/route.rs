@@ -7,6 +7,7 @@ pub const DIRECTORYS_LIST_PATH: &str =      genroute!("/directory/list");
 pub const DIRECTORYS_ACTION_PATH: &str =    genroute!("/directory/action");
 pub const UPLOAD_CHUNK_PATH: &str =         genroute!("/directory/raw_upload/chunk");
 pub const COMPLETE_UPLOAD_PATH: &str =      genroute!("/directory/raw_upload/complete");
+pub const UPLOAD_STATUS_PATH: &str =        genroute!("/directory/raw_upload/status");
 pub const RAW_DOWNLOAD_META_PATH: &str =    genroute!("/directory/raw_download/meta");
 pub const RAW_DOWNLOAD_CHUNK_PATH: &str =   genroute!("/directory/raw_download/chunk");
 pub const RAW_URL_PROXY_PATH: &str =        genroute!("/directory/asset/{file_id_with_extension}"); // for proxying raw urls 302 redirect to temp presigned s3 urls
@@ -36,6 +37,11 @@ pub fn init_routes() {
             COMPLETE_UPLOAD_PATH,
             |req, params| Box::pin(crate::rest::directory::handler::directorys_handlers::handle_complete_upload(req, params)),
         ),
+        (
+            "GET",
+            UPLOAD_STATUS_PATH,
+            |req, params| Box::pin(crate::rest::directory::handler::directorys_handlers::handle_upload_status(req, params)),
+        ),
         (
             "GET",
             RAW_DOWNLOAD_META_PATH,
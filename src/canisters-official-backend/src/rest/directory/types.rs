@@ -4,7 +4,7 @@ use std::{borrow::Cow, collections::HashMap, fmt};
 use candid::CandidType;
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Deserialize, Serialize, Deserializer, Serializer, ser::SerializeStruct};
-use crate::{core::{state::{directory::types::{DriveClippedFilePath, DriveFullFilePath, FileID, FileRecord, FolderID, FolderRecord}, drives::state::state::OWNER_ID, labels::{state::validate_uuid4_string_with_prefix, types::{redact_label, LabelStringValue}}, permissions::types::{DirectoryPermissionID, DirectoryPermissionType, SystemPermissionType}, raw_storage::types::UploadStatus}, types::{ClientSuggestedUUID, IDPrefix}}, rest::{types::{validate_description, validate_external_id, validate_external_payload, validate_id_string, validate_short_string, validate_unclaimed_uuid, validate_url, validate_url_endpoint, ValidationError}, webhooks::types::SortDirection}};
+use crate::{core::{state::{directory::types::{DriveClippedFilePath, DriveFullFilePath, FileID, FileRecord, FileVersionID, FolderID, FolderRecord}, drives::state::state::OWNER_ID, labels::{state::validate_uuid4_string_with_prefix, types::{redact_label, LabelStringValue}}, permissions::types::{DirectoryPermissionID, DirectoryPermissionType, SystemPermissionType}, raw_storage::types::UploadStatus}, types::{ClientSuggestedUUID, IDPrefix}}, rest::{types::{validate_description, validate_external_id, validate_external_payload, validate_id_string, validate_short_string, validate_unclaimed_uuid, validate_url, validate_url_endpoint, ErrorCode, ValidationError}, webhooks::types::SortDirection}};
 use crate::core::{
     state::disks::types::{DiskID, DiskTypeEnum},
     types::{ICPPrincipalString, UserID}
@@ -218,19 +218,26 @@ pub struct UploadChunkRequest {
     pub file_id: String,
     pub chunk_index: u32,
     pub chunk_data: Vec<u8>,
-    pub total_chunks: u32
+    pub total_chunks: u32,
+    #[serde(default)]
+    pub chunk_sha256: Option<String>, // rejected if the received bytes hash differently
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
 pub struct UploadChunkResponse {
     pub chunk_id: String,
-    pub bytes_received: usize
+    pub bytes_received: usize,
+    pub sha256: String,
 }
 
 #[derive(Debug, Clone, Deserialize, CandidType)] 
 pub struct CompleteUploadRequest {
     pub file_id: String,
-    pub filename: String
+    pub filename: String,
+    #[serde(default)]
+    pub file_sha256: Option<String>,
+    #[serde(default)]
+    pub chunk_sha256s: Option<Vec<String>>, // one per chunk, in chunk index order
 }
 impl CompleteUploadRequest {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -239,17 +246,57 @@ impl CompleteUploadRequest {
         
         // Validate filename
         validate_short_string(&self.filename, "filename")?;
+
+        if let Some(file_sha256) = &self.file_sha256 {
+            validate_sha256_hex(file_sha256, "file_sha256")?;
+        }
+        if let Some(chunk_sha256s) = &self.chunk_sha256s {
+            for chunk_sha256 in chunk_sha256s {
+                validate_sha256_hex(chunk_sha256, "chunk_sha256s")?;
+            }
+        }
         
         Ok(())
     }
 }
 
+pub fn validate_sha256_hex(value: &str, field_name: &str) -> Result<(), ValidationError> {
+    if value.len() != 64 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ValidationError {
+            field: field_name.to_string(),
+            message: "Must be a hex encoded SHA-256 digest (64 characters)".to_string(),
+        });
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
 pub struct CompleteUploadResponse {
     pub file_id: String,
     pub size: usize,
     pub chunks: u32,
-    pub filename: String
+    pub filename: String,
+    pub sha256: Option<String>, // only hashed when the client asked for file_sha256 verification
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct UploadedChunkInfo {
+    pub chunk_index: u32,
+    pub size: usize,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct UploadStatusResponse {
+    pub file_id: String,
+    pub upload_status: UploadStatus,
+    pub total_chunks: Option<u32>,        // None until the first chunk announces it
+    pub received_chunks: Vec<UploadedChunkInfo>,
+    pub missing_chunks: Vec<u32>,
+    pub bytes_received: usize,
+    pub started_at: Option<u64>,
+    pub last_chunk_at: Option<u64>,
+    pub expires_at: Option<u64>,          // when the upload is discarded if no further chunk arrives
 }
 
 
@@ -426,6 +473,33 @@ impl DirectoryAction {
                     }),
                 }
             },
+            DirectoryActionEnum::ListFileVersions => {
+                match &self.payload {
+                    DirectoryActionPayload::ListFileVersions(payload) => payload.validate_body()?,
+                    _ => return Err(ValidationError {
+                        field: "payload".to_string(),
+                        message: "Invalid payload type for LIST_FILE_VERSIONS action".to_string(),
+                    }),
+                }
+            },
+            DirectoryActionEnum::GetFileVersion => {
+                match &self.payload {
+                    DirectoryActionPayload::GetFileVersion(payload) => payload.validate_body()?,
+                    _ => return Err(ValidationError {
+                        field: "payload".to_string(),
+                        message: "Invalid payload type for GET_FILE_VERSION action".to_string(),
+                    }),
+                }
+            },
+            DirectoryActionEnum::RestoreFileVersion => {
+                match &self.payload {
+                    DirectoryActionPayload::RestoreFileVersion(payload) => payload.validate_body()?,
+                    _ => return Err(ValidationError {
+                        field: "payload".to_string(),
+                        message: "Invalid payload type for RESTORE_FILE_VERSION action".to_string(),
+                    }),
+                }
+            },
         }
         
         Ok(())
@@ -457,6 +531,8 @@ pub struct DirectoryActionResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectoryActionRequestBody {
     pub actions: Vec<DirectoryAction>,
+    #[serde(default)]
+    pub atomic: bool, // undo the whole batch if any action fails
 }
 impl DirectoryActionRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -544,6 +620,18 @@ impl<'de> Deserialize<'de> for DirectoryAction {
                 DirectoryActionPayload::RestoreTrash(serde_json::from_value(raw.payload)
                     .map_err(de::Error::custom)?)
             }
+            DirectoryActionEnum::ListFileVersions => {
+                DirectoryActionPayload::ListFileVersions(serde_json::from_value(raw.payload)
+                    .map_err(de::Error::custom)?)
+            }
+            DirectoryActionEnum::GetFileVersion => {
+                DirectoryActionPayload::GetFileVersion(serde_json::from_value(raw.payload)
+                    .map_err(de::Error::custom)?)
+            }
+            DirectoryActionEnum::RestoreFileVersion => {
+                DirectoryActionPayload::RestoreFileVersion(serde_json::from_value(raw.payload)
+                    .map_err(de::Error::custom)?)
+            }
         };
 
         Ok(DirectoryAction {
@@ -576,6 +664,9 @@ impl Serialize for DirectoryAction {
             DirectoryActionPayload::MoveFile(p) => state.serialize_field("payload", p)?,
             DirectoryActionPayload::MoveFolder(p) => state.serialize_field("payload", p)?,
             DirectoryActionPayload::RestoreTrash(p) => state.serialize_field("payload", p)?,
+            DirectoryActionPayload::ListFileVersions(p) => state.serialize_field("payload", p)?,
+            DirectoryActionPayload::GetFileVersion(p) => state.serialize_field("payload", p)?,
+            DirectoryActionPayload::RestoreFileVersion(p) => state.serialize_field("payload", p)?,
         }
         state.end()
     }
@@ -584,6 +675,7 @@ impl Serialize for DirectoryAction {
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
 pub struct DirectoryActionError {
     pub code: i32,
+    pub error_code: ErrorCode,
     pub message: String,
 }
 
@@ -603,6 +695,9 @@ pub enum DirectoryActionEnum {
     MoveFile,
     MoveFolder,
     RestoreTrash,
+    ListFileVersions,
+    GetFileVersion,
+    RestoreFileVersion,
 }
 
 
@@ -685,6 +780,9 @@ pub enum DirectoryActionPayload {
     MoveFile(MoveFilePayload),
     MoveFolder(MoveFolderPayload),
     RestoreTrash(RestoreTrashPayload),
+    ListFileVersions(ListFileVersionsPayload),
+    GetFileVersion(GetFileVersionPayload),
+    RestoreFileVersion(RestoreFileVersionPayload),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
@@ -1224,6 +1322,43 @@ impl RestoreTrashPayload {
 }
 
 
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+#[serde(deny_unknown_fields)]
+pub struct ListFileVersionsPayload {
+    pub id: FileID,
+}
+impl ListFileVersionsPayload {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_id_string(&self.id.0, "id")?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+#[serde(deny_unknown_fields)]
+pub struct GetFileVersionPayload {
+    pub version_id: FileVersionID,
+}
+impl GetFileVersionPayload {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_id_string(&self.version_id.0, "version_id")?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+#[serde(deny_unknown_fields)]
+pub struct RestoreFileVersionPayload {
+    pub version_id: FileVersionID,
+}
+impl RestoreFileVersionPayload {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_id_string(&self.version_id.0, "version_id")?;
+        Ok(())
+    }
+}
+
+
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
@@ -1255,7 +1390,10 @@ pub enum DirectoryActionResult {
     CopyFolder(FolderRecordFE),
     MoveFile(FileRecordFE),
     MoveFolder(FolderRecordFE),
-    RestoreTrash(RestoreTrashResponse)
+    RestoreTrash(RestoreTrashResponse),
+    ListFileVersions(ListFileVersionsResponse),
+    GetFileVersion(GetFileVersionResponse),
+    RestoreFileVersion(RestoreFileVersionResponse),
 }
 
 
@@ -1304,6 +1442,26 @@ pub struct RestoreTrashResponse {
     pub restored_folders: Vec<FolderID>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct ListFileVersionsResponse {
+    pub file_id: FileID,
+    pub versions: Vec<FileRecordFE>, // newest first, the first entry is the current version
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct GetFileVersionResponse {
+    pub version: FileRecordFE,
+    pub is_current: bool,
+    pub content_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct RestoreFileVersionResponse {
+    pub file: FileRecordFE,
+    pub restored_from: FileVersionID,
+    pub pruned_versions: Vec<FileVersionID>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, CandidType)]
 pub struct DirectoryResourcePermissionFE {
     pub permission_id: String,
@@ -3,9 +3,11 @@
 
 pub mod disks_handlers {
     use crate::{
-        core::{api::{internals::drive_internals::validate_auth_json, permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{disks::{state::state::{ensure_disk_root_and_trash_folder, DISKS_BY_ID_HASHTABLE, DISKS_BY_TIME_LIST, DISKS_BY_TIME_MEMORY_ID}, types::{AwsBucketAuth, Disk, DiskID, DiskTypeEnum}}, drives::{state::state::{update_external_id_mapping, DRIVE_ID, OWNER_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, types::IDPrefix}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, disks::types::{ CreateDiskRequestBody, CreateDiskResponse, DeleteDiskRequest, DeleteDiskResponse, DeletedDiskData, ErrorResponse, GetDiskResponse, ListDisksRequestBody, ListDisksResponse, ListDisksResponseData, UpdateDiskRequestBody, UpdateDiskResponse}, webhooks::types::SortDirection}, MEMORY_MANAGER
+        core::{api::{internals::drive_internals::validate_auth_json, permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{disks::{state::state::{ensure_disk_root_and_trash_folder, DISKS_BY_ID_HASHTABLE, DISKS_BY_TIME_LIST, DISKS_BY_TIME_MEMORY_ID}, types::{AwsBucketAuth, Disk, DiskID, DiskTypeEnum, FileVersionRetention}}, drives::{state::state::{update_external_id_mapping, DRIVE_ID, OWNER_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, search::{state::state::index_search_resource, types::SearchResultResourceID}}, types::IDPrefix}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, disks::types::{ CreateDiskRequestBody, CreateDiskResponse, DeleteDiskRequest, DeleteDiskResponse, DeletedDiskData, ErrorResponse, GetDiskResponse, ListDisksRequestBody, ListDisksResponse, ListDisksResponseData, UpdateDiskRequestBody, UpdateDiskResponse}, webhooks::types::SortDirection}, MEMORY_MANAGER
         
     };
+    use crate::core::state::audit::{state::state::audit_summary_disk, types::AuditActionEnum};
+    use crate::rest::helpers::record_audit_event;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use ic_stable_structures::{StableVec};
     use matchit::Params;
@@ -107,7 +109,7 @@ pub mod disks_handlers {
          if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -292,7 +294,7 @@ pub mod disks_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -347,6 +349,8 @@ pub mod disks_handlers {
             external_id: new_external_id.clone(),
             external_payload: Some(ExternalPayload(create_req.external_payload.unwrap_or("".to_string()))),
             endpoint: create_req.endpoint,
+            version_retention: create_req.version_retention,
+            trash_retention_days: create_req.trash_retention_days,
         };
         update_external_id_mapping(
             None,
@@ -358,6 +362,7 @@ pub mod disks_handlers {
         DISKS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(disk_id.clone(), disk.clone());
         });
+        index_search_resource(SearchResultResourceID::Disk(disk_id.clone()));
 
         DISKS_BY_TIME_LIST.with(|store| {
             store.borrow_mut().push(&disk_id.clone());
@@ -365,6 +370,8 @@ pub mod disks_handlers {
         mark_claimed_uuid(&disk_id.clone().to_string());
 
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DiskCreate, &disk_id, None, Some(audit_summary_disk(&disk)));
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Create Disk {}", 
@@ -396,7 +403,7 @@ pub mod disks_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -427,6 +434,8 @@ pub mod disks_handlers {
                 return create_auth_error_response();
             }
         }
+        let audit_before = audit_summary_disk(&disk);
+        let old_auth_json = disk.auth_json.clone();
         let prestate = snapshot_prestate();
 
         // Update fields
@@ -465,10 +474,28 @@ pub mod disks_handlers {
         if let Some(endpoint) = update_req.endpoint {
             disk.endpoint = Some(endpoint);
         }
+        if let Some(version_retention) = update_req.version_retention {
+            // Sending both limits as null turns retention off again
+            disk.version_retention = match version_retention {
+                FileVersionRetention { keep_versions: None, keep_days: None } => None,
+                retention => Some(retention),
+            };
+        }
+        if let Some(trash_retention_days) = update_req.trash_retention_days {
+            // Zero turns automatic trash purging off again
+            disk.trash_retention_days = match trash_retention_days {
+                0 => None,
+                days => Some(days),
+            };
+        }
 
         DISKS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(disk_id.clone(), disk.clone());
         });
+        index_search_resource(SearchResultResourceID::Disk(disk_id.clone()));
+
+        let credentials_note = if disk.auth_json != old_auth_json { " (credentials changed)" } else { "" };
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DiskUpdate, &disk_id, Some(audit_before), Some(format!("{}{}", audit_summary_disk(&disk), credentials_note)));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -508,7 +535,7 @@ pub mod disks_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -540,6 +567,7 @@ pub mod disks_handlers {
         DISKS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&disk_id);
         });
+        index_search_resource(SearchResultResourceID::Disk(disk_id.clone()));
 
         // For removing items from DISKS_BY_TIME_LIST
         DISKS_BY_TIME_LIST.with(|store| {
@@ -562,6 +590,8 @@ pub mod disks_handlers {
             *store.borrow_mut() = new_vec;
         });
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DiskDelete, &disk_id, disk.as_ref().map(audit_summary_disk), None);
+
         // Remove from external ID mappings
         if let Some(disk) = disk {
             update_external_id_mapping(
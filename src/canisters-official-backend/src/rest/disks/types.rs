@@ -3,12 +3,14 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::{api::permissions::system::check_system_permissions, state::{disks::types::{Disk, DiskID, DiskTypeEnum}, drives::state::state::OWNER_ID, labels::{state::validate_uuid4_string_with_prefix, types::redact_label}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, types::{ClientSuggestedUUID, IDPrefix, UserID}},
+    core::{api::permissions::system::check_system_permissions, state::{disks::types::{Disk, DiskID, DiskTypeEnum, FileVersionRetention}, drives::state::state::OWNER_ID, labels::{state::validate_uuid4_string_with_prefix, types::redact_label}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, types::{ClientSuggestedUUID, IDPrefix, UserID}},
     rest::{types::{validate_external_id, validate_external_payload, validate_id_string, validate_short_string, validate_unclaimed_uuid, validate_url, ApiResponse, ValidationError}, webhooks::types::SortDirection},
 };
 
 
 
+pub const MAX_TRASH_RETENTION_DAYS: u32 = 3650;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiskFE {
     #[serde(flatten)] 
@@ -115,6 +117,8 @@ pub struct CreateDiskRequestBody {
     pub external_id: Option<String>,
     pub external_payload: Option<String>,
     pub endpoint: Option<String>,
+    pub version_retention: Option<FileVersionRetention>,
+    pub trash_retention_days: Option<u32>,
 }
 impl CreateDiskRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -171,6 +175,19 @@ impl CreateDiskRequestBody {
             validate_url(endpoint, "endpoint")?;
         }
 
+        if let Some(version_retention) = &self.version_retention {
+            validate_version_retention(version_retention)?;
+        }
+
+        if let Some(trash_retention_days) = self.trash_retention_days {
+            if trash_retention_days < 1 || trash_retention_days > MAX_TRASH_RETENTION_DAYS {
+                return Err(ValidationError {
+                    field: "trash_retention_days".to_string(),
+                    message: "Trash retention must be between 1 and 3,650 days".to_string(),
+                });
+            }
+        }
+
         Ok(())
     }
 }
@@ -192,6 +209,10 @@ pub struct UpdateDiskRequestBody {
     pub external_payload: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_retention: Option<FileVersionRetention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trash_retention_days: Option<u32>, // 0 turns automatic purging off
 }
 impl UpdateDiskRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
@@ -243,10 +264,43 @@ impl UpdateDiskRequestBody {
             validate_external_payload(external_payload)?;
         }
 
+        if let Some(version_retention) = &self.version_retention {
+            validate_version_retention(version_retention)?;
+        }
+
+        if let Some(trash_retention_days) = self.trash_retention_days {
+            if trash_retention_days > MAX_TRASH_RETENTION_DAYS {
+                return Err(ValidationError {
+                    field: "trash_retention_days".to_string(),
+                    message: "Trash retention must be 3,650 days or less".to_string(),
+                });
+            }
+        }
+
         Ok(())
     }
 }
 
+fn validate_version_retention(retention: &FileVersionRetention) -> Result<(), ValidationError> {
+    if let Some(keep_versions) = retention.keep_versions {
+        if keep_versions < 1 || keep_versions > 1000 {
+            return Err(ValidationError {
+                field: "version_retention.keep_versions".to_string(),
+                message: "Keep versions must be between 1 and 1,000".to_string(),
+            });
+        }
+    }
+    if let Some(keep_days) = retention.keep_days {
+        if keep_days < 1 || keep_days > 3650 {
+            return Err(ValidationError {
+                field: "version_retention.keep_days".to_string(),
+                message: "Keep days must be between 1 and 3,650".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct DeleteDiskRequest {
     pub id: DiskID,
@@ -20,7 +20,8 @@ pub mod drives_handlers {
                 }, permissions::types::{
                     PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum,
                     SystemResourceID, SystemTableEnum
-                }
+                },
+                search::{state::state::index_search_resource, types::SearchResultResourceID},
             },
             types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}
         }, debug_log, rest::{
@@ -129,8 +130,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -321,8 +321,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -369,6 +368,7 @@ pub mod drives_handlers {
         DRIVES_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(drive_id.clone(), drive.clone());
         });
+        index_search_resource(SearchResultResourceID::Drive(drive_id.clone()));
 
         DRIVES_BY_TIME_LIST.with(|store| {
             store.borrow_mut().push(&drive_id.clone());
@@ -412,8 +412,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -478,6 +477,7 @@ pub mod drives_handlers {
         DRIVES_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(drive_id.clone(), drive.clone());
         });
+        index_search_resource(SearchResultResourceID::Drive(drive_id.clone()));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -515,8 +515,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -552,6 +551,7 @@ pub mod drives_handlers {
         DRIVES_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&drive_id);
         });
+        index_search_resource(SearchResultResourceID::Drive(drive_id.clone()));
 
         // Remove from time list similar to DISKS_BY_TIME_LIST
         DRIVES_BY_TIME_LIST.with(|store| {
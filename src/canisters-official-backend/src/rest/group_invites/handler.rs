@@ -9,6 +9,7 @@ pub mod group_invites_handlers {
     use crate::core::state::group_invites::{
         types::GroupInvite,
     };
+    use crate::rest::types::ErrorCode;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
     use serde::Deserialize;
@@ -76,10 +77,7 @@ pub mod group_invites_handlers {
         if let Err(validation_err) = query.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_err.field, validation_err.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_err).encode()
             );
         }
     
@@ -263,10 +261,7 @@ pub mod group_invites_handlers {
         if let Err(validation_err) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_err.field, validation_err.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_err).encode()
             );
         }
 
@@ -448,10 +443,7 @@ pub mod group_invites_handlers {
         if let Err(validation_err) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_err.field, validation_err.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_err).encode()
             );
         }
 
@@ -629,10 +621,7 @@ pub mod group_invites_handlers {
         if let Err(validation_err) = delete_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_err.field, validation_err.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_err).encode()
             );
         }
     
@@ -872,8 +861,8 @@ pub mod group_invites_handlers {
             // Handle original placeholder invitee case
             if invite.from_placeholder_invitee.is_some() {
                 return create_response(
-                    StatusCode::BAD_REQUEST,
-                    ErrorResponse::err(400, "Invite has already been redeemed".to_string()).encode()
+                    StatusCode::CONFLICT,
+                    ErrorResponse::error(ErrorCode::Conflict, "Invite has already been redeemed".to_string()).encode()
                 );
             }
     
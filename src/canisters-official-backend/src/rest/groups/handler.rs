@@ -3,7 +3,7 @@
 
 pub mod groups_handlers {
     use crate::{
-        core::{api::{internals::drive_internals::is_user_in_group, permissions::{self, system::check_system_permissions}, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{drives::{state::state::{update_external_id_mapping, DRIVE_ID, OWNER_ID, URL_ENDPOINT}, types::{DriveID, DriveRESTUrlEndpoint, ExternalID, ExternalPayload}}, group_invites::{state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, types::GroupInvite}, groups::{state::state::{is_user_on_group, GROUPS_BY_ID_HASHTABLE, GROUPS_BY_TIME_LIST, GROUPS_BY_TIME_MEMORY_ID}, types::{Group, GroupID}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, types::{IDPrefix, PublicKeyICP}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, groups::types::{CreateGroupRequestBody, CreateGroupResponse, DeleteGroupRequestBody, DeleteGroupResponse, DeletedGroupData, ErrorResponse, GetGroupResponse, ListGroupsRequestBody, ListGroupsResponse, ListGroupsResponseData, UpdateGroupRequestBody, UpdateGroupResponse, ValidateGroupRequestBody, ValidateGroupResponse, ValidateGroupResponseData}, types::ApiResponse, webhooks::types::SortDirection}, MEMORY_MANAGER
+        core::{api::{internals::drive_internals::is_user_in_group, permissions::{self, system::check_system_permissions}, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{drives::{state::state::{update_external_id_mapping, DRIVE_ID, OWNER_ID, URL_ENDPOINT}, types::{DriveID, DriveRESTUrlEndpoint, ExternalID, ExternalPayload}}, group_invites::{state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, types::GroupInvite}, groups::{state::state::{is_user_on_group, GROUPS_BY_ID_HASHTABLE, GROUPS_BY_TIME_LIST, GROUPS_BY_TIME_MEMORY_ID}, types::{Group, GroupID}}, search::{state::state::index_search_resource, types::SearchResultResourceID}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, types::{IDPrefix, PublicKeyICP}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, groups::types::{CreateGroupRequestBody, CreateGroupResponse, DeleteGroupRequestBody, DeleteGroupResponse, DeletedGroupData, ErrorResponse, GetGroupResponse, ListGroupsRequestBody, ListGroupsResponse, ListGroupsResponseData, UpdateGroupRequestBody, UpdateGroupResponse, ValidateGroupRequestBody, ValidateGroupResponse, ValidateGroupResponseData}, types::ApiResponse, webhooks::types::SortDirection}, MEMORY_MANAGER
         
     };
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
@@ -76,10 +76,7 @@ pub mod groups_handlers {
         if let Err(validation_err) = query.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_err.field, validation_err.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_err).encode()
             );
         }
     
@@ -251,10 +248,7 @@ pub mod groups_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -309,6 +303,7 @@ pub mod groups_handlers {
         GROUPS_BY_TIME_LIST.with(|list| {
             list.borrow_mut().push(&group_id.clone());
         });
+        index_search_resource(SearchResultResourceID::Group(group_id.clone()));
 
         mark_claimed_uuid(&group_id.clone().to_string());
 
@@ -343,10 +338,7 @@ pub mod groups_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -414,6 +406,7 @@ pub mod groups_handlers {
         GROUPS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(group.id.clone(), group.clone());
         });
+        index_search_resource(SearchResultResourceID::Group(group.id.clone()));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -449,10 +442,7 @@ pub mod groups_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400, 
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -524,6 +514,7 @@ pub mod groups_handlers {
         GROUPS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&group_id);
         });
+        index_search_resource(SearchResultResourceID::Group(group_id.clone()));
     
         // Remove group from GROUPS_BY_TIME_LIST
         GROUPS_BY_TIME_LIST.with(|store| {
@@ -579,10 +570,7 @@ pub mod groups_handlers {
         if let Err(validation_error) = validate_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(
-                    400,
-                    format!("Validation error: {} - {}", validation_error.field, validation_error.message)
-                ).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
         
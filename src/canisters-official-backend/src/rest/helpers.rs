@@ -4,17 +4,16 @@ use serde_json::json;
 use std::borrow::Cow;
 use url::{form_urlencoded, Url};
 
-use crate::core::{state::contacts::state::state::CONTACTS_BY_ID_HASHTABLE, types::UserID};
+use crate::{core::{state::{api_keys::types::ApiKey, audit::{state::state::record_audit_entry, types::AuditActionEnum}, contacts::state::state::CONTACTS_BY_ID_HASHTABLE, drives::state::state::ALLOWED_DOMAINS}, types::UserID}, rest::{auth::drive_relative_path, types::ErrorCode}};
 
 pub fn create_response(status_code: StatusCode, body: String) -> HttpResponse<'static> {
     let headers = vec![
         ("Content-Type".to_string(), "application/json".to_string()),
-        ("Access-Control-Allow-Origin".to_string(), "*".to_string()),
         ("Access-Control-Max-Age".to_string(), "86400".to_string()),
         // ("Access-Control-Allow-Methods".to_string(), "GET, POST, PUT, DELETE, OPTIONS".to_string()),
         // ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key".to_string()),
     ("Access-Control-Allow-Methods".to_string(), "GET, POST, PUT, DELETE, OPTIONS".to_string()),
-    ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key, Authorization".to_string()),
+    ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key, Authorization, Idempotency-Key, X-Directory-Password".to_string()),
     ];
     
     HttpResponse::builder()
@@ -29,20 +28,20 @@ pub fn not_found_response() -> HttpResponse<'static> {
     let error_payload = json!({
         "err": {
             "code": 404,
+            "error_code": ErrorCode::NotFound,
             "message": "REST API route not found"
         }
     });
 
-    // The headers for the "not found" response. We set the Access-Control-Allow-Origin
-    // to "*" to allow requests from any origin. We also set the
+    // The headers for the "not found" response. Access-Control-Allow-Origin is added
+    // by the router from the drive's allowed domains. We also set the
     // Access-Control-Allow-Methods and Access-Control-Allow-Headers to allow
     // requests with the given methods and headers.
     let headers = vec![
         ("Content-Type".to_string(), "application/json".to_string()),
-        ("Access-Control-Allow-Origin".to_string(), "*".to_string()),
         ("Access-Control-Max-Age".to_string(), "86400".to_string()),
         ("Access-Control-Allow-Methods".to_string(), "GET, POST, PUT, DELETE, OPTIONS".to_string()),
-        ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key, Authorization".to_string()),
+        ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key, Authorization, Idempotency-Key, X-Directory-Password".to_string()),
     ];
 
     HttpResponse::builder()
@@ -53,6 +52,140 @@ pub fn not_found_response() -> HttpResponse<'static> {
 }
 
 
+/// One entry of the drive's allowed domains, e.g. `https://app.example.com`,
+/// `localhost:5173` or `*.example.com`. Scheme and port are only checked when given.
+struct AllowedOrigin {
+    scheme: Option<String>,
+    host: String,
+    port: Option<u16>,
+}
+
+impl AllowedOrigin {
+    fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim().trim_end_matches('/').to_ascii_lowercase();
+        let (scheme, rest) = match entry.split_once("://") {
+            Some((scheme, rest)) if scheme == "http" || scheme == "https" => (Some(scheme.to_string()), rest.to_string()),
+            Some(_) => return None,
+            None => (None, entry),
+        };
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) => (host.to_string(), Some(port.parse::<u16>().ok()?)),
+            None => (rest, None),
+        };
+        let bare_host = host.strip_prefix("*.").unwrap_or(&host);
+        if bare_host.is_empty()
+            || bare_host.starts_with('.')
+            || bare_host.ends_with('.')
+            || !bare_host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        {
+            return None;
+        }
+        Some(Self { scheme, host, port })
+    }
+
+    fn matches(&self, scheme: &str, host: &str, port: Option<u16>) -> bool {
+        if self.scheme.as_deref().map_or(false, |s| s != scheme) {
+            return false;
+        }
+        if self.port.is_some() && self.port != port {
+            return false;
+        }
+        match self.host.strip_prefix("*.") {
+            // Wildcards cover subdomains only, list the apex domain separately
+            Some(suffix) => host.len() > suffix.len() && host.ends_with(&format!(".{}", suffix)),
+            None => self.host == host,
+        }
+    }
+}
+
+/// Splits an allowed domains string on commas or whitespace and normalizes each entry.
+/// Returns the offending entry when one isn't a valid origin pattern.
+pub fn parse_allowed_domains(raw: &str) -> Result<Vec<String>, String> {
+    raw.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            if entry == "*" {
+                return Ok(entry.to_string());
+            }
+            match AllowedOrigin::parse(entry) {
+                Some(_) => Ok(entry.trim_end_matches('/').to_ascii_lowercase()),
+                None => Err(entry.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Value of Access-Control-Allow-Origin for a request carrying `origin`.
+/// With no allowed domains configured (or a `*` entry) any origin is allowed,
+/// otherwise the origin is echoed back only when it matches an entry.
+pub fn cors_allow_origin(origin: Option<&str>) -> Option<String> {
+    let allowed = ALLOWED_DOMAINS.with(|store| store.borrow().get().clone());
+    let entries: Vec<&str> = allowed
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    if entries.is_empty() || entries.contains(&"*") {
+        return Some("*".to_string());
+    }
+
+    let origin = origin?;
+    let url = Url::parse(origin).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let allowed = entries
+        .iter()
+        .filter_map(|entry| AllowedOrigin::parse(entry))
+        .any(|entry| entry.matches(url.scheme(), &host, url.port_or_known_default()));
+
+    if allowed {
+        Some(origin.to_string())
+    } else {
+        None
+    }
+}
+
+/// Sets Access-Control-Allow-Origin on an outgoing response from the request's Origin header.
+/// Disallowed origins get no header at all so browsers block the response.
+pub fn apply_cors_headers(req: &HttpRequest, mut response: HttpResponse<'static>) -> HttpResponse<'static> {
+    let origin = req
+        .headers()
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("origin"))
+        .map(|(_, value)| value.as_str());
+    let allow_origin = cors_allow_origin(origin);
+
+    let headers = response.headers_mut();
+    headers.retain(|(name, _)| {
+        !name.eq_ignore_ascii_case("access-control-allow-origin")
+            && !name.eq_ignore_ascii_case("access-control-expose-headers")
+    });
+    // Lets browser clients read the rate limit backoff and idempotent replay markers
+    headers.push(("Access-Control-Expose-Headers".to_string(), "Retry-After, Idempotent-Replayed".to_string()));
+    match allow_origin {
+        Some(value) if value == "*" => {
+            headers.push(("Access-Control-Allow-Origin".to_string(), value));
+        },
+        Some(value) => {
+            headers.push(("Access-Control-Allow-Origin".to_string(), value));
+            headers.push(("Vary".to_string(), "Origin".to_string()));
+        },
+        None => {},
+    }
+    response
+}
+
+/// Records a security relevant action in the audit log, taking the route from the request
+pub fn record_audit_event(
+    request: &HttpRequest,
+    actor: &ApiKey,
+    action: AuditActionEnum,
+    resource_id: impl ToString,
+    before: Option<String>,
+    after: Option<String>,
+) {
+    let route = format!("{} {}", request.method().as_str(), drive_relative_path(request));
+    record_audit_entry(actor, action, route, resource_id.to_string(), before, after);
+}
+
 /// Use `url::form_urlencoded` to parse query string into key-value pairs.
 pub fn parse_query_string(query: &str) -> std::collections::HashMap<String, String> {
     form_urlencoded::parse(query.as_bytes()).into_owned().collect()
@@ -70,4 +203,74 @@ pub fn update_last_online_at(userID: &UserID) {
             map_mut.insert(userID.clone(), contact);
         }
     });
-}
\ No newline at end of file
+}
+/// Content-Type for a file served straight from the canister, based on its extension
+pub fn content_type_for_extension(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "flac" => "audio/flac",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "txt" | "md" | "csv" => "text/plain; charset=utf-8",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Parses a single `Range: bytes=...` header against a resource of `total_size` bytes.
+/// Returns the inclusive byte span, Ok(None) when the header isn't a byte range we understand
+/// (the whole resource should be served), or Err when the range can't be satisfied.
+/// Only the first range of a multi range request is honoured.
+pub fn parse_range_header(value: &str, total_size: u64) -> Result<Option<(u64, u64)>, ()> {
+    let spec = match value.trim().strip_prefix("bytes=") {
+        Some(spec) => spec,
+        None => return Ok(None),
+    };
+    let first = spec.split(',').next().unwrap_or("").trim();
+    let (start, end) = match first.split_once('-') {
+        Some(parts) => parts,
+        None => return Ok(None),
+    };
+
+    let span = match (start.trim(), end.trim()) {
+        ("", "") => return Ok(None),
+        // bytes=-500 is the last 500 bytes
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().map_err(|_| ())?;
+            if suffix == 0 || total_size == 0 {
+                return Err(());
+            }
+            (total_size.saturating_sub(suffix), total_size - 1)
+        },
+        (start, "") => (start.parse().map_err(|_| ())?, total_size.saturating_sub(1)),
+        (start, end) => {
+            let start: u64 = start.parse().map_err(|_| ())?;
+            let end: u64 = end.parse().map_err(|_| ())?;
+            if end < start {
+                return Err(());
+            }
+            (start, end.min(total_size.saturating_sub(1)))
+        },
+    };
+
+    if span.0 >= total_size {
+        return Err(());
+    }
+    Ok(Some(span))
+}
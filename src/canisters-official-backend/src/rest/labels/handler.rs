@@ -14,7 +14,7 @@ pub mod labels_handlers {
                         add_label_to_resource, parse_label_resource_id, remove_label_from_resource, update_label_string_value, validate_color, validate_label_value, LABELS_BY_ID_HASHTABLE, LABELS_BY_TIME_LIST, LABELS_BY_TIME_MEMORY_ID, LABELS_BY_VALUE_HASHTABLE
                     }, 
                     types::{HexColorString, Label, LabelID, LabelResourceID, LabelStringValue}
-                }, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, webhooks::types::WebhookEventLabel
+                }, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, search::{state::state::index_search_resource, types::SearchResultResourceID}, webhooks::types::WebhookEventLabel
             }, 
             types::IDPrefix
         }, 
@@ -27,6 +27,7 @@ pub mod labels_handlers {
             webhooks::types::{LabelWebhookData, SortDirection}
         }, MEMORY_MANAGER
     };
+    use crate::rest::types::ErrorCode;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
     use serde::Deserialize;
@@ -136,7 +137,7 @@ pub mod labels_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("{}: {}", validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -325,7 +326,7 @@ pub mod labels_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("{}: {}", validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -357,8 +358,8 @@ pub mod labels_handlers {
         
         if label_exists {
             return create_response(
-                StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Label '{}' already exists", create_req.value)).encode()
+                StatusCode::CONFLICT,
+                ErrorResponse::error(ErrorCode::NameConflict, format!("Label '{}' already exists", create_req.value)).encode()
             );
         }
         
@@ -405,6 +406,7 @@ pub mod labels_handlers {
         LABELS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(label_id.clone(), label.clone());
         });
+        index_search_resource(SearchResultResourceID::Label(label_id.clone()));
 
         // Store the label value mapping
         LABELS_BY_VALUE_HASHTABLE.with(|store| {
@@ -448,7 +450,7 @@ pub mod labels_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("{}: {}", validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -551,6 +553,7 @@ pub mod labels_handlers {
         LABELS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(label_id.clone(), label.clone());
         });
+        index_search_resource(SearchResultResourceID::Label(label_id.clone()));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -589,7 +592,7 @@ pub mod labels_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("{}: {}", validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -642,6 +645,7 @@ pub mod labels_handlers {
         LABELS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&label_id);
         });
+        index_search_resource(SearchResultResourceID::Label(label_id.clone()));
 
         LABELS_BY_TIME_LIST.with(|store| {
             let mut new_vec = StableVec::init(
@@ -704,7 +708,7 @@ pub mod labels_handlers {
         if let Err(validation_error) = label_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("{}: {}", validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
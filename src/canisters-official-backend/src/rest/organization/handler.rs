@@ -3,14 +3,27 @@
 
 pub mod drives_handlers {
     use crate::{
-        core::{api::{helpers::is_local_environment, permissions::{directory::{can_user_access_directory_permission, check_directory_permissions}, system::{can_user_access_system_permission, check_system_permissions}}, replay::diff::{apply_state_diff, convert_state_to_serializable, safely_apply_diffs, snapshot_entire_state, snapshot_poststate, snapshot_prestate}, uuid::generate_uuidv4, webhooks::organization::{fire_org_inbox_new_notif_webhook, fire_superswap_user_webhook, get_org_inbox_webhooks, get_superswap_user_webhooks}}, state::{api_keys::state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE, USERS_APIKEYS_HASHTABLE}, contacts::state::state::{CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE, CONTACTS_BY_ID_HASHTABLE, CONTACTS_BY_TIME_LIST}, directory::state::state::{file_uuid_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid, full_folder_path_to_uuid}, disks::state::state::{DISKS_BY_ID_HASHTABLE, DISKS_BY_TIME_LIST}, drives::{state::state::{superswap_userid, update_external_id_mapping, CANISTER_ID, DRIVES_BY_ID_HASHTABLE, DRIVES_BY_TIME_LIST, DRIVE_ID, DRIVE_STATE_CHECKSUM, DRIVE_STATE_TIMESTAMP_NS, EXTERNAL_ID_MAPPINGS, OWNER_ID, SPAWN_NOTE, SPAWN_REDEEM_CODE, TRANSFER_OWNER_ID, URL_ENDPOINT, VERSION}, types::{Drive, DriveID, DriveRESTUrlEndpoint, DriveStateDiffID, ExternalID, ExternalPayload, InboxNotifID, SpawnRedeemCode}}, group_invites::state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, groups::state::state::{is_group_admin, GROUPS_BY_ID_HASHTABLE, GROUPS_BY_TIME_LIST}, labels::{state::{add_label_to_resource, parse_label_resource_id, remove_label_from_resource, validate_label_value}, types::{LabelOperationResponse, LabelResourceID}}, permissions::{state::state::{DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, SYSTEM_PERMISSIONS_BY_ID_HASHTABLE}, types::{DirectoryPermissionType, PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, search::types::{SearchCategoryEnum, SearchResult}, webhooks::types::WebhookEventLabel}, types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, directory::types::DirectoryResourceID, organization::types::{AboutDriveResponse, AboutDriveResponseData, ErrorResponse, ExternalIDsDriveRequestBody, ExternalIDsDriveResponse, ExternalIDsDriveResponseData, ExternalIDvsInternalIDMaps, GetWhoAmIResponse, InboxOrgRequestBody, InboxOrgResponse, InboxOrgResponseData, RedeemOrgRequestBody, RedeemOrgResponse, RedeemOrgResponseData, ReindexDriveRequestBody, ReindexDriveResponse, ReindexDriveResponseData, ReplayDriveRequestBody, ReplayDriveResponse, ReplayDriveResponseData, SearchDriveRequestBody, SearchDriveResponse, SearchDriveResponseData, SearchSortByEnum, SuperswapUserIDRequestBody, SuperswapUserIDResponse, SuperswapUserIDResponseData, TransferOwnershipDriveRequestBody, TransferOwnershipDriveResponse, TransferOwnershipResponseData, TransferOwnershipStatusEnum, UpdateAllowedDomainsDriveRequestBody, UpdateAllowedDomainsDriveResponse, UpdateAllowedDomainsDriveResponseData, WhoAmIReport}, webhooks::types::SortDirection}
+        core::{api::{helpers::is_local_environment, internals::drive_internals::is_user_in_group, permissions::{directory::{can_user_access_directory_permission, check_directory_permissions}, system::{can_user_access_system_permission, check_system_permissions}}, replay::diff::{apply_state_diff, convert_state_to_serializable, safely_apply_diffs, snapshot_entire_state, snapshot_poststate, snapshot_prestate}, uuid::generate_uuidv4, webhooks::organization::{fire_org_inbox_new_notif_webhook, fire_superswap_user_webhook, get_org_inbox_webhooks, get_superswap_user_webhooks}}, state::{api_keys::state::state::{APIKEYS_BY_ID_HASHTABLE, APIKEYS_BY_VALUE_HASHTABLE, USERS_APIKEYS_HASHTABLE}, contacts::state::state::{CONTACTS_BY_ICP_PRINCIPAL_HASHTABLE, CONTACTS_BY_ID_HASHTABLE, CONTACTS_BY_TIME_LIST}, directory::state::state::{file_uuid_to_metadata, folder_uuid_to_metadata, full_file_path_to_uuid, full_folder_path_to_uuid}, disks::state::state::{DISKS_BY_ID_HASHTABLE, DISKS_BY_TIME_LIST}, drives::{state::state::{superswap_userid, update_external_id_mapping, ALLOWED_DOMAINS, CANISTER_ID, DRIVES_BY_ID_HASHTABLE, DRIVES_BY_TIME_LIST, DRIVE_ID, DRIVE_STATE_CHECKSUM, DRIVE_STATE_TIMESTAMP_NS, EXTERNAL_ID_MAPPINGS, OWNER_ID, SPAWN_NOTE, SPAWN_REDEEM_CODE, TRANSFER_OWNER_ID, URL_ENDPOINT, VERSION}, types::{Drive, DriveID, DriveRESTUrlEndpoint, DriveStateDiffID, ExternalID, ExternalPayload, InboxNotifID, SpawnRedeemCode}}, group_invites::state::state::{INVITES_BY_ID_HASHTABLE, USERS_INVITES_LIST_HASHTABLE}, groups::state::state::{is_group_admin, GROUPS_BY_ID_HASHTABLE, GROUPS_BY_TIME_LIST}, inbox::{state::state::{get_inbox_recipients, get_recipient_inbox_notifs, remove_inbox_notif, store_inbox_notif, INBOX_NOTIFS_BY_ID_HASHTABLE}, types::{InboxNotif, InboxRecipientID}}, rate_limits::{state::state::RATE_LIMIT_CONFIG, types::RateLimitConfig}, labels::{state::{add_label_to_resource, parse_label_resource_id, remove_label_from_resource, validate_label_value}, types::{LabelOperationResponse, LabelResourceID}}, permissions::{state::state::{DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, SYSTEM_PERMISSIONS_BY_ID_HASHTABLE}, types::{DirectoryPermissionType, PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, search::types::{SearchCategoryEnum, SearchResult}, webhooks::types::WebhookEventLabel}, types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, directory::types::DirectoryResourceID, helpers::parse_allowed_domains, organization::types::{AboutDriveResponse, AboutDriveResponseData, ErrorResponse, ExternalIDsDriveRequestBody, ExternalIDsDriveResponse, ExternalIDsDriveResponseData, DeleteInboxOrgRequestBody, DeleteInboxOrgResponse, DeleteInboxOrgResponseData, ExternalIDvsInternalIDMaps, GetWhoAmIResponse, InboxOrgRequestBody, InboxOrgResponse, InboxOrgResponseData, ListInboxOrgRequestBody, ListInboxOrgResponse, ListInboxOrgResponseData, MarkReadInboxOrgRequestBody, MarkReadInboxOrgResponse, MarkReadInboxOrgResponseData, RedeemOrgRequestBody, RedeemOrgResponse, RedeemOrgResponseData, ReindexDriveRequestBody, ReindexDriveResponse, ReindexDriveResponseData, ReplayDriveRequestBody, ReplayDriveResponse, ReplayDriveResponseData, SearchDriveRequestBody, SearchDriveResponse, SearchDriveResponseData, SearchSortByEnum, SuperswapUserIDRequestBody, SuperswapUserIDResponse, SuperswapUserIDResponseData, TransferOwnershipDriveRequestBody, TransferOwnershipDriveResponse, TransferOwnershipResponseData, TransferOwnershipStatusEnum, UpdateAllowedDomainsDriveRequestBody, UpdateAllowedDomainsDriveResponse, UpdateAllowedDomainsDriveResponseData, GetRateLimitsDriveResponse, UpdateRateLimitsDriveRequestBody, UpdateRateLimitsDriveResponse, WhoAmIReport}, webhooks::types::SortDirection}
         
     };
     use candid::Principal;
     use ic_cdk::api::management_canister::main::CanisterIdRecord;
     use ic_types::crypto::canister_threshold_sig::PublicKey;
     use serde_json::json;
-    use crate::core::state::search::state::state::{raw_query,filter_search_results_by_permission};
+    use crate::core::state::search::state::state::{raw_query,filter_search_results_by_permission, MAX_SEARCH_EDIT_DISTANCE};
+    use crate::core::state::audit::{state::state::{audit_entry_count, list_audit_entries}, types::AuditActionEnum};
+    use crate::rest::helpers::record_audit_event;
+    use crate::rest::organization::types::{ListAuditOrgRequestBody, ListAuditOrgResponse, ListAuditOrgResponseData};
+    use crate::core::state::idempotency::{state::state::IDEMPOTENCY_CONFIG, types::IdempotencyConfig};
+    use crate::rest::organization::types::{GetIdempotencyDriveResponse, UpdateIdempotencyDriveRequestBody, UpdateIdempotencyDriveResponse};
+    use crate::core::state::cycles::{state::state::{cycles_burn_rate_per_day, cycles_report, recent_cycles_samples, CYCLES_ALERT_STATE, CYCLES_MONITOR_CONFIG}, types::CyclesMonitorConfig};
+    use crate::rest::organization::types::{CyclesDriveResponse, CyclesDriveResponseData, UpdateCyclesMonitorDriveRequestBody, UpdateCyclesMonitorDriveResponse};
+    use crate::core::api::archive::archive::{abort_cold_archive_import, activate_cold_archive_import, generate_archive_seed_phrase, get_cold_archive_import_progress, read_cold_archive_export_part, receive_cold_archive_part, start_cold_archive_export, start_cold_archive_import, ColdArchiveError};
+    use crate::rest::organization::types::{AbortArchiveImportDriveResponse, AbortArchiveImportDriveResponseData, ArchiveActionDriveRequestBody, ArchiveImportProgressDriveResponse, ArchivePartDriveRequestBody, ArchivePartDriveResponse, ArchivePartDriveResponseData, ExportArchiveDriveRequestBody, ExportArchiveDriveResponse, ExportArchiveDriveResponseData, ImportArchiveDriveRequestBody, ImportArchiveDriveResponse, UploadArchivePartDriveRequestBody};
+    use base64::{engine::general_purpose, Engine as _};
+    use crate::core::api::replay::snapshot::{end_paged_snapshot, read_snapshot_page, snapshot_excluded_tables, start_paged_snapshot, SnapshotPageError};
+    use crate::rest::organization::types::{EndPagedSnapshotDriveRequestBody, EndPagedSnapshotDriveResponse, EndPagedSnapshotDriveResponseData, PagedSnapshotDriveRequestBody, PagedSnapshotDriveResponse, StartPagedSnapshotDriveResponse, StartPagedSnapshotDriveResponseData};
+    use crate::rest::types::ErrorCode;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
     use serde::Deserialize;
@@ -97,6 +110,7 @@ pub mod drives_handlers {
         }
 
         let version = VERSION.with(|v| v.borrow().get().clone());
+        let daily_cycle_burn_rate = cycles_burn_rate_per_day(ic_cdk::api::time() / 1_000_000).to_string();
 
         // Create response data
         let response_data = AboutDriveResponseData {
@@ -107,6 +121,7 @@ pub mod drives_handlers {
             host: endpoint,
             canister_id,
             daily_idle_cycle_burn_rate,
+            daily_cycle_burn_rate,
             controllers,
             version,
         };
@@ -156,6 +171,120 @@ pub mod drives_handlers {
         }
     }
 
+    pub async fn start_paged_snapshot_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can snapshot
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        let (marker, tables) = start_paged_snapshot(&requester_api_key.user_id);
+        create_response(
+            StatusCode::OK,
+            StartPagedSnapshotDriveResponse::ok(&StartPagedSnapshotDriveResponseData { marker, tables, excluded_tables: snapshot_excluded_tables() }).encode()
+        )
+    }
+
+    pub async fn paged_snapshot_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can snapshot
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let page_request = match serde_json::from_slice::<PagedSnapshotDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = page_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        match read_snapshot_page(&requester_api_key.user_id, &page_request.snapshot_id, page_request.cursor.as_deref(), page_request.page_size) {
+            Ok(page) => create_response(
+                StatusCode::OK,
+                PagedSnapshotDriveResponse::ok(&page).encode()
+            ),
+            Err(SnapshotPageError::NotFound) => create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::err(404, "Snapshot not found or expired, start a new one".to_string()).encode()
+            ),
+            Err(SnapshotPageError::InvalidCursor) => create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid cursor".to_string()).encode()
+            ),
+            Err(SnapshotPageError::DriveChanged) => create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::error(ErrorCode::DriveChanged, "drive_changed: the drive changed since the snapshot started, start a new one".to_string()).encode()
+            ),
+        }
+    }
+
+    pub async fn end_paged_snapshot_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can snapshot
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let end_request = match serde_json::from_slice::<EndPagedSnapshotDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = end_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let ended = end_paged_snapshot(&requester_api_key.user_id, &end_request.snapshot_id);
+        create_response(
+            StatusCode::OK,
+            EndPagedSnapshotDriveResponse::ok(&EndPagedSnapshotDriveResponseData {
+                snapshot_id: end_request.snapshot_id,
+                ended,
+            }).encode()
+        )
+    }
+
     pub async fn replay_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
         // Authenticate request
         let requester_api_key = match authenticate_request(request) {
@@ -180,8 +309,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = replay_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -274,16 +402,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
-            );
-        }
-    
-        // Check if search query is provided (already validated in validate_body, but double-checking)
-        if request_body.query.trim().is_empty() {
-            return create_response(
-                StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, "Search query cannot be empty".to_string()).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -296,12 +415,18 @@ pub mod drives_handlers {
         // Get page size with default if not provided
         let page_size = request_body.page_size.unwrap_or_else(|| 50);
     
-        // Get sort direction with default if not provided
-        let direction = request_body.direction.unwrap_or_default();
+        // Get sort direction with default if not provided, score sorts default to best matches first
+        let direction = match request_body.direction {
+            Some(direction) => direction,
+            None => match &request_body.sort_by {
+                Some(SearchSortByEnum::Score | SearchSortByEnum::Relevance) => SortDirection::Desc,
+                _ => SortDirection::default(),
+            },
+        };
     
         // Perform the search using the search module
-        let max_edit_distance = 2; // Allow up to 2 character edits for fuzzy matching
-        let search_results = raw_query(&request_body.query, max_edit_distance, categories);
+        let max_edit_distance = request_body.max_edit_distance.unwrap_or(MAX_SEARCH_EDIT_DISTANCE);
+        let search_results = raw_query(&request_body.query, max_edit_distance, categories, request_body.filters.as_ref());
         
         // Create a PermissionGranteeID from the requester's user ID for permission checks
         let grantee_id = PermissionGranteeID::User(requester_api_key.user_id.clone());
@@ -316,9 +441,16 @@ pub mod drives_handlers {
                     // Sort by created_at timestamp
                     filtered_results.sort_by(|a, b| a.created_at.cmp(&b.created_at));
                 },
-                _ => {
+                SearchSortByEnum::UpdatedAt => {
                     // Sort by updated_at timestamp
-                    filtered_results.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+                    filtered_results.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+                },
+                SearchSortByEnum::Score | SearchSortByEnum::Relevance => {
+                    // Lowest score first, DESC puts the best matches on top
+                    filtered_results.sort_by(|a, b| a.score.cmp(&b.score));
+                },
+                SearchSortByEnum::Alphabetical => {
+                    filtered_results.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
                 }
             }
         }
@@ -455,8 +587,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -473,33 +604,21 @@ pub mod drives_handlers {
             );
         }
         
-        // Perform the reindex
-        let reindex_result = crate::core::state::search::state::state::reindex_drive();
-        
-        match reindex_result {
-            Ok(indexed_count) => {
-                // Get the updated timestamp
-                let new_timestamp = crate::core::state::search::state::state::get_last_index_update_time();
-                
-                // Prepare response
-                let response_data = ReindexDriveResponseData {
-                    success: true,
-                    timestamp_ms: new_timestamp,
-                    indexed_count,
-                };
-                
-                create_response(
-                    StatusCode::OK,
-                    ReindexDriveResponse::ok(&response_data).encode()
-                )
-            },
-            Err(error) => {
-                create_response(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    ErrorResponse::err(500, format!("Failed to reindex drive: {}", error)).encode()
-                )
-            }
-        }
+        // The rebuild runs in batches on timers, a request while one runs reports its progress
+        crate::core::state::search::state::state::start_reindex();
+        let progress = crate::core::state::search::state::state::reindex_progress();
+
+        let response_data = ReindexDriveResponseData {
+            success: true,
+            timestamp_ms: last_index_time,
+            indexed_count: progress.as_ref().map_or(0, |progress| progress.documents_read),
+            in_progress: progress.is_some(),
+        };
+
+        create_response(
+            StatusCode::ACCEPTED,
+            ReindexDriveResponse::ok(&response_data).encode()
+        )
     }
 
 
@@ -553,8 +672,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -631,8 +749,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = transfer_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -709,6 +826,14 @@ pub mod drives_handlers {
             }
         };
         
+        record_audit_event(
+            request,
+            &requester_api_key,
+            AuditActionEnum::OwnershipTransfer,
+            &next_owner_id,
+            Some(requester_api_key.user_id.to_string()),
+            Some(log_message.clone()),
+        );
         snapshot_poststate(prestate, Some(log_message));
     
         create_response(
@@ -745,154 +870,721 @@ pub mod drives_handlers {
         if let Err(validation_error) = update_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
+        // Stored normalized, validate_body already rejected malformed entries
+        let allowed_domains = parse_allowed_domains(&update_request.allowed_domains)
+            .unwrap_or_default()
+            .join(", ");
+        ALLOWED_DOMAINS.with(|store| {
+            store.borrow_mut().set(allowed_domains.clone())
+                .expect("Failed to update ALLOWED_DOMAINS");
+        });
+
         create_response(
             StatusCode::OK,
             UpdateAllowedDomainsDriveResponse::ok(&UpdateAllowedDomainsDriveResponseData {
                 success: true,
                 message: "Allowed domains updated successfully".to_string(),
+                allowed_domains,
             }).encode()
         )
     }
 
-    pub async fn whoami_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+    pub async fn get_rate_limits_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
         // Authenticate request
         let requester_api_key = match authenticate_request(request) {
             Some(key) => key,
             None => return create_auth_error_response(),
         };
-    
+        // Only owner can view
         let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
-    
-        // Get organization ID from params
-        let param_org_id = params.get("organization_id").unwrap().to_string();
-        
-        // If organization_id is "default", use the DRIVE_ID
-        let organization_id = if param_org_id == "default" {
-            DRIVE_ID.with(|id| id.clone())
-        } else {
-            DriveID(param_org_id)
-        };
-    
-        // Get drive nickname from DRIVES_BY_ID_HASHTABLE
-        let drive_nickname = DRIVES_BY_ID_HASHTABLE.with(|store| {
-            store.borrow()
-                .get(&organization_id)
-                .map(|drive| drive.name.clone())
-                .unwrap_or_else(|| "".to_string())
-        });
-    
-        // Get EVM public address from contacts
-        let evm_public_address = CONTACTS_BY_ID_HASHTABLE.with(|store| {
-            store.borrow()
-                .get(&requester_api_key.user_id)
-                .map(|contact| contact.evm_public_address.clone())
-                .unwrap_or_else(|| String::new())
-        });
-        
-        // Get nickname from contacts
-        let nickname = CONTACTS_BY_ID_HASHTABLE.with(|store| {
-            store.borrow()
-                .get(&requester_api_key.user_id)
-                .map(|contact| contact.name.clone())
-                .unwrap_or_else(|| String::new())
-        });
-        
-        // Extract the principal ID by removing "UserID_" prefix
-        let user_id_str = requester_api_key.user_id.to_string();
-        let icp_principal = if user_id_str.starts_with("UserID_") {
-            user_id_str[7..].to_string()
-        } else {
-            user_id_str
-        };
-        
-        let whoamireport = WhoAmIReport {
-            nickname,
-            userID: requester_api_key.user_id.clone(),
-            driveID: organization_id,
-            icp_principal: ICPPrincipalString(PublicKeyICP(icp_principal)),
-            evm_public_address: Some(evm_public_address),
-            is_owner,
-            drive_nickname,
-        };
-    
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        let config = RATE_LIMIT_CONFIG.with(|store| store.borrow().get().clone());
         create_response(
             StatusCode::OK,
-            GetWhoAmIResponse::ok(&whoamireport).encode()
+            GetRateLimitsDriveResponse::ok(&config).encode()
         )
     }
 
-    pub async fn superswap_userid_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+    pub async fn update_rate_limits_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
         // Authenticate request
         let requester_api_key = match authenticate_request(request) {
             Some(key) => key,
             None => return create_auth_error_response(),
         };
-    
-        // Check if user is owner
+        // Only owner can update
         let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
-        
-        match is_owner {
-            true => {
-                // Parse request body
-                let body = request.body();
-                let request_body: SuperswapUserIDRequestBody = match serde_json::from_slice(body) {
-                    Ok(body) => body,
-                    Err(_) => return create_response(
-                        StatusCode::BAD_REQUEST,
-                        ErrorResponse::err(400, "Invalid request format".to_string()).encode()
-                    ),
-                };
-    
-                if let Err(validation_error) = request_body.validate_body() {
-                    return create_response(
-                        StatusCode::BAD_REQUEST,
-                        ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                            validation_error.field, validation_error.message)).encode()
-                    );
-                }
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
 
-                // snapshot prestate
-                let prestate = snapshot_prestate();
-    
-                match superswap_userid(
-                    UserID(request_body.current_user_id.clone()),
-                    UserID(request_body.new_user_id.clone())
-                ) {
-                    Ok(update_count) => {
+        // Parse request body
+        let update_request = match serde_json::from_slice::<UpdateRateLimitsDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
 
-                        // check webhooks and fire
-                        let active_webhooks = get_superswap_user_webhooks(
-                            WebhookEventLabel::OrganizationSuperswapUser
-                        );
+        if let Err(validation_error) = update_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
 
-                        fire_superswap_user_webhook(
-                            WebhookEventLabel::OrganizationSuperswapUser,
-                            active_webhooks,
-                            Some(UserID(request_body.current_user_id.clone())),
-                            Some(UserID(request_body.new_user_id.clone())),
-                            Some(format!("'{}' superswapped to '{}', updated {} records", 
-                                request_body.current_user_id, request_body.new_user_id, update_count))
-                        );
+        let config = RateLimitConfig { rules: update_request.rules };
+        RATE_LIMIT_CONFIG.with(|store| {
+            store.borrow_mut().set(config.clone())
+                .expect("Failed to update RATE_LIMIT_CONFIG");
+        });
 
-                        // snapshot poststate
-                        snapshot_poststate(prestate, Some(format!("'{}' superswapped to '{}'", 
-                            request_body.current_user_id, request_body.new_user_id)));
+        create_response(
+            StatusCode::OK,
+            UpdateRateLimitsDriveResponse::ok(&config).encode()
+        )
+    }
 
-                        // rest response
-                        let response_data = SuperswapUserIDResponseData {
-                            success: true,
-                            message: format!("'{}' superswapped to '{}', updated {} records", 
-                                request_body.current_user_id, request_body.new_user_id, update_count), 
-                        };
-                        create_response(
-                            StatusCode::OK,
-                            SuperswapUserIDResponse::ok(&response_data).encode()
-                        )
+    pub async fn get_idempotency_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can view
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        let config = IDEMPOTENCY_CONFIG.with(|store| store.borrow().get().clone());
+        create_response(
+            StatusCode::OK,
+            GetIdempotencyDriveResponse::ok(&config).encode()
+        )
+    }
+
+    pub async fn update_idempotency_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can update
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let update_request = match serde_json::from_slice::<UpdateIdempotencyDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = update_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        // Applies to existing records too, they are checked against the window on every lookup
+        let config = IdempotencyConfig { window_ms: update_request.window_ms };
+        IDEMPOTENCY_CONFIG.with(|store| {
+            store.borrow_mut().set(config.clone())
+                .expect("Failed to update IDEMPOTENCY_CONFIG");
+        });
+
+        create_response(
+            StatusCode::OK,
+            UpdateIdempotencyDriveResponse::ok(&config).encode()
+        )
+    }
+
+    pub async fn cycles_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can view
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Report on the live balance without recording a sample, the monitor timer owns the history
+        let now_ms = ic_cdk::api::time() / 1_000_000;
+        let response_data = CyclesDriveResponseData {
+            report: cycles_report(now_ms, ic_cdk::api::canister_balance()),
+            config: CYCLES_MONITOR_CONFIG.with(|config| config.borrow().get().clone()),
+            low_cycles_alerted_at_ms: CYCLES_ALERT_STATE.with(|alert| alert.borrow().get().low_cycles_alerted_at_ms),
+            samples: recent_cycles_samples(7 * 24),
+        };
+        create_response(
+            StatusCode::OK,
+            CyclesDriveResponse::ok(&response_data).encode()
+        )
+    }
+
+    pub async fn update_cycles_monitor_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can update
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let update_request = match serde_json::from_slice::<UpdateCyclesMonitorDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = update_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        // Takes effect on the next monitor tick
+        let config = CyclesMonitorConfig {
+            low_cycles_threshold: update_request.low_cycles_threshold,
+            low_runway_days: update_request.low_runway_days,
+        };
+        CYCLES_MONITOR_CONFIG.with(|store| {
+            store.borrow_mut().set(config.clone())
+                .expect("Failed to update CYCLES_MONITOR_CONFIG");
+        });
+
+        create_response(
+            StatusCode::OK,
+            UpdateCyclesMonitorDriveResponse::ok(&config).encode()
+        )
+    }
+
+    pub async fn export_archive_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can export the drive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let export_request = match serde_json::from_slice::<ExportArchiveDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = export_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let (seed_phrase, generated) = match export_request.seed_phrase {
+            Some(seed_phrase) => (seed_phrase, false),
+            None => match generate_archive_seed_phrase().await {
+                Ok(seed_phrase) => (seed_phrase, true),
+                Err(e) => return create_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErrorResponse::server_error(e).encode()
+                ),
+            },
+        };
+
+        match start_cold_archive_export(&seed_phrase).await {
+            Ok(header) => {
+                record_audit_event(
+                    request,
+                    &requester_api_key,
+                    AuditActionEnum::ArchiveExport,
+                    &header.archive_id,
+                    None,
+                    Some(format!("{} parts, state {}", header.total_parts(), header.state_checksum)),
+                );
+                create_response(
+                    StatusCode::OK,
+                    ExportArchiveDriveResponse::ok(&ExportArchiveDriveResponseData {
+                        total_parts: header.total_parts(),
+                        header,
+                        seed_phrase: if generated { Some(seed_phrase) } else { None },
+                    }).encode()
+                )
+            },
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    pub async fn export_archive_part_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can export the drive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let part_request = match serde_json::from_slice::<ArchivePartDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = part_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        match read_cold_archive_export_part(&part_request.archive_id, part_request.index) {
+            Ok((data, total_parts)) => create_response(
+                StatusCode::OK,
+                ArchivePartDriveResponse::ok(&ArchivePartDriveResponseData {
+                    archive_id: part_request.archive_id,
+                    index: part_request.index,
+                    total_parts,
+                    data_base64: general_purpose::STANDARD.encode(data),
+                }).encode()
+            ),
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    pub async fn import_archive_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can import an archive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let import_request = match serde_json::from_slice::<ImportArchiveDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = import_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let header_part = match general_purpose::STANDARD.decode(&import_request.header_base64) {
+            Ok(bytes) => bytes,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Header is not valid base64".to_string()).encode()
+            ),
+        };
+
+        match start_cold_archive_import(&import_request.seed_phrase, &header_part) {
+            Ok(header) => create_response(
+                StatusCode::OK,
+                ImportArchiveDriveResponse::ok(&header).encode()
+            ),
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    pub async fn import_archive_part_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can import an archive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let part_request = match serde_json::from_slice::<UploadArchivePartDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = part_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let data = match general_purpose::STANDARD.decode(&part_request.data_base64) {
+            Ok(bytes) => bytes,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Part is not valid base64".to_string()).encode()
+            ),
+        };
+
+        match receive_cold_archive_part(&part_request.archive_id, part_request.index, &data) {
+            Ok(progress) => create_response(
+                StatusCode::OK,
+                ArchiveImportProgressDriveResponse::ok(&progress).encode()
+            ),
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    pub async fn import_archive_status_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can import an archive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        let archive_id = params.get("archive_id").unwrap().to_string();
+        match get_cold_archive_import_progress(&archive_id) {
+            Ok(progress) => create_response(
+                StatusCode::OK,
+                ArchiveImportProgressDriveResponse::ok(&progress).encode()
+            ),
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    pub async fn activate_archive_import_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can import an archive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let activate_request = match serde_json::from_slice::<ArchiveActionDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = activate_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let state_before = DRIVE_STATE_CHECKSUM.with(|cs| cs.borrow().get().0.clone());
+
+        // Ownership moves to the archived owner from here on
+        match activate_cold_archive_import(&activate_request.archive_id) {
+            Ok(header) => {
+                // The audit log is not part of the archive, so this entry stays with the drive
+                record_audit_event(
+                    request,
+                    &requester_api_key,
+                    AuditActionEnum::ArchiveImport,
+                    &header.archive_id,
+                    Some(format!("state {}", state_before)),
+                    Some(format!("drive {} owned by {}, state {}", header.drive_id, header.owner_id, header.state_checksum)),
+                );
+                create_response(
+                    StatusCode::OK,
+                    ImportArchiveDriveResponse::ok(&header).encode()
+                )
+            },
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    pub async fn abort_archive_import_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can import an archive
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let abort_request = match serde_json::from_slice::<ArchiveActionDriveRequestBody>(request.body()) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = abort_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        match abort_cold_archive_import(&abort_request.archive_id) {
+            Ok(()) => create_response(
+                StatusCode::OK,
+                AbortArchiveImportDriveResponse::ok(&AbortArchiveImportDriveResponseData {
+                    archive_id: abort_request.archive_id,
+                    aborted: true,
+                }).encode()
+            ),
+            Err(e) => cold_archive_error_response(e),
+        }
+    }
+
+    fn cold_archive_error_response(error: ColdArchiveError) -> HttpResponse<'static> {
+        let status = match error {
+            ColdArchiveError::InvalidSeedPhrase | ColdArchiveError::Integrity(_) => StatusCode::BAD_REQUEST,
+            ColdArchiveError::NotFound => StatusCode::NOT_FOUND,
+            ColdArchiveError::NotFresh
+            | ColdArchiveError::OutOfOrder(_)
+            | ColdArchiveError::Incomplete(_)
+            | ColdArchiveError::Unavailable(_) => StatusCode::CONFLICT,
+        };
+        create_response(
+            status,
+            ErrorResponse::err(status.as_u16(), error.message()).encode()
+        )
+    }
+
+    pub async fn whoami_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+    
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+    
+        // Get organization ID from params
+        let param_org_id = params.get("organization_id").unwrap().to_string();
+        
+        // If organization_id is "default", use the DRIVE_ID
+        let organization_id = if param_org_id == "default" {
+            DRIVE_ID.with(|id| id.clone())
+        } else {
+            DriveID(param_org_id)
+        };
+    
+        // Get drive nickname from DRIVES_BY_ID_HASHTABLE
+        let drive_nickname = DRIVES_BY_ID_HASHTABLE.with(|store| {
+            store.borrow()
+                .get(&organization_id)
+                .map(|drive| drive.name.clone())
+                .unwrap_or_else(|| "".to_string())
+        });
+    
+        // Get EVM public address from contacts
+        let evm_public_address = CONTACTS_BY_ID_HASHTABLE.with(|store| {
+            store.borrow()
+                .get(&requester_api_key.user_id)
+                .map(|contact| contact.evm_public_address.clone())
+                .unwrap_or_else(|| String::new())
+        });
+        
+        // Get nickname from contacts
+        let nickname = CONTACTS_BY_ID_HASHTABLE.with(|store| {
+            store.borrow()
+                .get(&requester_api_key.user_id)
+                .map(|contact| contact.name.clone())
+                .unwrap_or_else(|| String::new())
+        });
+        
+        // Extract the principal ID by removing "UserID_" prefix
+        let user_id_str = requester_api_key.user_id.to_string();
+        let icp_principal = if user_id_str.starts_with("UserID_") {
+            user_id_str[7..].to_string()
+        } else {
+            user_id_str
+        };
+        
+        let whoamireport = WhoAmIReport {
+            nickname,
+            userID: requester_api_key.user_id.clone(),
+            driveID: organization_id,
+            icp_principal: ICPPrincipalString(PublicKeyICP(icp_principal)),
+            evm_public_address: Some(evm_public_address),
+            is_owner,
+            drive_nickname,
+        };
+    
+        create_response(
+            StatusCode::OK,
+            GetWhoAmIResponse::ok(&whoamireport).encode()
+        )
+    }
+
+    pub async fn superswap_userid_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+    
+        // Check if user is owner
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        
+        match is_owner {
+            true => {
+                // Parse request body
+                let body = request.body();
+                let request_body: SuperswapUserIDRequestBody = match serde_json::from_slice(body) {
+                    Ok(body) => body,
+                    Err(_) => return create_response(
+                        StatusCode::BAD_REQUEST,
+                        ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+                    ),
+                };
+    
+                if let Err(validation_error) = request_body.validate_body() {
+                    return create_response(
+                        StatusCode::BAD_REQUEST,
+                        ErrorResponse::validation_failed(&validation_error).encode()
+                    );
+                }
+
+                // snapshot prestate
+                let prestate = snapshot_prestate();
+    
+                match superswap_userid(
+                    UserID(request_body.current_user_id.clone()),
+                    UserID(request_body.new_user_id.clone())
+                ) {
+                    Ok(update_count) => {
+
+                        // check webhooks and fire
+                        let active_webhooks = get_superswap_user_webhooks(
+                            WebhookEventLabel::OrganizationSuperswapUser
+                        );
+
+                        fire_superswap_user_webhook(
+                            WebhookEventLabel::OrganizationSuperswapUser,
+                            active_webhooks,
+                            Some(UserID(request_body.current_user_id.clone())),
+                            Some(UserID(request_body.new_user_id.clone())),
+                            Some(format!("'{}' superswapped to '{}', updated {} records", 
+                                request_body.current_user_id, request_body.new_user_id, update_count))
+                        );
+
+                        record_audit_event(
+                            request,
+                            &requester_api_key,
+                            AuditActionEnum::SuperswapUser,
+                            &request_body.current_user_id,
+                            Some(request_body.current_user_id.clone()),
+                            Some(format!("{}, updated {} records", request_body.new_user_id, update_count)),
+                        );
+
+                        // snapshot poststate
+                        snapshot_poststate(prestate, Some(format!("'{}' superswapped to '{}'", 
+                            request_body.current_user_id, request_body.new_user_id)));
+
+                        // rest response
+                        let response_data = SuperswapUserIDResponseData {
+                            success: true,
+                            message: format!("'{}' superswapped to '{}', updated {} records", 
+                                request_body.current_user_id, request_body.new_user_id, update_count), 
+                        };
+                        create_response(
+                            StatusCode::OK,
+                            SuperswapUserIDResponse::ok(&response_data).encode()
+                        )
                     },
                     Err(_) => {
                         create_response(
@@ -928,7 +1620,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -938,8 +1630,8 @@ pub mod drives_handlers {
         // Check if the code has already been redeemed (empty string)
         if stored_redeem_code.is_empty() {
             return create_response(
-                StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, "Spawn code has already been redeemed".to_string()).encode()
+                StatusCode::CONFLICT,
+                ErrorResponse::error(ErrorCode::Conflict, "Spawn code has already been redeemed".to_string()).encode()
             );
         }
         
@@ -1023,8 +1715,7 @@ pub mod drives_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, format!("Validation error: {}: {}", 
-                    validation_error.field, validation_error.message)).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -1053,9 +1744,24 @@ pub mod drives_handlers {
 
         let inbox_notif_id = InboxNotifID(generate_uuidv4(IDPrefix::InboxNotifID));
         let timestamp_ms = ic_cdk::api::time() / 1_000_000;
+
+        // Keep a copy in the recipient's inbox so it can be read without a webhook
+        let recipient = match &request_body.recipient {
+            Some(recipient) => InboxRecipientID(recipient.clone()),
+            None => InboxRecipientID::user(&OWNER_ID.with(|owner_id| owner_id.borrow().get().clone())),
+        };
+        store_inbox_notif(InboxNotif {
+            id: inbox_notif_id.clone(),
+            recipient,
+            sender: requester_api_key.user_id.clone(),
+            topic: request_body.topic.clone(),
+            payload: request_body.payload.as_ref().map(|payload| payload.to_string()),
+            read_at: None,
+            created_at: timestamp_ms,
+        });
     
 
-        let active_webhooks = get_org_inbox_webhooks(request_body.topic.as_ref());
+        let active_webhooks = get_org_inbox_webhooks(request_body.topic.as_ref(), &requester_api_key.user_id);
 
         debug_log!("Active webhooks: {:?}", active_webhooks);
 
@@ -1082,6 +1788,283 @@ pub mod drives_handlers {
     }
 
 
+    pub async fn list_inbox_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Parse request body
+        let body = request.body();
+        let request_body: ListInboxOrgRequestBody = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = request_body.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        // Parse cursor if provided
+        let start_index = match &request_body.cursor {
+            Some(cursor) => match cursor.parse::<usize>() {
+                Ok(idx) => idx,
+                Err(_) => return create_response(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::err(400, "Invalid cursor format".to_string()).encode()
+                ),
+            },
+            None => 0,
+        };
+
+        // Owner and View on the Inbox table can read every inbox, everyone else only their own and their groups'
+        let can_view_all = can_access_all_inboxes(&requester_api_key.user_id, SystemPermissionType::View);
+
+        let recipients: Vec<InboxRecipientID> = match &request_body.recipient {
+            Some(recipient) => {
+                let recipient = InboxRecipientID(recipient.clone());
+                if !can_view_all && !is_own_inbox(&requester_api_key.user_id, &recipient) {
+                    return create_auth_error_response();
+                }
+                vec![recipient]
+            },
+            None => get_inbox_recipients().into_iter()
+                .filter(|recipient| can_view_all || is_own_inbox(&requester_api_key.user_id, recipient))
+                .collect(),
+        };
+
+        let mut notifs: Vec<InboxNotif> = recipients.iter()
+            .flat_map(|recipient| get_recipient_inbox_notifs(recipient))
+            .filter(|notif| request_body.topic.as_ref().map_or(true, |topic| notif.topic.as_ref() == Some(topic)))
+            .filter(|notif| !request_body.unread_only || notif.read_at.is_none())
+            .collect();
+
+        match request_body.direction {
+            SortDirection::Asc => notifs.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+            SortDirection::Desc => notifs.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
+        }
+
+        let total_count = notifs.len();
+        let start_index = start_index.min(total_count);
+        let end_bound = (start_index + request_body.page_size).min(total_count);
+        let page = notifs[start_index..end_bound].to_vec();
+
+        let next_cursor = if end_bound < total_count {
+            Some(end_bound.to_string())
+        } else {
+            None
+        };
+
+        create_response(
+            StatusCode::OK,
+            ListInboxOrgResponse::ok(&ListInboxOrgResponseData {
+                page_size: page.len(),
+                items: page,
+                total: total_count,
+                direction: request_body.direction,
+                cursor: next_cursor,
+            }).encode()
+        )
+    }
+
+    pub async fn list_audit_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+        // Only owner can read the audit log
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == *owner_id.borrow().get());
+        if !is_owner {
+            return create_response(
+                StatusCode::UNAUTHORIZED,
+                ErrorResponse::unauthorized().encode()
+            );
+        }
+
+        // Parse request body
+        let body = request.body();
+        let request_body: ListAuditOrgRequestBody = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = request_body.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        // Cursor is the sequence number of the first entry on the page
+        let cursor = match &request_body.cursor {
+            Some(cursor) => match cursor.parse::<u64>() {
+                Ok(sequence) => Some(sequence),
+                Err(_) => return create_response(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::err(400, "Invalid cursor format".to_string()).encode()
+                ),
+            },
+            None => None,
+        };
+
+        let (page, next_cursor) = list_audit_entries(
+            |entry| {
+                request_body.actor.as_ref().map_or(true, |actor| entry.actor.0 == *actor)
+                    && request_body.action.map_or(true, |action| entry.action == action)
+                    && request_body.resource_id.as_ref().map_or(true, |resource_id| entry.resource_id == *resource_id)
+                    && request_body.route.as_ref().map_or(true, |route| entry.route.contains(route.as_str()))
+                    && request_body.after_ms.map_or(true, |after_ms| entry.timestamp_ms >= after_ms)
+                    && request_body.before_ms.map_or(true, |before_ms| entry.timestamp_ms <= before_ms)
+            },
+            cursor,
+            request_body.page_size,
+            request_body.direction == SortDirection::Desc,
+        );
+
+        create_response(
+            StatusCode::OK,
+            ListAuditOrgResponse::ok(&ListAuditOrgResponseData {
+                page_size: page.len(),
+                items: page,
+                total: audit_entry_count() as usize,
+                direction: request_body.direction,
+                cursor: next_cursor.map(|sequence| sequence.to_string()),
+            }).encode()
+        )
+    }
+
+    pub async fn mark_read_inbox_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Parse request body
+        let body = request.body();
+        let request_body: MarkReadInboxOrgRequestBody = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = request_body.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let can_edit_all = can_access_all_inboxes(&requester_api_key.user_id, SystemPermissionType::Edit);
+
+        // Check every notif up front so a forbidden id doesn't leave the batch half applied
+        let notifs: Vec<InboxNotif> = request_body.ids.iter()
+            .filter_map(|id| INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| store.borrow().get(&InboxNotifID(id.clone()))))
+            .collect();
+        if !can_edit_all && notifs.iter().any(|notif| !is_own_inbox(&requester_api_key.user_id, &notif.recipient)) {
+            return create_auth_error_response();
+        }
+
+        let read_at = if request_body.read {
+            Some(ic_cdk::api::time() / 1_000_000)
+        } else {
+            None
+        };
+
+        let mut updated = Vec::new();
+        INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| {
+            let mut store = store.borrow_mut();
+            for mut notif in notifs {
+                // Keep the original read time when marking read twice
+                if request_body.read && notif.read_at.is_some() {
+                    continue;
+                }
+                notif.read_at = read_at;
+                updated.push(notif.id.clone());
+                store.insert(notif.id.clone(), notif);
+            }
+        });
+
+        create_response(
+            StatusCode::OK,
+            MarkReadInboxOrgResponse::ok(&MarkReadInboxOrgResponseData { updated }).encode()
+        )
+    }
+
+    pub async fn delete_inbox_drive_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Parse request body
+        let body = request.body();
+        let request_body: DeleteInboxOrgRequestBody = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = request_body.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let can_delete_all = can_access_all_inboxes(&requester_api_key.user_id, SystemPermissionType::Delete);
+
+        let notifs: Vec<InboxNotif> = request_body.ids.iter()
+            .filter_map(|id| INBOX_NOTIFS_BY_ID_HASHTABLE.with(|store| store.borrow().get(&InboxNotifID(id.clone()))))
+            .collect();
+        if !can_delete_all && notifs.iter().any(|notif| !is_own_inbox(&requester_api_key.user_id, &notif.recipient)) {
+            return create_auth_error_response();
+        }
+
+        let deleted: Vec<InboxNotifID> = notifs.iter()
+            .filter_map(|notif| remove_inbox_notif(&notif.id).map(|removed| removed.id))
+            .collect();
+
+        create_response(
+            StatusCode::OK,
+            DeleteInboxOrgResponse::ok(&DeleteInboxOrgResponseData { deleted }).encode()
+        )
+    }
+
+    fn can_access_all_inboxes(user_id: &UserID, permission: SystemPermissionType) -> bool {
+        let is_owner = OWNER_ID.with(|owner_id| user_id == &*owner_id.borrow().get());
+        is_owner || check_system_permissions(
+            SystemResourceID::Table(SystemTableEnum::Inbox),
+            PermissionGranteeID::User(user_id.clone())
+        ).contains(&permission)
+    }
+
+    fn is_own_inbox(user_id: &UserID, recipient: &InboxRecipientID) -> bool {
+        if recipient == &InboxRecipientID::user(user_id) {
+            return true;
+        }
+        match recipient.as_group() {
+            Some(group_id) => is_user_in_group(user_id, &group_id),
+            None => false,
+        }
+    }
+
     fn json_decode<T>(value: &[u8]) -> T
     where
         T: for<'de> Deserialize<'de>,
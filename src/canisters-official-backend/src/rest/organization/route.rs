@@ -5,13 +5,33 @@ use crate::rest::types::RouteHandler;
 
 pub const ORG_ABOUT_PATH: &str =                    genroute!("/organization/about");
 pub const ORG_INBOX_PATH: &str =                    genroute!("/organization/inbox");
+pub const ORG_INBOX_LIST_PATH: &str =               genroute!("/organization/inbox/list");
+pub const ORG_INBOX_MARK_READ_PATH: &str =          genroute!("/organization/inbox/mark_read");
+pub const ORG_INBOX_DELETE_PATH: &str =             genroute!("/organization/inbox/delete");
+pub const ORG_AUDIT_LIST_PATH: &str =               genroute!("/organization/audit/list");
 pub const ORG_SNAPSHOT_PATH: &str =                 genroute!("/organization/snapshot");
+pub const ORG_SNAPSHOT_START_PATH: &str =           genroute!("/organization/snapshot/start");
+pub const ORG_SNAPSHOT_PAGE_PATH: &str =            genroute!("/organization/snapshot/page");
+pub const ORG_SNAPSHOT_END_PATH: &str =             genroute!("/organization/snapshot/end");
 pub const ORG_REPLAY_PATH: &str =                   genroute!("/organization/replay");
 pub const ORG_SEARCH_PATH: &str =                   genroute!("/organization/search");
 pub const ORG_REINDEX_PATH: &str =                  genroute!("/organization/reindex");
 pub const ORG_EXTERNAL_ID_PATH: &str =              genroute!("/organization/external_id");
 pub const ORG_TRANSFER_OWNERSHIP_PATH: &str =       genroute!("/organization/transfer_ownership");
 pub const ORG_UPDATE_ALLOWED_DOMAINS_PATH: &str =   genroute!("/organization/update_allowed_domains");
+pub const ORG_RATE_LIMITS_PATH: &str =              genroute!("/organization/rate_limits");
+pub const ORG_UPDATE_RATE_LIMITS_PATH: &str =       genroute!("/organization/update_rate_limits");
+pub const ORG_IDEMPOTENCY_PATH: &str =              genroute!("/organization/idempotency");
+pub const ORG_UPDATE_IDEMPOTENCY_PATH: &str =       genroute!("/organization/update_idempotency");
+pub const ORG_CYCLES_PATH: &str =                   genroute!("/organization/cycles");
+pub const ORG_UPDATE_CYCLES_MONITOR_PATH: &str =    genroute!("/organization/update_cycles_monitor");
+pub const ORG_ARCHIVE_EXPORT_PATH: &str =           genroute!("/organization/archive/export");
+pub const ORG_ARCHIVE_EXPORT_PART_PATH: &str =      genroute!("/organization/archive/export/part");
+pub const ORG_ARCHIVE_IMPORT_PATH: &str =           genroute!("/organization/archive/import");
+pub const ORG_ARCHIVE_IMPORT_PART_PATH: &str =      genroute!("/organization/archive/import/part");
+pub const ORG_ARCHIVE_IMPORT_STATUS_PATH: &str =    genroute!("/organization/archive/import/status/{archive_id}");
+pub const ORG_ARCHIVE_IMPORT_ACTIVATE_PATH: &str =  genroute!("/organization/archive/import/activate");
+pub const ORG_ARCHIVE_IMPORT_ABORT_PATH: &str =     genroute!("/organization/archive/import/abort");
 pub const ORG_WHOAMI_PATH: &str =                   genroute!("/organization/whoami");
 pub const ORG_SUPERSWAP_PATH: &str =                genroute!("/organization/superswap_user");
 pub const ORG_REDEEM_SPAWN_PATH: &str =             genroute!("/organization/redeem");
@@ -30,11 +50,41 @@ pub fn init_routes() {
             ORG_SNAPSHOT_PATH,
             |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::snapshot_drive_handler(req, params)),
         ),
+        (
+            "POST",
+            ORG_SNAPSHOT_START_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::start_paged_snapshot_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_SNAPSHOT_PAGE_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::paged_snapshot_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_SNAPSHOT_END_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::end_paged_snapshot_drive_handler(req, params)),
+        ),
         (
             "POST",
             ORG_INBOX_PATH,
             |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::inbox_drive_handler(req, params)),
         ),
+        (
+            "POST",
+            ORG_INBOX_LIST_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::list_inbox_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_INBOX_MARK_READ_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::mark_read_inbox_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_INBOX_DELETE_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::delete_inbox_drive_handler(req, params)),
+        ),
         (
             "POST",
             ORG_REPLAY_PATH,
@@ -61,11 +111,81 @@ pub fn init_routes() {
             // transfering ownership requires owner call this route twice with the same body at least 24 hours apart
             |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::transfer_ownership_drive_handler(req, params)),
         ),
+        (
+            "POST",
+            ORG_AUDIT_LIST_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::list_audit_drive_handler(req, params)),
+        ),
         (
             "POST",
             ORG_UPDATE_ALLOWED_DOMAINS_PATH,
             |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::update_allowed_domains_drive_handler(req, params)),
         ),
+        (
+            "GET",
+            ORG_RATE_LIMITS_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::get_rate_limits_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_UPDATE_RATE_LIMITS_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::update_rate_limits_drive_handler(req, params)),
+        ),
+        (
+            "GET",
+            ORG_IDEMPOTENCY_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::get_idempotency_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_UPDATE_IDEMPOTENCY_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::update_idempotency_drive_handler(req, params)),
+        ),
+        (
+            "GET",
+            ORG_CYCLES_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::cycles_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_UPDATE_CYCLES_MONITOR_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::update_cycles_monitor_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_ARCHIVE_EXPORT_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::export_archive_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_ARCHIVE_EXPORT_PART_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::export_archive_part_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_ARCHIVE_IMPORT_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::import_archive_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_ARCHIVE_IMPORT_PART_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::import_archive_part_drive_handler(req, params)),
+        ),
+        (
+            "GET",
+            ORG_ARCHIVE_IMPORT_STATUS_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::import_archive_status_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_ARCHIVE_IMPORT_ACTIVATE_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::activate_archive_import_drive_handler(req, params)),
+        ),
+        (
+            "POST",
+            ORG_ARCHIVE_IMPORT_ABORT_PATH,
+            |req, params| Box::pin(crate::rest::organization::handler::drives_handlers::abort_archive_import_drive_handler(req, params)),
+        ),
         (
             "GET",
             ORG_WHOAMI_PATH,
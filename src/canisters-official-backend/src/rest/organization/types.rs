@@ -4,9 +4,18 @@ use std::error::Error;
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
+use crate::core::api::replay::snapshot::{SnapshotMarker, SnapshotPage, SnapshotTableInfo, DEFAULT_SNAPSHOT_PAGE_SIZE, MAX_SNAPSHOT_PAGE_SIZE};
+use crate::core::api::archive::archive::{ColdArchiveHeader, ColdArchiveImportProgress, COLD_ARCHIVE_SEGMENT_BYTES};
+use crate::core::state::audit::types::{AuditActionEnum, AuditEntry};
+use crate::core::state::cycles::types::{CyclesMonitorConfig, CyclesReport, CyclesSample};
 use crate::core::state::drives::types::{Drive, DriveID, DriveStateDiffID, ExternalID, InboxNotifID, StateChecksum, StateDiffRecord};
-use crate::core::state::search::types::{SearchCategoryEnum, SearchResult};
-use crate::core::types::{ICPPrincipalString, PublicKeyICP, UserID};
+use crate::core::state::idempotency::types::IdempotencyConfig;
+use crate::core::state::inbox::types::{InboxNotif, InboxRecipientID};
+use crate::core::state::rate_limits::types::{RateLimitConfig, RateLimitRule};
+use crate::core::state::search::state::state::MAX_SEARCH_EDIT_DISTANCE;
+use crate::core::state::search::types::{SearchCategoryEnum, SearchFilters, SearchResult};
+use crate::core::types::{ICPPrincipalString, IDPrefix, PublicKeyICP, UserID};
+use crate::rest::helpers::parse_allowed_domains;
 use crate::rest::webhooks::types::{SortDirection};
 use crate::rest::types::{validate_drive_id, validate_external_id, validate_external_payload, validate_icp_principal, validate_id_string, validate_seed_phrase, validate_short_string, validate_user_id, ApiResponse, ValidationError};
 
@@ -78,7 +87,10 @@ impl Default for SearchSortByEnum {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchDriveRequestBody {
-    pub query: String,
+    #[serde(default)]
+    pub query: String, // may be empty when filters are set
+    #[serde(default)]
+    pub filters: Option<SearchFilters>,
     #[serde(default)]
     pub categories: Option<Vec<SearchCategoryEnum>>,
     pub page_size: Option<usize>,
@@ -87,14 +99,17 @@ pub struct SearchDriveRequestBody {
     pub sort_by: Option<SearchSortByEnum>,
     #[serde(default)]
     pub direction: Option<SortDirection>,
+    #[serde(default)]
+    pub max_edit_distance: Option<u32>, // typo tolerance per word, defaults to MAX_SEARCH_EDIT_DISTANCE
 }
 impl SearchDriveRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
-        // Validate query (cannot be empty and should have a reasonable length)
-        if self.query.trim().is_empty() {
+        // Validate query (cannot be empty unless filtering, and should have a reasonable length)
+        let has_filters = self.filters.as_ref().map_or(false, |filters| !filters.is_empty());
+        if self.query.trim().is_empty() && !has_filters {
             return Err(ValidationError {
                 field: "query".to_string(),
-                message: "Search query cannot be empty".to_string(),
+                message: "Search query cannot be empty without filters".to_string(),
             });
         }
 
@@ -125,10 +140,67 @@ impl SearchDriveRequestBody {
             }
         }
 
+        if let Some(filters) = &self.filters {
+            validate_search_filters(filters)?;
+        }
+
+        if let Some(max_edit_distance) = self.max_edit_distance {
+            if max_edit_distance > MAX_SEARCH_EDIT_DISTANCE {
+                return Err(ValidationError {
+                    field: "max_edit_distance".to_string(),
+                    message: format!("Max edit distance must be between 0 and {}", MAX_SEARCH_EDIT_DISTANCE),
+                });
+            }
+        }
+
         Ok(())
     }
 }
 
+fn validate_search_filters(filters: &SearchFilters) -> Result<(), ValidationError> {
+    if filters.labels.len() > 32 {
+        return Err(ValidationError {
+            field: "filters.labels".to_string(),
+            message: "At most 32 labels can be filtered on".to_string(),
+        });
+    }
+    for label in &filters.labels {
+        validate_short_string(label, "filters.labels")?;
+    }
+    if let Some(notes) = &filters.notes {
+        validate_short_string(notes, "filters.notes")?;
+    }
+    if let Some(external_id) = &filters.external_id {
+        validate_external_id(external_id)?;
+    }
+    if let Some(disk_id) = &filters.disk_id {
+        validate_id_string(disk_id, "filters.disk_id")?;
+    }
+    if let Some(extension) = &filters.extension {
+        validate_short_string(extension, "filters.extension")?;
+    }
+    if let Some(created_by) = &filters.created_by {
+        validate_user_id(created_by)?;
+    }
+
+    let ranges = [
+        ("filters.created_after", filters.created_after, filters.created_before),
+        ("filters.updated_after", filters.updated_after, filters.updated_before),
+        ("filters.min_file_size", filters.min_file_size, filters.max_file_size),
+    ];
+    for (field, low, high) in ranges {
+        if let (Some(low), Some(high)) = (low, high) {
+            if low > high {
+                return Err(ValidationError {
+                    field: field.to_string(),
+                    message: "Range start must not be after its end".to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
 
 fn default_page_size() -> usize {
     50
@@ -161,8 +233,9 @@ impl ReindexDriveRequestBody {
 #[derive(Debug, Clone, Serialize)]
 pub struct ReindexDriveResponseData {
     pub success: bool,
-    pub timestamp_ms: u64,
-    pub indexed_count: usize,
+    pub timestamp_ms: u64,     // when the last completed rebuild finished
+    pub indexed_count: usize,  // documents read so far by the running rebuild
+    pub in_progress: bool,
 }
 
 pub type ReindexDriveResponse<'a> = ApiResponse<'a, ReindexDriveResponseData>;
@@ -250,8 +323,24 @@ pub struct UpdateAllowedDomainsDriveRequestBody {
 }
 impl UpdateAllowedDomainsDriveRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
-        // Validate allowed_domains format
-        validate_short_string(&self.allowed_domains, "allowed_domains")?;
+        // Empty resets CORS to allow any origin
+        if self.allowed_domains.len() > 4096 {
+            return Err(ValidationError {
+                field: "allowed_domains".to_string(),
+                message: "allowed_domains must be 4096 characters or less".to_string(),
+            });
+        }
+
+        let domains = parse_allowed_domains(&self.allowed_domains).map_err(|entry| ValidationError {
+            field: "allowed_domains".to_string(),
+            message: format!("'{}' is not a valid origin, expected e.g. https://app.example.com or *.example.com", entry),
+        })?;
+        if domains.len() > 64 {
+            return Err(ValidationError {
+                field: "allowed_domains".to_string(),
+                message: "allowed_domains can list at most 64 origins".to_string(),
+            });
+        }
         Ok(())
     }
 }
@@ -260,10 +349,273 @@ impl UpdateAllowedDomainsDriveRequestBody {
 pub struct UpdateAllowedDomainsDriveResponseData {
     pub success: bool,
     pub message: String,
+    pub allowed_domains: String,
 }
 pub type UpdateAllowedDomainsDriveResponse<'a> = ApiResponse<'a, UpdateAllowedDomainsDriveResponseData>;
 
 
+pub type GetRateLimitsDriveResponse<'a> = ApiResponse<'a, RateLimitConfig>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateRateLimitsDriveRequestBody {
+    pub rules: Vec<RateLimitRule>,
+}
+impl UpdateRateLimitsDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        // Leaving a group out turns its rate limit off
+        let mut seen_groups = Vec::new();
+        for rule in &self.rules {
+            if seen_groups.contains(&rule.group) {
+                return Err(ValidationError {
+                    field: "rules".to_string(),
+                    message: format!("Duplicate rule for {}", rule.group),
+                });
+            }
+            seen_groups.push(rule.group);
+
+            if rule.burst == 0 || rule.burst > 100_000 {
+                return Err(ValidationError {
+                    field: "burst".to_string(),
+                    message: format!("burst for {} must be between 1 and 100000", rule.group),
+                });
+            }
+            if rule.per_minute == 0 || rule.per_minute > 100_000 {
+                return Err(ValidationError {
+                    field: "per_minute".to_string(),
+                    message: format!("per_minute for {} must be between 1 and 100000", rule.group),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+pub type UpdateRateLimitsDriveResponse<'a> = ApiResponse<'a, RateLimitConfig>;
+
+
+pub type GetIdempotencyDriveResponse<'a> = ApiResponse<'a, IdempotencyConfig>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateIdempotencyDriveRequestBody {
+    pub window_ms: u64,
+}
+impl UpdateIdempotencyDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        // Between a minute and a week
+        if self.window_ms < 60_000 || self.window_ms > 7 * 24 * 60 * 60 * 1000 {
+            return Err(ValidationError {
+                field: "window_ms".to_string(),
+                message: "window_ms must be between 60000 (1 minute) and 604800000 (7 days)".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+pub type UpdateIdempotencyDriveResponse<'a> = ApiResponse<'a, IdempotencyConfig>;
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CyclesDriveResponseData {
+    pub report: CyclesReport,
+    pub config: CyclesMonitorConfig,
+    pub low_cycles_alerted_at_ms: Option<u64>,
+    pub samples: Vec<CyclesSample>, // newest first
+}
+pub type CyclesDriveResponse<'a> = ApiResponse<'a, CyclesDriveResponseData>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCyclesMonitorDriveRequestBody {
+    pub low_cycles_threshold: u64,
+    pub low_runway_days: u32,
+}
+impl UpdateCyclesMonitorDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        if self.low_runway_days > 365 {
+            return Err(ValidationError {
+                field: "low_runway_days".to_string(),
+                message: "low_runway_days must be at most 365".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+pub type UpdateCyclesMonitorDriveResponse<'a> = ApiResponse<'a, CyclesMonitorConfig>;
+
+
+fn validate_archive_id(archive_id: &str) -> Result<(), ValidationError> {
+    if archive_id.len() != 32 || !archive_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ValidationError {
+            field: "archive_id".to_string(),
+            message: "Archive ID must be 32 hex characters".to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Sealed parts are a little larger than their plaintext, and base64 adds a third
+fn validate_archive_part_base64(data_base64: &str, field: &str) -> Result<(), ValidationError> {
+    if data_base64.is_empty() || data_base64.len() > (COLD_ARCHIVE_SEGMENT_BYTES + 1024) * 4 / 3 + 4 {
+        return Err(ValidationError {
+            field: field.to_string(),
+            message: "Archive part is empty or too large".to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportArchiveDriveRequestBody {
+    // A new 24 word phrase is generated and returned when left out
+    pub seed_phrase: Option<String>,
+}
+impl ExportArchiveDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        if let Some(seed_phrase) = &self.seed_phrase {
+            validate_seed_phrase(seed_phrase)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportArchiveDriveResponseData {
+    pub header: ColdArchiveHeader,
+    pub total_parts: u32,
+    pub seed_phrase: Option<String>, // only when it was generated, it is not stored anywhere
+}
+pub type ExportArchiveDriveResponse<'a> = ApiResponse<'a, ExportArchiveDriveResponseData>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchivePartDriveRequestBody {
+    pub archive_id: String,
+    pub index: u32,
+}
+impl ArchivePartDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_archive_id(&self.archive_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivePartDriveResponseData {
+    pub archive_id: String,
+    pub index: u32,
+    pub total_parts: u32,
+    pub data_base64: String,
+}
+pub type ArchivePartDriveResponse<'a> = ApiResponse<'a, ArchivePartDriveResponseData>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportArchiveDriveRequestBody {
+    pub seed_phrase: String,
+    pub header_base64: String, // part 0
+}
+impl ImportArchiveDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_seed_phrase(&self.seed_phrase)?;
+        validate_archive_part_base64(&self.header_base64, "header_base64")
+    }
+}
+pub type ImportArchiveDriveResponse<'a> = ApiResponse<'a, ColdArchiveHeader>;
+pub type ArchiveImportProgressDriveResponse<'a> = ApiResponse<'a, ColdArchiveImportProgress>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadArchivePartDriveRequestBody {
+    pub archive_id: String,
+    pub index: u32,
+    pub data_base64: String,
+}
+impl UploadArchivePartDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_archive_id(&self.archive_id)?;
+        validate_archive_part_base64(&self.data_base64, "data_base64")
+    }
+}
+
+// Shared by activate and abort
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiveActionDriveRequestBody {
+    pub archive_id: String,
+}
+impl ArchiveActionDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_archive_id(&self.archive_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AbortArchiveImportDriveResponseData {
+    pub archive_id: String,
+    pub aborted: bool,
+}
+pub type AbortArchiveImportDriveResponse<'a> = ApiResponse<'a, AbortArchiveImportDriveResponseData>;
+
+
+fn validate_snapshot_id(snapshot_id: &str) -> Result<(), ValidationError> {
+    validate_id_string(snapshot_id, "snapshot_id")?;
+    if !snapshot_id.starts_with(IDPrefix::Snapshot.as_str()) {
+        return Err(ValidationError {
+            field: "snapshot_id".to_string(),
+            message: format!("Snapshot ID must start with '{}'", IDPrefix::Snapshot.as_str()),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartPagedSnapshotDriveResponseData {
+    pub marker: SnapshotMarker,
+    pub tables: Vec<SnapshotTableInfo>,
+    pub excluded_tables: Vec<String>, // stable tables no page covers
+}
+pub type StartPagedSnapshotDriveResponse<'a> = ApiResponse<'a, StartPagedSnapshotDriveResponseData>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PagedSnapshotDriveRequestBody {
+    pub snapshot_id: String,
+    pub cursor: Option<String>,
+    #[serde(default = "default_snapshot_page_size")]
+    pub page_size: usize,
+}
+
+fn default_snapshot_page_size() -> usize {
+    DEFAULT_SNAPSHOT_PAGE_SIZE
+}
+
+impl PagedSnapshotDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_snapshot_id(&self.snapshot_id)?;
+        if self.page_size == 0 || self.page_size > MAX_SNAPSHOT_PAGE_SIZE {
+            return Err(ValidationError {
+                field: "page_size".to_string(),
+                message: format!("Page size must be between 1 and {}", MAX_SNAPSHOT_PAGE_SIZE),
+            });
+        }
+        if let Some(cursor) = &self.cursor {
+            validate_short_string(cursor, "cursor")?;
+        }
+        Ok(())
+    }
+}
+pub type PagedSnapshotDriveResponse<'a> = ApiResponse<'a, SnapshotPage>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EndPagedSnapshotDriveRequestBody {
+    pub snapshot_id: String,
+}
+impl EndPagedSnapshotDriveRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_snapshot_id(&self.snapshot_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndPagedSnapshotDriveResponseData {
+    pub snapshot_id: String,
+    pub ended: bool,
+}
+pub type EndPagedSnapshotDriveResponse<'a> = ApiResponse<'a, EndPagedSnapshotDriveResponseData>;
+
+
 #[derive(Debug, Clone, Serialize)]
 pub struct WhoAmIReport {
     pub nickname: String,
@@ -346,14 +698,41 @@ pub struct InboxOrgRequestBody {
     pub drive_id: Option<DriveID>,
     pub topic: Option<String>,
     pub payload: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>, // UserID or GroupID, defaults to the drive owner
 }
 impl InboxOrgRequestBody {
     pub fn validate_body(&self) -> Result<(), ValidationError> {
-        // nothing is validated, as the payload can be any shape
+        // the payload can be any shape, only its size is checked
+        if let Some(payload) = &self.payload {
+            if payload.to_string().len() > INBOX_MAX_PAYLOAD_BYTES {
+                return Err(ValidationError {
+                    field: "payload".to_string(),
+                    message: format!("Payload must be {} bytes or less", INBOX_MAX_PAYLOAD_BYTES),
+                });
+            }
+        }
+
+        if let Some(topic) = &self.topic {
+            validate_short_string(topic, "topic")?;
+        }
+
+        if let Some(recipient) = &self.recipient {
+            validate_id_string(recipient, "recipient")?;
+            if !InboxRecipientID(recipient.clone()).is_valid() {
+                return Err(ValidationError {
+                    field: "recipient".to_string(),
+                    message: "Recipient must be a UserID or GroupID".to_string(),
+                });
+            }
+        }
+
         Ok(())
     }
 }
 
+pub const INBOX_MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InboxOrgResponseData {
     pub inbox_notif_id: InboxNotifID,
@@ -364,6 +743,200 @@ pub struct InboxOrgResponseData {
 pub type InboxOrgResponse<'a> = ApiResponse<'a, InboxOrgResponseData>;
 
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListInboxOrgRequestBody {
+    #[serde(default)]
+    pub recipient: Option<String>,
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub unread_only: bool,
+    #[serde(default = "default_inbox_page_size")]
+    pub page_size: usize,
+    #[serde(default)]
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+}
+
+fn default_inbox_page_size() -> usize {
+    50
+}
+
+impl ListInboxOrgRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        if let Some(recipient) = &self.recipient {
+            validate_id_string(recipient, "recipient")?;
+        }
+
+        if let Some(topic) = &self.topic {
+            validate_short_string(topic, "topic")?;
+        }
+
+        if self.page_size == 0 || self.page_size > 1000 {
+            return Err(ValidationError {
+                field: "page_size".to_string(),
+                message: "Page size must be between 1 and 1000".to_string(),
+            });
+        }
+
+        if let Some(cursor) = &self.cursor {
+            if cursor.len() > 256 {
+                return Err(ValidationError {
+                    field: "cursor".to_string(),
+                    message: "Cursor must be 256 characters or less".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListInboxOrgResponseData {
+    pub items: Vec<InboxNotif>,
+    pub page_size: usize,
+    pub total: usize,
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+}
+pub type ListInboxOrgResponse<'a> = ApiResponse<'a, ListInboxOrgResponseData>;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAuditOrgRequestBody {
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub action: Option<AuditActionEnum>,
+    #[serde(default)]
+    pub resource_id: Option<String>,
+    #[serde(default)]
+    pub route: Option<String>, // substring match, eg. "/permissions/"
+    #[serde(default)]
+    pub after_ms: Option<u64>,
+    #[serde(default)]
+    pub before_ms: Option<u64>,
+    #[serde(default = "default_audit_page_size")]
+    pub page_size: usize,
+    #[serde(default)]
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+}
+
+fn default_audit_page_size() -> usize {
+    50
+}
+
+impl ListAuditOrgRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        if let Some(actor) = &self.actor {
+            validate_user_id(actor)?;
+        }
+
+        if let Some(resource_id) = &self.resource_id {
+            validate_id_string(resource_id, "resource_id")?;
+        }
+
+        if let Some(route) = &self.route {
+            validate_short_string(route, "route")?;
+        }
+
+        if let (Some(after_ms), Some(before_ms)) = (self.after_ms, self.before_ms) {
+            if after_ms > before_ms {
+                return Err(ValidationError {
+                    field: "after_ms".to_string(),
+                    message: "after_ms must not be later than before_ms".to_string(),
+                });
+            }
+        }
+
+        if self.page_size == 0 || self.page_size > 1000 {
+            return Err(ValidationError {
+                field: "page_size".to_string(),
+                message: "Page size must be between 1 and 1000".to_string(),
+            });
+        }
+
+        if let Some(cursor) = &self.cursor {
+            if cursor.len() > 256 {
+                return Err(ValidationError {
+                    field: "cursor".to_string(),
+                    message: "Cursor must be 256 characters or less".to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAuditOrgResponseData {
+    pub items: Vec<AuditEntry>,
+    pub page_size: usize,
+    pub total: usize, // entries currently retained, before filtering
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+}
+pub type ListAuditOrgResponse<'a> = ApiResponse<'a, ListAuditOrgResponseData>;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkReadInboxOrgRequestBody {
+    pub ids: Vec<String>,
+    #[serde(default = "default_mark_read")]
+    pub read: bool, // false marks them unread again
+}
+
+fn default_mark_read() -> bool {
+    true
+}
+
+impl MarkReadInboxOrgRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_inbox_notif_ids(&self.ids)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkReadInboxOrgResponseData {
+    pub updated: Vec<InboxNotifID>,
+}
+pub type MarkReadInboxOrgResponse<'a> = ApiResponse<'a, MarkReadInboxOrgResponseData>;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteInboxOrgRequestBody {
+    pub ids: Vec<String>,
+}
+
+impl DeleteInboxOrgRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_inbox_notif_ids(&self.ids)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteInboxOrgResponseData {
+    pub deleted: Vec<InboxNotifID>,
+}
+pub type DeleteInboxOrgResponse<'a> = ApiResponse<'a, DeleteInboxOrgResponseData>;
+
+fn validate_inbox_notif_ids(ids: &[String]) -> Result<(), ValidationError> {
+    if ids.is_empty() || ids.len() > 100 {
+        return Err(ValidationError {
+            field: "ids".to_string(),
+            message: "Between 1 and 100 ids are required".to_string(),
+        });
+    }
+    for id in ids {
+        validate_id_string(id, "ids")?;
+    }
+    Ok(())
+}
+
+
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AboutDriveResponseData {
@@ -374,6 +947,7 @@ pub struct AboutDriveResponseData {
     pub host: String,
     pub canister_id: String,
     pub daily_idle_cycle_burn_rate: String,
+    pub daily_cycle_burn_rate: String, // measured from balance samples, includes update calls
     pub controllers: Vec<String>,
     pub version: String,
 }
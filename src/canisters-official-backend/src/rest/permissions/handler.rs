@@ -5,9 +5,12 @@ pub mod permissions_handlers {
     use std::collections::HashSet;
 
     use crate::{
-        core::{api::{permissions::{directory::{can_user_access_directory_permission, check_directory_permissions, get_inherited_resources_list, has_directory_manage_permission, parse_directory_resource_id, parse_permission_grantee_id}, system::{can_user_access_system_permission, check_permissions_table_access, check_system_permissions, has_system_manage_permission}}, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::DriveFullFilePath}, drives::{state::state::{update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, groups::state::state::{is_group_admin, is_user_on_group}, labels::types::redact_label, permissions::{state::{helpers::{remove_system_permission_from_grantee, remove_system_permission_from_resource, update_system_permissions_time_list}, state::{DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE, DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE, DIRECTORY_PERMISSIONS_BY_TIME_LIST, SYSTEM_GRANTEE_PERMISSIONS_HASHTABLE, SYSTEM_PERMISSIONS_BY_ID_HASHTABLE, SYSTEM_PERMISSIONS_BY_RESOURCE_HASHTABLE, SYSTEM_PERMISSIONS_BY_TIME_LIST}}, types::{DirectoryPermission, DirectoryPermissionID, DirectoryPermissionIDList, DirectoryPermissionType, PermissionGranteeID, PlaceholderPermissionGranteeID, SystemPermission, SystemPermissionID, SystemPermissionIDList, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}}, types::{IDPrefix, UserID}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, directory::types::DirectoryResourceID, permissions::types::{CheckPermissionResponse, CheckPermissionResult, CheckSystemPermissionResponse, CheckSystemPermissionResult, CreateDirectoryPermissionsRequestBody, CreateDirectoryPermissionsResponseData, CreatePermissionsResponse, CreateSystemPermissionsRequestBody, CreateSystemPermissionsResponse, CreateSystemPermissionsResponseData, DeletePermissionRequest, DeletePermissionResponse, DeletePermissionResponseData, DeleteSystemPermissionRequest, DeleteSystemPermissionResponse, DeleteSystemPermissionResponseData, ErrorResponse, GetPermissionResponse, GetSystemPermissionResponse, ListDirectoryPermissionsRequestBody, ListDirectoryPermissionsResponse, ListDirectoryPermissionsResponseData, ListSystemPermissionsRequestBody, ListSystemPermissionsRequestBodyFilters, ListSystemPermissionsResponse, ListSystemPermissionsResponseData, PermissionCheckRequest, RedeemPermissionRequest, RedeemPermissionResponse, RedeemPermissionResponseData, RedeemSystemPermissionRequest, RedeemSystemPermissionResponse, RedeemSystemPermissionResponseData, SystemPermissionCheckRequest, UpdateDirectoryPermissionsRequestBody, UpdateDirectoryPermissionsResponseData, UpdatePermissionsResponse, UpdateSystemPermissionsRequestBody, UpdateSystemPermissionsResponse, UpdateSystemPermissionsResponseData}, webhooks::types::SortDirection},
+        core::{api::{permissions::{directory::{can_user_access_directory_permission, check_directory_permissions, get_inherited_resources_list, hash_directory_password_metadata, has_directory_manage_permission, parse_directory_resource_id, parse_permission_grantee_id}, system::{can_user_access_system_permission, check_permissions_table_access, check_system_permissions, has_system_manage_permission}}, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}}, state::{directory::{state::state::{file_uuid_to_metadata, folder_uuid_to_metadata}, types::DriveFullFilePath}, drives::{state::state::{update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, groups::state::state::{is_group_admin, is_user_on_group}, labels::types::redact_label, permissions::{state::{helpers::{remove_system_permission_from_grantee, remove_system_permission_from_resource, update_system_permissions_time_list}, state::{DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE, DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE, DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE, DIRECTORY_PERMISSIONS_BY_TIME_LIST, SYSTEM_GRANTEE_PERMISSIONS_HASHTABLE, SYSTEM_PERMISSIONS_BY_ID_HASHTABLE, SYSTEM_PERMISSIONS_BY_RESOURCE_HASHTABLE, SYSTEM_PERMISSIONS_BY_TIME_LIST}}, types::{DirectoryPermission, DirectoryPermissionID, DirectoryPermissionIDList, DirectoryPermissionType, PermissionGranteeID, PlaceholderPermissionGranteeID, SystemPermission, SystemPermissionID, SystemPermissionIDList, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}}, search::{state::state::index_search_resource, types::SearchResultResourceID}}, types::{IDPrefix, UserID}}, debug_log, rest::{auth::{authenticate_request, create_auth_error_response}, directory::types::DirectoryResourceID, permissions::types::{CheckPermissionResponse, CheckPermissionResult, CheckSystemPermissionResponse, CheckSystemPermissionResult, CreateDirectoryPermissionsRequestBody, CreateDirectoryPermissionsResponseData, CreatePermissionsResponse, CreateSystemPermissionsRequestBody, CreateSystemPermissionsResponse, CreateSystemPermissionsResponseData, DeletePermissionRequest, DeletePermissionResponse, DeletePermissionResponseData, DeleteSystemPermissionRequest, DeleteSystemPermissionResponse, DeleteSystemPermissionResponseData, ErrorResponse, GetPermissionResponse, GetSystemPermissionResponse, ListDirectoryPermissionsRequestBody, ListDirectoryPermissionsResponse, ListDirectoryPermissionsResponseData, ListSystemPermissionsRequestBody, ListSystemPermissionsRequestBodyFilters, ListSystemPermissionsResponse, ListSystemPermissionsResponseData, PermissionCheckRequest, RedeemPermissionRequest, RedeemPermissionResponse, RedeemPermissionResponseData, RedeemSystemPermissionRequest, RedeemSystemPermissionResponse, RedeemSystemPermissionResponseData, SystemPermissionCheckRequest, UpdateDirectoryPermissionsRequestBody, UpdateDirectoryPermissionsResponseData, UpdatePermissionsResponse, UpdateSystemPermissionsRequestBody, UpdateSystemPermissionsResponse, UpdateSystemPermissionsResponseData}, webhooks::types::SortDirection},
         
     };
+    use crate::core::state::audit::{state::state::{audit_summary_directory_permission, audit_summary_system_permission}, types::AuditActionEnum};
+    use crate::rest::helpers::record_audit_event;
+    use crate::rest::types::ErrorCode;
     use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
     use matchit::Params;
     use serde::Deserialize;
@@ -88,7 +91,7 @@ pub mod permissions_handlers {
         if let Err(e) = check_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
 
@@ -339,7 +342,7 @@ pub mod permissions_handlers {
         if let Err(e) = upsert_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
 
@@ -473,7 +476,7 @@ pub mod permissions_handlers {
             created_at: current_time,
             last_modified_at: current_time,
             from_placeholder_grantee: None,
-            metadata: upsert_request.metadata.clone(),
+            metadata: hash_directory_password_metadata(upsert_request.metadata.clone()),
             redeem_code,
             labels: vec![],
             external_id: Some(ExternalID(upsert_request.external_id.clone().unwrap_or_default())),
@@ -486,6 +489,7 @@ pub mod permissions_handlers {
         DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().insert(permission_id.clone(), new_permission.clone());
         });
+        index_search_resource(SearchResultResourceID::DirectoryPermission(permission_id.clone()));
 
         debug_log!("update state indices");
 
@@ -541,6 +545,8 @@ pub mod permissions_handlers {
 
         mark_claimed_uuid(&permission_id.clone().to_string());
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DirectoryPermissionCreate, &permission_id, None, Some(audit_summary_directory_permission(&new_permission)));
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Create Directory Permission {}", 
@@ -580,7 +586,7 @@ pub mod permissions_handlers {
         if let Err(e) = upsert_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
     
@@ -646,6 +652,7 @@ pub mod permissions_handlers {
     
         let current_time = ic_cdk::api::time() / 1_000_000; // Convert from ns to ms
 
+        let audit_before = audit_summary_directory_permission(&existing_permission);
         let prestate = snapshot_prestate();
     
         // Update modifiable fields
@@ -669,7 +676,7 @@ pub mod permissions_handlers {
             existing_permission.note = upsert_request.note.unwrap_or_default();
         }      
         if (upsert_request.metadata.is_some()) {
-            existing_permission.metadata = upsert_request.metadata.clone();
+            existing_permission.metadata = hash_directory_password_metadata(upsert_request.metadata.clone());
         }
         existing_permission.last_modified_at = current_time;
 
@@ -677,6 +684,9 @@ pub mod permissions_handlers {
         DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().insert(id.clone(), existing_permission.clone());
         });
+        index_search_resource(SearchResultResourceID::DirectoryPermission(id.clone()));
+
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DirectoryPermissionUpdate, &id, Some(audit_before), Some(audit_summary_directory_permission(&existing_permission)));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -716,7 +726,7 @@ pub mod permissions_handlers {
         if let Err(e) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
     
@@ -766,6 +776,7 @@ pub mod permissions_handlers {
         DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().remove(&delete_request.permission_id);
         });
+        index_search_resource(SearchResultResourceID::DirectoryPermission(delete_request.permission_id.clone()));
 
         // Remove from DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE
         DIRECTORY_PERMISSIONS_BY_RESOURCE_HASHTABLE.with(|permissions_by_resource| {
@@ -838,6 +849,8 @@ pub mod permissions_handlers {
             *permissions_by_time.borrow_mut() = new_list;
         });
     
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DirectoryPermissionDelete, &delete_request.permission_id, Some(audit_summary_directory_permission(&permission)), None);
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Delete Directory Permission {}", 
@@ -874,7 +887,7 @@ pub mod permissions_handlers {
         if let Err(e) = redeem_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
      
@@ -897,8 +910,8 @@ pub mod permissions_handlers {
             PermissionGranteeID::PlaceholderDirectoryPermissionGrantee(link_id) => {
                 if permission.from_placeholder_grantee.is_some() {
                     return create_response(
-                        StatusCode::BAD_REQUEST,
-                        ErrorResponse::err(400, "Permission has already been redeemed".to_string()).encode()
+                        StatusCode::CONFLICT,
+                        ErrorResponse::error(ErrorCode::Conflict, "Permission has already been redeemed".to_string()).encode()
                     );
                 }
                 
@@ -941,6 +954,7 @@ pub mod permissions_handlers {
             );
         }
 
+        let audit_before = audit_summary_directory_permission(&permission);
         let prestate = snapshot_prestate();
     
         // 6. Update permission and state
@@ -954,6 +968,7 @@ pub mod permissions_handlers {
         DIRECTORY_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().insert(permission_id.clone(), permission.clone());
         });
+        index_search_resource(SearchResultResourceID::DirectoryPermission(permission_id.clone()));
     
         // Update grantee permissions - remove old, add new
         DIRECTORY_GRANTEE_PERMISSIONS_HASHTABLE.with(|grantee_permissions| {
@@ -984,6 +999,8 @@ pub mod permissions_handlers {
             table.insert(new_grantee, new_grantee_list);
         });
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::DirectoryPermissionRedeem, &permission_id, Some(audit_before), Some(audit_summary_directory_permission(&permission)));
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Redeem Directory Permission {}", 
@@ -1340,7 +1357,7 @@ pub mod permissions_handlers {
         if let Err(e) = upsert_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
 
@@ -1456,6 +1473,7 @@ pub mod permissions_handlers {
         SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().insert(permission_id.clone(), new_permission.clone());
         });
+        index_search_resource(SearchResultResourceID::SystemPermission(permission_id.clone()));
 
         debug_log!("SYSTEM_PERMISSIONS_BY_ID_HASHTABLE");
 
@@ -1533,6 +1551,8 @@ pub mod permissions_handlers {
 
         debug_log!("update_external_id_mapping");
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::SystemPermissionCreate, &permission_id, None, Some(audit_summary_system_permission(&new_permission)));
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Create System Permission {}", 
@@ -1574,7 +1594,7 @@ pub mod permissions_handlers {
         if let Err(e) = upsert_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
     
@@ -1606,6 +1626,7 @@ pub mod permissions_handlers {
         }
         
 
+        let audit_before = audit_summary_system_permission(&existing_permission);
         let prestate = snapshot_prestate();
 
         // Update modifiable fields
@@ -1641,6 +1662,9 @@ pub mod permissions_handlers {
         SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().insert(id.clone(), existing_permission.clone());
         });
+        index_search_resource(SearchResultResourceID::SystemPermission(id.clone()));
+
+        record_audit_event(request, &requester_api_key, AuditActionEnum::SystemPermissionUpdate, &id, Some(audit_before), Some(audit_summary_system_permission(&existing_permission)));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -1681,7 +1705,7 @@ pub mod permissions_handlers {
         if let Err(e) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
     
@@ -1720,6 +1744,7 @@ pub mod permissions_handlers {
         {SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().remove(&delete_request.permission_id);
         });}
+        index_search_resource(SearchResultResourceID::SystemPermission(delete_request.permission_id.clone()));
         
     
         debug_log!("Delete request resource_id {:?}", permission.resource_id.clone());
@@ -1737,6 +1762,8 @@ pub mod permissions_handlers {
             Some(old_internal_id),
         );
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::SystemPermissionDelete, &delete_request.permission_id, Some(audit_summary_system_permission(&permission)), None);
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Delete System Permission {}", 
@@ -1778,7 +1805,7 @@ pub mod permissions_handlers {
         if let Err(e) = check_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
     
@@ -1883,7 +1910,7 @@ pub mod permissions_handlers {
         if let Err(e) = redeem_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, e.message).encode()
+                ErrorResponse::validation_failed(&e).encode()
             );
         }
     
@@ -1906,8 +1933,8 @@ pub mod permissions_handlers {
             PermissionGranteeID::PlaceholderDirectoryPermissionGrantee(link_id) => {
                 if permission.from_placeholder_grantee.is_some() {
                     return create_response(
-                        StatusCode::BAD_REQUEST,
-                        ErrorResponse::err(400, "Permission has already been redeemed".to_string()).encode()
+                        StatusCode::CONFLICT,
+                        ErrorResponse::error(ErrorCode::Conflict, "Permission has already been redeemed".to_string()).encode()
                     );
                 }
                 
@@ -1950,6 +1977,7 @@ pub mod permissions_handlers {
             );
         }
 
+        let audit_before = audit_summary_system_permission(&permission);
         let prestate = snapshot_prestate();
     
         // 6. Update permission and state
@@ -1962,10 +1990,13 @@ pub mod permissions_handlers {
         SYSTEM_PERMISSIONS_BY_ID_HASHTABLE.with(|permissions| {
             permissions.borrow_mut().insert(permission_id.clone(), permission.clone());
         });
+        index_search_resource(SearchResultResourceID::SystemPermission(permission_id.clone()));
     
         // Update grantee permissions - remove old, add new
         remove_system_permission_from_grantee(&old_grantee, &permission_id);
 
+        record_audit_event(request, &requester_api_key, AuditActionEnum::SystemPermissionRedeem, &permission_id, Some(audit_before), Some(audit_summary_system_permission(&permission)));
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Redeem System Permission {}", 
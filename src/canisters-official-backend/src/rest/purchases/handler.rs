@@ -14,7 +14,7 @@ pub mod purchases_handlers {
                     types::{Purchase, PurchaseID, PurchaseStatus},
                 },
                 permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum},
-                
+                search::{state::state::index_search_resource, types::SearchResultResourceID},
             },
             types::{IDPrefix, UserID},
         }, debug_log, rest::{
@@ -97,7 +97,7 @@ pub mod purchases_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -250,7 +250,7 @@ pub mod purchases_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -305,6 +305,7 @@ pub mod purchases_handlers {
         PURCHASES_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(purchase_id.clone(), purchase.clone());
         });
+        index_search_resource(SearchResultResourceID::Purchase(purchase_id.clone()));
 
         PURCHASES_BY_TIME_LIST.with(|store| {
             store.borrow_mut().push(&purchase_id.clone());
@@ -348,7 +349,7 @@ pub mod purchases_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -457,6 +458,7 @@ pub mod purchases_handlers {
         PURCHASES_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(purchase_id.clone(), purchase.clone());
         });
+        index_search_resource(SearchResultResourceID::Purchase(purchase_id.clone()));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -493,7 +495,7 @@ pub mod purchases_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -527,6 +529,7 @@ pub mod purchases_handlers {
         PURCHASES_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&purchase_id);
         });
+        index_search_resource(SearchResultResourceID::Purchase(purchase_id.clone()));
 
         // Rebuild StableVec for PURCHASES_BY_TIME_LIST to remove the item
         PURCHASES_BY_TIME_LIST.with(|store| {
@@ -1,15 +1,19 @@
 // src/rest/router.rs
-use crate::{debug_log, rest::helpers};
+use crate::{core::state::{api_keys::types::ApiKey, idempotency::{state::state::{begin_idempotent_request, finish_idempotent_request}, types::{IdempotencyLookup, IdempotencyRecord}}}, debug_log, rest::{api_keys::types::ErrorResponse, auth, helpers, types::ErrorCode}};
 use crate::rest::types::RouteHandler;
 use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
 use matchit::{Params, Router};
-use std::{cell::RefCell, collections::HashMap};
+use sha2::{Digest, Sha256};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
 
 thread_local! {
     static ROUTER: RefCell<HashMap<String, Router<RouteHandler>>> = RefCell::new(HashMap::new());
 }
 
 const WILDCARD_PATH: &str = "/*";
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const IDEMPOTENT_METHODS: &[&str] = &["POST", "PUT", "PATCH", "DELETE"];
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
 
 macro_rules! genroute {
     ($path:expr) => {
@@ -62,9 +66,68 @@ pub fn init_routes() {
 }
 
 pub async fn handle_request(req: HttpRequest<'_>) -> HttpResponse<'static> {
+    let response = dispatch_request(&req).await;
+    auth::forget_request_auth();
+    helpers::apply_cors_headers(&req, response)
+}
+
+async fn dispatch_request(req: &HttpRequest<'_>) -> HttpResponse<'static> {
 
     debug_log!("Handling request...");
 
+    // Credentials are resolved once here, handlers calling authenticate_request reuse them
+    let auth_result = auth::resolve_request_auth(req);
+    auth::remember_request_auth(req, &auth_result);
+
+    // Handlers treat an out of scope key as no key, so the 403 is answered before routing
+    if let auth::AuthResult::OutOfScope(_) = auth_result {
+        return auth::create_scope_error_response();
+    }
+
+    if let Some(response) = auth::rate_limit_response(req, &auth_result) {
+        return response;
+    }
+
+    // Mutating requests sent with an Idempotency-Key run once, repeats get the first response back
+    let scope_key = match idempotency_scope_key(req, auth_result.api_key()) {
+        Ok(Some(scope_key)) => scope_key,
+        Ok(None) => return route_request(req).await,
+        Err(response) => return response,
+    };
+    let request_hash = idempotency_request_hash(req);
+    match begin_idempotent_request(&scope_key, &request_hash) {
+        IdempotencyLookup::Miss => {},
+        IdempotencyLookup::Replay(record) => return replay_idempotent_response(record),
+        IdempotencyLookup::Conflict => return helpers::create_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            String::from_utf8(ErrorResponse::error(ErrorCode::IdempotencyKeyReused, "Idempotency-Key was already used with a different request".to_string()).encode())
+                .unwrap_or_else(|_| String::from("Unprocessable Entity")),
+        ),
+        IdempotencyLookup::InFlight => return helpers::create_response(
+            StatusCode::CONFLICT,
+            String::from_utf8(ErrorResponse::error(ErrorCode::Conflict, "A request with this Idempotency-Key is still in progress".to_string()).encode())
+                .unwrap_or_else(|_| String::from("Conflict")),
+        ),
+    }
+
+    let response = route_request(req).await;
+    // Server errors are worth retrying for real, so they are not kept
+    let record = if response.status_code().is_server_error() {
+        None
+    } else {
+        Some(IdempotencyRecord {
+            request_hash,
+            status_code: response.status_code().as_u16(),
+            headers: response.headers().to_vec(),
+            body: response.body().to_vec(),
+            created_at_ms: ic_cdk::api::time() / 1_000_000,
+        })
+    };
+    finish_idempotent_request(&scope_key, record);
+    response
+}
+
+async fn route_request(req: &HttpRequest<'_>) -> HttpResponse<'static> {
     let req_path: String = match req.get_path() {
         Ok(path) => path,
         Err(_) => return helpers::not_found_response(),
@@ -88,13 +151,53 @@ pub async fn handle_request(req: HttpRequest<'_>) -> HttpResponse<'static> {
     match method_router.at(&req_path) {
         Ok(handler_match) => {
             let handler = handler_match.value;
-            let future = handler(&req, &handler_match.params);
+            let future = handler(req, &handler_match.params);
             future.await
         },
         Err(_) => helpers::not_found_response()
     }
 }
 
+/// "{user_id}:{key}" for an authenticated mutating request with an Idempotency-Key header.
+/// Unauthenticated requests are left to their handler, which answers 401 anyway.
+fn idempotency_scope_key(req: &HttpRequest, api_key: Option<&ApiKey>) -> Result<Option<String>, HttpResponse<'static>> {
+    if !IDEMPOTENT_METHODS.iter().any(|method| req.method().as_str().eq_ignore_ascii_case(method)) {
+        return Ok(None);
+    }
+    let key = match req.headers().iter().find(|(name, _)| name.eq_ignore_ascii_case(IDEMPOTENCY_KEY_HEADER)) {
+        Some((_, value)) => value.trim(),
+        None => return Ok(None),
+    };
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        return Err(helpers::create_response(
+            StatusCode::BAD_REQUEST,
+            String::from_utf8(ErrorResponse::err(400, format!("Idempotency-Key must be 1 to {} characters", MAX_IDEMPOTENCY_KEY_LENGTH)).encode())
+                .unwrap_or_else(|_| String::from("Bad Request")),
+        ));
+    }
+    Ok(api_key.map(|api_key| format!("{}:{}", api_key.user_id, key)))
+}
+
+fn idempotency_request_hash(req: &HttpRequest) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str().to_uppercase().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(req.url().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(req.body());
+    hex::encode(hasher.finalize())
+}
+
+fn replay_idempotent_response(record: IdempotencyRecord) -> HttpResponse<'static> {
+    let mut headers = record.headers;
+    headers.push(("Idempotent-Replayed".to_string(), "true".to_string()));
+    HttpResponse::builder()
+        .with_status_code(StatusCode::from_u16(record.status_code).unwrap_or(StatusCode::OK))
+        .with_headers(headers)
+        .with_body(Cow::Owned(record.body))
+        .build()
+}
+
 pub(crate) fn insert_route(method: &str, path: &str, route_handler: RouteHandler) {
     ROUTER.with_borrow_mut(|router| {
         let method_router = router.entry(method.to_string()).or_default();
@@ -109,13 +212,13 @@ pub async fn handle_options_request<'a, 'k, 'v>(
 ) -> HttpResponse<'static> {
     debug_log!("Handling OPTIONS request");
 
+    // Access-Control-Allow-Origin is reflected from the Origin header in handle_request
     let headers = vec![
-        ("Access-Control-Allow-Origin".to_string(), "*".to_string()),
         ("Access-Control-Max-Age".to_string(), "86400".to_string()),
         // ("Access-Control-Allow-Methods".to_string(), "GET, POST, PUT, DELETE, OPTIONS".to_string()),
         // ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key".to_string()),
     ("Access-Control-Allow-Methods".to_string(), "GET, POST, PUT, DELETE, OPTIONS".to_string()),
-    ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key, Authorization".to_string()),
+    ("Access-Control-Allow-Headers".to_string(), "Content-Type, Api-Key, Authorization, Idempotency-Key, X-Directory-Password".to_string()),
     ];
 
     HttpResponse::builder()
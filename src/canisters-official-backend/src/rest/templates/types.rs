@@ -4,35 +4,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::core::{state::templates::types::{TemplateID, TemplateItem}, types::ClientSuggestedUUID};
 
-#[derive(Debug, Clone, Serialize)]
-pub enum TemplateResponse<'a, T = ()> {
-    #[serde(rename = "ok")]
-    Ok { data: &'a T },
-    #[serde(rename = "err")]
-    Err { code: u16, message: String },
-}
-
-impl<'a, T: Serialize> TemplateResponse<'a, T> {
-    pub fn ok(data: &'a T) -> TemplateResponse<T> {
-        Self::Ok { data }
-    }
-
-    pub fn not_found() -> Self {
-        Self::err(404, "Not found".to_string())
-    }
-
-    pub fn unauthorized() -> Self {
-        Self::err(401, "Unauthorized".to_string())
-    }
-
-    pub fn err(code: u16, message: String) -> Self {
-        Self::Err { code, message }
-    }
-
-    pub fn encode(&self) -> Vec<u8> {
-        serde_json::to_vec(self).expect("Failed to serialize value")
-    }
-}
+// Same shape as every other route, including error codes
+pub type TemplateResponse<'a, T = ()> = crate::rest::types::ApiResponse<'a, T>;
 
 
 
@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 use ic_http_certification::{HttpRequest, HttpResponse};
 use matchit::Params;
-use candid::Principal;
+use candid::{CandidType, Principal};
 use serde_diff::SerdeDiff;
 use std::{fmt, str::FromStr};
 
@@ -21,7 +21,13 @@ pub enum ApiResponse<'a, T = ()> {
     #[serde(rename = "ok")]
     Ok { data: &'a T },
     #[serde(rename = "err")]
-    Err { code: u16, message: String },
+    Err {
+        code: u16,
+        error_code: ErrorCode,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        field: Option<String>,
+    },
 }
 
 impl<'a, T: Serialize> ApiResponse<'a, T> {
@@ -30,34 +36,47 @@ impl<'a, T: Serialize> ApiResponse<'a, T> {
     }
 
     pub fn not_found() -> Self {
-        Self::err(404, "Not found".to_string())
+        Self::error(ErrorCode::NotFound, "Not found".to_string())
     }
 
     pub fn unauthorized() -> Self {
-        Self::err(401, "Unauthorized".to_string())
+        Self::error(ErrorCode::Unauthorized, "Unauthorized".to_string())
     }
 
     pub fn forbidden() -> Self {
-        Self::err(403, "Forbidden".to_string())
+        Self::error(ErrorCode::PermissionDenied, "Forbidden".to_string())
     }
 
     pub fn bad_request(message: String) -> Self {
-        Self::err(400, message)
+        Self::error(ErrorCode::InvalidRequest, message)
     }
 
     pub fn server_error(message: String) -> Self {
-        Self::err(500, message)
+        Self::error(ErrorCode::InternalError, message)
     }
 
+    /// Picks the error code from the HTTP status, prefer `error` when a more specific code applies
     pub fn err(code: u16, message: String) -> Self {
-        Self::Err { code, message }
+        Self::Err { code, error_code: ErrorCode::from_status(code), message, field: None }
+    }
+
+    pub fn error(error_code: ErrorCode, message: String) -> Self {
+        Self::Err { code: error_code.status(), error_code, message, field: None }
+    }
+
+    pub fn validation_failed(validation_error: &ValidationError) -> Self {
+        Self::Err {
+            code: ErrorCode::ValidationFailed.status(),
+            error_code: ErrorCode::ValidationFailed,
+            message: format!("Validation error: {}: {}", validation_error.field, validation_error.message),
+            field: Some(validation_error.field.clone()),
+        }
     }
 
     pub fn encode(&self) -> Vec<u8> {
         serde_json::to_vec(self).unwrap_or_else(|_| 
             // Explicitly specify the type parameter
-            serde_json::to_vec(&ApiResponse::<()>::err(
-                500, 
+            serde_json::to_vec(&ApiResponse::<()>::server_error(
                 "Failed to serialize response".to_string()
             ))
             .unwrap_or_default()
@@ -65,6 +84,59 @@ impl<'a, T: Serialize> ApiResponse<'a, T> {
     }
 }
 
+/// Stable machine readable error codes sent as `error_code` next to the HTTP status.
+/// Clients should branch on these, messages are for humans and may change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, CandidType)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidRequest,        // malformed body, query or path
+    ValidationFailed,      // well formed but a field is invalid, see `field`
+    Unauthorized,          // missing or unknown credentials, also owner only routes
+    PermissionDenied,      // authenticated but not allowed, including api key scopes
+    NotFound,
+    NameConflict,          // a file, folder or label with that name already exists
+    Conflict,              // the resource is in a state that does not allow this, eg. already redeemed
+    IdempotencyKeyReused,  // same Idempotency-Key, different request
+    DriveChanged,          // the drive changed under a paged snapshot, start a new one
+    RateLimited,
+    DiskQuotaExceeded,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidRequest => 400,
+            ErrorCode::ValidationFailed => 400,
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::PermissionDenied => 403,
+            ErrorCode::NotFound => 404,
+            ErrorCode::NameConflict => 409,
+            ErrorCode::Conflict => 409,
+            ErrorCode::IdempotencyKeyReused => 422,
+            ErrorCode::DriveChanged => 409,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::DiskQuotaExceeded => 507,
+            ErrorCode::InternalError => 500,
+        }
+    }
+
+    /// Fallback for errors that only carry a status
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::PermissionDenied,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::Conflict,
+            422 => ErrorCode::ValidationFailed,
+            429 => ErrorCode::RateLimited,
+            507 => ErrorCode::DiskQuotaExceeded,
+            status if status >= 500 => ErrorCode::InternalError,
+            _ => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
     // Add ValidationError struct
 #[derive(Debug, Clone)]
 pub struct ValidationError {
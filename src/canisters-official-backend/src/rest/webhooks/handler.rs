@@ -6,15 +6,15 @@ pub mod webhooks_handlers {
 
     use crate::{
         core::{
-            api::{permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}},
-            state::{drives::{state::state::{update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, webhooks::{
-                state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE, WEBHOOKS_BY_TIME_LIST, WEBHOOKS_BY_TIME_MEMORY_ID}, types::{Webhook, WebhookAltIndexID, WebhookEventLabel, WebhookID, WebhookIDList}
+            api::{permissions::system::check_system_permissions, replay::diff::{snapshot_poststate, snapshot_prestate}, uuid::{generate_uuidv4, mark_claimed_uuid}, webhooks::deliveries::{purge_webhook_deliveries, retry_webhook_delivery, WebhookDeliveryRetryError}},
+            state::{drives::{state::state::{update_external_id_mapping, OWNER_ID}, types::{ExternalID, ExternalPayload}}, permissions::types::{PermissionGranteeID, SystemPermissionType, SystemRecordIDEnum, SystemResourceID, SystemTableEnum}, search::{state::state::index_search_resource, types::SearchResultResourceID}, webhooks::{
+                state::state::{WEBHOOKS_BY_ALT_INDEX_HASHTABLE, WEBHOOKS_BY_ID_HASHTABLE, WEBHOOKS_BY_TIME_LIST, WEBHOOKS_BY_TIME_MEMORY_ID, WEBHOOK_DEAD_LETTERS_HASHTABLE, WEBHOOK_DELIVERIES_BY_ID_HASHTABLE}, types::{Webhook, WebhookAltIndexID, WebhookDelivery, WebhookDeliveryID, WebhookEventLabel, WebhookID, WebhookIDList}
             }}, types::IDPrefix
         },
         debug_log,
         rest::{
             auth::{authenticate_request, create_auth_error_response}, webhooks::types::{
-                CreateWebhookRequestBody, CreateWebhookResponse, DeleteWebhookRequest, DeleteWebhookResponse, DeletedWebhookData, ErrorResponse, GetWebhookResponse, ListWebhooksRequestBody, ListWebhooksResponse, ListWebhooksResponseData, SortDirection, UpdateWebhookRequestBody, UpdateWebhookResponse
+                CreateWebhookRequestBody, CreateWebhookResponse, DeleteWebhookRequest, DeleteWebhookResponse, DeletedWebhookData, ErrorResponse, GetWebhookResponse, ListWebhookDeliveriesRequestBody, ListWebhookDeliveriesResponse, ListWebhookDeliveriesResponseData, ListWebhooksRequestBody, ListWebhooksResponse, ListWebhooksResponseData, RetryWebhookDeliveryRequestBody, DEFAULT_SIGNATURE_OVERLAP_MS, RetryWebhookDeliveryResponse, SortDirection, UpdateWebhookRequestBody, UpdateWebhookResponse
             }
         }, MEMORY_MANAGER,
     };
@@ -105,7 +105,7 @@ pub mod webhooks_handlers {
         if let Err(validation_error) = request_body.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
     
@@ -330,7 +330,7 @@ pub mod webhooks_handlers {
         if let Err(validation_error) = create_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -363,6 +363,8 @@ pub mod webhooks_handlers {
             url: create_req.url,
             event: event_name.clone(),
             signature: create_req.signature.unwrap_or_default(),
+            previous_signature: None,
+            previous_signature_expires_at: None,
             name: create_req.name.unwrap_or(format!("{}@{}", event_name.clone().to_string(), alt_index.0.clone())),
             note: create_req.note,
             active: create_req.active.unwrap_or(true),
@@ -392,6 +394,7 @@ pub mod webhooks_handlers {
         WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(webhook_id.clone(), webhook.clone());
         });
+        index_search_resource(SearchResultResourceID::Webhook(webhook_id.clone()));
 
         WEBHOOKS_BY_TIME_LIST.with(|store| {
             store.borrow_mut().push(&webhook_id.clone());
@@ -431,7 +434,7 @@ pub mod webhooks_handlers {
         if let Err(validation_error) = update_req.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -469,7 +472,18 @@ pub mod webhooks_handlers {
             webhook.url = url;
         }
         if let Some(signature) = update_req.signature {
-            webhook.signature = signature;
+            if signature != webhook.signature {
+                // Keep signing with the old secret too until the overlap window ends
+                let overlap_ms = update_req.signature_overlap_ms.unwrap_or(DEFAULT_SIGNATURE_OVERLAP_MS);
+                if overlap_ms > 0 && !webhook.signature.is_empty() {
+                    webhook.previous_signature = Some(webhook.signature.clone());
+                    webhook.previous_signature_expires_at = Some(ic_cdk::api::time() / 1_000_000 + overlap_ms);
+                } else {
+                    webhook.previous_signature = None;
+                    webhook.previous_signature_expires_at = None;
+                }
+                webhook.signature = signature;
+            }
         }
         if let Some(name) = update_req.name {
             webhook.name = name;
@@ -502,6 +516,7 @@ pub mod webhooks_handlers {
         WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().insert(webhook_id.clone(), webhook.clone());
         });
+        index_search_resource(SearchResultResourceID::Webhook(webhook_id.clone()));
 
         snapshot_poststate(prestate, Some(
             format!(
@@ -538,7 +553,7 @@ pub mod webhooks_handlers {
         if let Err(validation_error) = delete_request.validate_body() {
             return create_response(
                 StatusCode::BAD_REQUEST,
-                ErrorResponse::err(400, validation_error.message).encode()
+                ErrorResponse::validation_failed(&validation_error).encode()
             );
         }
 
@@ -593,6 +608,7 @@ pub mod webhooks_handlers {
         WEBHOOKS_BY_ID_HASHTABLE.with(|store| {
             store.borrow_mut().remove(&webhook_id);
         });
+        index_search_resource(SearchResultResourceID::Webhook(webhook_id.clone()));
 
         WEBHOOKS_BY_TIME_LIST.with(|store| {
             let mut time_list = store.borrow_mut();
@@ -626,6 +642,9 @@ pub mod webhooks_handlers {
 
         update_external_id_mapping(old_external_id, None, old_internal_id);
 
+        // Pending deliveries have nowhere to go anymore
+        purge_webhook_deliveries(&webhook_id);
+
         snapshot_poststate(prestate, Some(
             format!(
                 "{}: Delete Webhook {}", 
@@ -643,6 +662,164 @@ pub mod webhooks_handlers {
         )
     }
 
+    pub async fn list_webhook_deliveries_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == owner_id.borrow().get().clone());
+        let has_table_permission = is_owner || check_system_permissions(
+            SystemResourceID::Table(SystemTableEnum::Webhooks),
+            PermissionGranteeID::User(requester_api_key.user_id.clone())
+        ).contains(&SystemPermissionType::View);
+
+        // Parse request body
+        let body = request.body();
+        let request_body: ListWebhookDeliveriesRequestBody = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+        if let Err(validation_error) = request_body.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        // Parse cursor if provided
+        let start_index = match &request_body.cursor {
+            Some(cursor) => match cursor.parse::<usize>() {
+                Ok(idx) => idx,
+                Err(_) => return create_response(
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::err(400, "Invalid cursor format".to_string()).encode()
+                ),
+            },
+            None => 0,
+        };
+
+        let webhook_filter = request_body.webhook_id.clone().map(WebhookID);
+
+        // Pending and dead-lettered deliveries together, dead letters are capped so this stays bounded
+        let mut deliveries: Vec<WebhookDelivery> = WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| {
+            store.borrow().iter().map(|(_, delivery)| delivery).collect()
+        });
+        deliveries.extend(WEBHOOK_DEAD_LETTERS_HASHTABLE.with(|store| {
+            store.borrow().iter().map(|(_, delivery)| delivery).collect::<Vec<_>>()
+        }));
+
+        let mut filtered_deliveries: Vec<WebhookDelivery> = deliveries.into_iter()
+            .filter(|delivery| webhook_filter.as_ref().map_or(true, |id| &delivery.webhook_id == id))
+            .filter(|delivery| request_body.status.as_ref().map_or(true, |status| &delivery.status == status))
+            .filter(|delivery| {
+                has_table_permission || check_system_permissions(
+                    SystemResourceID::Record(SystemRecordIDEnum::Webhook(delivery.webhook_id.to_string())),
+                    PermissionGranteeID::User(requester_api_key.user_id.clone())
+                ).contains(&SystemPermissionType::View)
+            })
+            .collect();
+
+        match request_body.direction {
+            SortDirection::Asc => filtered_deliveries.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+            SortDirection::Desc => filtered_deliveries.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
+        }
+
+        let total_count = filtered_deliveries.len();
+        let start_index = start_index.min(total_count);
+        let end_bound = (start_index + request_body.page_size).min(total_count);
+        let page = filtered_deliveries[start_index..end_bound].to_vec();
+
+        let next_cursor = if end_bound < total_count {
+            Some(end_bound.to_string())
+        } else {
+            None
+        };
+
+        create_response(
+            StatusCode::OK,
+            ListWebhookDeliveriesResponse::ok(&ListWebhookDeliveriesResponseData {
+                page_size: page.len(),
+                items: page,
+                total: total_count,
+                direction: request_body.direction,
+                cursor: next_cursor,
+            }).encode()
+        )
+    }
+
+    pub async fn retry_webhook_delivery_handler<'a, 'k, 'v>(request: &'a HttpRequest<'a>, params: &'a Params<'k, 'v>) -> HttpResponse<'static> {
+        // Authenticate request
+        let requester_api_key = match authenticate_request(request) {
+            Some(key) => key,
+            None => return create_auth_error_response(),
+        };
+
+        // Parse request body
+        let body: &[u8] = request.body();
+        let retry_request = match serde_json::from_slice::<RetryWebhookDeliveryRequestBody>(body) {
+            Ok(req) => req,
+            Err(_) => return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::err(400, "Invalid request format".to_string()).encode()
+            ),
+        };
+
+        if let Err(validation_error) = retry_request.validate_body() {
+            return create_response(
+                StatusCode::BAD_REQUEST,
+                ErrorResponse::validation_failed(&validation_error).encode()
+            );
+        }
+
+        let delivery_id = WebhookDeliveryID(retry_request.id.clone());
+        let delivery = WEBHOOK_DELIVERIES_BY_ID_HASHTABLE.with(|store| store.borrow().get(&delivery_id))
+            .or_else(|| WEBHOOK_DEAD_LETTERS_HASHTABLE.with(|store| store.borrow().get(&delivery_id)));
+        let delivery = match delivery {
+            Some(delivery) => delivery,
+            None => return create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::not_found().encode()
+            ),
+        };
+
+        // Retrying counts as editing the webhook
+        let is_owner = OWNER_ID.with(|owner_id| requester_api_key.user_id == owner_id.borrow().get().clone());
+        if !is_owner {
+            let record_permissions = check_system_permissions(
+                SystemResourceID::Record(SystemRecordIDEnum::Webhook(delivery.webhook_id.to_string())),
+                PermissionGranteeID::User(requester_api_key.user_id.clone())
+            );
+            let table_permissions = check_system_permissions(
+                SystemResourceID::Table(SystemTableEnum::Webhooks),
+                PermissionGranteeID::User(requester_api_key.user_id.clone())
+            );
+
+            if !record_permissions.contains(&SystemPermissionType::Edit) && !table_permissions.contains(&SystemPermissionType::Edit) {
+                return create_auth_error_response();
+            }
+        }
+
+        match retry_webhook_delivery(&delivery_id) {
+            Ok(delivery) => create_response(
+                StatusCode::OK,
+                RetryWebhookDeliveryResponse::ok(&delivery).encode()
+            ),
+            Err(WebhookDeliveryRetryError::NotFound) => create_response(
+                StatusCode::NOT_FOUND,
+                ErrorResponse::not_found().encode()
+            ),
+            Err(WebhookDeliveryRetryError::NotRetryable(status)) => create_response(
+                StatusCode::CONFLICT,
+                ErrorResponse::err(409, format!("Webhook delivery {} is {:?}, only failed and dead-lettered deliveries can be retried", delivery_id, status)).encode()
+            ),
+        }
+    }
+
     fn json_decode<T>(value: &[u8]) -> T
     where
         T: for<'de> Deserialize<'de>,
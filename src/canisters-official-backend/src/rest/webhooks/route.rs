@@ -9,6 +9,8 @@ pub const WEBHOOKS_LIST_PATH: &str =    genroute!("/webhooks/list");
 pub const WEBHOOKS_CREATE_PATH: &str =  genroute!("/webhooks/create");
 pub const WEBHOOKS_UPDATE_PATH: &str =  genroute!("/webhooks/update");
 pub const WEBHOOKS_DELETE_PATH: &str =  genroute!("/webhooks/delete");
+pub const WEBHOOKS_DELIVERIES_LIST_PATH: &str =  genroute!("/webhooks/deliveries/list");
+pub const WEBHOOKS_DELIVERIES_RETRY_PATH: &str = genroute!("/webhooks/deliveries/retry");
 
 type HandlerEntry = (&'static str, &'static str, RouteHandler);
 
@@ -38,6 +40,16 @@ pub fn init_routes() {
             "POST",
             WEBHOOKS_DELETE_PATH,
             |req, params| Box::pin(crate::rest::webhooks::handler::webhooks_handlers::delete_webhook_handler(req, params)),
+        ),
+        (
+            "POST",
+            WEBHOOKS_DELIVERIES_LIST_PATH,
+            |req, params| Box::pin(crate::rest::webhooks::handler::webhooks_handlers::list_webhook_deliveries_handler(req, params)),
+        ),
+        (
+            "POST",
+            WEBHOOKS_DELIVERIES_RETRY_PATH,
+            |req, params| Box::pin(crate::rest::webhooks::handler::webhooks_handlers::retry_webhook_delivery_handler(req, params)),
         )
     ];
 
@@ -3,7 +3,9 @@
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 use crate::core::api::uuid::ShareTrackHash;
+use crate::core::api::webhooks::filters::parse_webhook_filter;
 use crate::core::state::directory::types::{FileRecord, FolderRecord, ShareTrackID, ShareTrackResourceID};
+use crate::core::state::cycles::types::{CyclesMonitorConfig, CyclesReport};
 use crate::core::state::drives::state::state::OWNER_ID;
 use crate::core::state::drives::types::{DriveID, DriveRESTUrlEndpoint, StateChecksum, DriveStateDiffID, DriveStateDiffImplementationType, StateDiffRecord, DriveStateDiffString};
 use crate::core::state::permissions::types::SystemPermissionType;
@@ -11,8 +13,7 @@ use crate::core::state::labels::state::validate_uuid4_string_with_prefix;
 use crate::core::state::labels::types::{redact_label, Label, LabelID, LabelResourceID, LabelStringValue};
 use crate::core::state::group_invites::types::GroupInvite;
 use crate::core::state::groups::types::Group;
-use crate::core::state::webhooks::state::state::WEBHOOKS_BY_ID_HASHTABLE;
-use crate::core::state::webhooks::types::{WebhookAltIndexID, WebhookEventLabel};
+use crate::core::state::webhooks::types::{WebhookAltIndexID, WebhookDelivery, WebhookDeliveryStatus};
 use crate::core::state::webhooks::types::{WebhookID, Webhook};
 use crate::core::types::{ClientSuggestedUUID, IDPrefix, UserID};
 use crate::rest::directory::types::DirectoryResourcePermissionFE;
@@ -40,6 +41,7 @@ impl WebhookFE {
             // 2nd most sensitive
             if !has_edit_permissions {
                 redacted.webhook.signature = "".to_string();
+                redacted.webhook.previous_signature = None;
             }
         }
         // Filter labels
@@ -156,15 +158,12 @@ impl CreateWebhookRequestBody {
                     message: "Filters must be 256 characters or less".to_string(),
                 });
             }
-            
-            // If this is an inbox webhook, validate that the filter contains valid topic JSON
-            if self.event == "organization.inbox.new_mail" && !filters.is_empty() {
-                if let Err(_) = serde_json::from_str::<serde_json::Value>(filters) {
-                    return Err(ValidationError {
-                        field: "filters".to_string(),
-                        message: "For inbox webhooks, filters must be valid JSON".to_string(),
-                    });
-                }
+
+            if let Err(message) = parse_webhook_filter(filters) {
+                return Err(ValidationError {
+                    field: "filters".to_string(),
+                    message,
+                });
             }
         }
         
@@ -216,6 +215,9 @@ impl CreateWebhookRequestBody {
 }
 
 
+pub const DEFAULT_SIGNATURE_OVERLAP_MS: u64 = 24 * 60 * 60 * 1000;
+pub const MAX_SIGNATURE_OVERLAP_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
 #[derive(Debug, Clone, Deserialize, CandidType)]
 pub struct UpdateWebhookRequestBody {
     pub id: String,
@@ -223,6 +225,9 @@ pub struct UpdateWebhookRequestBody {
     pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
+    // How long the previous signature keeps being used after a rotation, 0 to drop it immediately
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_overlap_ms: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -254,20 +259,12 @@ impl UpdateWebhookRequestBody {
                     message: "Filters must be 256 characters or less".to_string(),
                 });
             }
-            
-            // Check if this is an inbox webhook by finding it
-            let webhook_id = WebhookID(self.id.clone());
-            let webhook = WEBHOOKS_BY_ID_HASHTABLE.with(|store| store.borrow().get(&webhook_id).clone());
-            
-            if let Some(webhook) = webhook {
-                if webhook.event == WebhookEventLabel::OrganizationInboxNewNotif && !filters.is_empty() {
-                    if let Err(_) = serde_json::from_str::<serde_json::Value>(filters) {
-                        return Err(ValidationError {
-                            field: "filters".to_string(),
-                            message: "For inbox webhooks, filters must be valid JSON".to_string(),
-                        });
-                    }
-                }
+
+            if let Err(message) = parse_webhook_filter(filters) {
+                return Err(ValidationError {
+                    field: "filters".to_string(),
+                    message,
+                });
             }
         }
         
@@ -275,6 +272,14 @@ impl UpdateWebhookRequestBody {
         if let Some(signature) = &self.signature {
             validate_short_string(signature, "signature")?;
         }
+        if let Some(overlap_ms) = self.signature_overlap_ms {
+            if overlap_ms > MAX_SIGNATURE_OVERLAP_MS {
+                return Err(ValidationError {
+                    field: "signature_overlap_ms".to_string(),
+                    message: "Signature overlap cannot exceed 7 days".to_string(),
+                });
+            }
+        }
         
         // Validate description if provided
         if let Some(name) = &self.name {
@@ -329,11 +334,67 @@ pub struct DeletedWebhookData {
 }
 
 
+#[derive(Debug, Clone, Deserialize, CandidType)]
+pub struct ListWebhookDeliveriesRequestBody {
+    pub webhook_id: Option<String>,
+    pub status: Option<WebhookDeliveryStatus>,
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    #[serde(default)]
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+}
+impl ListWebhookDeliveriesRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        if let Some(webhook_id) = &self.webhook_id {
+            validate_id_string(webhook_id, "webhook_id")?;
+        }
+
+        // Validate page_size is reasonable
+        if self.page_size == 0 || self.page_size > 1000 {
+            return Err(ValidationError {
+                field: "page_size".to_string(),
+                message: "Page size must be between 1 and 1000".to_string(),
+            });
+        }
+
+        // Validate cursor strings if present
+        if let Some(cursor) = &self.cursor {
+            validate_short_string(cursor, "cursor")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, CandidType)]
+pub struct ListWebhookDeliveriesResponseData {
+    pub items: Vec<WebhookDelivery>,
+    pub page_size: usize,
+    pub total: usize,
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, CandidType)]
+pub struct RetryWebhookDeliveryRequestBody {
+    pub id: String,
+}
+impl RetryWebhookDeliveryRequestBody {
+    pub fn validate_body(&self) -> Result<(), ValidationError> {
+        validate_id_string(&self.id, "id")?;
+        Ok(())
+    }
+}
+
+
 pub type GetWebhookResponse<'a> = ApiResponse<'a, WebhookFE>;
 pub type ListWebhooksResponse<'a> = ApiResponse<'a, ListWebhooksResponseData>;
 pub type CreateWebhookResponse<'a> = ApiResponse<'a, WebhookFE>;
 pub type UpdateWebhookResponse<'a> = ApiResponse<'a, WebhookFE>;
 pub type DeleteWebhookResponse<'a> = ApiResponse<'a, DeletedWebhookData>;
+pub type ListWebhookDeliveriesResponse<'a> = ApiResponse<'a, ListWebhookDeliveriesResponseData>;
+pub type RetryWebhookDeliveryResponse<'a> = ApiResponse<'a, WebhookDelivery>;
 pub type ErrorResponse<'a> = ApiResponse<'a, ()>;
 
 
@@ -383,6 +444,8 @@ pub enum WebhookResourceData {
     SuperswapUserID(UserID),
     #[serde(rename = "org_inbox_new_notif")]
     OrgInboxNewNotif(InboxOrgRequestBody),
+    #[serde(rename = "low_cycles")]
+    LowCycles(LowCyclesWebhookData),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
@@ -404,6 +467,13 @@ pub struct DriveStateDiffWebhookData {
     pub data: StateDiffRecord
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
+pub struct LowCyclesWebhookData {
+    pub drive_id: DriveID,
+    pub report: CyclesReport,
+    pub config: CyclesMonitorConfig,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShareTrackingWebhookData {
     pub id: ShareTrackID,